@@ -120,10 +120,42 @@ fn bench_one_to_one_filtering(c: &mut Criterion) {
     group.finish();
 }
 
+/// Benchmark: best-buddy scaffold chaining of one large (query, target) group
+fn bench_best_buddy_chaining(c: &mut Criterion) {
+    use sweepga::paf_filter::{best_buddy_predecessors, ChainSpan};
+
+    let mut group = c.benchmark_group("best_buddy_chaining");
+
+    for size in [10_000u64, 100_000].iter() {
+        group.throughput(Throughput::Elements(*size));
+        group.sample_size(10);
+
+        // Collinear 2kb hits every 500bp, plus a 1Mb mapping every 1000 hits
+        let spans: Vec<ChainSpan> = (0..*size)
+            .map(|i| {
+                let len = if i % 1000 == 0 { 1_000_000 } else { 2_000 };
+                ChainSpan {
+                    query_start: i * 500,
+                    query_end: i * 500 + len,
+                    target_start: i * 500,
+                    target_end: i * 500 + len,
+                }
+            })
+            .collect();
+
+        group.bench_with_input(BenchmarkId::from_parameter(size), &spans, |b, spans| {
+            b.iter(|| best_buddy_predecessors(black_box(spans), '+', 50_000));
+        });
+    }
+
+    group.finish();
+}
+
 criterion_group!(
     benches,
     bench_paf_filtering_pipeline,
-    bench_one_to_one_filtering
+    bench_one_to_one_filtering,
+    bench_best_buddy_chaining
 );
 
 criterion_main!(benches);
@@ -14,6 +14,16 @@ fn main() {
     println!("cargo:rerun-if-changed=build.rs");
     println!("cargo:rerun-if-changed=Cargo.lock");
 
+    // Aligner revisions, recorded by `--manifest`
+    for (dep, var) in [
+        ("fastga-rs", "SWEEPGA_FASTGA_REV"),
+        ("wfmash-rs", "SWEEPGA_WFMASH_REV"),
+    ] {
+        if let Some(rev) = dep_rev_from_lockfile(dep) {
+            println!("cargo:rustc-env={var}={rev}");
+        }
+    }
+
     // OUT_DIR = target/{profile}/build/sweepga-{hash}/out
     let out_dir = PathBuf::from(env::var("OUT_DIR").unwrap());
 
@@ -54,6 +54,7 @@ fn main() -> Result<()> {
         scoring_function: ScoringFunction::LogLengthIdentity,
         min_identity: 0.0,
         min_scaffold_identity: 0.0,
+        min_scaffold_count: 1,
     };
 
     let filter = PafFilter::new(config);
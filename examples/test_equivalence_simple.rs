@@ -55,6 +55,7 @@ fn main() -> Result<()> {
         scoring_function: ScoringFunction::LogLengthIdentity,
         min_identity: 0.0,
         min_scaffold_identity: 0.0,
+        min_scaffold_count: 1,
     };
 
     // Filter .1aln → .1aln
@@ -85,6 +85,7 @@ fn main() -> Result<()> {
         scoring_function: ScoringFunction::LogLengthIdentity,
         min_identity: 0.0,
         min_scaffold_identity: 0.0,
+        min_scaffold_count: 1,
     };
 
     filter_file(
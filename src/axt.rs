@@ -0,0 +1,311 @@
+//! AXT export (`--axt`)
+//!
+//! Every output record with a `cg:Z:` CIGAR becomes one Blastz/AXT block:
+//! a summary line (`number target start end query start end strand score`,
+//! 1-based closed coordinates), the gapped target row, the gapped query row
+//! and a blank line. Target is the primary sequence; on `-` the query row is
+//! reverse-complemented and its coordinates are on the minus strand, as the
+//! format specifies. Scores use the UCSC defaults (HOXD70, gap open 400,
+//! extend 30).
+//!
+//! [`gapped_rows`] rebuilds the gapped rows of any CIGAR alignment and is
+//! meant to be shared by other alignment-text exporters.
+
+use anyhow::{Context, Result};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use crate::chain_format::ChainMember;
+use crate::trim_overlaps::parse_cigar_ops;
+
+/// HOXD70 substitution scores, rows and columns in `ACGT` order
+const HOXD70: [[i64; 4]; 4] = [
+    [91, -114, -31, -123],
+    [-114, 100, -125, -31],
+    [-31, -125, 100, -114],
+    [-123, -31, -114, 91],
+];
+/// Score of a column with an ambiguous base (N etc.)
+const AMBIGUOUS_SCORE: i64 = -100;
+const GAP_OPEN: i64 = 400;
+const GAP_EXTEND: i64 = 30;
+
+fn base_index(base: u8) -> Option<usize> {
+    match base.to_ascii_uppercase() {
+        b'A' => Some(0),
+        b'C' => Some(1),
+        b'G' => Some(2),
+        b'T' => Some(3),
+        _ => None,
+    }
+}
+
+/// Reverse complement, keeping case and gaps (`-`); other non-ACGT bases
+/// become `N`/`n`
+pub fn reverse_complement(seq: &[u8]) -> Vec<u8> {
+    seq.iter()
+        .rev()
+        .map(|&base| match base {
+            b'-' => b'-',
+            b'A' => b'T',
+            b'C' => b'G',
+            b'G' => b'C',
+            b'T' => b'A',
+            b'a' => b't',
+            b'c' => b'g',
+            b'g' => b'c',
+            b't' => b'a',
+            b if b.is_ascii_lowercase() => b'n',
+            _ => b'N',
+        })
+        .collect()
+}
+
+/// Lay `query` and `target` out as equal-length rows along `cigar`, with
+/// `-` in the query row for deletions and in the target row for insertions.
+/// The CIGAR must consume both sequences exactly.
+pub fn gapped_rows(
+    query: &[u8],
+    target: &[u8],
+    cigar: &[(u64, char)],
+) -> Result<(Vec<u8>, Vec<u8>)> {
+    let columns: u64 = cigar.iter().map(|&(len, _)| len).sum();
+    let mut query_row = Vec::with_capacity(columns as usize);
+    let mut target_row = Vec::with_capacity(columns as usize);
+    let (mut q, mut t) = (0usize, 0usize);
+    for &(len, op) in cigar {
+        let len = len as usize;
+        let (takes_query, takes_target) = match op {
+            'M' | '=' | 'X' => (true, true),
+            'I' => (true, false),
+            'D' => (false, true),
+            _ => anyhow::bail!("Unsupported CIGAR operation '{op}'"),
+        };
+        if takes_query {
+            let bases = query
+                .get(q..q + len)
+                .context("CIGAR runs past the end of the query span")?;
+            query_row.extend_from_slice(bases);
+            q += len;
+        } else {
+            query_row.resize(query_row.len() + len, b'-');
+        }
+        if takes_target {
+            let bases = target
+                .get(t..t + len)
+                .context("CIGAR runs past the end of the target span")?;
+            target_row.extend_from_slice(bases);
+            t += len;
+        } else {
+            target_row.resize(target_row.len() + len, b'-');
+        }
+    }
+    anyhow::ensure!(
+        q == query.len() && t == target.len(),
+        "CIGAR covers {q} query and {t} target bases, but the record spans {} and {}",
+        query.len(),
+        target.len()
+    );
+    Ok((query_row, target_row))
+}
+
+/// Blastz score of two gapped rows. A gap run in either row costs
+/// `GAP_OPEN + GAP_EXTEND * length`.
+pub fn blastz_score(query_row: &[u8], target_row: &[u8]) -> i64 {
+    let mut score = 0;
+    // Row holding the current gap run: false = query, true = target
+    let mut gap_in: Option<bool> = None;
+    for (&q, &t) in query_row.iter().zip(target_row) {
+        if q == b'-' || t == b'-' {
+            let row = t == b'-';
+            if gap_in != Some(row) {
+                score -= GAP_OPEN;
+                gap_in = Some(row);
+            }
+            score -= GAP_EXTEND;
+            continue;
+        }
+        gap_in = None;
+        score += match (base_index(q), base_index(t)) {
+            (Some(q), Some(t)) => HOXD70[q][t],
+            _ => AMBIGUOUS_SCORE,
+        };
+    }
+    score
+}
+
+/// Write one AXT block numbered `number` for `member`, aligned by `cigar`.
+/// `query` and `target` are the forward-strand bases of the aligned spans.
+pub fn write_block<W: Write>(
+    writer: &mut W,
+    number: usize,
+    member: &ChainMember,
+    cigar: &[(u64, char)],
+    query: &[u8],
+    target: &[u8],
+) -> Result<()> {
+    let reverse = member.strand == '-';
+    let query_strand = if reverse {
+        reverse_complement(query)
+    } else {
+        query.to_vec()
+    };
+    let (query_row, target_row) = gapped_rows(&query_strand, target, cigar)?;
+    let (query_start, query_end) = if reverse {
+        (
+            member.query_len - member.query_end,
+            member.query_len - member.query_start,
+        )
+    } else {
+        (member.query_start, member.query_end)
+    };
+
+    writeln!(
+        writer,
+        "{number} {} {} {} {} {} {query_end} {} {}",
+        member.target_name,
+        member.target_start + 1,
+        member.target_end,
+        member.query_name,
+        query_start + 1,
+        member.strand,
+        blastz_score(&query_row, &target_row),
+    )?;
+    writer.write_all(&target_row)?;
+    writeln!(writer)?;
+    writer.write_all(&query_row)?;
+    writeln!(writer, "\n")?;
+    Ok(())
+}
+
+/// Indexed FASTA files, fetching sequence ranges by name
+pub struct FastaSequences {
+    readers: Vec<rust_htslib::faidx::Reader>,
+    /// Sequence name -> index of the reader holding it
+    files: HashMap<String, usize>,
+}
+
+impl FastaSequences {
+    /// Open (indexing if needed) each FASTA; names are looked up in the first
+    /// file that has them
+    pub fn open(paths: &[PathBuf]) -> Result<Self> {
+        let mut readers = Vec::with_capacity(paths.len());
+        let mut files = HashMap::new();
+        for path in paths {
+            let reader = rust_htslib::faidx::Reader::from_path(path).map_err(|e| {
+                anyhow::anyhow!(
+                    "Failed to read/create FASTA index for {}: {e}",
+                    path.display()
+                )
+            })?;
+            let fai_path = format!("{}.fai", path.display());
+            let fai = std::fs::read_to_string(&fai_path)
+                .with_context(|| format!("FASTA index not found: {fai_path}"))?;
+            for name in fai.lines().filter_map(|line| line.split('\t').next()) {
+                files.entry(name.to_string()).or_insert(readers.len());
+            }
+            readers.push(reader);
+        }
+        Ok(FastaSequences { readers, files })
+    }
+
+    /// Bases `[start, end)` of sequence `name`
+    pub fn fetch(&self, name: &str, start: u64, end: u64) -> Result<Vec<u8>> {
+        if end <= start {
+            return Ok(Vec::new());
+        }
+        let reader = self
+            .files
+            .get(name)
+            .map(|&i| &self.readers[i])
+            .with_context(|| format!("Sequence {name} not found in the input FASTA files"))?;
+        let seq = reader
+            .fetch_seq_string(name, start as usize, end as usize - 1)
+            .map_err(|e| anyhow::anyhow!("Failed to fetch {name}:{start}-{end}: {e}"))?;
+        Ok(seq.into_bytes())
+    }
+}
+
+/// Write the 0-based PAF `lines` as AXT blocks to `path`, numbering them
+/// from 0 and taking bases from `fastas`. Records without a `cg:Z:` CIGAR
+/// are skipped. Returns the number of blocks written.
+pub fn write_axt_file(path: &Path, lines: &[String], fastas: &[PathBuf]) -> Result<usize> {
+    let sequences = FastaSequences::open(fastas)?;
+    let file = File::create(path)
+        .with_context(|| format!("Failed to create AXT file {}", path.display()))?;
+    let mut writer = BufWriter::new(file);
+    let mut written = 0;
+    let mut skipped = 0;
+    for line in lines {
+        let Some(cigar) = line.split('\t').find_map(|f| f.strip_prefix("cg:Z:")) else {
+            skipped += 1;
+            continue;
+        };
+        let cigar = parse_cigar_ops(cigar)?;
+        let member = ChainMember::from_paf_line(line)?;
+        let query = sequences.fetch(&member.query_name, member.query_start, member.query_end)?;
+        let target =
+            sequences.fetch(&member.target_name, member.target_start, member.target_end)?;
+        write_block(&mut writer, written, &member, &cigar, &query, &target)
+            .with_context(|| format!("Failed to write AXT block for record: {line}"))?;
+        written += 1;
+    }
+    writer.flush()?;
+    if skipped > 0 {
+        log::warn!("[sweepga] --axt: skipped {skipped} records without a cg:Z: CIGAR");
+    }
+    Ok(written)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn block(number: usize, line: &str, query: &[u8], target: &[u8]) -> String {
+        let member = ChainMember::from_paf_line(line).unwrap();
+        let cigar = line
+            .split('\t')
+            .find_map(|f| f.strip_prefix("cg:Z:"))
+            .unwrap();
+        let mut out = Vec::new();
+        write_block(
+            &mut out,
+            number,
+            &member,
+            &parse_cigar_ops(cigar).unwrap(),
+            query,
+            target,
+        )
+        .unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_insertion_is_gapped_in_target_row() {
+        // 10 matching bases (3 A, 3 C, 2 G, 2 T = 955) and a 1-base gap (-430)
+        let line = "q\t20\t5\t16\t+\tt\t30\t10\t20\t10\t11\t60\tcg:Z:4=1I6=";
+        assert_eq!(
+            block(0, line, b"ACGTTACGTAC", b"ACGTACGTAC"),
+            "0 t 11 20 q 6 16 + 525\nACGT-ACGTAC\nACGTTACGTAC\n\n"
+        );
+    }
+
+    #[test]
+    fn test_reverse_strand_block() {
+        // Query 2-9 is 11-18 (0-based) on the minus strand; its reverse
+        // complement AACGGTT lacks the target's fourth base
+        let line = "q\t20\t2\t9\t-\tt\t30\t0\t8\t7\t8\t60\tcg:Z:3=1D4=";
+        assert_eq!(
+            block(1, line, b"AACCGTT", b"AACCGGTT"),
+            "1 t 1 8 q 12 18 - 234\nAACCGGTT\nAAC-GGTT\n\n"
+        );
+    }
+
+    #[test]
+    fn test_cigar_must_cover_the_record() {
+        let err = gapped_rows(b"ACGT", b"ACGT", &[(3, '=')]).unwrap_err();
+        assert!(err.to_string().contains("covers 3 query"), "{err}");
+    }
+}
@@ -99,6 +99,12 @@ impl FastGABatchAligner {
             zstd_level,
         }
     }
+
+    /// Append raw arguments to every FastGA invocation (unvalidated).
+    pub fn with_extra_args(mut self, extra_args: Vec<String>) -> Self {
+        self.fastga = self.fastga.with_extra_args(extra_args);
+        self
+    }
 }
 
 impl BatchAligner for FastGABatchAligner {
@@ -184,6 +190,8 @@ pub struct WfmashBatchAligner {
     /// Mapping density (wfmash `-x`). Forwarded verbatim to every per-batch
     /// `WfmashIntegration`. `None` (or `Some(1.0)`) keeps all mappings.
     sparsify: Option<f64>,
+    /// PanSN field separator wfmash groups sequences by.
+    prefix_delimiter: char,
 }
 
 impl WfmashBatchAligner {
@@ -200,9 +208,17 @@ impl WfmashBatchAligner {
             map_pct_identity,
             temp_dir,
             sparsify,
+            prefix_delimiter: '#',
         }
     }
 
+    /// Group sequences into genomes on `delimiter` (`--prefix-delimiter`)
+    /// in place of `#`.
+    pub fn with_prefix_delimiter(mut self, delimiter: char) -> Self {
+        self.prefix_delimiter = delimiter;
+        self
+    }
+
     /// Create a WfmashIntegration configured for a specific batch FASTA.
     fn create_wfmash_for(&self, fasta_path: &Path) -> Result<crate::wfmash_integration::WfmashIntegration> {
         let avg_seq = avg_seq_len_from_fai(fasta_path).ok();
@@ -216,6 +232,7 @@ impl WfmashBatchAligner {
             self.sparsify,
             None, // num_mappings
             None, // pairs_file
+            self.prefix_delimiter,
         )
     }
 }
@@ -305,9 +322,14 @@ pub fn format_bytes(bytes: u64) -> String {
     }
 }
 
-/// Parse genome sizes from FASTA file(s)
+/// Parse genome sizes from FASTA file(s), grouping sequences by their
+/// `delimiter`/`depth` genome prefix
 /// Returns map of genome prefix -> GenomeInfo
-pub fn parse_genome_sizes(fasta_files: &[String]) -> Result<Vec<GenomeInfo>> {
+pub fn parse_genome_sizes(
+    fasta_files: &[String],
+    delimiter: char,
+    depth: usize,
+) -> Result<Vec<GenomeInfo>> {
     let mut genomes: HashMap<String, GenomeInfo> = HashMap::new();
 
     for fasta_file in fasta_files {
@@ -346,7 +368,7 @@ pub fn parse_genome_sizes(fasta_files: &[String]) -> Result<Vec<GenomeInfo>> {
                     .split_whitespace()
                     .next()
                     .unwrap_or("");
-                let prefix = extract_pansn_prefix(name);
+                let prefix = extract_pansn_prefix(name, delimiter, depth);
                 current_prefix = Some(prefix);
                 current_bp = 0;
             } else if !trimmed.is_empty() {
@@ -397,13 +419,13 @@ fn avg_seq_len_from_fai(path: &Path) -> Result<u64> {
 }
 
 /// Extract PanSN genome prefix from sequence name
-/// E.g., "SGDref#1#chrI" -> "SGDref#1#"
-fn extract_pansn_prefix(name: &str) -> String {
-    let parts: Vec<&str> = name.split('#').collect();
-    if parts.len() >= 2 {
-        format!("{}#{}#", parts[0], parts[1])
+/// E.g., "SGDref#1#chrI" -> "SGDref#1#"; names without the delimiter get
+/// one appended so every prefix ends in it
+fn extract_pansn_prefix(name: &str, delimiter: char, depth: usize) -> String {
+    if name.contains(delimiter) {
+        crate::pansn::genome_prefix(name, delimiter, depth).to_string()
     } else {
-        format!("{}#", name)
+        format!("{name}{delimiter}")
     }
 }
 
@@ -470,7 +492,12 @@ pub fn partition_into_batches_by_count(genomes: Vec<GenomeInfo>, max_count: usiz
 }
 
 /// Write a subset of genomes to a temporary FASTA file
-pub fn write_batch_fasta(batch: &GenomeBatch, output_path: &Path) -> Result<()> {
+pub fn write_batch_fasta(
+    batch: &GenomeBatch,
+    output_path: &Path,
+    delimiter: char,
+    depth: usize,
+) -> Result<()> {
     let mut output = File::create(output_path)
         .with_context(|| format!("Failed to create batch FASTA: {}", output_path.display()))?;
 
@@ -507,7 +534,7 @@ pub fn write_batch_fasta(batch: &GenomeBatch, output_path: &Path) -> Result<()>
                     .split_whitespace()
                     .next()
                     .unwrap_or("");
-                let prefix = extract_pansn_prefix(name);
+                let prefix = extract_pansn_prefix(name, delimiter, depth);
                 include_current = prefixes.iter().any(|p| *p == prefix);
             }
 
@@ -601,6 +628,13 @@ pub fn compute_batch_bp_from_budget(
     Some(max_batch_bp.max(largest_genome))
 }
 
+/// Disk needed regardless of batching or threads: batch FASTAs, all GDBs and
+/// the PAF reserve. A run cannot finish with less than this.
+pub fn estimate_min_disk(total_bp: u64) -> u64 {
+    let paf_reserve = total_bp / 10;
+    (total_bp as f64 * (1.0 + GDB_FACTOR)) as u64 + paf_reserve
+}
+
 /// Estimate peak disk usage for a given total basepairs and thread count.
 pub fn estimate_peak_disk(
     total_bp: u64,
@@ -611,8 +645,7 @@ pub fn estimate_peak_disk(
 ) -> u64 {
     let zstd_factor: f64 = if zstd { 0.5 } else { 1.0 };
     let index_factor = n_threads as f64 * KTAB_PER_THREAD * zstd_factor;
-    let paf_reserve = total_bp / 10;
-    let fixed = (total_bp as f64 * (1.0 + GDB_FACTOR)) as u64 + paf_reserve;
+    let fixed = estimate_min_disk(total_bp);
     let index_bp = batch_bp.unwrap_or(total_bp);
     let index_cost = (index_bp as f64 * index_factor) as u64;
     fixed + index_cost
@@ -729,15 +762,16 @@ pub fn resolve_batch_bytes(
     fasta_files: &[String],
     n_threads: usize,
     zstd: bool,
-    quiet: bool,
+    config: &BatchAlignConfig,
 ) -> Result<Option<u64>> {
+    let quiet = config.quiet;
     if max_disk.is_none() {
         // Fast path: nothing to compute if no budget was set.
         return resolve_batch_bytes_from_sizes(
             max_disk, batch_bytes, &[], 0, n_threads, zstd, quiet,
         );
     }
-    let genomes = parse_genome_sizes(fasta_files)?;
+    let genomes = parse_genome_sizes(fasta_files, config.prefix_delimiter, config.prefix_depth)?;
     let genome_sizes: Vec<u64> = genomes.iter().map(|g| g.total_bp).collect();
     resolve_batch_bytes_from_sizes(
         max_disk,
@@ -758,6 +792,9 @@ pub fn resolve_batch_bytes(
 pub struct BatchAlignConfig {
     pub keep_self: bool,
     pub quiet: bool,
+    /// PanSN delimiter and depth that group sequences into genomes
+    pub prefix_delimiter: char,
+    pub prefix_depth: usize,
 }
 
 /// Maximum number of adaptive restarts before giving up.
@@ -787,7 +824,7 @@ pub fn run_batch_alignment_with_budget(
     if !config.quiet {
         log::info!("[batch] Scanning input files for genome sizes...");
     }
-    let genomes = parse_genome_sizes(fasta_files)?;
+    let genomes = parse_genome_sizes(fasta_files, config.prefix_delimiter, config.prefix_depth)?;
     let total_bp: u64 = genomes.iter().map(|g| g.total_bp).sum();
     let genome_bp: Vec<u64> = genomes.iter().map(|g| g.total_bp).collect();
 
@@ -852,7 +889,12 @@ pub fn run_batch_alignment_with_budget(
                     batch.genomes.len(),
                 );
             }
-            write_batch_fasta(batch, &batch_path)?;
+            write_batch_fasta(
+                batch,
+                &batch_path,
+                config.prefix_delimiter,
+                config.prefix_depth,
+            )?;
             batch_files.push(batch_path);
         }
 
@@ -985,6 +1027,8 @@ pub fn run_batch_alignment_with_budget(
                 merged_paf.path(),
                 &genome_prefixes,
                 !config.keep_self,
+                config.prefix_delimiter,
+                config.prefix_depth,
             )?;
             if !config.quiet {
                 log::info!(
@@ -1087,7 +1131,7 @@ pub fn run_batch_alignment_generic(
     if !config.quiet {
         log::info!("[batch] Scanning input files for genome sizes...");
     }
-    let genomes = parse_genome_sizes(fasta_files)?;
+    let genomes = parse_genome_sizes(fasta_files, config.prefix_delimiter, config.prefix_depth)?;
     let total_bp: u64 = genomes.iter().map(|g| g.total_bp).sum();
 
     if genomes.is_empty() {
@@ -1124,7 +1168,12 @@ pub fn run_batch_alignment_generic(
                 batch.genomes.len()
             );
         }
-        write_batch_fasta(batch, &batch_path)?;
+        write_batch_fasta(
+            batch,
+            &batch_path,
+            config.prefix_delimiter,
+            config.prefix_depth,
+        )?;
         batch_files.push(batch_path);
     }
 
@@ -1203,6 +1252,8 @@ pub fn run_batch_alignment_generic(
         merged_paf.path(),
         &genome_prefixes,
         !config.keep_self,
+        config.prefix_delimiter,
+        config.prefix_depth,
     )?;
     if !config.quiet {
         log::info!(
@@ -1241,7 +1292,7 @@ pub fn run_batch_alignment_by_count(
     if !config.quiet {
         log::info!("[batch] Scanning input files for genome sizes...");
     }
-    let genomes = parse_genome_sizes(fasta_files)?;
+    let genomes = parse_genome_sizes(fasta_files, config.prefix_delimiter, config.prefix_depth)?;
     let total_bp: u64 = genomes.iter().map(|g| g.total_bp).sum();
 
     if genomes.is_empty() {
@@ -1275,7 +1326,12 @@ pub fn run_batch_alignment_by_count(
                 batch.genomes.len()
             );
         }
-        write_batch_fasta(batch, &batch_path)?;
+        write_batch_fasta(
+            batch,
+            &batch_path,
+            config.prefix_delimiter,
+            config.prefix_depth,
+        )?;
         batch_files.push(batch_path);
     }
 
@@ -1347,6 +1403,8 @@ pub fn run_batch_alignment_by_count(
         merged_paf.path(),
         &genome_prefixes,
         !config.keep_self,
+        config.prefix_delimiter,
+        config.prefix_depth,
     )?;
     if !config.quiet {
         log::info!(
@@ -1360,6 +1418,19 @@ pub fn run_batch_alignment_by_count(
     Ok(merged_paf)
 }
 
+/// Append the contents of a per-pair PAF file to a merged output writer.
+///
+/// The source file is fully written by the aligner before it is appended,
+/// so callers can wrap `writer` in a large `BufWriter` and flush once at
+/// the end instead of after every pair. Returns the number of PAF lines
+/// appended.
+pub fn append_paf_file<W: Write>(paf_path: &Path, writer: &mut W) -> Result<usize> {
+    let contents = std::fs::read(paf_path)
+        .with_context(|| format!("Failed to read PAF: {}", paf_path.display()))?;
+    writer.write_all(&contents)?;
+    Ok(contents.iter().filter(|&&b| b == b'\n').count())
+}
+
 /// Result of batch completeness verification.
 ///
 /// The `missing` pair list is surfaced to the user via `log::warn!`
@@ -1379,11 +1450,14 @@ pub struct BatchVerification {
 /// expected pair is represented. Missing pairs are logged as warnings.
 ///
 /// `exclude_self` controls whether self-alignments (same genome prefix) are
-/// expected. When `true`, pairs like A→A are not required.
+/// expected. When `true`, pairs like A→A are not required. Sequence names are
+/// grouped into genomes by `delimiter` and `depth`, as in `parse_genome_sizes`.
 pub fn verify_batch_completeness(
     paf_path: &Path,
     expected_genomes: &[String],
     exclude_self: bool,
+    delimiter: char,
+    depth: usize,
 ) -> Result<BatchVerification> {
     // Build expected pairs
     let mut expected: HashSet<(String, String)> = HashSet::new();
@@ -1413,8 +1487,8 @@ pub fn verify_batch_completeness(
         if fields.len() < 6 {
             continue;
         }
-        let query_prefix = extract_pansn_prefix(fields[0]);
-        let target_prefix = extract_pansn_prefix(fields[5]);
+        let query_prefix = extract_pansn_prefix(fields[0], delimiter, depth);
+        let target_prefix = extract_pansn_prefix(fields[5], delimiter, depth);
         found.insert((query_prefix, target_prefix));
     }
 
@@ -1454,9 +1528,13 @@ mod tests {
 
     #[test]
     fn test_extract_pansn_prefix() {
-        assert_eq!(extract_pansn_prefix("SGDref#1#chrI"), "SGDref#1#");
-        assert_eq!(extract_pansn_prefix("genome#hap#seq"), "genome#hap#");
-        assert_eq!(extract_pansn_prefix("simple"), "simple#");
+        assert_eq!(extract_pansn_prefix("SGDref#1#chrI", '#', 0), "SGDref#1#");
+        assert_eq!(extract_pansn_prefix("genome#hap#seq", '#', 0), "genome#hap#");
+        assert_eq!(extract_pansn_prefix("simple", '#', 0), "simple#");
+        // --prefix-depth and --prefix-delimiter are honoured
+        assert_eq!(extract_pansn_prefix("A#1#chr1#x", '#', 1), "A#");
+        assert_eq!(extract_pansn_prefix("A|1|chr1", '|', 0), "A|1|");
+        assert_eq!(extract_pansn_prefix("A#1#chr1", '|', 0), "A#1#chr1|");
     }
 
     #[test]
@@ -1482,7 +1560,7 @@ mod tests {
         paf.flush().unwrap();
 
         let genomes = vec!["A#1#".to_string(), "B#1#".to_string(), "C#1#".to_string()];
-        let result = verify_batch_completeness(paf.path(), &genomes, true).unwrap();
+        let result = verify_batch_completeness(paf.path(), &genomes, true, '#', 0).unwrap();
         assert_eq!(result.expected_pairs, 6); // 3 genomes, exclude self = 3*2
         assert_eq!(result.found_pairs, 6);
         assert_eq!(result.found_pairs, result.expected_pairs);
@@ -1502,7 +1580,7 @@ mod tests {
         paf.flush().unwrap();
 
         let genomes = vec!["A#1#".to_string(), "B#1#".to_string(), "C#1#".to_string()];
-        let result = verify_batch_completeness(paf.path(), &genomes, true).unwrap();
+        let result = verify_batch_completeness(paf.path(), &genomes, true, '#', 0).unwrap();
         assert_eq!(result.expected_pairs, 6);
         assert_eq!(result.found_pairs, 2);
         assert_eq!(result.expected_pairs - result.found_pairs, 4); // A->C, C->A, B->C, C->B
@@ -1528,7 +1606,7 @@ mod tests {
 
         let genomes = vec!["A#1#".to_string(), "B#1#".to_string()];
         // exclude_self = false means we expect self-pairs too
-        let result = verify_batch_completeness(paf.path(), &genomes, false).unwrap();
+        let result = verify_batch_completeness(paf.path(), &genomes, false, '#', 0).unwrap();
         assert_eq!(result.expected_pairs, 4); // 2*2 = 4 (A->A, A->B, B->A, B->B)
         assert_eq!(result.found_pairs, 4);
         assert_eq!(result.found_pairs, result.expected_pairs);
@@ -1703,4 +1781,35 @@ mod tests {
             "Zstd peak {} should be less than non-zstd peak {}",
             peak_zstd, peak_batched);
     }
+
+    #[test]
+    fn test_append_paf_file_many_pairs_buffered() {
+        let dir = tempfile::tempdir().unwrap();
+        let merged_path = dir.path().join("merged.paf");
+        let mut expected = String::new();
+
+        {
+            // Tiny buffer forces many intermediate writes without explicit flushes
+            let mut writer =
+                std::io::BufWriter::with_capacity(64, File::create(&merged_path).unwrap());
+            let mut total = 0;
+            for i in 0..200 {
+                let pair_path = dir.path().join(format!("pair_{i}.paf"));
+                let mut content = String::new();
+                for j in 0..(i % 4) {
+                    content.push_str(&format!(
+                        "A#1#chr{i}\t1000\t{j}\t100\t+\tB#1#chr{i}\t1000\t0\t100\t90\t100\t60\n"
+                    ));
+                }
+                std::fs::write(&pair_path, &content).unwrap();
+                expected.push_str(&content);
+                total += append_paf_file(&pair_path, &mut writer).unwrap();
+            }
+            writer.flush().unwrap();
+            assert_eq!(total, expected.lines().count());
+        }
+
+        let merged = std::fs::read_to_string(&merged_path).unwrap();
+        assert_eq!(merged, expected);
+    }
 }
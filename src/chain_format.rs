@@ -0,0 +1,215 @@
+//! UCSC chain format export (`--chain`)
+//!
+//! Every output scaffold chain (the `ch:Z:` tag) becomes one chain block, as
+//! used by liftOver; mappings outside any chain become single-member chains.
+//! PAF records are not split at their internal indels here, so each member
+//! contributes one ungapped block of `min(query span, target span)` bases and
+//! the gap lines come from the distances between consecutive members.
+//!
+//! Target is the chain's reference (`t` fields, always `+`); reverse-strand
+//! chains give query coordinates on the minus strand, per the format.
+
+use anyhow::{Context, Result};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+/// One chain member, parsed from a 0-based PAF line
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChainMember {
+    pub query_name: String,
+    pub query_len: u64,
+    pub query_start: u64,
+    pub query_end: u64,
+    pub strand: char,
+    pub target_name: String,
+    pub target_len: u64,
+    pub target_start: u64,
+    pub target_end: u64,
+    pub matches: u64,
+}
+
+impl ChainMember {
+    /// Parse the first 10 columns of a PAF line
+    pub fn from_paf_line(line: &str) -> Result<Self> {
+        let fields: Vec<&str> = line.split('\t').collect();
+        anyhow::ensure!(fields.len() >= 11, "PAF record has fewer than 11 columns");
+        let num = |i: usize| -> Result<u64> {
+            fields[i]
+                .parse()
+                .with_context(|| format!("Invalid PAF column {}: {}", i + 1, fields[i]))
+        };
+        Ok(ChainMember {
+            query_name: fields[0].to_string(),
+            query_len: num(1)?,
+            query_start: num(2)?,
+            query_end: num(3)?,
+            strand: if fields[4] == "-" { '-' } else { '+' },
+            target_name: fields[5].to_string(),
+            target_len: num(6)?,
+            target_start: num(7)?,
+            target_end: num(8)?,
+            matches: num(9)?,
+        })
+    }
+
+    /// Query start on the chain's query strand
+    fn strand_query_start(&self) -> u64 {
+        match self.strand {
+            '-' => self.query_len - self.query_end,
+            _ => self.query_start,
+        }
+    }
+}
+
+/// An ungapped block: target start, query start (on the query strand), size
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Block {
+    target_start: u64,
+    query_start: u64,
+    size: u64,
+}
+
+/// Lay members out as ascending, non-overlapping blocks. A member overlapping
+/// the previous block on either axis is clipped at its start; one that is
+/// fully covered (or out of order) is skipped.
+fn member_blocks(members: &[ChainMember]) -> Vec<(Block, &ChainMember)> {
+    let mut ordered: Vec<&ChainMember> = members.iter().collect();
+    ordered.sort_by_key(|m| (m.target_start, m.strand_query_start()));
+
+    let mut blocks: Vec<(Block, &ChainMember)> = Vec::new();
+    for member in ordered {
+        let mut block = Block {
+            target_start: member.target_start,
+            query_start: member.strand_query_start(),
+            size: (member.target_end - member.target_start)
+                .min(member.query_end - member.query_start),
+        };
+        if let Some((prev, _)) = blocks.last() {
+            let clip = (prev.target_start + prev.size)
+                .saturating_sub(block.target_start)
+                .max((prev.query_start + prev.size).saturating_sub(block.query_start));
+            if clip >= block.size {
+                continue;
+            }
+            block.target_start += clip;
+            block.query_start += clip;
+            block.size -= clip;
+        }
+        if block.size > 0 {
+            blocks.push((block, member));
+        }
+    }
+    blocks
+}
+
+/// Write one chain block with the given ID; chains with no usable blocks
+/// are skipped. Returns whether anything was written.
+pub fn write_chain<W: Write>(writer: &mut W, members: &[ChainMember], id: usize) -> Result<bool> {
+    let blocks = member_blocks(members);
+    let (Some((first, head)), Some((last, _))) = (blocks.first(), blocks.last()) else {
+        return Ok(false);
+    };
+    let score: u64 = blocks.iter().map(|(_, m)| m.matches).sum();
+
+    writeln!(
+        writer,
+        "chain {score} {} {} + {} {} {} {} {} {} {} {id}",
+        head.target_name,
+        head.target_len,
+        first.target_start,
+        last.target_start + last.size,
+        head.query_name,
+        head.query_len,
+        head.strand,
+        first.query_start,
+        last.query_start + last.size,
+    )?;
+    for pair in blocks.windows(2) {
+        let (block, next) = (pair[0].0, pair[1].0);
+        writeln!(
+            writer,
+            "{}\t{}\t{}",
+            block.size,
+            next.target_start - (block.target_start + block.size),
+            next.query_start - (block.query_start + block.size),
+        )?;
+    }
+    writeln!(writer, "{}\n", last.size)?;
+    Ok(true)
+}
+
+/// Write all chains to `path`, numbering them from 1. Returns the count.
+pub fn write_chain_file(path: &Path, chains: &[Vec<ChainMember>]) -> Result<usize> {
+    let file = File::create(path)
+        .with_context(|| format!("Failed to create chain file {}", path.display()))?;
+    let mut writer = BufWriter::new(file);
+    let mut written = 0;
+    for members in chains {
+        if write_chain(&mut writer, members, written + 1)? {
+            written += 1;
+        }
+    }
+    writer.flush()?;
+    Ok(written)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn member(line: &str) -> ChainMember {
+        ChainMember::from_paf_line(line).unwrap()
+    }
+
+    fn chain_text(members: &[ChainMember]) -> String {
+        let mut out = Vec::new();
+        write_chain(&mut out, members, 1).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_two_member_chain_gaps() {
+        let members = [
+            member("q\t10000\t1500\t2500\t+\tt\t20000\t1800\t2800\t950\t1000\t60"),
+            member("q\t10000\t0\t1000\t+\tt\t20000\t0\t1000\t900\t1000\t60"),
+        ];
+        assert_eq!(
+            chain_text(&members),
+            "chain 1850 t 20000 + 0 2800 q 10000 + 0 2500 1\n\
+             1000\t800\t500\n\
+             1000\n\n"
+        );
+    }
+
+    #[test]
+    fn test_reverse_chain_uses_minus_strand_query() {
+        // Query 8000-9000 then 6000-7000 on the forward strand is
+        // 1000-2000 then 3000-4000 on the minus strand
+        let members = [
+            member("q\t10000\t8000\t9000\t-\tt\t20000\t0\t1000\t1000\t1000\t60"),
+            member("q\t10000\t6000\t7000\t-\tt\t20000\t1500\t2500\t1000\t1000\t60"),
+        ];
+        assert_eq!(
+            chain_text(&members),
+            "chain 2000 t 20000 + 0 2500 q 10000 - 1000 4000 1\n\
+             1000\t500\t1000\n\
+             1000\n\n"
+        );
+    }
+
+    #[test]
+    fn test_overlapping_member_is_clipped() {
+        let members = [
+            member("q\t10000\t0\t1000\t+\tt\t20000\t0\t1000\t1000\t1000\t60"),
+            member("q\t10000\t900\t1900\t+\tt\t20000\t950\t1950\t1000\t1000\t60"),
+        ];
+        // Clipped by 100 (the larger, query-axis overlap) to start at q1000/t1050
+        assert_eq!(
+            chain_text(&members),
+            "chain 2000 t 20000 + 0 1950 q 10000 + 0 1900 1\n\
+             1000\t50\t0\n\
+             900\n\n"
+        );
+    }
+}
@@ -17,7 +17,12 @@
 use anyhow::Result;
 use clap::Args;
 
+use crate::filter_types::{
+    AlnIdentity, BlockLengthSource, IdentityTransform, OutputSort, OverlapAxis, RescueMetric,
+    ScoringFunction, StrandFilter,
+};
 use crate::knn_graph::SparsificationStrategy;
+use crate::paf_filter::sorted_percentile;
 
 /// Parse a number that may have a metric suffix (k/K=1e3, m/M=1e6, g/G=1e9).
 ///
@@ -60,28 +65,60 @@ pub fn parse_metric_number(s: &str) -> Result<u64, String> {
     Ok(result as u64)
 }
 
+/// Validate a `--scoring` value, keeping it as given (parsed again where used)
+fn parse_scoring(s: &str) -> Result<String, String> {
+    s.parse::<ScoringFunction>().map(|_| s.to_string())
+}
+
+/// Retention fraction of a `--scaffold-filter` value such as `0.8f`, if it is one
+pub fn scaffold_filter_fraction(s: &str) -> Option<f64> {
+    s.strip_suffix('f')?.parse().ok()
+}
+
+/// Validate a `--scaffold-filter` value: an `f`-suffixed fraction must lie in (0, 1]
+fn parse_scaffold_filter(s: &str) -> Result<String, String> {
+    if s.ends_with('f') {
+        match scaffold_filter_fraction(s) {
+            Some(fraction) if fraction > 0.0 && fraction <= 1.0 => {}
+            _ => return Err(format!("scaffold fraction must be in (0, 1], got '{s}'")),
+        }
+    }
+    Ok(s.to_string())
+}
+
+/// Parse a `--prefix-delimiter` value: exactly one character
+fn parse_prefix_delimiter(s: &str) -> Result<char, String> {
+    let mut chars = s.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => Ok(c),
+        _ => Err(format!(
+            "prefix delimiter must be a single character, got '{s}'"
+        )),
+    }
+}
+
 /// Parse an identity threshold string into a 0..=1 fraction.
 ///
 /// Accepts three forms:
 /// - A fraction in [0, 1] (e.g. `"0.9"`).
 /// - A percentage > 1 (e.g. `"90"` → 0.9).
-/// - An `aniN[+/-offset]` preset (e.g. `"ani50"`, `"ani50-2"`). Requires
-///   `ani_percentile` to be `Some(median_identity)`; typically populated
-///   by a first-pass ANI survey of the input alignments. Only the
-///   median (`ani50`) percentile is currently honored; other values are
-///   silently mapped to the median.
+/// - An `aniN[+/-offset]` preset (e.g. `"ani50"`, `"ani75-2"`): the Nth
+///   percentile (1-99) of `ani_values`, the ascending per-genome-pair ANI
+///   from a first-pass survey of the input alignments, interpolated between
+///   the two nearest ranks ([`sorted_percentile`]). `"ani"` without N means
+///   `ani50`.
 ///
 /// Returns an error for unparseable inputs or ANI-based values when
-/// `ani_percentile` is None.
-pub fn parse_identity_value(value: &str, ani_percentile: Option<f64>) -> Result<f64> {
+/// `ani_values` is None.
+pub fn parse_identity_value(value: &str, ani_values: Option<&[f64]>) -> Result<f64> {
     let lower = value.to_lowercase();
 
     if let Some(remainder) = lower.strip_prefix("ani") {
         // Parse aniN, aniN+X, or aniN-X
-        if let Some(ani_value) = ani_percentile {
+        if let Some(ani_values) = ani_values {
             if remainder.is_empty() {
                 // Default "ani" means ani50
-                return Ok(ani_value);
+                return Ok(sorted_percentile(ani_values, 50.0));
             }
 
             // Parse percentile number and optional offset
@@ -99,9 +136,17 @@ pub fn parse_identity_value(value: &str, ani_percentile: Option<f64>) -> Result<
                 (remainder, None)
             };
 
-            // Only ani50 (median) honored today; other percentiles silently
-            // fall back to it. TODO: extend for ani25/ani75 presets.
-            let _ = percentile_str;
+            // An offset alone ("ani-2") is relative to ani50
+            let percentile = if percentile_str.is_empty() {
+                50
+            } else {
+                percentile_str
+                    .parse::<u32>()
+                    .ok()
+                    .filter(|n| (1..=99).contains(n))
+                    .ok_or_else(|| anyhow::anyhow!("ANI percentile must be 1-99: {value}"))?
+            };
+            let ani_value = sorted_percentile(ani_values, f64::from(percentile));
 
             if let Some((sign, offset_str)) = offset_part {
                 let offset: f64 = offset_str
@@ -138,8 +183,8 @@ pub struct AlnArgs {
     // Alignment (FASTA input only)
     // ========================================================================
     /// Aligner to use for FASTA input
-    #[clap(long = "aligner", default_value = "fastga", value_parser = ["fastga", "wfmash"],
-           help_heading = "Alignment options")]
+    #[clap(long = "aligner", default_value = "fastga",
+           value_parser = ["fastga", "wfmash", "minimap2"], help_heading = "Alignment options")]
     pub aligner: String,
 
     /// Use FastGA aligner (shorthand for --aligner fastga)
@@ -163,14 +208,53 @@ pub struct AlnArgs {
            help_heading = "Alignment options")]
     pub fastga_frequency_multiplier: usize,
 
+    /// Extra raw arguments appended to every FastGA invocation, e.g.
+    /// `--fastga-args "-s2 -c"`. Split with shell quoting rules; NOT validated.
+    #[clap(long = "fastga-args", allow_hyphen_values = true,
+           help_heading = "Alignment options")]
+    pub fastga_args: Option<String>,
+
     /// Minimum percent identity for wfmash mapping (e.g. "90" or ANI preset "ani50-2")
     #[clap(long = "map-pct-identity", help_heading = "Alignment options")]
     pub map_pct_identity: Option<String>,
 
+    /// minimap2 preset (`-x`) for --aligner minimap2, e.g. asm5, asm10, asm20
+    #[clap(long = "minimap2-preset", default_value = "asm20", value_name = "PRESET",
+           help_heading = "Alignment options")]
+    pub minimap2_preset: String,
+
     /// Align all genome pairs separately (slower, uses more memory, but handles many genomes)
     #[clap(long = "all-pairs", help_heading = "Alignment options")]
     pub all_pairs: bool,
 
+    /// Grow an existing alignment set: align only the genome pairs involving
+    /// a genome of the FASTA input that is not yet in this PAF, and write the
+    /// filtered new records after its records. The FASTA input must also
+    /// hold the genomes already in the PAF
+    #[clap(long = "add-to", value_name = "PAF",
+           help_heading = "Alignment options")]
+    pub add_to: Option<String>,
+
+    /// Keep each finished genome pair's PAF in this directory
+    /// ({query}_vs_{target}.paf) and reuse the ones already there, so an
+    /// interrupted multi-genome run resumes where it stopped. The aligner
+    /// settings are recorded there too, and a directory written with other
+    /// settings is refused. Aligns pair by pair, as with --all-pairs
+    #[clap(long = "checkpoint-dir", value_name = "DIR",
+           help_heading = "Alignment options")]
+    pub checkpoint_dir: Option<String>,
+
+    /// Keep the per-genome FASTAs and FastGA indices (.1gdb/.gix) in this
+    /// directory and reuse the ones already there, so re-runs on the same
+    /// genomes skip indexing. A stamp next to each genome records its source
+    /// FASTA's size and modification time and the FastGA k-mer frequency; a
+    /// genome whose stamp does not match is rewritten and reindexed. Aligns
+    /// pair by pair, as with --all-pairs, so --batch-size/--batch-bytes/
+    /// --max-disk are not used
+    #[clap(long = "index-dir", value_name = "DIR",
+           help_heading = "Alignment options")]
+    pub index_dir: Option<String>,
+
     /// Maximum sequence data per batch (e.g., "50M", "2G"). Partitions
     /// genomes into batches. Stored as a raw string so library consumers
     /// (like impg's `sweepga::align_self_paf`) can pass it verbatim;
@@ -197,6 +281,12 @@ pub struct AlnArgs {
     #[clap(long = "zstd-level", default_value = "3", help_heading = "Alignment options")]
     pub zstd_level: u32,
 
+    /// Write buffer size for merging per-pair alignment output (accepts k/m/g suffix).
+    /// The merged PAF is flushed once at the end rather than after every pair.
+    #[clap(long = "io-buffer", default_value = "8M", value_parser = parse_metric_number,
+           help_heading = "Alignment options")]
+    pub io_buffer: u64,
+
     // ========================================================================
     // Basic Filtering
     // ========================================================================
@@ -215,9 +305,20 @@ pub struct AlnArgs {
     #[clap(long = "overlap", default_value = "0.95", help_heading = "Basic filtering")]
     pub overlap: f64,
 
-    /// Scoring function for plane sweep
-    #[clap(long = "scoring", default_value = "log-length-ani",
-           value_parser = ["ani", "length", "length-ani", "log-length-ani", "matches"],
+    /// Axes of the mapping plane sweep: query (best mappings per query
+    /// region, e.g. reads against an assembly), target, or both
+    /// (mappings kept by both sweeps)
+    #[clap(long = "overlap-axis", value_enum, default_value = "both",
+           help_heading = "Basic filtering")]
+    pub overlap_axis: OverlapAxis,
+
+    /// Scoring function for plane sweep: ani, length, length-ani,
+    /// log-length-ani, matches, mapq-identity, gap-compressed-identity
+    /// (indel runs count once, from cg:Z:), weighted:<a>:<b>
+    /// (a * identity + b * log length, each relative to the best competitor),
+    /// or custom:<formula> such as custom:0.7*loglen+0.3*identity (terms
+    /// identity, len or loglen; unnormalized)
+    #[clap(long = "scoring", default_value = "log-length-ani", value_parser = parse_scoring,
            help_heading = "Basic filtering")]
     pub scoring: String,
 
@@ -228,10 +329,98 @@ pub struct AlnArgs {
     #[clap(long = "min-aln-identity", default_value = "0", help_heading = "Basic filtering")]
     pub min_identity: String,
 
+    /// Correct observed identity for multiple substitutions before identity
+    /// thresholds and ANI: "jukes-cantor" or "kimura" (two-parameter,
+    /// ts/tv = 2) turn it into 1 - evolutionary distance
+    #[clap(long = "identity-transform", value_enum, default_value = "none",
+           help_heading = "Basic filtering")]
+    pub identity_transform: IdentityTransform,
+
+    /// Per query, drop mappings scoring below this fraction of the query's
+    /// best mapping score (0 = disabled). Uses the --scoring function.
+    #[clap(long = "min-relative-score", default_value = "0", help_heading = "Basic filtering")]
+    pub min_relative_score: f64,
+
+    /// MAPQ for records without a real mapping quality: assumed for input
+    /// PAF lacking column 12 and for .1aln records (default 255, meaning
+    /// "unavailable" per SAM convention), and written when converting .1aln
+    /// to PAF (default 60)
+    #[clap(long = "default-mapq", value_name = "N", help_heading = "Basic filtering")]
+    pub default_mapq: Option<u8>,
+
+    /// Reference mode: sweep each query against all target sequences at once,
+    /// keeping its best hit per region (or -n's per-query count) whichever
+    /// reference chromosome it lands on; no per-target limit
+    #[clap(long = "reference-mode", help_heading = "Basic filtering")]
+    pub reference_mode: bool,
+
+    /// Drop all mappings of a genome pair if the kept mappings cover less
+    /// than this fraction of the pair's smaller genome (0-1)
+    #[clap(long = "min-pair-coverage", value_name = "F", help_heading = "Basic filtering")]
+    pub min_pair_coverage: Option<f64>,
+
+    /// Drop mappings covering less than this fraction of their query
+    /// sequence (0-1, 0 = disabled)
+    #[clap(long = "min-query-coverage", default_value = "0", help_heading = "Basic filtering")]
+    pub min_query_coverage: f64,
+
+    /// Drop mappings covering less than this fraction of their target
+    /// sequence (0-1, 0 = disabled)
+    #[clap(long = "min-target-coverage", default_value = "0", help_heading = "Basic filtering")]
+    pub min_target_coverage: f64,
+
+    /// Drop a truncated final PAF line (no trailing newline) with a warning,
+    /// e.g. when filtering the partial output of a crashed run before
+    /// resuming it with --pairs-done/--pairs-remaining
+    #[clap(long = "repair-input", help_heading = "Basic filtering")]
+    pub repair_input: bool,
+
+    /// Fail on the first malformed PAF line (too few columns, non-numeric
+    /// coordinates), reporting its line number; by default such lines are
+    /// skipped and counted in the summary
+    #[clap(long = "strict", conflicts_with = "repair_input",
+           help_heading = "Basic filtering")]
+    pub strict: bool,
+
     /// Keep self-mappings (excluded by default)
     #[clap(long = "self", help_heading = "Basic filtering")]
     pub keep_self: bool,
 
+    /// Count mappings between haplotypes of one sample (same first PanSN
+    /// field, e.g. HG002#1 and HG002#2) as self-mappings; --self keeps them
+    #[clap(long = "self-genome", help_heading = "Basic filtering")]
+    pub self_genome: bool,
+
+    /// Keep only records whose query or target genome (PanSN prefix, e.g.
+    /// HG002#1) is in this comma-separated list; also limits the ANI stats
+    #[clap(long = "keep-pairs", value_name = "PREFIXES", value_delimiter = ',',
+           help_heading = "Basic filtering")]
+    pub keep_pairs: Vec<String>,
+
+    /// Drop records whose query or target genome (PanSN prefix) is in this
+    /// comma-separated list; also limits the ANI stats
+    #[clap(long = "exclude-pairs", value_name = "PREFIXES", value_delimiter = ',',
+           help_heading = "Basic filtering")]
+    pub exclude_pairs: Vec<String>,
+
+    /// Keep each genome pair in one direction only: records whose query
+    /// genome (PanSN prefix) sorts before the target genome. Self-pairs are
+    /// unaffected
+    #[clap(long = "canonical-pairs", help_heading = "Basic filtering")]
+    pub canonical_pairs: bool,
+
+    /// Keep only records on this strand: forward (collinear) or reverse
+    /// (inversions). Applied before the plane sweep and scaffolding
+    #[clap(long = "strand", value_enum, default_value = "both",
+           help_heading = "Basic filtering")]
+    pub strand: StrandFilter,
+
+    /// After filtering, clip kept mappings so none overlap on the query axis
+    /// (lower-scoring one is trimmed; CIGAR adjusted). Unlike every other
+    /// filter this MODIFIES coordinates instead of only keeping/dropping records.
+    #[clap(long = "trim-overlaps", help_heading = "Basic filtering")]
+    pub trim_overlaps: bool,
+
     /// Disable all filtering
     #[clap(long = "no-filter", help_heading = "Basic filtering")]
     pub no_filter: bool,
@@ -251,9 +440,11 @@ pub struct AlnArgs {
            help_heading = "Scaffolding and chaining")]
     pub scaffold_mass: u64,
 
-    /// Scaffold filter mode: "1:1" (best), "M:N" (M per query, N per target), "many" (unbounded)
+    /// Scaffold filter mode: "1:1" (best), "M:N" (M per query, N per target), "many" (unbounded),
+    /// or a fraction like "0.8f": keep ceil(0.8 × n) of the n scaffolds on each chromosome pair,
+    /// per query and per target position, in place of M:N counts
     #[clap(long = "scaffold-filter", default_value = "many:many",
-           help_heading = "Scaffolding and chaining")]
+           value_parser = parse_scaffold_filter, help_heading = "Scaffolding and chaining")]
     pub scaffold_filter: String,
 
     /// Scaffold chain overlap threshold for plane sweep filtering
@@ -266,15 +457,137 @@ pub struct AlnArgs {
            help_heading = "Scaffolding and chaining")]
     pub scaffold_dist: u64,
 
+    /// Distance from a mapping to a scaffold anchor for --scaffold-dist rescue:
+    /// euclidean, chebyshev (larger axis offset), or query-only (ignore target
+    /// drift, e.g. in tandem repeats)
+    #[clap(long = "rescue-metric", value_enum, default_value = "euclidean",
+           help_heading = "Scaffolding and chaining")]
+    pub rescue_metric: RescueMetric,
+
     /// Minimum scaffold identity threshold (0-1 fraction, 1-100%, "aniN", or defaults to --min-aln-identity)
     #[clap(long = "min-scaffold-identity", default_value = "0",
            help_heading = "Scaffolding and chaining")]
     pub min_scaffold_identity: String,
 
+    /// Minimum number of member mappings in a scaffold chain, so a single
+    /// long mapping does not count as a scaffold on its own
+    #[clap(long = "min-scaffold-count", value_name = "N", default_value = "1",
+           help_heading = "Scaffolding and chaining")]
+    pub min_scaffold_count: usize,
+
+    /// Split scaffold chains whose member mappings overlap on the query
+    /// (collapsed tandem duplications) instead of only reporting them
+    #[clap(long = "split-overlapping-members", help_heading = "Scaffolding and chaining")]
+    pub split_overlapping_members: bool,
+
+    /// Query overlap (bp) allowed between members of one scaffold chain before
+    /// the chain is flagged as a collapsed tandem duplication (accepts k/m/g suffix)
+    #[clap(long = "member-overlap-tolerance", value_name = "BP", default_value = "100",
+           value_parser = parse_metric_number, help_heading = "Scaffolding and chaining")]
+    pub member_overlap_tolerance: u64,
+
+    /// Chain collinear scaffolds across an opposite-strand segment between
+    /// them (a local inversion); joined segments are listed in iv:Z:
+    #[clap(long = "allow-inversions", help_heading = "Scaffolding and chaining")]
+    pub allow_inversions: bool,
+
+    /// Compute scaffold chain identity from the longest collinear subset of
+    /// members (a match-weighted LIS), so out-of-order members lower it
+    #[clap(long = "chain-identity-lis", help_heading = "Scaffolding and chaining")]
+    pub chain_identity_lis: bool,
+
+    /// Keep at most N rescued mappings per scaffold chain, the ones closest to its
+    /// anchors (default: no limit)
+    #[clap(
+        long = "max-rescued-per-scaffold",
+        value_name = "N",
+        help_heading = "Scaffolding and chaining"
+    )]
+    pub max_rescued_per_scaffold: Option<usize>,
+
+    /// Drop chain members whose diagonal deviates from the chain's median
+    /// diagonal by more than this many bp (anti-chimera; 0 = off, accepts k/m/g suffix)
+    #[clap(long = "max-diagonal-deviation", default_value = "0", value_parser = parse_metric_number,
+           help_heading = "Scaffolding and chaining")]
+    pub max_diagonal_deviation: u64,
+
+    /// Build scaffolds from all raw mappings instead of plane-swept ones,
+    /// leaving the competition to the scaffold sweep (as in some wfmash setups)
+    #[clap(long = "no-plane-sweep", help_heading = "Scaffolding and chaining")]
+    pub no_plane_sweep: bool,
+
     /// Output scaffold chains only (for debugging)
     #[clap(long = "scaffolds-only", help_heading = "Scaffolding and chaining")]
     pub scaffolds_only: bool,
 
+    /// Keep scaffold anchors only, rescuing no mappings whatever --scaffold-dist
+    /// says: an alias for --scaffold-dist 0. Unlike --scaffolds-only, which
+    /// outputs the chain members as soon as the scaffolds are built, the
+    /// anchors still gain reverse mappings on a forward scaffold's diagonal,
+    /// as in normal filtering
+    #[clap(long = "no-rescue", help_heading = "Scaffolding and chaining")]
+    pub no_rescue: bool,
+
+    /// Write surviving reverse-strand scaffold chains (candidate inversions),
+    /// grouped by genome pair with flanking forward chains, to this TSV file.
+    /// Requires scaffolding (--scaffold-jump > 0)
+    #[clap(long = "inversions", value_name = "FILE",
+           help_heading = "Scaffolding and chaining")]
+    pub inversions: Option<String>,
+
+    /// Write rearrangement breakpoints to this TSV file: per genome pair,
+    /// junctions between consecutive scaffold chains (along the query) that
+    /// lie on different diagonals, with the diagonal shift. Requires
+    /// scaffolding (--scaffold-jump > 0)
+    #[clap(long = "breakpoints", value_name = "FILE",
+           help_heading = "Scaffolding and chaining")]
+    pub breakpoints: Option<String>,
+
+    /// Write the merged query span of each kept scaffold chain as BED
+    /// (query, start, end, chain ID as in ch:Z:, length, strand), unlike
+    /// --scaffolds-only which outputs the member mappings. Requires
+    /// scaffolding (--scaffold-jump > 0)
+    #[clap(long = "scaffold-bed", value_name = "FILE",
+           help_heading = "Scaffolding and chaining")]
+    pub scaffold_bed: Option<String>,
+
+    /// Write rescued mappings lying near anchors of two different scaffold
+    /// chains (candidate bridges for scaffold assembly) to this TSV file.
+    /// Requires rescue (--scaffold-dist > 0)
+    #[clap(long = "report-bridges", value_name = "FILE",
+           help_heading = "Scaffolding and chaining")]
+    pub report_bridges: Option<String>,
+
+    /// Write one row per kept scaffold chain to this TSV file: its ID (as in
+    /// ch:Z:), query and target spans, strand, anchor and rescued mapping
+    /// counts, weighted identity and summed matches. Requires scaffolding
+    /// (--scaffold-jump > 0)
+    #[clap(long = "chain-report", value_name = "FILE",
+           help_heading = "Scaffolding and chaining")]
+    pub chain_report: Option<String>,
+
+    /// Tag each output record with cc:i:<id>, the connected component of the
+    /// genome graph (genomes linked by output mappings) its genome pair is in
+    #[clap(long = "component-tag", help_heading = "Scaffolding and chaining")]
+    pub component_tag: bool,
+
+    /// Also write the output chains in UCSC chain format (for liftOver) to
+    /// this file. Without scaffolding every mapping is its own chain
+    #[clap(long = "chain", value_name = "FILE",
+           help_heading = "Scaffolding and chaining")]
+    pub chain: Option<String>,
+
+    /// Write records grouped by scaffold chain, best chain (most matches)
+    /// first, instead of in input order
+    #[clap(long = "group-by-chain", help_heading = "Scaffolding and chaining")]
+    pub group_by_chain: bool,
+
+    /// Write each scaffold chain to its own <chain id>.paf in this directory:
+    /// a header line summarizing the chain, then its member records
+    #[clap(long = "scaffold-bundle", value_name = "DIR",
+           help_heading = "Scaffolding and chaining")]
+    pub scaffold_bundle: Option<String>,
+
     /// Disable scaffold-parameter adaptation to input sequence length.
     ///
     /// By default, when the FASTA average sequence length is known,
@@ -303,6 +616,11 @@ pub struct AlnArgs {
     )]
     pub sparsify: SparsificationStrategy,
 
+    /// Seed for the random pairs of --sparsify/--sparsify-pairs; the same
+    /// seed reproduces a sample, different seeds draw independent ones
+    #[clap(long = "seed", help_heading = "Advanced filtering")]
+    pub seed: Option<u64>,
+
     /// K-mer size used by the mash sketches driving tree/giant sparsification
     #[clap(long = "mash-kmer-size", default_value_t = crate::mash::DEFAULT_KMER_SIZE,
            help_heading = "Advanced filtering")]
@@ -329,6 +647,148 @@ pub struct AlnArgs {
     #[clap(long = "ani-method", default_value = "n100", help_heading = "Advanced filtering")]
     pub ani_method: String,
 
+    /// Identity exponent a of the nX-score ANI sort, identity^a * log(length):
+    /// higher values favor identity over length
+    #[clap(long = "ani-score-weight", default_value = "1", help_heading = "Advanced filtering")]
+    pub ani_score_weight: f64,
+
+    /// Logarithm base of the nX-score ANI sort (default: e)
+    #[clap(long = "ani-score-log-base", help_heading = "Advanced filtering")]
+    pub ani_score_log_base: Option<f64>,
+
+    /// Write the weighted ANI between every pair of genomes (PanSN prefixes)
+    /// of the input alignments as a TSV matrix, NA for pairs never aligned
+    #[clap(long = "ani-matrix", value_name = "FILE", help_heading = "Advanced filtering")]
+    pub ani_matrix: Option<String>,
+
+    /// Also write the number of alignments behind each --ani-matrix cell
+    #[clap(long = "ani-matrix-counts", value_name = "FILE", requires = "ani_matrix",
+           help_heading = "Advanced filtering")]
+    pub ani_matrix_counts: Option<String>,
+
+    /// Write the identity (0.5% bins) and block length (log-spaced bins)
+    /// distributions of the output mappings to this TSV file
+    #[clap(long = "histogram", value_name = "FILE", help_heading = "Advanced filtering")]
+    pub histogram: Option<String>,
+
+    /// Write the weighted ANI of every pair of sequences from different genomes
+    /// (full names, e.g. HG002#1#chr1 vs HG005#1#chr1) as TSV: query_chr,
+    /// target_chr, matches, block_len, ANI
+    #[clap(long = "ani-per-chrom", value_name = "FILE", help_heading = "Advanced filtering")]
+    pub ani_per_chrom: Option<String>,
+
+    /// Character separating PanSN name fields (SAMPLE#HAP#CONTIG), used for
+    /// genome grouping, ANI, pair selection and self-genome detection
+    #[clap(long = "prefix-delimiter", value_name = "CHAR", default_value = "#",
+           value_parser = parse_prefix_delimiter, help_heading = "Advanced filtering")]
+    pub prefix_delimiter: char,
+
+    /// Group mappings by full sequence name instead of genome prefix, so each
+    /// sequence is filtered on its own (e.g. contigs of one non-PanSN assembly)
+    #[clap(long = "skip-prefix", alias = "no-grouping",
+           help_heading = "Advanced filtering")]
+    pub skip_prefix: bool,
+
+    /// Number of `#`-separated name fields forming a genome group (for
+    /// grouping, ANI and self-genome detection): 1 = SAMPLE#, 2 = SAMPLE#HAP#.
+    /// Default: everything before the last `#` (the contig). The separator
+    /// follows --prefix-delimiter
+    #[clap(long = "prefix-depth", value_parser = clap::value_parser!(u32).range(1..),
+           help_heading = "Advanced filtering")]
+    pub prefix_depth: Option<u32>,
+
+    /// Copy leading '#'/'@' header and comment lines of PAF input to the output
+    #[clap(long = "preserve-headers", help_heading = "Advanced filtering")]
+    pub preserve_headers: bool,
+
+    /// Write forward- and reverse-strand mappings to separate files next to
+    /// --output-file (out.paf -> out.fwd.paf, out.rev.paf)
+    #[clap(long = "split-strand", help_heading = "Advanced filtering")]
+    pub split_strand: bool,
+
+    /// Write records sorted by query (name, start), target (name, start) or
+    /// score (best first) instead of in input order
+    #[clap(long = "sort-output", value_name = "KEY", value_enum,
+           conflicts_with = "group_by_chain", help_heading = "Advanced filtering")]
+    pub sort_output: Option<OutputSort>,
+
+    /// Write the sequence registry of the PAF input to FILE as TSV: each
+    /// sequence name with its ID (in order of first appearance) and length
+    #[clap(long = "dump-registry", value_name = "FILE", help_heading = "Advanced filtering")]
+    pub dump_registry: Option<String>,
+
+    /// After filtering, keep only the best-scoring mappings (per --scoring)
+    /// whose total block length fits in SIZE bp (accepts k/m/g suffix)
+    #[clap(long = "max-output-bp", value_name = "SIZE", value_parser = parse_metric_number,
+           help_heading = "Advanced filtering")]
+    pub max_output_bp: Option<u64>,
+
+    /// Tag each output record with sr:i:<n>, its rank among the competing
+    /// mappings kept at its locus by the plane sweep (0 = primary)
+    #[clap(long = "tag-sweep-rank", help_heading = "Advanced filtering")]
+    pub tag_sweep_rank: bool,
+
+    /// Keep the mappings dropped by the plane sweep and scaffold sweep,
+    /// tagging records tp:A:P (passed) or tp:A:S (secondary) like minimap2;
+    /// all other filters still apply. Cannot be combined with
+    /// --max-output-bp or --min-pair-coverage
+    #[clap(long = "mark-secondary", help_heading = "Advanced filtering")]
+    pub mark_secondary: bool,
+
+    /// Filter one query sequence at a time in a single pass, so memory is
+    /// bounded by the largest query's records. Assumes PAF input grouped by
+    /// query name (e.g. `sort -k1,1`); needs --scaffold-jump 0 and
+    /// --reference-mode or --no-plane-sweep
+    #[clap(long = "low-memory", help_heading = "Advanced filtering")]
+    pub low_memory: bool,
+
+    /// Memory budget for filtering (accepts k/m/g suffixes): PAF input
+    /// estimated to need more is filtered as with --low-memory, or rejected
+    /// up front if the other options need all records at once
+    #[clap(long = "max-memory", value_name = "SIZE", value_parser = parse_metric_number,
+           help_heading = "Advanced filtering")]
+    pub max_memory: Option<u64>,
+
+    /// Definition of block length used for thresholds, identity and scoring:
+    /// PAF column 11 or a coordinate span (aligners disagree on column 11)
+    #[clap(long = "block-length-from", value_enum, default_value = "column",
+           help_heading = "Advanced filtering")]
+    pub block_length_from: BlockLengthSource,
+
+    /// Identity definition when reading .1aln natively: alignment columns
+    /// (matches ALNtoPAF) or the query span, which ignores indels
+    #[clap(long = "aln-identity", value_enum, default_value = "columns",
+           help_heading = "Advanced filtering")]
+    pub aln_identity: AlnIdentity,
+
+    /// Coordinate base of input PAF starts: 0 (PAF standard, half-open) or
+    /// 1 (1-based inclusive, as used by some internal formats)
+    #[clap(long = "coord-base", default_value = "0",
+           value_parser = clap::value_parser!(u8).range(0..=1),
+           help_heading = "Advanced filtering")]
+    pub coord_base: u8,
+
+    /// Coordinate base of output PAF starts (0 or 1), independent of --coord-base
+    #[clap(long = "output-coord-base", default_value = "0",
+           value_parser = clap::value_parser!(u8).range(0..=1),
+           help_heading = "Advanced filtering")]
+    pub output_coord_base: u8,
+
+    /// Write reverse-strand target coordinates on the minus strand
+    /// (target_len - end, target_len - start), as in chain/axt files
+    #[clap(long = "minus-strand-coords", help_heading = "Advanced filtering")]
+    pub minus_strand_coords: bool,
+
+    /// Also write the output alignments in AXT format to this file. Needs
+    /// FASTA input (for the aligned bases) and cg:Z: CIGARs
+    #[clap(long = "axt", value_name = "FILE", help_heading = "Advanced filtering")]
+    pub axt: Option<String>,
+
+    /// Re-read the filtered PAF and fail unless it holds each passing input
+    /// record exactly once, with its input coordinates (a self-check)
+    #[clap(long = "validate-output", help_heading = "Advanced filtering")]
+    pub validate_output: bool,
+
     // ========================================================================
     // Temp directory (cross-cutting but alignment-scoped)
     // ========================================================================
@@ -395,6 +855,21 @@ pub struct AlnArgs {
     pub pair_start: usize,
 
     /// Sparsification strategy for pair selection (none, auto, random:<frac>, giant:<prob>, tree:<near>:<far>:<random>[:<kmer>])
-    #[clap(long = "sparsify-pairs", default_value = "none", help_heading = "Pair selection")]
-    pub sparsify_pairs: String,
+    /// for AGC archives and multi-genome FASTA input; FASTA genomes are then
+    /// aligned pair by pair, as with --all-pairs
+    #[clap(
+        long = "sparsify-pairs",
+        default_value = "none",
+        help_heading = "Pair selection",
+        value_parser = |s: &str| s.parse::<SparsificationStrategy>()
+    )]
+    pub sparsify_pairs: SparsificationStrategy,
+
+    /// Skip aligning genome pairs of multi-genome FASTA input whose mash
+    /// distance exceeds this (0-1); each skipped pair is listed in a leading
+    /// `#sweepga:skipped` comment line of the PAF (and of its own file with
+    /// --output-dir). PAF output only
+    #[clap(long = "max-mash-distance", value_name = "DIST",
+           help_heading = "Pair selection")]
+    pub max_mash_distance: Option<f64>,
 }
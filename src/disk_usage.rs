@@ -165,6 +165,103 @@ pub fn available_disk_bytes<P: AsRef<Path>>(path: P) -> std::io::Result<u64> {
     }
 }
 
+/// Outcome of comparing free space against what a run needs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpaceCheck {
+    /// At least the estimated peak is available
+    Sufficient,
+    /// Enough for the minimum but not the estimated peak
+    Tight,
+    /// Less than the minimum the run needs
+    Insufficient,
+}
+
+/// Classify `available` bytes against the `minimum` a run needs and its
+/// `estimated` peak
+pub fn classify_free_space(available: u64, minimum: u64, estimated: u64) -> SpaceCheck {
+    if available < minimum {
+        SpaceCheck::Insufficient
+    } else if available < estimated {
+        SpaceCheck::Tight
+    } else {
+        SpaceCheck::Sufficient
+    }
+}
+
+/// Pre-flight check of free space in the temp directory `dir`, so a full
+/// disk fails early with sizes and the path instead of a cryptic
+/// "No space left on device" mid-run. Errors below `minimum`, warns below
+/// `estimated`. Does nothing if free space cannot be queried.
+pub fn check_temp_space(dir: &Path, minimum: u64, estimated: u64) -> anyhow::Result<()> {
+    let Ok(available) = available_disk_bytes(dir) else {
+        return Ok(());
+    };
+    check_free_space(dir, available, minimum, estimated)
+}
+
+/// [`check_temp_space`] given the `available` bytes in `dir`
+fn check_free_space(
+    dir: &Path,
+    available: u64,
+    minimum: u64,
+    estimated: u64,
+) -> anyhow::Result<()> {
+    match classify_free_space(available, minimum, estimated) {
+        SpaceCheck::Sufficient => {}
+        SpaceCheck::Tight => log::warn!(
+            "[sweepga] Temp directory {} has {} free; estimated peak usage is {}",
+            dir.display(),
+            format_bytes(available),
+            format_bytes(estimated),
+        ),
+        SpaceCheck::Insufficient => anyhow::bail!(
+            "Not enough space in temp directory {}: need at least {}, {} available \
+             (estimated peak {}). Use --temp-dir to point elsewhere",
+            dir.display(),
+            format_bytes(minimum),
+            format_bytes(available),
+            format_bytes(estimated),
+        ),
+    }
+    Ok(())
+}
+
+/// Removes a temp directory and everything in it when dropped, so it is
+/// cleaned up on early returns, errors and panics as well as on success.
+/// With `keep` (`--no-cleanup`) the directory is left in place and its
+/// path logged, for debugging.
+#[must_use = "the directory is removed when this guard is dropped"]
+pub struct TempDirGuard {
+    path: std::path::PathBuf,
+    keep: bool,
+}
+
+impl TempDirGuard {
+    pub fn new(path: std::path::PathBuf, keep: bool) -> Self {
+        TempDirGuard { path, keep }
+    }
+}
+
+impl Drop for TempDirGuard {
+    fn drop(&mut self) {
+        if self.keep {
+            log::info!(
+                "[sweepga] Keeping temp directory {} (--no-cleanup)",
+                self.path.display()
+            );
+            return;
+        }
+        if let Err(e) = std::fs::remove_dir_all(&self.path) {
+            if e.kind() != std::io::ErrorKind::NotFound {
+                log::warn!(
+                    "[sweepga] Failed to remove temp directory {}: {e}",
+                    self.path.display()
+                );
+            }
+        }
+    }
+}
+
 /// Reset all counters (useful for testing)
 #[allow(dead_code)]
 pub fn reset() {
@@ -344,6 +441,65 @@ mod tests {
         reset();
     }
 
+    #[test]
+    fn test_classify_free_space() {
+        assert_eq!(classify_free_space(100, 10, 50), SpaceCheck::Sufficient);
+        assert_eq!(classify_free_space(20, 10, 50), SpaceCheck::Tight);
+        assert_eq!(classify_free_space(5, 10, 50), SpaceCheck::Insufficient);
+    }
+
+    #[test]
+    fn test_check_free_space_on_a_small_disk() {
+        let dir = Path::new("/small/tmp");
+        assert!(check_free_space(dir, 100, 10, 50).is_ok());
+        // Short of the estimate only warns
+        assert!(check_free_space(dir, 20, 10, 50).is_ok());
+
+        let err = check_free_space(dir, 5, 10, 50).unwrap_err().to_string();
+        assert!(err.contains("/small/tmp"), "{err}");
+        assert!(
+            err.contains("need at least 10 bytes, 5 bytes available"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn test_check_temp_space() {
+        let dir = tempfile::tempdir().unwrap();
+        let available = available_disk_bytes(dir.path()).unwrap();
+
+        assert!(check_temp_space(dir.path(), 0, 0).is_ok());
+        // Short of the estimate only warns
+        assert!(check_temp_space(dir.path(), 0, available + 1).is_ok());
+
+        let err = check_temp_space(dir.path(), available + (1 << 40), u64::MAX)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains(&dir.path().display().to_string()), "{err}");
+        assert!(err.contains("need at least"), "{err}");
+    }
+
+    fn fill_then_fail(dir: &Path, keep: bool) -> anyhow::Result<()> {
+        std::fs::create_dir_all(dir)?;
+        let _guard = TempDirGuard::new(dir.to_path_buf(), keep);
+        std::fs::write(dir.join("genome.fa"), ">s\nACGT\n")?;
+        anyhow::bail!("induced failure")
+    }
+
+    #[test]
+    fn test_temp_dir_guard_cleans_up_on_error() {
+        let base = tempfile::tempdir().unwrap();
+        let dir = base.path().join("sweepga_genomes_test");
+        assert!(fill_then_fail(&dir, false).is_err());
+        assert!(!dir.exists(), "temp dir should be removed after an error");
+
+        assert!(fill_then_fail(&dir, true).is_err());
+        assert!(
+            dir.join("genome.fa").exists(),
+            "--no-cleanup keeps the temp dir"
+        );
+    }
+
     #[test]
     fn test_available_disk_bytes() {
         // /tmp should always be available on Unix
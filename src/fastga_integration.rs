@@ -11,6 +11,11 @@ use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use tempfile::NamedTempFile;
 
+/// Split a raw `--fastga-args` string respecting shell quoting.
+pub fn parse_extra_args(raw: &str) -> Result<Vec<String>> {
+    shlex::split(raw).with_context(|| format!("Invalid quoting in FastGA arguments: {raw}"))
+}
+
 /// Error types specific to GIXmake index creation
 #[derive(Debug)]
 pub enum IndexCreationError {
@@ -46,7 +51,7 @@ impl std::error::Error for IndexCreationError {}
 ///
 /// The special value "ramdisk" maps to /dev/shm (Linux shared-memory tmpfs)
 /// for users who want RAM-backed temp storage without knowing the path.
-fn get_temp_dir(override_dir: Option<&str>) -> String {
+pub(crate) fn get_temp_dir(override_dir: Option<&str>) -> String {
     // First check explicit override (from --temp-dir CLI option)
     if let Some(dir) = override_dir {
         if !dir.is_empty() {
@@ -198,6 +203,7 @@ impl FromStr for FastGAPreset {
 pub struct FastGAIntegration {
     config: Config,
     temp_dir: Option<String>,
+    extra_args: Vec<String>,
 }
 
 impl FastGAIntegration {
@@ -215,7 +221,80 @@ impl FastGAIntegration {
             .adaptive_seed_cutoff(frequency)
             .build();
 
-        FastGAIntegration { config, temp_dir }
+        FastGAIntegration {
+            config,
+            temp_dir,
+            extra_args: Vec::new(),
+        }
+    }
+
+    /// Append raw arguments to every FastGA invocation (unvalidated).
+    pub fn with_extra_args(mut self, extra_args: Vec<String>) -> Self {
+        self.extra_args = extra_args;
+        self
+    }
+
+    /// Run FastGA directly on `queries` vs `targets` from `working_dir`,
+    /// returning its stdout. Used instead of the fastga-rs orchestrator when
+    /// extra arguments are set, since the orchestrator cannot forward them.
+    fn run_fastga(
+        &self,
+        kmer_freq: i32,
+        queries: &Path,
+        targets: &Path,
+        output_flag: Option<&str>,
+        working_dir: &Path,
+    ) -> Result<Vec<u8>> {
+        let fastga_bin = std::env::var("FASTGA_BIN_DIR")
+            .map(|dir| format!("{}/FastGA", dir))
+            .unwrap_or_else(|_| "FastGA".to_string());
+        let query_abs = queries
+            .canonicalize()
+            .unwrap_or_else(|_| queries.to_path_buf());
+        let target_abs = targets
+            .canonicalize()
+            .unwrap_or_else(|_| targets.to_path_buf());
+
+        let mut cmd = self.fastga_command(&fastga_bin, kmer_freq);
+        if let Some(flag) = output_flag {
+            cmd.arg(flag);
+        }
+        let output = cmd
+            .arg(&query_abs)
+            .arg(&target_abs)
+            .current_dir(working_dir)
+            .output()
+            .with_context(|| format!("Failed to run FastGA: {}", fastga_bin))?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            anyhow::bail!("FastGA failed: {}", stderr);
+        }
+        Ok(output.stdout)
+    }
+
+    /// Build a FastGA command with the configured options and any extra
+    /// user arguments. Input paths and output flags are added by the caller.
+    fn fastga_command(&self, fastga_bin: &str, kmer_freq: i32) -> std::process::Command {
+        let mut cmd = std::process::Command::new(fastga_bin);
+        cmd.arg(format!("-T{}", self.config.num_threads));
+
+        if kmer_freq != 10 {
+            cmd.arg(format!("-f{}", kmer_freq));
+        }
+
+        if self.config.min_alignment_length > 0 {
+            cmd.arg(format!("-l{}", self.config.min_alignment_length));
+        }
+
+        if let Some(min_id) = self.config.min_identity {
+            if min_id > 0.0 {
+                cmd.arg(format!("-i{:.2}", min_id));
+            }
+        }
+
+        cmd.args(&self.extra_args);
+        cmd
     }
 
     pub fn prepare_gdb(&self, fasta_path: &Path) -> Result<String> {
@@ -440,23 +519,8 @@ impl FastGAIntegration {
         let temp_aln_filename = temp_aln.file_name().unwrap();
 
         // Build FastGA command - output to .1aln format
-        let mut cmd = Command::new(&fastga_bin);
-        cmd.arg(format!("-1:{}", temp_aln_filename.to_string_lossy()))
-            .arg(format!("-T{}", self.config.num_threads));
-
-        if kmer_freq != 10 {
-            cmd.arg(format!("-f{}", kmer_freq));
-        }
-
-        if self.config.min_alignment_length > 0 {
-            cmd.arg(format!("-l{}", self.config.min_alignment_length));
-        }
-
-        if let Some(min_id) = self.config.min_identity {
-            if min_id > 0.0 {
-                cmd.arg(format!("-i{:.2}", min_id));
-            }
-        }
+        let mut cmd = self.fastga_command(&fastga_bin, kmer_freq);
+        cmd.arg(format!("-1:{}", temp_aln_filename.to_string_lossy()));
 
         // Use absolute paths so cross-directory batch alignments work correctly
         cmd.arg(&query_abs)
@@ -494,19 +558,7 @@ impl FastGAIntegration {
                 );
 
                 // Re-run FastGA with direct PAF output (no CIGAR)
-                let mut fallback_cmd = Command::new(&fastga_bin);
-                fallback_cmd.arg(format!("-T{}", self.config.num_threads));
-                if kmer_freq != 10 {
-                    fallback_cmd.arg(format!("-f{}", kmer_freq));
-                }
-                if self.config.min_alignment_length > 0 {
-                    fallback_cmd.arg(format!("-l{}", self.config.min_alignment_length));
-                }
-                if let Some(min_id) = self.config.min_identity {
-                    if min_id > 0.0 {
-                        fallback_cmd.arg(format!("-i{:.2}", min_id));
-                    }
-                }
+                let mut fallback_cmd = self.fastga_command(&fastga_bin, kmer_freq);
                 fallback_cmd
                     .arg(&query_abs)
                     .arg(&target_abs)
@@ -611,9 +663,35 @@ impl FastGAIntegration {
         };
 
         // Run alignment - FastGA creates BOTH .1aln and .1gdb files
-        let aln_path = orchestrator
-            .align_to_1aln(queries, targets)
-            .map_err(|e| anyhow::anyhow!("Failed to run FastGA alignment: {e}"))?;
+        let aln_path = if self.extra_args.is_empty() {
+            orchestrator
+                .align_to_1aln(queries, targets)
+                .map_err(|e| anyhow::anyhow!("Failed to run FastGA alignment: {e}"))?
+        } else {
+            let working_dir = Path::new(&orchestrator.temp_dir)
+                .canonicalize()
+                .context("Failed to resolve FastGA temp directory")?;
+            let aln_name = format!(
+                "_tmp_{}_{}.1aln",
+                std::process::id(),
+                std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .map(|d| d.as_nanos())
+                    .unwrap_or(0)
+            );
+            let aln_path = working_dir.join(&aln_name);
+            if let Err(e) = self.run_fastga(
+                kmer_freq,
+                queries,
+                targets,
+                Some(&format!("-1:{aln_name}")),
+                &working_dir,
+            ) {
+                let _ = std::fs::remove_file(&aln_path);
+                return Err(e);
+            }
+            aln_path.to_string_lossy().into_owned()
+        };
 
         // Derive .1gdb path from .1aln path
         let gdb_path = aln_path
@@ -742,13 +820,18 @@ impl FastGAIntegration {
         });
 
         // Run alignment with existing indices (returns PAF bytes directly)
-        let paf_output = orchestrator
-            .align_with_existing_indices(queries, targets)
-            .map_err(|e| anyhow::anyhow!("Failed to run FastGA alignment: {e}"))?;
+        let paf_output = if self.extra_args.is_empty() {
+            orchestrator
+                .align_with_existing_indices(queries, targets)
+                .map_err(|e| anyhow::anyhow!("Failed to run FastGA alignment: {e}"))
+        } else {
+            self.run_fastga(kmer_freq, queries, targets, None, &index_dir)
+        };
 
         // Stop the monitor thread (it will cleanup the index bytes on exit)
         stop_flag.store(true, Ordering::Relaxed);
         let _ = monitor_handle.join();
+        let paf_output = paf_output?;
 
         // Best-effort cleanup of the GDB/GIX artifacts we created upfront.
         let _ = Self::cleanup_all(&q_gdb);
@@ -791,3 +874,148 @@ impl crate::aligner::Aligner for FastGAIntegration {
         "fastga"
     }
 }
+
+/// Convert a .1aln file to PAF with `cg:Z:` extended CIGARs (`=`/`X`/`I`/`D`)
+/// reconstructed from the trace points, via `ALNtoPAF -x`.
+///
+/// The native `AlnReader` only exposes coordinates and diff counts, so it
+/// cannot emit CIGARs. Use this path whenever the PAF may be converted back
+/// to .1aln or consumed by CIGAR-aware scoring.
+pub fn aln_to_paf_with_cigar(aln_path: &Path, threads: usize) -> Result<Vec<u8>> {
+    let alnto_paf_bin = crate::binary_paths::get_embedded_binary_path("ALNtoPAF")?;
+
+    let output = std::process::Command::new(&alnto_paf_bin)
+        .arg("-x") // Generate CIGAR with X/= operators
+        .arg(format!("-T{threads}"))
+        .arg(aln_path)
+        .output()
+        .with_context(|| format!("Failed to run ALNtoPAF: {}", alnto_paf_bin.display()))?;
+
+    if !output.status.success() {
+        anyhow::bail!(
+            "ALNtoPAF conversion failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+
+    Ok(output.stdout)
+}
+
+/// Like [`aln_to_paf_with_cigar`], but hands each PAF line to `each_line` as
+/// ALNtoPAF writes it instead of buffering the whole output.
+pub fn for_each_aln_paf_line(
+    aln_path: &Path,
+    threads: usize,
+    mut each_line: impl FnMut(&str) -> Result<()>,
+) -> Result<()> {
+    use std::io::{BufRead, Read};
+
+    let alnto_paf_bin = crate::binary_paths::get_embedded_binary_path("ALNtoPAF")?;
+
+    let mut child = std::process::Command::new(&alnto_paf_bin)
+        .arg("-x") // Generate CIGAR with X/= operators
+        .arg(format!("-T{threads}"))
+        .arg(aln_path)
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .spawn()
+        .with_context(|| format!("Failed to run ALNtoPAF: {}", alnto_paf_bin.display()))?;
+
+    // Drain stderr on its own thread so a chatty ALNtoPAF cannot block on it
+    let mut stderr = child.stderr.take().expect("stderr is piped");
+    let stderr_reader = std::thread::spawn(move || {
+        let mut message = String::new();
+        let _ = stderr.read_to_string(&mut message);
+        message
+    });
+
+    let stdout = std::io::BufReader::new(child.stdout.take().expect("stdout is piped"));
+    let mut result = Ok(());
+    for line in stdout.lines() {
+        result = line
+            .context("Failed to read ALNtoPAF output")
+            .and_then(|line| each_line(&line));
+        if result.is_err() {
+            let _ = child.kill();
+            break;
+        }
+    }
+
+    let status = child.wait().context("Failed to wait for ALNtoPAF")?;
+    let message = stderr_reader.join().unwrap_or_default();
+    result?;
+    if !status.success() {
+        anyhow::bail!("ALNtoPAF conversion failed: {message}");
+    }
+    Ok(())
+}
+
+/// Convert a PAF file to .1aln via `PAFtoALN`, returning the path of the
+/// written .1aln.
+///
+/// `paf_path` must end in `.paf` (PAFtoALN appends the extension itself and
+/// writes `<stem>.1aln` next to it). `cg:Z:` CIGARs with `=`/`X` operators are
+/// turned into trace points, so a PAF produced by [`aln_to_paf_with_cigar`]
+/// round-trips with its differences intact. `cs:Z:` tags are not read by
+/// PAFtoALN; convert them to `cg:Z:` first. `sources` are the FASTA or .1gdb
+/// files providing sequence metadata.
+pub fn paf_to_aln(
+    paf_path: &Path,
+    sources: &[std::path::PathBuf],
+    threads: usize,
+    quiet: bool,
+) -> Result<std::path::PathBuf> {
+    let paftoaln_bin = crate::binary_paths::get_embedded_binary_path("PAFtoALN")
+        .map_err(|_| anyhow::anyhow!("PAFtoALN tool not found. Cannot convert PAF to .1aln. Use --paf flag to output PAF format instead."))?;
+
+    let stem = paf_path.with_extension("");
+
+    let mut cmd = std::process::Command::new(&paftoaln_bin);
+    cmd.arg(format!("-T{threads}")).arg(&stem);
+    for source in sources {
+        cmd.arg(source);
+    }
+
+    // Suppress PAFtoALN's normal output unless in verbose mode
+    if quiet {
+        cmd.stdout(std::process::Stdio::null())
+            .stderr(std::process::Stdio::null());
+    }
+
+    let status = cmd.status()?;
+    if !status.success() {
+        anyhow::bail!("PAFtoALN conversion failed");
+    }
+
+    // PAFtoALN writes <stem>.1aln; append rather than replace so dotted stems survive
+    let mut aln_path = stem.into_os_string();
+    aln_path.push(".1aln");
+    Ok(aln_path.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_extra_args_respects_quoting() {
+        let args = parse_extra_args("-s2 -P'/tmp/my dir'").unwrap();
+        assert_eq!(args, vec!["-s2", "-P/tmp/my dir"]);
+        assert!(parse_extra_args("-P'unterminated").is_err());
+    }
+
+    #[test]
+    fn test_extra_args_forwarded_to_fastga_command() {
+        let fastga = FastGAIntegration::new(10, 2, 100, None)
+            .with_extra_args(parse_extra_args("-s2").unwrap());
+        let cmd = fastga.fastga_command("FastGA", 10);
+        let args: Vec<String> = cmd
+            .get_args()
+            .map(|a| a.to_string_lossy().into_owned())
+            .collect();
+
+        assert!(args.contains(&"-T2".to_string()));
+        assert!(args.contains(&"-l100".to_string()));
+        assert_eq!(args.last().map(String::as_str), Some("-s2"));
+    }
+}
@@ -863,7 +863,7 @@ impl StreamFilter {
                     meta.target_end,
                     meta.block_length,  // matches
                     meta.block_length,  // block length
-                    255  // mapping quality
+                    self.config.default_mapq  // mapping quality
                 )?;
             }
             writer.flush()?;
@@ -11,6 +11,128 @@ pub enum ScoringFunction {
     LengthIdentity,    // Length * Identity
     LogLengthIdentity, // log(Length) * Identity (default)
     Matches,           // Total matches only (gap-neutral)
+    MapqIdentity,      // Identity * MAPQ weight (prefers confidently placed mappings)
+    /// Matches / (matches + mismatches + gap opens) from the `cg:Z:` CIGAR,
+    /// like minimap2's `de:f:`; block identity for records without one
+    GapCompressedIdentity,
+    /// `identity * normalized_identity + length * normalized_log_length`,
+    /// normalized against the best of each competing group (`weighted:<a>:<b>`)
+    Weighted {
+        identity: f64,
+        length: f64,
+    },
+    /// `len_weight * length + id_weight * identity`, with `ln(length)` when
+    /// `use_log`, unnormalized (`custom:0.7*loglen+0.3*identity`)
+    Custom {
+        len_weight: f64,
+        id_weight: f64,
+        use_log: bool,
+    },
+}
+
+impl std::str::FromStr for ScoringFunction {
+    type Err = String;
+
+    /// Parse a `--scoring` value (accepts both `ani` and `identity` spellings)
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "ani" | "identity" => Ok(ScoringFunction::Identity),
+            "length" => Ok(ScoringFunction::Length),
+            "length-ani" | "length-identity" => Ok(ScoringFunction::LengthIdentity),
+            "log-length-ani" | "log-length-identity" => Ok(ScoringFunction::LogLengthIdentity),
+            "matches" => Ok(ScoringFunction::Matches),
+            "mapq-ani" | "mapq-identity" => Ok(ScoringFunction::MapqIdentity),
+            "gap-compressed-ani" | "gap-compressed-identity" => {
+                Ok(ScoringFunction::GapCompressedIdentity)
+            }
+            _ => {
+                if let Some(weights) = s.strip_prefix("weighted:") {
+                    parse_weights(weights)
+                } else if let Some(formula) = s.strip_prefix("custom:") {
+                    parse_custom(formula)
+                } else {
+                    Err(format!("Unknown scoring function '{s}'"))
+                }
+            }
+        }
+    }
+}
+
+impl ScoringFunction {
+    /// The identity this function scores by: `gap_compressed` for
+    /// `GapCompressedIdentity`, `identity` (block identity) otherwise
+    pub fn scored_identity(self, identity: f64, gap_compressed: f64) -> f64 {
+        if self == ScoringFunction::GapCompressedIdentity {
+            gap_compressed
+        } else {
+            identity
+        }
+    }
+}
+
+/// Parse the `<a>:<b>` of `weighted:<a>:<b>` (non-negative, not both zero)
+fn parse_weights(weights: &str) -> Result<ScoringFunction, String> {
+    let invalid = || format!("Invalid weights '{weights}': expected weighted:<a>:<b>");
+    let (a, b) = weights.split_once(':').ok_or_else(invalid)?;
+    let identity: f64 = a.parse().map_err(|_| invalid())?;
+    let length: f64 = b.parse().map_err(|_| invalid())?;
+    let valid = |weight: f64| weight.is_finite() && weight >= 0.0;
+    if !valid(identity) || !valid(length) || identity + length == 0.0 {
+        return Err(format!(
+            "Weights in '{weights}' must be non-negative and not both zero"
+        ));
+    }
+    Ok(ScoringFunction::Weighted { identity, length })
+}
+
+/// Parse the formula of `custom:<formula>`: a sum of `[<weight>*]<term>`
+/// with terms `identity` (or `ani`) and one of `len`/`length` or `loglen`,
+/// each at most once. Weights default to 1 and must be non-negative, and
+/// not all zero.
+fn parse_custom(formula: &str) -> Result<ScoringFunction, String> {
+    let invalid = |why: &str| format!("Invalid custom scoring '{formula}': {why}");
+    let mut len_weight = None;
+    let mut id_weight = None;
+    let mut use_log = false;
+    for term in formula.split('+').map(str::trim) {
+        let (weight, name) = match term.split_once('*') {
+            Some((weight, name)) => {
+                let weight: f64 = weight
+                    .trim()
+                    .parse()
+                    .map_err(|_| invalid(&format!("bad weight in '{term}'")))?;
+                (weight, name.trim())
+            }
+            None => (1.0, term),
+        };
+        if !weight.is_finite() || weight < 0.0 {
+            return Err(invalid("weights must be non-negative"));
+        }
+        let slot = match name {
+            "identity" | "ani" => &mut id_weight,
+            "len" | "length" | "loglen" => {
+                use_log = name == "loglen";
+                &mut len_weight
+            }
+            _ => {
+                return Err(invalid(&format!(
+                    "unknown term '{name}' (expected identity, len or loglen)"
+                )))
+            }
+        };
+        if slot.replace(weight).is_some() {
+            return Err(invalid(&format!("{name} appears more than once")));
+        }
+    }
+    let (len_weight, id_weight) = (len_weight.unwrap_or(0.0), id_weight.unwrap_or(0.0));
+    if len_weight + id_weight == 0.0 {
+        return Err(invalid("weights must not all be zero"));
+    }
+    Ok(ScoringFunction::Custom {
+        len_weight,
+        id_weight,
+        use_log,
+    })
 }
 
 /// Filtering mode
@@ -20,3 +142,186 @@ pub enum FilterMode {
     OneToMany,  // 1:N - best mapping per query, N per target
     ManyToMany, // N:N - N mappings per query and per target
 }
+
+/// Definition of a record's block length (`--block-length-from`), used for
+/// length thresholds, identity and scoring. Aligners disagree on whether PAF
+/// column 11 includes gaps, so it can be replaced by a coordinate span.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum BlockLengthSource {
+    /// PAF column 11 as written by the aligner
+    #[default]
+    Column,
+    /// Query end - query start
+    QuerySpan,
+    /// Target end - target start
+    TargetSpan,
+    /// The larger of the query and target spans
+    MaxSpan,
+}
+
+impl BlockLengthSource {
+    /// Block length of a record from its column 11 value and coordinate spans
+    pub fn block_length(self, column: u64, query_span: u64, target_span: u64) -> u64 {
+        match self {
+            BlockLengthSource::Column => column,
+            BlockLengthSource::QuerySpan => query_span,
+            BlockLengthSource::TargetSpan => target_span,
+            BlockLengthSource::MaxSpan => query_span.max(target_span),
+        }
+    }
+}
+
+/// Distance between a mapping and a scaffold anchor when rescuing mappings
+/// (`--rescue-metric`), from the distances between their centers along the
+/// query and target axes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum RescueMetric {
+    /// sqrt(query² + target²)
+    #[default]
+    Euclidean,
+    /// max(query, target)
+    Chebyshev,
+    /// Query axis only, ignoring drift along the target (tandem repeats)
+    QueryOnly,
+}
+
+impl RescueMetric {
+    /// Distance for center offsets `query_diff` and `target_diff`
+    pub fn distance(self, query_diff: u64, target_diff: u64) -> u64 {
+        match self {
+            RescueMetric::Euclidean => {
+                ((query_diff * query_diff + target_diff * target_diff) as f64).sqrt() as u64
+            }
+            RescueMetric::Chebyshev => query_diff.max(target_diff),
+            RescueMetric::QueryOnly => query_diff,
+        }
+    }
+}
+
+/// Axes along which the mapping plane sweep limits overlapping mappings
+/// (`--overlap-axis`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum OverlapAxis {
+    /// Best mappings per query region, whatever the target crowding
+    Query,
+    /// Best mappings per target region, whatever the query crowding
+    Target,
+    /// Mappings kept by both the query and the target sweeps
+    #[default]
+    Both,
+}
+
+impl OverlapAxis {
+    pub fn sweeps_query(self) -> bool {
+        self != OverlapAxis::Target
+    }
+
+    pub fn sweeps_target(self) -> bool {
+        self != OverlapAxis::Query
+    }
+}
+
+/// Order of the filtered PAF records (`--sort-output`); ties keep input order
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum OutputSort {
+    /// By query name, then query start and end
+    Query,
+    /// By target name, then target start and end
+    Target,
+    /// By mapping score (the --scoring function), best first
+    Score,
+}
+
+/// Strand of the records kept (`--strand`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum StrandFilter {
+    /// Both strands
+    #[default]
+    Both,
+    /// `+` records only (collinear alignments)
+    Forward,
+    /// `-` records only (inversions)
+    Reverse,
+}
+
+impl StrandFilter {
+    /// Whether a record on `strand` (`+` or `-`) is kept
+    pub fn accepts(self, strand: char) -> bool {
+        match self {
+            StrandFilter::Both => true,
+            StrandFilter::Forward => strand == '+',
+            StrandFilter::Reverse => strand == '-',
+        }
+    }
+}
+
+/// How the native .1aln reader derives matches and alignment length from a
+/// record's spans and edit distance (`--aln-identity`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum AlnIdentity {
+    /// Alignment columns (matches + diffs): the span difference counts as
+    /// indels and the remaining diffs as mismatches, as in ALNtoPAF's CIGARs
+    #[default]
+    Columns,
+    /// (query span - diffs) / query span, ignoring indels (the old fast path)
+    QuerySpan,
+}
+
+impl AlnIdentity {
+    /// `(matches, alignment length)` of a record
+    pub fn matches_and_length(self, query_span: u64, target_span: u64, diffs: u64) -> (u64, u64) {
+        match self {
+            AlnIdentity::Columns => {
+                let indels = query_span.abs_diff(target_span);
+                let mismatches = diffs.saturating_sub(indels);
+                let matches = query_span.min(target_span).saturating_sub(mismatches);
+                (matches, matches + diffs)
+            }
+            AlnIdentity::QuerySpan => (query_span.saturating_sub(diffs), query_span),
+        }
+    }
+}
+
+/// Model correcting observed identity for multiple substitutions per site
+/// (`--identity-transform`). With `p = 1 - identity` the observed fraction of
+/// differing sites, each model gives an evolutionary distance `d` and the
+/// corrected identity `1 - d`; it is 0 once the model saturates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum IdentityTransform {
+    /// Observed identity, uncorrected
+    #[default]
+    None,
+    /// Jukes-Cantor: `d = -3/4 ln(1 - 4p/3)`
+    JukesCantor,
+    /// Kimura two-parameter, assuming a transition/transversion ratio of 2
+    /// (transitions `P = 2p/3`, transversions `Q = p/3`):
+    /// `d = -1/2 ln(1 - 2P - Q) - 1/4 ln(1 - 2Q)`
+    Kimura,
+}
+
+impl IdentityTransform {
+    /// Corrected identity for an observed identity in [0, 1]
+    pub fn apply(self, identity: f64) -> f64 {
+        let p = (1.0 - identity).clamp(0.0, 1.0);
+        let distance = match self {
+            IdentityTransform::None => return identity,
+            IdentityTransform::JukesCantor => {
+                let arg = 1.0 - 4.0 * p / 3.0;
+                if arg <= 0.0 {
+                    return 0.0;
+                }
+                -0.75 * arg.ln()
+            }
+            IdentityTransform::Kimura => {
+                let (transitions, transversions) = (2.0 * p / 3.0, p / 3.0);
+                let a = 1.0 - 2.0 * transitions - transversions;
+                let b = 1.0 - 2.0 * transversions;
+                if a <= 0.0 || b <= 0.0 {
+                    return 0.0;
+                }
+                -0.5 * a.ln() - 0.25 * b.ln()
+            }
+        };
+        (1.0 - distance).max(0.0)
+    }
+}
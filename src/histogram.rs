@@ -0,0 +1,156 @@
+//! Identity and length histograms of the output mappings (`--histogram`)
+//!
+//! The file holds two tab-separated tables, separated by a blank line: the
+//! identities in 0.5% bins, then the block lengths in log-spaced bins (four
+//! per decade). Each row is `bin_start bin_end count` with `bin_end`
+//! exclusive; bins run from the lowest to the highest non-empty one, empty
+//! ones included, so the tables plot as is.
+
+use anyhow::{Context, Result};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+/// Width of an identity bin (0.5%)
+const IDENTITY_BIN_WIDTH: f64 = 0.005;
+/// Length bins per factor of ten
+const LENGTH_BINS_PER_DECADE: u32 = 4;
+
+/// Counts in consecutive bins, the first one numbered `first`
+struct Bins {
+    first: usize,
+    counts: Vec<usize>,
+}
+
+impl Bins {
+    fn from_indices(indices: impl Iterator<Item = usize>) -> Self {
+        let indices: Vec<usize> = indices.collect();
+        let (Some(&first), Some(&last)) = (indices.iter().min(), indices.iter().max()) else {
+            return Bins {
+                first: 0,
+                counts: Vec::new(),
+            };
+        };
+        let mut counts = vec![0; last - first + 1];
+        for index in indices {
+            counts[index - first] += 1;
+        }
+        Bins { first, counts }
+    }
+
+    /// `(bin number, count)` of every bin
+    fn iter(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        (self.first..).zip(self.counts.iter().copied())
+    }
+}
+
+/// Identity bin of `identity`; 100% joins the top bin
+fn identity_bin(identity: f64) -> usize {
+    let top = (1.0 / IDENTITY_BIN_WIDTH).round() as usize - 1;
+    ((identity.clamp(0.0, 1.0) / IDENTITY_BIN_WIDTH) as usize).min(top)
+}
+
+/// Length bin of `length`: `floor(log10(length) * LENGTH_BINS_PER_DECADE)`
+fn length_bin(length: u64) -> usize {
+    let mut bin = ((length.max(1) as f64).log10() * LENGTH_BINS_PER_DECADE as f64) as usize;
+    // Guard against log10 rounding across a bin edge
+    while bin > 0 && length_bin_start(bin) > length {
+        bin -= 1;
+    }
+    while length_bin_start(bin + 1) <= length {
+        bin += 1;
+    }
+    bin
+}
+
+/// Smallest length in bin `bin`
+fn length_bin_start(bin: usize) -> u64 {
+    10f64
+        .powf(bin as f64 / LENGTH_BINS_PER_DECADE as f64)
+        .ceil() as u64
+}
+
+/// Write the identity and length histograms of `records`, given as
+/// `(identity, block length)` pairs, to `path`
+pub fn write_histogram(path: &Path, records: impl Iterator<Item = (f64, u64)>) -> Result<()> {
+    let (identities, lengths): (Vec<f64>, Vec<u64>) = records.unzip();
+    let file = File::create(path)
+        .with_context(|| format!("Failed to create histogram file {}", path.display()))?;
+    let mut writer = BufWriter::new(file);
+
+    writeln!(writer, "#identity_start\tidentity_end\tcount")?;
+    let identity_bins = Bins::from_indices(identities.into_iter().map(identity_bin));
+    for (bin, count) in identity_bins.iter() {
+        writeln!(
+            writer,
+            "{:.3}\t{:.3}\t{count}",
+            bin as f64 * IDENTITY_BIN_WIDTH,
+            (bin + 1) as f64 * IDENTITY_BIN_WIDTH
+        )?;
+    }
+
+    writeln!(writer, "\n#length_start\tlength_end\tcount")?;
+    let length_bins = Bins::from_indices(lengths.into_iter().map(length_bin));
+    for (bin, count) in length_bins.iter() {
+        writeln!(
+            writer,
+            "{}\t{}\t{count}",
+            length_bin_start(bin),
+            length_bin_start(bin + 1)
+        )?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bins_cover_edges() {
+        assert_eq!(identity_bin(0.0), 0);
+        assert_eq!(identity_bin(0.9949), 198);
+        assert_eq!(identity_bin(0.995), 199);
+        assert_eq!(identity_bin(1.0), 199);
+
+        assert_eq!(length_bin(1), 0);
+        assert_eq!(length_bin(999), 11);
+        assert_eq!(length_bin(1000), 12);
+        assert_eq!(length_bin_start(12), 1000);
+        assert_eq!(length_bin_start(13), 1779);
+    }
+
+    #[test]
+    fn test_histogram_tables() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("hist.tsv");
+        let records = [(0.991, 1000), (0.999, 1500), (0.98, 10_000)];
+        write_histogram(&path, records.into_iter()).unwrap();
+
+        let text = std::fs::read_to_string(&path).unwrap();
+        let (identity, length) = text.split_once("\n\n").unwrap();
+        let identity: Vec<&str> = identity.lines().collect();
+        assert_eq!(identity[0], "#identity_start\tidentity_end\tcount");
+        assert_eq!(
+            &identity[1..],
+            [
+                "0.980\t0.985\t1",
+                "0.985\t0.990\t0",
+                "0.990\t0.995\t1",
+                "0.995\t1.000\t1"
+            ]
+        );
+        let length: Vec<&str> = length.lines().collect();
+        assert_eq!(
+            &length[1..],
+            [
+                "1000\t1779\t2",
+                "1779\t3163\t0",
+                "3163\t5624\t0",
+                "5624\t10000\t0",
+                "10000\t17783\t1"
+            ]
+        );
+    }
+}
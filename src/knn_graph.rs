@@ -224,6 +224,25 @@ pub fn extract_tree_pairs(
     k_farthest: usize,
     random_fraction: f64,
     mash_params: &MashParams,
+) -> Vec<(usize, usize)> {
+    extract_tree_pairs_with_seed(
+        sequences,
+        k_nearest,
+        k_farthest,
+        random_fraction,
+        mash_params,
+        None,
+    )
+}
+
+/// [`extract_tree_pairs`] with its random pairs drawn for `seed`
+pub fn extract_tree_pairs_with_seed(
+    sequences: &[Vec<u8>],
+    k_nearest: usize,
+    k_farthest: usize,
+    random_fraction: f64,
+    mash_params: &MashParams,
+    seed: Option<u64>,
 ) -> Vec<(usize, usize)> {
     if sequences.len() < 2 {
         return Vec::new();
@@ -236,7 +255,13 @@ pub fn extract_tree_pairs(
         mash_params.sketch_size,
     );
 
-    extract_tree_pairs_from_matrix(&distance_matrix, k_nearest, k_farthest, random_fraction)
+    extract_tree_pairs_from_matrix_with_seed(
+        &distance_matrix,
+        k_nearest,
+        k_farthest,
+        random_fraction,
+        seed,
+    )
 }
 
 /// Extract tree pairs from pre-computed distance matrix
@@ -245,6 +270,23 @@ pub fn extract_tree_pairs_from_matrix(
     k_nearest: usize,
     k_farthest: usize,
     random_fraction: f64,
+) -> Vec<(usize, usize)> {
+    extract_tree_pairs_from_matrix_with_seed(
+        distance_matrix,
+        k_nearest,
+        k_farthest,
+        random_fraction,
+        None,
+    )
+}
+
+/// [`extract_tree_pairs_from_matrix`] with its random pairs drawn for `seed`
+pub fn extract_tree_pairs_from_matrix_with_seed(
+    distance_matrix: &[Vec<f64>],
+    k_nearest: usize,
+    k_farthest: usize,
+    random_fraction: f64,
+    seed: Option<u64>,
 ) -> Vec<(usize, usize)> {
     let n = distance_matrix.len();
     if n < 2 {
@@ -264,7 +306,7 @@ pub fn extract_tree_pairs_from_matrix(
     }
 
     if random_fraction > 0.0 {
-        let random_pairs = generate_random_pairs(n, random_fraction);
+        let random_pairs = generate_random_pairs_with_seed(n, random_fraction, seed);
         all_pairs.extend(random_pairs);
     }
 
@@ -288,6 +330,26 @@ pub fn extract_tree_pairs_separated(
     k_farthest: usize,
     random_fraction: f64,
     mash_params: &MashParams,
+) -> (Vec<(usize, usize)>, Vec<(usize, usize)>) {
+    extract_tree_pairs_separated_with_seed(
+        sequences,
+        k_nearest,
+        k_farthest,
+        random_fraction,
+        mash_params,
+        None,
+    )
+}
+
+/// [`extract_tree_pairs_separated`] with its random pairs drawn for `seed`
+#[allow(clippy::type_complexity)]
+pub fn extract_tree_pairs_separated_with_seed(
+    sequences: &[Vec<u8>],
+    k_nearest: usize,
+    k_farthest: usize,
+    random_fraction: f64,
+    mash_params: &MashParams,
+    seed: Option<u64>,
 ) -> (Vec<(usize, usize)>, Vec<(usize, usize)>) {
     if sequences.len() < 2 {
         return (Vec::new(), Vec::new());
@@ -321,7 +383,7 @@ pub fn extract_tree_pairs_separated(
 
     // Generate random pairs
     let mut random_pairs = if random_fraction > 0.0 {
-        generate_random_pairs(sequences.len(), random_fraction)
+        generate_random_pairs_with_seed(sequences.len(), random_fraction, seed)
     } else {
         Vec::new()
     };
@@ -367,20 +429,34 @@ fn build_knn_graph(
     pairs
 }
 
+/// SplitMix64 finalizer: a fixed bit mixer, so sampled pairs stay the same
+/// across Rust releases (unlike `DefaultHasher`)
+fn splitmix64(x: u64) -> u64 {
+    let mut z = x.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
 /// Generate random pairs with deterministic hashing based on indices
 pub fn generate_random_pairs(n: usize, fraction: f64) -> Vec<(usize, usize)> {
-    use std::collections::hash_map::DefaultHasher;
-    use std::hash::Hasher;
+    generate_random_pairs_with_seed(n, fraction, None)
+}
 
+/// [`generate_random_pairs`] salted with `seed` if given: the same seed
+/// always draws the same pairs, and different seeds draw independent samples
+pub fn generate_random_pairs_with_seed(
+    n: usize,
+    fraction: f64,
+    seed: Option<u64>,
+) -> Vec<(usize, usize)> {
     let mut pairs = Vec::new();
     let threshold = (fraction * u64::MAX as f64) as u64;
+    let salt = seed.map_or(0, splitmix64);
 
     for i in 0..n {
         for j in i + 1..n {
-            let mut hasher = DefaultHasher::new();
-            hasher.write_usize(i);
-            hasher.write_usize(j);
-            let hash = hasher.finish();
+            let hash = splitmix64(splitmix64(salt ^ i as u64) ^ j as u64);
 
             if hash <= threshold {
                 pairs.push((i, j));
@@ -412,6 +488,17 @@ pub fn select_pairs(
     sequences: Option<&[Vec<u8>]>,
     strategy: &SparsificationStrategy,
     mash_params: &MashParams,
+) -> Vec<(usize, usize)> {
+    select_pairs_with_seed(sample_count, sequences, strategy, mash_params, None)
+}
+
+/// [`select_pairs`] with random pairs drawn for `seed` (`--seed`)
+pub fn select_pairs_with_seed(
+    sample_count: usize,
+    sequences: Option<&[Vec<u8>]>,
+    strategy: &SparsificationStrategy,
+    mash_params: &MashParams,
+    seed: Option<u64>,
 ) -> Vec<(usize, usize)> {
     match strategy {
         SparsificationStrategy::None => {
@@ -436,22 +523,25 @@ pub fn select_pairs(
                 pairs
             } else if sample_count <= 50 {
                 // Medium: giant-component pair selection
-                select_pairs(
+                select_pairs_with_seed(
                     sample_count,
                     sequences,
                     &SparsificationStrategy::Connectivity(0.99),
                     mash_params,
+                    seed,
                 )
             } else {
                 // Large: tree-based k-NN sampling
                 if let Some(seqs) = sequences {
-                    extract_tree_pairs(seqs, 5, 2, 0.05, mash_params)
+                    extract_tree_pairs_with_seed(seqs, 5, 2, 0.05, mash_params, seed)
                 } else {
-                    generate_random_pairs(sample_count, 0.1)
+                    generate_random_pairs_with_seed(sample_count, 0.1, seed)
                 }
             }
         }
-        SparsificationStrategy::Random(fraction) => generate_random_pairs(sample_count, *fraction),
+        SparsificationStrategy::Random(fraction) => {
+            generate_random_pairs_with_seed(sample_count, *fraction, seed)
+        }
         SparsificationStrategy::Connectivity(prob) => {
             // Giant component: use enough pairs to guarantee connectivity with given probability
             // For n nodes, we need roughly n*ln(n) / 2 edges for connectivity
@@ -465,17 +555,24 @@ pub fn select_pairs(
             if let Some(seqs) = sequences {
                 // Use tree sampling with enough nearest neighbors
                 let k_nearest = ((fraction * sample_count as f64).ceil() as usize).max(2);
-                extract_tree_pairs(seqs, k_nearest, 1, 0.01, mash_params)
+                extract_tree_pairs_with_seed(seqs, k_nearest, 1, 0.01, mash_params, seed)
             } else {
-                generate_random_pairs(sample_count, fraction)
+                generate_random_pairs_with_seed(sample_count, fraction, seed)
             }
         }
         SparsificationStrategy::TreeSampling(k_nearest, k_farthest, random_frac) => {
             if let Some(seqs) = sequences {
-                extract_tree_pairs(seqs, *k_nearest, *k_farthest, *random_frac, mash_params)
+                extract_tree_pairs_with_seed(
+                    seqs,
+                    *k_nearest,
+                    *k_farthest,
+                    *random_frac,
+                    mash_params,
+                    seed,
+                )
             } else {
                 // Fallback without sequences - just use random
-                generate_random_pairs(sample_count, *random_frac)
+                generate_random_pairs_with_seed(sample_count, *random_frac, seed)
             }
         }
         SparsificationStrategy::WfmashDensity(_) => {
@@ -498,6 +595,15 @@ pub fn select_pairs(
 pub fn select_pairs_from_sketches(
     sketches: &[mash::KmerSketch],
     strategy: &SparsificationStrategy,
+) -> Vec<(usize, usize)> {
+    select_pairs_from_sketches_with_seed(sketches, strategy, None)
+}
+
+/// [`select_pairs_from_sketches`] with random pairs drawn for `seed` (`--seed`)
+pub fn select_pairs_from_sketches_with_seed(
+    sketches: &[mash::KmerSketch],
+    strategy: &SparsificationStrategy,
+    seed: Option<u64>,
 ) -> Vec<(usize, usize)> {
     let sample_count = sketches.len();
 
@@ -521,16 +627,19 @@ pub fn select_pairs_from_sketches(
                 }
                 pairs
             } else if sample_count <= 50 {
-                select_pairs_from_sketches(
+                select_pairs_from_sketches_with_seed(
                     sketches,
                     &SparsificationStrategy::Connectivity(0.99),
+                    seed,
                 )
             } else {
                 let distance_matrix = mash::distance_matrix_from_sketches(sketches);
-                extract_tree_pairs_from_matrix(&distance_matrix, 5, 2, 0.05)
+                extract_tree_pairs_from_matrix_with_seed(&distance_matrix, 5, 2, 0.05, seed)
             }
         }
-        SparsificationStrategy::Random(fraction) => generate_random_pairs(sample_count, *fraction),
+        SparsificationStrategy::Random(fraction) => {
+            generate_random_pairs_with_seed(sample_count, *fraction, seed)
+        }
         SparsificationStrategy::Connectivity(prob) => {
             let target_edges = ((sample_count as f64) * (sample_count as f64).ln() / 2.0
                 * (-prob.ln()))
@@ -540,11 +649,17 @@ pub fn select_pairs_from_sketches(
 
             let k_nearest = ((fraction * sample_count as f64).ceil() as usize).max(2);
             let distance_matrix = mash::distance_matrix_from_sketches(sketches);
-            extract_tree_pairs_from_matrix(&distance_matrix, k_nearest, 1, 0.01)
+            extract_tree_pairs_from_matrix_with_seed(&distance_matrix, k_nearest, 1, 0.01, seed)
         }
         SparsificationStrategy::TreeSampling(k_nearest, k_farthest, random_frac) => {
             let distance_matrix = mash::distance_matrix_from_sketches(sketches);
-            extract_tree_pairs_from_matrix(&distance_matrix, *k_nearest, *k_farthest, *random_frac)
+            extract_tree_pairs_from_matrix_with_seed(
+                &distance_matrix,
+                *k_nearest,
+                *k_farthest,
+                *random_frac,
+                seed,
+            )
         }
         SparsificationStrategy::WfmashDensity(_) => {
             // WfmashDensity = all pairs (density applied at aligner level)
@@ -639,6 +754,24 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_random_pairs_seed() {
+        let draw = |seed| generate_random_pairs_with_seed(40, 0.3, seed);
+        assert_eq!(draw(None), draw(None));
+        assert_eq!(draw(Some(7)), draw(Some(7)));
+        assert_ne!(draw(Some(7)), draw(Some(8)));
+        assert_ne!(draw(Some(7)), draw(None));
+    }
+
+    #[test]
+    fn test_random_pairs_are_pinned() {
+        // A seed must draw the same pairs with every toolchain
+        assert_eq!(
+            generate_random_pairs_with_seed(6, 0.3, Some(42)),
+            [(0, 3), (0, 4), (0, 5), (2, 3), (2, 4)]
+        );
+    }
+
     #[test]
     fn test_build_knn_graph_k2() {
         // Test with k=2 nearest neighbors
@@ -1,19 +1,25 @@
 // Library exports for sweepga
 pub mod agc;
 pub mod aligner;
+pub mod axt;
 pub mod batch_align;
 pub mod binary_paths;
+pub mod chain_format;
 pub mod cli;
 pub mod compact_mapping;
 pub mod disk_usage;
 pub mod fastga_integration;
 pub mod filter_types;
 pub mod grouped_mappings;
+pub mod histogram;
 pub mod joblist;
 pub mod knn_graph;
 pub mod library_api;
+pub mod maf;
+pub mod manifest;
 pub mod mapping;
 pub mod mash;
+pub mod minimap2_integration;
 pub mod orchestrator;
 pub mod paf;
 pub mod pansn;
@@ -21,13 +27,20 @@ pub mod paf_filter;
 pub mod plane_sweep_core;
 pub mod plane_sweep_exact;
 pub mod plane_sweep_scaffold;
+pub mod profile;
+pub mod sam;
+pub mod scaffold_bundle;
 pub mod seq_registry;
 pub mod sequence_index;
+pub mod serve;
+pub mod trim_overlaps;
 pub mod unified_filter;
 pub mod union_find;
 pub mod wfmash_integration;
 
 pub use cli::{parse_identity_value, parse_metric_number, AlnArgs};
+pub use paf_filter::{FilterConfig, FilterConfigBuilder, FilterStats, PafFilter};
+pub use unified_filter::filter_file;
 
 use anyhow::Result;
 use std::path::Path;
@@ -80,6 +93,8 @@ pub fn align_self_paf_batched(
     let batch_config = batch_align::BatchAlignConfig {
         keep_self: true,
         quiet,
+        prefix_delimiter: '#',
+        prefix_depth: 0,
     };
 
     let fasta_path_str = fasta_path.to_string_lossy().to_string();
@@ -11,7 +11,8 @@
 //! Public surface:
 //! - [`SweepgaAlignConfig`] — flags forwarded to the alignment call.
 //! - [`sweepga_align`] — dispatches between all-vs-all and pairwise modes.
-//! - [`generate_pairs_for_sequences`] — PanSN-aware pair selection helper.
+//! - [`generate_pairs_for_sequences`] — PanSN-aware pair selection helper
+//!   (seeded with [`generate_pairs_for_sequences_with_seed`]).
 //! - [`apply_paf_filter`] — runs sweepga's `PafFilter` on a PAF file.
 //! - [`create_aligner_adaptive`] — factory for the boxed `Aligner` trait
 //!   object; picks FastGA or wfmash based on the `aligner_name` string.
@@ -95,6 +96,7 @@ pub fn create_aligner_adaptive(
                 sparsify,
                 num_mappings,
                 pairs_file,
+                '#',
             )
             .map_err(|e| anyhow::anyhow!("Failed to create wfmash aligner: {e}"))?;
             Ok(Box::new(wfmash))
@@ -144,6 +146,8 @@ pub struct SweepgaAlignConfig {
     pub sparsify: SparsificationStrategy,
     /// Mash distance parameters for sparsification sketching.
     pub mash_params: crate::knn_graph::MashParams,
+    /// Seed for random pair sampling. `None` = unseeded.
+    pub seed: Option<u64>,
     /// Aligner backend: `"wfmash"` or `"fastga"`.
     pub aligner: String,
     /// Minimum mapping identity for wfmash (e.g. `"70"`). `None` = wfmash auto-estimates.
@@ -170,6 +174,7 @@ impl Default for SweepgaAlignConfig {
             temp_dir: None,
             sparsify: SparsificationStrategy::None,
             mash_params: crate::knn_graph::MashParams::default(),
+            seed: None,
             aligner: "wfmash".to_string(),
             map_pct_identity: None,
             batch_bytes: None,
@@ -184,6 +189,17 @@ pub fn generate_pairs_for_sequences(
     sequences: &[(String, &[u8])],
     strategy: &SparsificationStrategy,
     mash_params: &crate::knn_graph::MashParams,
+) -> Vec<(usize, usize)> {
+    generate_pairs_for_sequences_with_seed(sequences, strategy, mash_params, None)
+}
+
+/// [`generate_pairs_for_sequences`] with random pairs drawn for `seed`
+/// (see [`SweepgaAlignConfig::seed`])
+pub fn generate_pairs_for_sequences_with_seed(
+    sequences: &[(String, &[u8])],
+    strategy: &SparsificationStrategy,
+    mash_params: &crate::knn_graph::MashParams,
+    seed: Option<u64>,
 ) -> Vec<(usize, usize)> {
     let n = sequences.len();
     if n <= 1 {
@@ -194,7 +210,7 @@ pub fn generate_pairs_for_sequences(
         SparsificationStrategy::None
         | SparsificationStrategy::Random(_)
         | SparsificationStrategy::WfmashDensity(_) => {
-            crate::knn_graph::select_pairs(n, None, strategy, mash_params)
+            crate::knn_graph::select_pairs_with_seed(n, None, strategy, mash_params, seed)
         }
         _ => {
             let raw_seqs: Vec<Vec<u8>> = sequences.iter().map(|(_, s)| s.to_vec()).collect();
@@ -203,7 +219,7 @@ pub fn generate_pairs_for_sequences(
                 mash_params.kmer_size,
                 mash_params.sketch_size,
             );
-            crate::knn_graph::select_pairs_from_sketches(&sketches, strategy)
+            crate::knn_graph::select_pairs_from_sketches_with_seed(&sketches, strategy, seed)
         }
     }
 }
@@ -255,6 +271,7 @@ pub fn filter_config_from_align_cfg(cfg: &SweepgaAlignConfig, avg_seq_len: u64)
         scoring_function: ScoringFunction::LogLengthIdentity,
         min_identity: cfg.min_identity,
         min_scaffold_identity: cfg.min_identity,
+        ..FilterConfig::default()
     }
 }
 
@@ -297,7 +314,12 @@ pub fn sweepga_align(
         let temp = tempfile::Builder::new().suffix(".paf").tempfile()?;
         return Ok(temp);
     }
-    let pairs = generate_pairs_for_sequences(sequences, &config.sparsify, &config.mash_params);
+    let pairs = generate_pairs_for_sequences_with_seed(
+        sequences,
+        &config.sparsify,
+        &config.mash_params,
+        config.seed,
+    );
     let total_possible = sequences.len() * (sequences.len() - 1) / 2;
 
     if pairs.is_empty() {
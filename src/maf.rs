@@ -0,0 +1,150 @@
+//! MAF export (`--maf`)
+//!
+//! Every output record with a `cg:Z:` CIGAR becomes one pairwise `a` block:
+//! the query on the forward strand, then the target. On `-` the block is
+//! flipped to keep the query forward, so the target row holds the
+//! reverse-complemented target bases and its `s` line the `-` sign, with the
+//! start counted on that strand as the format specifies. Blocks are scored
+//! like AXT (blastz defaults); the bases come from the input FASTA files.
+
+use anyhow::{Context, Result};
+use std::fs::File;
+use std::io::{BufRead, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use crate::axt::{blastz_score, gapped_rows, reverse_complement, FastaSequences};
+use crate::chain_format::ChainMember;
+use crate::trim_overlaps::parse_cigar_ops;
+
+/// Write one `a` block for `member`, aligned by `cigar`. `query` and
+/// `target` are the forward-strand bases of the aligned spans.
+pub fn write_block<W: Write>(
+    writer: &mut W,
+    member: &ChainMember,
+    cigar: &[(u64, char)],
+    query: &[u8],
+    target: &[u8],
+) -> Result<()> {
+    let reverse = member.strand == '-';
+    let (query_row, target_row) = if reverse {
+        // Rows of the reverse query against the forward target, flipped
+        let (query_row, target_row) = gapped_rows(&reverse_complement(query), target, cigar)?;
+        (
+            reverse_complement(&query_row),
+            reverse_complement(&target_row),
+        )
+    } else {
+        gapped_rows(query, target, cigar)?
+    };
+    let target_start = if reverse {
+        member.target_len - member.target_end
+    } else {
+        member.target_start
+    };
+
+    writeln!(writer, "a score={}", blastz_score(&query_row, &target_row))?;
+    write!(
+        writer,
+        "s {} {} {} + {} ",
+        member.query_name,
+        member.query_start,
+        member.query_end - member.query_start,
+        member.query_len,
+    )?;
+    writer.write_all(&query_row)?;
+    write!(
+        writer,
+        "\ns {} {target_start} {} {} {} ",
+        member.target_name,
+        member.target_end - member.target_start,
+        member.strand,
+        member.target_len,
+    )?;
+    writer.write_all(&target_row)?;
+    writeln!(writer, "\n")?;
+    Ok(())
+}
+
+/// Convert the 0-based PAF file `paf_path` to MAF at `maf_path`, taking the
+/// bases from `fastas`. Records without a `cg:Z:` CIGAR are skipped.
+/// Returns the number of blocks written.
+pub fn write_maf(paf_path: &Path, maf_path: &Path, fastas: &[PathBuf]) -> Result<usize> {
+    let sequences = FastaSequences::open(fastas)?;
+    let file = File::create(maf_path)
+        .with_context(|| format!("Failed to create MAF file {}", maf_path.display()))?;
+    let mut writer = BufWriter::new(file);
+    writeln!(writer, "##maf version=1 scoring=blastz\n")?;
+
+    let mut written = 0;
+    let mut skipped = 0;
+    for line in crate::paf::open_paf_input(paf_path)?.lines() {
+        let line = line?;
+        if line.is_empty() || crate::paf::is_header_line(&line) {
+            continue;
+        }
+        let Some(cigar) = line.split('\t').find_map(|f| f.strip_prefix("cg:Z:")) else {
+            skipped += 1;
+            continue;
+        };
+        let cigar = parse_cigar_ops(cigar)?;
+        let member = ChainMember::from_paf_line(&line)?;
+        let query = sequences.fetch(&member.query_name, member.query_start, member.query_end)?;
+        let target =
+            sequences.fetch(&member.target_name, member.target_start, member.target_end)?;
+        write_block(&mut writer, &member, &cigar, &query, &target)
+            .with_context(|| format!("Failed to write MAF block for record: {line}"))?;
+        written += 1;
+    }
+    writer.flush()?;
+    if skipped > 0 {
+        log::warn!("[sweepga] --maf: skipped {skipped} records without a cg:Z: CIGAR");
+    }
+    Ok(written)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn block(line: &str, query: &[u8], target: &[u8]) -> String {
+        let member = ChainMember::from_paf_line(line).unwrap();
+        let cigar = line
+            .split('\t')
+            .find_map(|f| f.strip_prefix("cg:Z:"))
+            .unwrap();
+        let mut out = Vec::new();
+        write_block(
+            &mut out,
+            &member,
+            &parse_cigar_ops(cigar).unwrap(),
+            query,
+            target,
+        )
+        .unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_forward_block() {
+        let line = "q\t20\t5\t16\t+\tt\t30\t10\t20\t10\t11\t60\tcg:Z:4=1I6=";
+        assert_eq!(
+            block(line, b"ACGTTACGTAC", b"ACGTACGTAC"),
+            "a score=525\n\
+             s q 5 11 + 20 ACGTTACGTAC\n\
+             s t 10 10 + 30 ACGT-ACGTAC\n\n"
+        );
+    }
+
+    #[test]
+    fn test_reverse_block_complements_target() {
+        // The query (AACCGTT) lacks one G of the target's reverse complement
+        // (AACCGGTT); target 0-8 of 30 is 22-30 on the minus strand
+        let line = "q\t20\t2\t9\t-\tt\t30\t0\t8\t7\t8\t60\tcg:Z:3=1D4=";
+        assert_eq!(
+            block(line, b"AACCGTT", b"AACCGGTT"),
+            "a score=234\n\
+             s q 2 7 + 20 AACC-GTT\n\
+             s t 22 8 - 30 AACCGGTT\n\n"
+        );
+    }
+}
@@ -1,18 +1,24 @@
 mod agc;
 mod aligner;
 mod aln_filter;
+mod axt;
 mod batch_align;
 mod binary_paths;
+mod chain_format;
 mod cli;
 mod compact_mapping;
 mod disk_usage;
 mod fastga_integration;
 mod filter_types;
 mod grouped_mappings;
+mod histogram;
 mod joblist;
 mod knn_graph;
+mod maf;
+mod manifest;
 mod mapping;
 mod mash;
+mod minimap2_integration;
 mod paf;
 mod orchestrator;
 mod paf_filter;
@@ -21,8 +27,13 @@ mod plane_sweep;
 mod plane_sweep_core;
 mod plane_sweep_exact;
 mod plane_sweep_scaffold;
+mod profile;
+mod sam;
+mod scaffold_bundle;
 mod sequence_index;
+mod serve;
 mod tree_filter;
+mod trim_overlaps;
 mod unified_filter;
 mod union_find;
 mod wfmash_integration;
@@ -31,8 +42,8 @@ use anyhow::{Context, Result};
 use clap::Parser;
 
 use crate::cli::{parse_identity_value, parse_metric_number, AlnArgs};
-use crate::paf_filter::{FilterConfig, FilterMode, PafFilter, ScoringFunction};
-use std::collections::HashMap;
+use crate::paf_filter::{FilterConfig, FilterMode, IdentityTransform, PafFilter, ScoringFunction};
+use std::collections::{BTreeSet, HashMap};
 use std::fs::File;
 use std::io::{BufRead, BufReader};
 use std::path::Path;
@@ -77,9 +88,14 @@ impl TimingContext {
         }
     }
 
+    /// Wall time since start, in seconds
+    fn elapsed(&self) -> f64 {
+        self.start_time.elapsed().as_secs_f64()
+    }
+
     /// Calculate elapsed wall time and CPU ratio
     fn stats(&self) -> (f64, f64) {
-        let elapsed = self.start_time.elapsed().as_secs_f64();
+        let elapsed = self.elapsed();
         let cpu_used = Self::cpu_time() - self.start_cpu;
         let cpu_ratio = if elapsed > 0.0 {
             cpu_used / elapsed
@@ -91,7 +107,7 @@ impl TimingContext {
 
     /// Log a phase message with wall time, RSS, and disk to stderr.
     fn log(&self, phase: &str, message: &str) {
-        let elapsed = self.start_time.elapsed().as_secs_f64();
+        let elapsed = self.elapsed();
         let rss = disk_usage::format_bytes(Self::peak_rss_bytes());
         let disk_cur = disk_usage::format_bytes(disk_usage::current_usage());
         let disk_peak = disk_usage::format_bytes(disk_usage::peak_usage());
@@ -123,13 +139,7 @@ fn detect_file_type(path: &str) -> Result<FileType> {
         return Ok(FileType::Aln);
     }
 
-    let file = File::open(path)?;
-    let mut reader: Box<dyn BufRead> = if path.ends_with(".gz") || path.ends_with(".bgz") {
-        // Use bgzf reader for .gz and .bgz files (handles both gzip and bgzip)
-        Box::new(BufReader::new(noodles::bgzf::io::reader::Reader::new(file)))
-    } else {
-        Box::new(BufReader::new(file))
-    };
+    let mut reader = paf::open_paf_input(path)?;
 
     // Read first non-empty line
     let mut line = String::new();
@@ -180,9 +190,34 @@ enum AniMethod {
 /// Sorting method for N-percentile calculation
 #[derive(Debug, Clone, Copy, PartialEq)]
 enum NSort {
-    Length,   // Sort by alignment length
-    Identity, // Sort by identity
-    Score,    // Sort by identity * log(length)
+    Length,                 // Sort by alignment length
+    Identity,               // Sort by identity
+    Score(AniScoreWeights), // Sort by identity^weight * log(length)
+}
+
+/// Weighting of the N-percentile `score` sort (`--ani-score-weight`,
+/// `--ani-score-log-base`): `identity^identity_weight * log_base(length)`
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct AniScoreWeights {
+    identity_weight: f64,
+    log_base: f64,
+}
+
+impl AniScoreWeights {
+    fn score(&self, identity: f64, length: f64) -> f64 {
+        identity.powf(self.identity_weight) * length.log(self.log_base).max(1.0)
+    }
+}
+
+/// What to produce when filtering keeps no records (`--on-empty`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+enum OnEmpty {
+    /// Valid empty output: empty PAF, header-only .1aln
+    EmptyFile,
+    /// Write nothing; an --output-file is not created
+    NoFile,
+    /// Exit with an error
+    Error,
 }
 
 /// SweepGA - Fast genome alignment with plane sweep filtering
@@ -207,6 +242,12 @@ struct Args {
     #[clap(long = "output-file")]
     output_file: Option<String>,
 
+    /// Write one filtered PAF per genome pair, `DIR/<query>__<target>.paf`,
+    /// instead of a single output file or stdout
+    #[clap(long = "output-dir", value_name = "DIR",
+           conflicts_with_all = ["output_file", "output_sam", "output_maf", "bgzip"])]
+    output_dir: Option<String>,
+
     /// Output PAF format (default, kept for clarity and backwards compatibility)
     #[clap(long = "paf")]
     output_paf: bool,
@@ -215,6 +256,24 @@ struct Args {
     #[clap(long = "1aln")]
     output_1aln: bool,
 
+    /// Output SAM instead of PAF, with CIGARs from the alignment traces
+    /// (cg:Z: tags for PAF input) and NM tags
+    #[clap(long = "sam", conflicts_with_all = ["output_paf", "output_1aln"])]
+    output_sam: bool,
+
+    /// Output MAF instead of PAF, with the aligned bases from the input FASTA
+    #[clap(long = "maf", conflicts_with_all = ["output_paf", "output_1aln", "output_sam"])]
+    output_maf: bool,
+
+    /// Compress the output with bgzip (BGZF, indexable), also to stdout;
+    /// implied by an --output-file ending in .gz
+    #[clap(long = "bgzip")]
+    bgzip: bool,
+
+    /// Behavior when filtering keeps no records
+    #[clap(long = "on-empty", value_enum, default_value = "empty-file")]
+    on_empty: OnEmpty,
+
     /// All alignment, filtering, and orchestration flags live in `AlnArgs` —
     /// the single flattenable struct exported by `sweepga::cli::AlnArgs`.
     #[clap(flatten)]
@@ -232,6 +291,27 @@ struct Args {
     #[clap(long = "quiet", help_heading = "General options")]
     quiet: bool,
 
+    /// Serve filter requests from stdin (`<in.paf>\t<out.paf>[\tkey=value...]`
+    /// per line) in one long-lived process; see `src/serve.rs` for the protocol
+    #[clap(long = "serve", help_heading = "General options")]
+    serve: bool,
+
+    /// Write a JSON run manifest (inputs/outputs with SHA-256 checksums,
+    /// resolved config, filter stage counts, versions, timings) to this file
+    #[clap(long = "manifest", value_name = "FILE", help_heading = "General options")]
+    manifest: Option<String>,
+
+    /// Write coarse per-stage self times of PAF filtering (parse, plane sweep
+    /// per genome pair, merge, rescue, ...) as folded stacks for
+    /// inferno/flamegraph.pl
+    #[clap(long = "profile", value_name = "FILE", help_heading = "General options")]
+    profile: Option<String>,
+
+    /// Keep the temp directory of per-genome FASTAs and indices written by
+    /// --all-pairs instead of removing it, for debugging
+    #[clap(long = "no-cleanup", help_heading = "General options")]
+    no_cleanup: bool,
+
     /// Check FastGA binary locations and exit (diagnostic tool)
     #[clap(long = "check-fastga", help_heading = "General options")]
     check_fastga: bool,
@@ -241,10 +321,34 @@ struct Args {
     disk_usage: bool,
 }
 
-fn parse_filter_mode(mode: &str, _filter_type: &str) -> (FilterMode, Option<usize>, Option<usize>) {
+/// Genomes selected by `--keep-pairs` / `--exclude-pairs`
+fn pair_selection(aln: &AlnArgs) -> pansn::PairSelection {
+    pansn::PairSelection::new(&aln.keep_pairs, &aln.exclude_pairs, aln.prefix_delimiter)
+}
+
+/// Name fields forming a genome group (`--prefix-depth`; 0 = up to the last
+/// delimiter)
+fn prefix_depth(aln: &AlnArgs) -> usize {
+    aln.prefix_depth.map_or(0, |depth| depth as usize)
+}
+
+/// Mash sketch parameters for pair sparsification (`--mash-kmer-size`,
+/// `--mash-sketch-size`)
+fn mash_params(aln: &AlnArgs) -> knn_graph::MashParams {
+    knn_graph::MashParams {
+        kmer_size: aln.mash_kmer_size,
+        sketch_size: aln.mash_sketch_size,
+    }
+}
+
+fn parse_filter_mode(mode: &str, filter_type: &str) -> (FilterMode, Option<usize>, Option<usize>) {
     // Handle various ways to specify "no filtering"
     let lower = mode.to_lowercase();
     match lower.as_str() {
+        // Fractional scaffold retention ("0.8f"): limits are set per group later
+        s if filter_type == "scaffold" && cli::scaffold_filter_fraction(s).is_some() => {
+            (FilterMode::ManyToMany, None, None)
+        }
         "1:1" => (FilterMode::OneToOne, Some(1), Some(1)),
         "1" | "1:∞" | "1:infinity" | "1:many" => (FilterMode::OneToMany, Some(1), None),
         "∞:1" | "infinity:1" | "many:1" => (FilterMode::ManyToMany, None, Some(1)),
@@ -293,7 +397,7 @@ fn parse_filter_mode(mode: &str, _filter_type: &str) -> (FilterMode, Option<usiz
 }
 
 /// Parse ANI calculation method from string
-fn parse_ani_method(method_str: &str) -> Option<AniMethod> {
+fn parse_ani_method(method_str: &str, score_weights: AniScoreWeights) -> Option<AniMethod> {
     let lower = method_str.to_lowercase();
 
     match lower.as_str() {
@@ -313,7 +417,7 @@ fn parse_ani_method(method_str: &str) -> Option<AniMethod> {
                             match parts[1] {
                                 "length" => NSort::Length,
                                 "identity" => NSort::Identity,
-                                "score" => NSort::Score,
+                                "score" => NSort::Score(score_weights),
                                 _ => return None,
                             }
                         } else {
@@ -330,8 +434,210 @@ fn parse_ani_method(method_str: &str) -> Option<AniMethod> {
     }
 }
 
-/// Calculate ANI statistics between genome pairs using specified method
-fn calculate_ani_stats(input_path: &str, method: AniMethod, quiet: bool) -> Result<f64> {
+/// Inter-genome alignments summed over one unordered genome pair
+#[derive(Default)]
+struct GenomePairTotals {
+    matches: f64,
+    block_length: f64,
+    alignments: usize,
+}
+
+impl GenomePairTotals {
+    /// Weighted ANI: total matches / total block length, corrected by `transform`
+    fn ani(&self, transform: IdentityTransform) -> f64 {
+        if self.block_length > 0.0 {
+            transform.apply(self.matches / self.block_length)
+        } else {
+            0.0
+        }
+    }
+}
+
+/// Sum the alignments of a PAF per genome pair (PanSN prefixes, smaller
+/// first), skipping self-comparisons. With `per_chrom`, pairs are keyed on
+/// the full sequence names instead, still skipping pairs within one genome.
+/// Records outside `pairs` are ignored. Also returns every genome seen.
+fn genome_pair_totals(
+    input_path: &str,
+    per_chrom: bool,
+    pairs: &pansn::PairSelection,
+    delimiter: char,
+    prefix_depth: usize,
+) -> Result<(
+    BTreeSet<String>,
+    HashMap<(String, String), GenomePairTotals>,
+)> {
+    let reader = paf::open_paf_input(input_path)?;
+
+    let mut genomes = BTreeSet::new();
+    let mut genome_pairs: HashMap<(String, String), GenomePairTotals> = HashMap::new();
+
+    for line in reader.lines() {
+        let line = line?;
+        if line.starts_with('#') || line.is_empty() {
+            continue;
+        }
+
+        let fields: Vec<&str> = line.split('\t').collect();
+        if fields.len() < 11 {
+            continue;
+        }
+
+        // Extract genome prefixes (see --prefix-depth)
+        // This handles haplotypes correctly: HG002#1#chr1 -> HG002#1#
+        let query_genome = pansn::genome_prefix(fields[0], delimiter, prefix_depth).to_string();
+        let target_genome = pansn::genome_prefix(fields[5], delimiter, prefix_depth).to_string();
+        if !pairs.accepts(&query_genome, &target_genome, delimiter) {
+            continue;
+        }
+        genomes.insert(query_genome.clone());
+        genomes.insert(target_genome.clone());
+
+        // Skip self-comparisons
+        if query_genome == target_genome {
+            continue;
+        }
+
+        // Parse matches and block length
+        let matches = fields[9].parse::<f64>().unwrap_or(0.0);
+        let block_len = fields[10].parse::<f64>().unwrap_or(1.0);
+
+        // Check for divergence tag (overrides matches-based identity)
+        let mut final_matches = matches;
+        for field in &fields[11..] {
+            if let Some(div_str) = field.strip_prefix("dv:f:") {
+                if let Ok(div) = div_str.parse::<f64>() {
+                    // Convert divergence back to equivalent matches
+                    final_matches = (1.0 - div) * block_len;
+                    break;
+                }
+            }
+        }
+
+        let (query_key, target_key) = if per_chrom {
+            (fields[0].to_string(), fields[5].to_string())
+        } else {
+            (query_genome, target_genome)
+        };
+        let key = if query_key < target_key {
+            (query_key, target_key)
+        } else {
+            (target_key, query_key)
+        };
+
+        // Accumulate total matches and total block length for weighted calculation
+        let entry = genome_pairs.entry(key).or_default();
+        entry.matches += final_matches;
+        entry.block_length += block_len;
+        entry.alignments += 1;
+    }
+
+    Ok((genomes, genome_pairs))
+}
+
+/// Write the symmetric matrix of weighted ANI between every pair of genomes
+/// in `input_path` as TSV with genome names as row and column headers, and
+/// optionally the matching matrix of alignment counts. Pairs without
+/// alignments are NA; a genome's ANI with itself is 1. Returns the number
+/// of genomes.
+fn write_ani_matrix(
+    input_path: &str,
+    pairs: &pansn::PairSelection,
+    delimiter: char,
+    prefix_depth: usize,
+    transform: IdentityTransform,
+    ani_path: &str,
+    counts_path: Option<&str>,
+) -> Result<usize> {
+    use std::io::Write;
+
+    let (genomes, genome_pairs) =
+        genome_pair_totals(input_path, false, pairs, delimiter, prefix_depth)?;
+    let genomes: Vec<String> = genomes.into_iter().collect();
+    let totals = |a: &String, b: &String| {
+        let key = if a < b {
+            (a.clone(), b.clone())
+        } else {
+            (b.clone(), a.clone())
+        };
+        genome_pairs.get(&key)
+    };
+    // PanSN prefixes end in the delimiter (HG002#1#); drop it for the labels
+    let labels: Vec<&str> = genomes
+        .iter()
+        .map(|g| g.trim_end_matches(delimiter))
+        .collect();
+    let write_matrix = |path: &str, cell: &dyn Fn(&String, &String) -> String| -> Result<()> {
+        let file = File::create(path).with_context(|| format!("Failed to create {path}"))?;
+        let mut out = std::io::BufWriter::new(file);
+        writeln!(out, "genome\t{}", labels.join("\t"))?;
+        for (row, label) in genomes.iter().zip(&labels) {
+            let cells: Vec<String> = genomes.iter().map(|col| cell(row, col)).collect();
+            writeln!(out, "{label}\t{}", cells.join("\t"))?;
+        }
+        out.flush()?;
+        Ok(())
+    };
+
+    write_matrix(ani_path, &|row, col| match totals(row, col) {
+        _ if row == col => "1".to_string(),
+        Some(pair) => format!("{:.6}", pair.ani(transform)),
+        None => "NA".to_string(),
+    })?;
+    if let Some(counts_path) = counts_path {
+        write_matrix(counts_path, &|row, col| {
+            totals(row, col)
+                .map_or(0, |pair| pair.alignments)
+                .to_string()
+        })?;
+    }
+    Ok(genomes.len())
+}
+
+/// Write the weighted ANI of every pair of sequences from different genomes
+/// in `input_path` as TSV (`query_chr`, `target_chr`, `matches`, `block_len`,
+/// `ani`), one row per unordered pair with the smaller name first. Returns
+/// the number of pairs.
+fn write_ani_per_chrom(
+    input_path: &str,
+    pairs: &pansn::PairSelection,
+    delimiter: char,
+    prefix_depth: usize,
+    transform: IdentityTransform,
+    path: &str,
+) -> Result<usize> {
+    use std::io::Write;
+
+    let (_, chrom_pairs) = genome_pair_totals(input_path, true, pairs, delimiter, prefix_depth)?;
+    let chrom_pairs: std::collections::BTreeMap<_, _> = chrom_pairs.into_iter().collect();
+
+    let file = File::create(path).with_context(|| format!("Failed to create {path}"))?;
+    let mut out = std::io::BufWriter::new(file);
+    writeln!(out, "query_chr\ttarget_chr\tmatches\tblock_len\tani")?;
+    for ((query, target), totals) in &chrom_pairs {
+        writeln!(
+            out,
+            "{query}\t{target}\t{}\t{}\t{:.6}",
+            totals.matches.round(),
+            totals.block_length,
+            totals.ani(transform)
+        )?;
+    }
+    out.flush()?;
+    Ok(chrom_pairs.len())
+}
+
+/// Calculate the ANI of each genome pair using specified method, corrected
+/// with `transform` and sorted ascending for percentile thresholds (`aniN`).
+fn calculate_ani_stats(
+    input_path: &str,
+    pairs: &pansn::PairSelection,
+    delimiter: char,
+    prefix_depth: usize,
+    method: AniMethod,
+    transform: IdentityTransform,
+    quiet: bool,
+) -> Result<Vec<f64>> {
     use crate::paf_filter::{FilterConfig, FilterMode, PafFilter, ScoringFunction};
     use tempfile::NamedTempFile;
 
@@ -366,14 +672,16 @@ fn calculate_ani_stats(input_path: &str, method: AniMethod, quiet: bool) -> Resu
                 min_scaffold_length: 0, // No scaffolding for ANI calculation
                 scaffold_overlap_threshold: 0.95,
                 scaffold_max_deviation: 0,
-                prefix_delimiter: '#',
+                prefix_delimiter: delimiter,
+                prefix_depth,
                 skip_prefix: false,
                 scoring_function: ScoringFunction::Matches,
                 min_identity: 0.0,
                 min_scaffold_identity: 0.0,
+                ..FilterConfig::default()
             };
 
-            let filter = PafFilter::new(filter_config);
+            let filter = PafFilter::new(filter_config).with_pair_selection(pairs.clone());
             filter.filter_paf(input_path, &filtered_path)?;
 
             // Keep temp file alive until we're done
@@ -388,127 +696,59 @@ fn calculate_ani_stats(input_path: &str, method: AniMethod, quiet: bool) -> Resu
 
     // For N-percentile methods, we need to collect all alignments first
     if let AniMethod::NPercentile(percentile, sort_method) = method {
-        return calculate_ani_n_percentile(input_path, percentile, sort_method, quiet);
+        return calculate_ani_n_percentile(
+            input_path,
+            pairs,
+            delimiter,
+            prefix_depth,
+            percentile,
+            sort_method,
+            transform,
+            quiet,
+        );
     }
 
     // For All and Orthogonal methods, calculate directly
-    let file = File::open(&final_input_path)?;
-    let reader = BufReader::new(file);
-
-    // Group alignments by genome pair (using PanSN prefixes)
-    // Store (total_matches, total_block_length) for proper weighted calculation
-    let mut genome_pairs: HashMap<(String, String), (f64, f64)> = HashMap::new();
-
-    for line in reader.lines() {
-        let line = line?;
-        if line.starts_with('#') || line.is_empty() {
-            continue;
-        }
-
-        let fields: Vec<&str> = line.split('\t').collect();
-        if fields.len() < 11 {
-            continue;
-        }
-
-        // Extract genome prefixes (up to and including last #)
-        // This handles haplotypes correctly: HG002#1#chr1 -> HG002#1#
-        let query_genome = if let Some(pos) = fields[0].rfind('#') {
-            fields[0][..=pos].to_string()
-        } else {
-            fields[0].to_string()
-        };
-        let target_genome = if let Some(pos) = fields[5].rfind('#') {
-            fields[5][..=pos].to_string()
-        } else {
-            fields[5].to_string()
-        };
-
-        // Skip self-comparisons
-        if query_genome == target_genome {
-            continue;
-        }
-
-        // Parse matches and block length
-        let matches = fields[9].parse::<f64>().unwrap_or(0.0);
-        let block_len = fields[10].parse::<f64>().unwrap_or(1.0);
-
-        // Check for divergence tag (overrides matches-based identity)
-        let mut final_matches = matches;
-        for field in &fields[11..] {
-            if let Some(div_str) = field.strip_prefix("dv:f:") {
-                if let Ok(div) = div_str.parse::<f64>() {
-                    // Convert divergence back to equivalent matches
-                    final_matches = (1.0 - div) * block_len;
-                    break;
-                }
-            }
-        }
-
-        let key = if query_genome < target_genome {
-            (query_genome, target_genome)
-        } else {
-            (target_genome, query_genome)
-        };
-
-        // Accumulate total matches and total block length for weighted calculation
-        let entry = genome_pairs.entry(key).or_insert((0.0, 0.0));
-        entry.0 += final_matches;
-        entry.1 += block_len;
-    }
+    let (_, genome_pairs) =
+        genome_pair_totals(&final_input_path, false, pairs, delimiter, prefix_depth)?;
 
     if genome_pairs.is_empty() {
         // log::info!("[sweepga] WARNING: No inter-genome alignments found for ANI calculation");
-        return Ok(0.0); // Default to no filtering
+        return Ok(vec![0.0]); // Default to no filtering
     }
 
     // Calculate weighted average ANI for each genome pair
     // ANI = total_matches / total_block_length
     let mut ani_values: Vec<f64> = genome_pairs
-        .iter()
-        .map(|((_q, _t), (total_matches, total_length))| {
-            if *total_length > 0.0 {
-                total_matches / total_length
-            } else {
-                0.0
-            }
-        })
+        .values()
+        .map(|totals| totals.ani(transform))
         .collect();
 
     ani_values.sort_by(|a, b| a.partial_cmp(b).unwrap());
 
-    // Get 50th percentile (median)
-    let median_idx = ani_values.len() / 2;
-    let ani50 = if ani_values.len().is_multiple_of(2) && ani_values.len() > 1 {
-        (ani_values[median_idx - 1] + ani_values[median_idx]) / 2.0
-    } else {
-        ani_values[median_idx]
-    };
-
     // log::info!(
     //     "[sweepga] ANI statistics from {} genome pairs:",
     //     genome_pairs.len()
     // );
-    // log::info!(
-    //     "[sweepga]   Min: {:.1}%, Median: {:.1}%, Max: {:.1}%",
-    //     ani_values.first().unwrap_or(&0.0) * 100.0,
-    //     ani50 * 100.0,
-    //     ani_values.last().unwrap_or(&0.0) * 100.0
-    // );
 
-    Ok(ani50)
+    Ok(ani_values)
 }
 
-/// Calculate ANI using N-percentile method - use best alignments covering N% of genome pairs
+/// Calculate ANI using N-percentile method - use best alignments covering N% of genome pairs.
+/// Returns the ANI of each genome pair, sorted ascending.
 fn calculate_ani_n_percentile(
     input_path: &str,
+    pairs: &pansn::PairSelection,
+    delimiter: char,
+    prefix_depth: usize,
     percentile: f64,
     sort_method: NSort,
+    transform: IdentityTransform,
     quiet: bool,
-) -> Result<f64> {
+) -> Result<Vec<f64>> {
     // Quiet mode or logging disabled
 
-    let file = File::open(input_path)?;
-    let reader = BufReader::new(file);
+    let reader = paf::open_paf_input(input_path)?;
 
     // Collect all alignments with their lengths and identity
     #[allow(dead_code)]
@@ -523,7 +763,7 @@ fn calculate_ani_n_percentile(
     }
 
     let mut alignments = Vec::new();
-    let mut genome_sizes: HashMap<String, u64> = HashMap::new();
+    let mut genome_sizes = paf::GenomeSizes::default();
 
     for line in reader.lines() {
         let line = line?;
@@ -537,19 +777,12 @@ fn calculate_ani_n_percentile(
         }
 
         // Extract genome prefixes
-        let query_genome = if let Some(pos) = fields[0].rfind('#') {
-            fields[0][..=pos].to_string()
-        } else {
-            fields[0].to_string()
-        };
-        let target_genome = if let Some(pos) = fields[5].rfind('#') {
-            fields[5][..=pos].to_string()
-        } else {
-            fields[5].to_string()
-        };
+        let query_genome = pansn::genome_prefix(fields[0], delimiter, prefix_depth).to_string();
+        let target_genome = pansn::genome_prefix(fields[5], delimiter, prefix_depth).to_string();
 
-        // Skip self-comparisons
-        if query_genome == target_genome {
+        // Skip self-comparisons and unselected genomes
+        if query_genome == target_genome || !pairs.accepts(&query_genome, &target_genome, delimiter)
+        {
             continue;
         }
 
@@ -557,19 +790,9 @@ fn calculate_ani_n_percentile(
         let query_length = fields[1].parse::<u64>().unwrap_or(0);
         let target_length = fields[6].parse::<u64>().unwrap_or(0);
 
-        // Track genome+chromosome sizes to avoid double counting
-        let query_key = format!(
-            "{}{}",
-            query_genome,
-            fields[0].rsplit('#').next().unwrap_or("")
-        );
-        let target_key = format!(
-            "{}{}",
-            target_genome,
-            fields[5].rsplit('#').next().unwrap_or("")
-        );
-        genome_sizes.entry(query_key).or_insert(query_length);
-        genome_sizes.entry(target_key).or_insert(target_length);
+        // Track sequence sizes, each counted once
+        genome_sizes.add(fields[0], query_length);
+        genome_sizes.add(fields[5], target_length);
 
         let matches = fields[9].parse::<f64>().unwrap_or(0.0);
         let block_len = fields[10].parse::<f64>().unwrap_or(1.0);
@@ -600,7 +823,7 @@ fn calculate_ani_n_percentile(
 
     if alignments.is_empty() {
         // log::info!("[sweepga] WARNING: No inter-genome alignments found for ANI calculation");
-        return Ok(0.0);
+        return Ok(vec![0.0]);
     }
 
     // Sort based on method
@@ -613,18 +836,18 @@ fn calculate_ani_n_percentile(
             // Sort by identity (descending)
             alignments.sort_by(|a, b| b.identity.partial_cmp(&a.identity).unwrap());
         }
-        NSort::Score => {
-            // Sort by identity * log(length) score (descending)
+        NSort::Score(weights) => {
+            // Sort by identity^weight * log(length) score (descending)
             alignments.sort_by(|a, b| {
-                let score_a = a.identity * a.block_length.ln().max(1.0);
-                let score_b = b.identity * b.block_length.ln().max(1.0);
+                let score_a = weights.score(a.identity, a.block_length);
+                let score_b = weights.score(b.identity, b.block_length);
                 score_b.partial_cmp(&score_a).unwrap()
             });
         }
     }
 
     // Calculate total genome sizes (sum of all unique genome+chromosome lengths)
-    let total_genome_size: f64 = genome_sizes.values().map(|&v| v as f64).sum();
+    let total_genome_size = genome_sizes.total() as f64;
 
     // Use genome size as denominator for N-percentile, not alignment length
     // This gives us true genome coverage percentage
@@ -666,7 +889,7 @@ fn calculate_ani_n_percentile(
         .iter()
         .map(|((_q, _t), (total_matches, total_length))| {
             if *total_length > 0.0 {
-                total_matches / total_length
+                transform.apply(total_matches / total_length)
             } else {
                 0.0
             }
@@ -675,19 +898,16 @@ fn calculate_ani_n_percentile(
 
     ani_values.sort_by(|a, b| a.partial_cmp(b).unwrap());
 
-    // Get median
-    let median_idx = ani_values.len() / 2;
-    let ani50 = if ani_values.len().is_multiple_of(2) && ani_values.len() > 1 {
-        (ani_values[median_idx - 1] + ani_values[median_idx]) / 2.0
-    } else {
-        ani_values[median_idx]
-    };
-
-    Ok(ani50)
+    Ok(ani_values)
 }
 
-/// Convert .1aln file to PAF using native reader (fast path)
-fn aln_to_paf_native(aln_path: &str) -> Result<tempfile::NamedTempFile> {
+/// Convert .1aln file to PAF using native reader (fast path), writing `mapq`
+/// in column 12
+fn aln_to_paf_native(
+    aln_path: &str,
+    identity_mode: filter_types::AlnIdentity,
+    mapq: u8,
+) -> Result<tempfile::NamedTempFile> {
     use fastga_rs::AlnReader;
     use std::io::Write;
 
@@ -702,8 +922,11 @@ fn aln_to_paf_native(aln_path: &str) -> Result<tempfile::NamedTempFile> {
         let qname = reader.get_seq_name(rec.query_id, 0)?;
         let tname = reader.get_seq_name(rec.target_id, 1)?;
 
-        let aln_len = (rec.query_end - rec.query_start) as usize;
-        let matches = aln_len.saturating_sub(rec.diffs as usize);
+        let (matches, aln_len) = identity_mode.matches_and_length(
+            (rec.query_end - rec.query_start) as u64,
+            (rec.target_end - rec.target_start) as u64,
+            rec.diffs as u64,
+        );
         let identity = if aln_len > 0 {
             100.0 * (matches as f64) / (aln_len as f64)
         } else {
@@ -713,7 +936,7 @@ fn aln_to_paf_native(aln_path: &str) -> Result<tempfile::NamedTempFile> {
         // PAF format: qname qlen qstart qend strand tname tlen tstart tend matches alen mapq [tags...]
         writeln!(
             temp_paf,
-            "{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t60\tid:f:{:.4}",
+            "{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\tid:f:{:.4}",
             qname,
             rec.query_len,
             rec.query_start,
@@ -725,6 +948,7 @@ fn aln_to_paf_native(aln_path: &str) -> Result<tempfile::NamedTempFile> {
             rec.target_end,
             matches,
             aln_len,
+            mapq,
             identity
         )?;
     }
@@ -734,62 +958,65 @@ fn aln_to_paf_native(aln_path: &str) -> Result<tempfile::NamedTempFile> {
 }
 
 /// Convert .1aln file to PAF using ALNtoPAF (fallback) or native reader (fast)
-fn aln_to_paf(aln_path: &str, threads: usize) -> Result<tempfile::NamedTempFile> {
+///
+/// When `with_cigar` is set the native reader is skipped: it cannot rebuild
+/// `cg:Z:` tags from trace points, and those are needed whenever the PAF is
+/// scored by matches or converted back to .1aln. The native reader writes
+/// MAPQ `default_mapq`, or 60 without `--default-mapq`.
+fn aln_to_paf(
+    aln_path: &str,
+    threads: usize,
+    with_cigar: bool,
+    identity_mode: filter_types::AlnIdentity,
+    default_mapq: Option<u8>,
+) -> Result<tempfile::NamedTempFile> {
     // Try native reader first (2.3x faster)
-    if let Ok(temp_paf) = aln_to_paf_native(aln_path) {
-        return Ok(temp_paf);
+    if !with_cigar {
+        let mapq = default_mapq.unwrap_or(paf::CONVERTED_MAPQ);
+        if let Ok(temp_paf) = aln_to_paf_native(aln_path, identity_mode, mapq) {
+            return Ok(temp_paf);
+        }
     }
 
-    // Fallback to ALNtoPAF (use embedded binary)
-    let alnto_paf_bin = sweepga::binary_paths::get_embedded_binary_path("ALNtoPAF")?;
+    // ALNtoPAF -x (embedded binary) emits CIGARs with X's, which PAFtoALN
+    // needs to rebuild trace points if we convert back to .1aln later
+    let paf_bytes = fastga_integration::aln_to_paf_with_cigar(Path::new(aln_path), threads)?;
 
     // Create temp file for PAF output
     let temp_paf = tempfile::NamedTempFile::with_suffix(".paf")?;
-    let paf_path = temp_paf.path();
-
-    // Run ALNtoPAF: ALNtoPAF [-mxsS] [-T<threads>] <alignment.1aln>
-    // -x: output CIGAR with X's (required if we want to convert back to .1aln later)
-    // It writes PAF to stdout
-    let output = std::process::Command::new(&alnto_paf_bin)
-        .arg("-x") // Generate CIGAR with X's
-        .arg(format!("-T{threads}"))
-        .arg(aln_path)
-        .output()?;
-
-    if !output.status.success() {
-        anyhow::bail!(
-            "ALNtoPAF conversion failed: {}",
-            String::from_utf8_lossy(&output.stderr)
-        );
-    }
-
-    // Write stdout to temp file
-    std::fs::write(paf_path, &output.stdout)?;
+    std::fs::write(temp_paf.path(), &paf_bytes)?;
 
     Ok(temp_paf)
 }
 
 // Note: Native .1aln writing is available via fastga-rs::AlnWriter
-// For PAF → .1aln conversion in sweepga, we still use PAFtoALN for compatibility
-// Future: implement direct PAF → .1aln conversion using AlnWriter
-
-/// Extract PanSN genome prefix from a sequence name
+// For PAF → .1aln conversion in sweepga, we still use PAFtoALN for compatibility:
+// it rebuilds trace points from cg:Z: CIGARs, which AlnWriter cannot take yet.
+// AlnWriter::write_mapping writes an empty T record and derives matches/diffs
+// from the rounded identity, so switching to it would lose the alignment;
+// test_cigar_survives_paf_1aln_roundtrip guards the PAFtoALN path.
+// Future: implement direct PAF → .1aln conversion using AlnWriter once
+// fastga-rs can write trace points (the writer lives in that crate, not here)
+//
+// PAF-only input still can't produce .1aln, even given sequence lengths: the
+// only AlnWriter constructor (`create_with_gdb`) copies the GDB skeleton of an
+// existing .1aln/.1gdb, and fastga-rs has no way to build one from names and
+// lengths. A `--lengths FILE` option needs that API upstream first.
+
+/// Extract PanSN genome prefix from a sequence name (see `--prefix-depth`)
 /// Example: "SGDref#1#chrI" -> Some("SGDref#1#")
-fn extract_genome_prefix(seq_name: &str) -> Option<String> {
-    // Look for PanSN format: genome#haplotype#chromosome
-    // We want to keep genome#haplotype# as the group
-    let parts: Vec<&str> = seq_name.split('#').collect();
-    if parts.len() >= 2 {
-        // Keep first two parts plus trailing #
-        Some(format!("{}#{}#", parts[0], parts[1]))
-    } else {
-        None
-    }
+fn extract_genome_prefix(seq_name: &str, delimiter: char, prefix_depth: usize) -> Option<String> {
+    seq_name
+        .contains(delimiter)
+        .then(|| pansn::genome_prefix(seq_name, delimiter, prefix_depth).to_string())
 }
 
 /// Detect genome groups from a FASTA file by reading headers
-fn detect_genome_groups(fasta_path: &Path) -> Result<Vec<String>> {
-    use std::collections::BTreeSet;
+fn detect_genome_groups(
+    fasta_path: &Path,
+    delimiter: char,
+    prefix_depth: usize,
+) -> Result<Vec<String>> {
     use std::io::{BufRead, BufReader};
 
     let file = File::open(fasta_path)?;
@@ -805,7 +1032,7 @@ fn detect_genome_groups(fasta_path: &Path) -> Result<Vec<String>> {
         let line = line?;
         if let Some(stripped) = line.strip_prefix('>') {
             let seq_name = stripped.split_whitespace().next().unwrap_or("");
-            if let Some(prefix) = extract_genome_prefix(seq_name) {
+            if let Some(prefix) = extract_genome_prefix(seq_name, delimiter, prefix_depth) {
                 groups.insert(prefix);
             }
         }
@@ -814,8 +1041,38 @@ fn detect_genome_groups(fasta_path: &Path) -> Result<Vec<String>> {
     Ok(groups.into_iter().collect())
 }
 
+/// Detect the genome groups aligned in a PAF file from its query and target names
+fn detect_paf_genome_groups(
+    paf_path: &Path,
+    delimiter: char,
+    prefix_depth: usize,
+) -> Result<BTreeSet<String>> {
+    let mut groups = BTreeSet::new();
+    for line in paf::open_paf_input(paf_path)?.lines() {
+        let line = line?;
+        if line.is_empty() || paf::is_header_line(&line) {
+            continue;
+        }
+        let mut fields = line.split('\t');
+        let names = [fields.next(), fields.nth(4)];
+        groups.extend(
+            names
+                .into_iter()
+                .flatten()
+                .filter_map(|name| extract_genome_prefix(name, delimiter, prefix_depth)),
+        );
+    }
+    Ok(groups)
+}
+
 /// Write sequences belonging to a specific genome group to a new FASTA file
-fn write_genome_fasta(input_path: &Path, output_path: &Path, genome_prefix: &str) -> Result<usize> {
+fn write_genome_fasta(
+    input_path: &Path,
+    output_path: &Path,
+    genome_prefix: &str,
+    delimiter: char,
+    prefix_depth: usize,
+) -> Result<usize> {
     use std::io::{BufRead, BufReader, Write};
 
     let file = File::open(input_path)?;
@@ -833,7 +1090,8 @@ fn write_genome_fasta(input_path: &Path, output_path: &Path, genome_prefix: &str
         let line = line?;
         if let Some(stripped) = line.strip_prefix('>') {
             let seq_name = stripped.split_whitespace().next().unwrap_or("");
-            writing = if let Some(prefix) = extract_genome_prefix(seq_name) {
+            writing = if let Some(prefix) = extract_genome_prefix(seq_name, delimiter, prefix_depth)
+            {
                 prefix == genome_prefix
             } else {
                 false
@@ -852,18 +1110,144 @@ fn write_genome_fasta(input_path: &Path, output_path: &Path, genome_prefix: &str
     Ok(seq_count)
 }
 
+/// Concatenate FASTA files (plain or gzipped) into `output_path`, so a single
+/// FastGA run covers them all and its .1aln carries one GDB with every input
+/// sequence. Sequence names must be unique across the files. Returns the
+/// number of sequences written.
+fn concatenate_fastas(paths: &[String], output_path: &Path) -> Result<usize> {
+    use std::collections::HashSet;
+    use std::io::{BufWriter, Write};
+
+    let mut seen = HashSet::new();
+    let mut output = BufWriter::new(File::create(output_path)?);
+    for path in paths {
+        for line in pansn::open_fasta(Path::new(path))?.lines() {
+            let line = line?;
+            if let Some(header) = line.strip_prefix('>') {
+                let name = header.split_whitespace().next().unwrap_or("");
+                anyhow::ensure!(
+                    seen.insert(name.to_string()),
+                    "Sequence {name} appears more than once across the input FASTA files"
+                );
+            }
+            writeln!(output, "{line}")?;
+        }
+    }
+    output.flush()?;
+    Ok(seen.len())
+}
+
 /// Count total PanSN genome groups across one or more FASTA files.
 ///
 /// Used to adapt wfmash mapping density (`-x`) for `--sparsify wfmash:auto`
 /// at CLI entry points that align one or more whole-genome FASTAs.
-fn count_total_genomes<P: AsRef<Path>>(fasta_paths: &[P]) -> Result<usize> {
+fn count_total_genomes<P: AsRef<Path>>(fasta_paths: &[P], prefix_depth: usize) -> Result<usize> {
     let mut n = 0;
     for p in fasta_paths {
-        n += detect_genome_groups(p.as_ref())?.len();
+        n += detect_genome_groups(p.as_ref(), delimiter, prefix_depth)?.len();
     }
     Ok(n)
 }
 
+/// File in a --checkpoint-dir recording the settings its pairs were aligned with
+const CHECKPOINT_SETTINGS: &str = "settings.txt";
+
+/// Make sure the checkpoints in `dir` were aligned with `settings` (one
+/// `key=value` per line): a new directory records them, and one written with
+/// other settings is refused, as its pairs would be silently reused
+fn check_checkpoint_settings(dir: &Path, settings: &str) -> Result<()> {
+    let path = dir.join(CHECKPOINT_SETTINGS);
+    match std::fs::read_to_string(&path) {
+        Ok(recorded) => {
+            let changed: Vec<&str> = settings
+                .lines()
+                .filter(|line| !recorded.lines().any(|r| r == *line))
+                .collect();
+            anyhow::ensure!(
+                changed.is_empty(),
+                "--checkpoint-dir {} holds pairs aligned with other settings (now {}); \
+                 use another directory or empty it",
+                dir.display(),
+                changed.join(", ")
+            );
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            let has_pairs = std::fs::read_dir(dir)?
+                .filter_map(|entry| entry.ok())
+                .any(|entry| entry.path().extension().is_some_and(|ext| ext == "paf"));
+            anyhow::ensure!(
+                !has_pairs,
+                "--checkpoint-dir {} holds pairs without a record of their settings; \
+                 use another directory or empty it",
+                dir.display()
+            );
+            std::fs::write(&path, settings)
+                .with_context(|| format!("Failed to write {}", path.display()))?;
+        }
+        Err(e) => {
+            return Err(e).with_context(|| format!("Failed to read {}", path.display()));
+        }
+    }
+    Ok(())
+}
+
+/// What a genome in an --index-dir was built from (one `key=value` per
+/// line): its source FASTA's path, size and modification time, and the
+/// FastGA k-mer frequency. A genome whose recorded stamp differs is rewritten
+/// and reindexed.
+fn index_stamp(source: &Path, frequency: usize) -> Result<String> {
+    let source = std::fs::canonicalize(source)
+        .with_context(|| format!("Failed to resolve path: {}", source.display()))?;
+    let metadata = std::fs::metadata(&source)?;
+    let mtime = metadata
+        .modified()?
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |d| d.as_nanos());
+    Ok(format!(
+        "source={}\nsize={}\nmtime={mtime}\nfrequency={frequency}\n",
+        source.display(),
+        metadata.len()
+    ))
+}
+
+/// Which genome pairs the all-pairs alignment aligns, and where it keeps
+/// per-pair alignments and per-genome indices between runs
+struct PairOptions<'a> {
+    /// --sparsify-pairs: align only the pairs this strategy selects
+    sparsify_pairs: &'a knn_graph::SparsificationStrategy,
+    mash_params: knn_graph::MashParams,
+    /// --seed: salts random pair sampling
+    seed: Option<u64>,
+    /// --max-mash-distance: skip pairs more divergent than this
+    max_mash_distance: Option<f64>,
+    /// --add-to: genomes already aligned to each other
+    existing_genomes: Option<&'a BTreeSet<String>>,
+    /// --checkpoint-dir: finished pairs, reused instead of realigned
+    checkpoint_dir: Option<&'a Path>,
+    /// --index-dir: per-genome FASTAs and FastGA indices, reused across runs
+    index_dir: Option<&'a Path>,
+    /// --prefix-delimiter / --prefix-depth: name fields forming the genomes
+    /// that are paired
+    prefix_delimiter: char,
+    prefix_depth: usize,
+}
+
+impl<'a> PairOptions<'a> {
+    fn from_args(aln: &'a AlnArgs, existing_genomes: Option<&'a BTreeSet<String>>) -> Self {
+        PairOptions {
+            sparsify_pairs: &aln.sparsify_pairs,
+            mash_params: mash_params(aln),
+            seed: aln.seed,
+            max_mash_distance: aln.max_mash_distance,
+            existing_genomes,
+            checkpoint_dir: aln.checkpoint_dir.as_deref().map(Path::new),
+            index_dir: aln.index_dir.as_deref().map(Path::new),
+            prefix_delimiter: aln.prefix_delimiter,
+            prefix_depth: prefix_depth(aln),
+        }
+    }
+}
+
 /// Perform all-pairs pairwise alignment for multiple FASTA files
 /// If a single FASTA with multiple genomes, splits by PanSN prefix
 #[allow(clippy::too_many_arguments)]
@@ -884,13 +1268,18 @@ fn align_multiple_fastas(
     zstd_compress: bool,
     zstd_level: u32,
     aligner_name: &str,
+    fastga_args: &[String],
+    minimap2_preset: &str,
     sparsify: &knn_graph::SparsificationStrategy,
+    pairs: &PairOptions,
+    io_buffer: usize,
+    no_cleanup: bool,
 ) -> Result<tempfile::NamedTempFile> {
     // Detect genome groups from input files
     let mut num_genomes = 0;
     for fasta_file in fasta_files {
         let path = Path::new(fasta_file);
-        let groups = detect_genome_groups(path)?;
+        let groups = detect_genome_groups(path, pairs.prefix_delimiter, pairs.prefix_depth)?;
         num_genomes += groups.len();
         if !quiet {
             timing.log(
@@ -909,35 +1298,66 @@ fn align_multiple_fastas(
         );
     }
 
+    let batch_config = batch_align::BatchAlignConfig {
+        keep_self,
+        quiet,
+        prefix_delimiter: pairs.prefix_delimiter,
+        prefix_depth: pairs.prefix_depth,
+    };
+
     // Resolve effective batch bytes from --max-disk / --batch-bytes (used when --batch-size is not set)
     let effective_batch_bytes = if batch_size.is_none() {
         batch_align::resolve_batch_bytes(
-            max_disk, batch_bytes, fasta_files, threads, zstd_compress, quiet,
+            max_disk,
+            batch_bytes,
+            fasta_files,
+            threads,
+            zstd_compress,
+            &batch_config,
         )?
     } else {
         None
     };
 
-    // Check for batch mode (either --batch-size or byte-based batching)
-    if batch_size.is_some() || effective_batch_bytes.is_some() {
-        let batch_config = batch_align::BatchAlignConfig { keep_self, quiet };
-
+    // Check for batch mode (either --batch-size or byte-based batching);
+    // --add-to, --sparsify-pairs and --max-mash-distance align only some
+    // genome pairs, --checkpoint-dir keeps each pair and --index-dir each
+    // genome's index, so they go one by one
+    let pair_by_pair = pairs.existing_genomes.is_some()
+        || *pairs.sparsify_pairs != knn_graph::SparsificationStrategy::None
+        || pairs.max_mash_distance.is_some()
+        || pairs.checkpoint_dir.is_some()
+        || pairs.index_dir.is_some();
+    if pair_by_pair && (batch_size.is_some() || effective_batch_bytes.is_some()) {
+        log::warn!(
+            "[sweepga] Aligning pair by pair: batching (--batch-size, --batch-bytes, \
+             --max-disk) is not used with --index-dir, --checkpoint-dir, --add-to, \
+             --sparsify-pairs or --max-mash-distance"
+        );
+    }
+    if !pair_by_pair && (batch_size.is_some() || effective_batch_bytes.is_some()) {
         let aligner: Box<dyn batch_align::BatchAligner> = match aligner_name {
-            "wfmash" => Box::new(batch_align::WfmashBatchAligner::new(
-                threads,
-                min_alignment_length,
-                map_pct_identity.clone(),
-                tempdir.map(String::from),
-                wfmash_density,
-            )),
-            _ => Box::new(batch_align::FastGABatchAligner::new(
-                frequency,
-                threads,
-                min_alignment_length.unwrap_or(0),
-                tempdir.map(String::from),
-                zstd_compress,
-                zstd_level,
-            )),
+            "wfmash" => Box::new(
+                batch_align::WfmashBatchAligner::new(
+                    threads,
+                    min_alignment_length,
+                    map_pct_identity.clone(),
+                    tempdir.map(String::from),
+                    wfmash_density,
+                )
+                .with_prefix_delimiter(pairs.prefix_delimiter),
+            ),
+            _ => Box::new(
+                batch_align::FastGABatchAligner::new(
+                    frequency,
+                    threads,
+                    min_alignment_length.unwrap_or(0),
+                    tempdir.map(String::from),
+                    zstd_compress,
+                    zstd_level,
+                )
+                .with_extra_args(fastga_args.to_vec()),
+            ),
         };
 
         // --batch-size: partition by genome count
@@ -995,7 +1415,7 @@ fn align_multiple_fastas(
     }
 
     // Decide on alignment mode
-    if all_pairs {
+    if all_pairs || pair_by_pair {
         // --all-pairs mode: split genomes and align each pair separately (bidirectional)
         return align_all_pairs_mode(
             fasta_files,
@@ -1010,7 +1430,12 @@ fn align_multiple_fastas(
             zstd_compress,
             zstd_level,
             aligner_name,
+            fastga_args,
+            minimap2_preset,
             sparsify,
+            pairs,
+            io_buffer,
+            no_cleanup,
         );
     }
 
@@ -1034,6 +1459,9 @@ fn align_multiple_fastas(
         tempdir.map(String::from),
         Some(avg_seq),
         wfmash_density,
+        fastga_args,
+        minimap2_preset,
+        pairs.prefix_delimiter,
     )?;
 
     // Run alignment on the original input (all genomes together)
@@ -1053,6 +1481,7 @@ fn align_multiple_fastas(
             threads,
             min_alignment_length.unwrap_or(0),
             tempdir.map(String::from),
+            fastga_args,
         )?;
         let gdb_base = fastga.prepare_gdb(&input_path)?;
         // Strip .fa extension if present (fastga-rs returns path with .fa but index uses base name)
@@ -1089,7 +1518,7 @@ fn is_pair_mode(args: &Args) -> bool {
         || args.aln.max_pairs > 0
         || args.aln.pairs_done.is_some()
         || args.aln.pairs_remaining.is_some()
-        || args.aln.sparsify_pairs != "none"
+        || args.aln.sparsify_pairs != knn_graph::SparsificationStrategy::None
 }
 
 fn process_agc_archive(
@@ -1219,7 +1648,11 @@ fn process_agc_archive(
 
     // Run alignment using direct PAF output
     let avg_seq = avg_seq_len_from_fai(&fasta_path)?;
-    let num_genomes = count_total_genomes(&[&fasta_path])?;
+    let num_genomes = count_total_genomes(
+        &[&fasta_path],
+        args.aln.prefix_delimiter,
+        prefix_depth(&args.aln),
+    )?;
     let wfmash_density = orchestrator::resolve_wfmash_density(&args.aln.sparsify, num_genomes);
     let aligner = create_aligner(
         &args.aln.aligner,
@@ -1230,6 +1663,9 @@ fn process_agc_archive(
         args.aln.tempdir.clone(),
         Some(avg_seq),
         wfmash_density,
+        &fastga_args(&args.aln)?,
+        &args.aln.minimap2_preset,
+        args.aln.prefix_delimiter,
     )?;
 
     if !args.quiet {
@@ -1500,15 +1936,9 @@ fn get_pairs_from_args(
         agc.list_samples()
     };
 
-    // Parse sparsification strategy
-    let strategy: knn_graph::SparsificationStrategy = args
-        .aln
-        .sparsify_pairs
-        .parse()
-        .map_err(|e| anyhow::anyhow!("Invalid sparsification strategy: {}", e))?;
-
     // Option 3: Sparsified pairs using minhash/knn
-    if strategy != knn_graph::SparsificationStrategy::None {
+    let strategy = &args.aln.sparsify_pairs;
+    if *strategy != knn_graph::SparsificationStrategy::None {
         if !args.quiet {
             timing.log("pairs", &format!("Sparsification: {}", strategy));
         }
@@ -1516,10 +1946,7 @@ fn get_pairs_from_args(
         // For sparsification, we need to compute mash sketches
         // Stream one sample at a time to avoid materializing all sequences in memory
 
-        let mash_params = knn_graph::MashParams {
-            kmer_size: args.aln.mash_kmer_size,
-            sketch_size: args.aln.mash_sketch_size,
-        };
+        let mash_params = mash_params(&args.aln);
 
         if !args.quiet {
             timing.log(
@@ -1560,7 +1987,8 @@ fn get_pairs_from_args(
         }
 
         // Select pairs using pre-computed sketches (no sequence data needed)
-        let pair_indices = knn_graph::select_pairs_from_sketches(&sketches, &strategy);
+        let pair_indices =
+            knn_graph::select_pairs_from_sketches_with_seed(&sketches, strategy, args.aln.seed);
 
         // Convert indices to SamplePair
         let pairs: Vec<SamplePair> = pair_indices
@@ -1723,6 +2151,9 @@ fn process_agc_pairs(
         Some(temp_base.to_string_lossy().to_string()),
         None,
         wfmash_density,
+        &fastga_args(&args.aln)?,
+        &args.aln.minimap2_preset,
+        args.aln.prefix_delimiter,
     )?;
 
     // Create work directory for extractions
@@ -1745,6 +2176,7 @@ fn process_agc_pairs(
             args.threads,
             args.aln.block_length.unwrap_or(0),
             Some(temp_base.to_string_lossy().to_string()),
+            &fastga_args(&args.aln)?,
         )?)
     } else {
         None
@@ -1764,8 +2196,9 @@ fn process_agc_pairs(
         let prev_total = total_alignments;
 
         // Extract query and target to temp FASTA files
-        let query_fasta = work_dir.join(format!("{}.fa", pair.query.replace('#', "_")));
-        let target_fasta = work_dir.join(format!("{}.fa", pair.target.replace('#', "_")));
+        let delimiter = args.aln.prefix_delimiter;
+        let query_fasta = work_dir.join(format!("{}.fa", pair.query.replace(delimiter, "_")));
+        let target_fasta = work_dir.join(format!("{}.fa", pair.target.replace(delimiter, "_")));
 
         // Extract samples (skip if same and file exists)
         if pair.query == pair.target {
@@ -1921,6 +2354,7 @@ fn process_agc_batched(
         args.threads,
         args.aln.block_length.unwrap_or(0),
         Some(temp_base.to_string_lossy().to_string()),
+        &fastga_args(&args.aln)?,
     )?;
 
     // Phase 2: Create GDB for all batches
@@ -2008,6 +2442,26 @@ fn process_agc_batched(
     Ok(output_paf)
 }
 
+/// Mash sketch of all sequences of a genome FASTA, concatenated
+fn sketch_genome_fasta(path: &Path, params: &knn_graph::MashParams) -> Result<mash::KmerSketch> {
+    let mut sequence = Vec::new();
+    for line in pansn::open_fasta(path)?.lines() {
+        let line = line?;
+        if !line.starts_with('>') {
+            sequence.extend_from_slice(line.trim_end().as_bytes());
+        }
+    }
+    Ok(mash::KmerSketch::from_sequence(
+        &sequence,
+        params.kmer_size,
+        params.sketch_size,
+    ))
+}
+
+/// Estimated peak temp space of `align_all_pairs_mode`, as a multiple of the
+/// input size: the genome FASTAs, a pair's indexes and the pair PAFs
+const ALL_PAIRS_TEMP_FACTOR: u64 = 3;
+
 /// Align all genome pairs separately in both directions (for --all-pairs mode)
 #[allow(clippy::too_many_arguments)]
 fn align_all_pairs_mode(
@@ -2023,8 +2477,24 @@ fn align_all_pairs_mode(
     zstd_compress: bool,
     zstd_level: u32,
     aligner_name: &str,
+    fastga_args: &[String],
+    minimap2_preset: &str,
     sparsify: &knn_graph::SparsificationStrategy,
+    pairs: &PairOptions,
+    io_buffer: usize,
+    no_cleanup: bool,
 ) -> Result<tempfile::NamedTempFile> {
+    let &PairOptions {
+        sparsify_pairs,
+        ref mash_params,
+        seed,
+        max_mash_distance,
+        existing_genomes,
+        checkpoint_dir,
+        index_dir,
+        prefix_delimiter: delimiter,
+        prefix_depth,
+    } = pairs;
     use std::io::Write;
 
     // Determine temp directory
@@ -2036,23 +2506,56 @@ fn align_all_pairs_mode(
         std::path::PathBuf::from("/tmp")
     };
 
-    // Create temp directory for genome FASTAs
-    let temp_genome_dir = temp_base.join(format!("sweepga_genomes_{}", std::process::id()));
-    std::fs::create_dir_all(&temp_genome_dir)?;
+    // The per-genome FASTAs copy the whole input, and the per-pair PAFs and
+    // indexes come on top of that while the pairs are aligned
+    let input_bytes = fasta_input_bytes(fasta_files)?;
+    disk_usage::check_temp_space(
+        &temp_base,
+        input_bytes,
+        input_bytes.saturating_mul(ALL_PAIRS_TEMP_FACTOR),
+    )?;
+
+    // Create temp directory for genome FASTAs; --index-dir keeps them and
+    // their indices in a stable directory instead, for later runs to reuse
+    let temp_genome_dir = match index_dir {
+        Some(dir) => dir.to_path_buf(),
+        None => temp_base.join(format!("sweepga_genomes_{}", std::process::id())),
+    };
+    std::fs::create_dir_all(&temp_genome_dir).with_context(|| {
+        format!("Failed to create genome directory {}", temp_genome_dir.display())
+    })?;
+    // Removes the genome FASTAs and their indices however this function exits
+    let _temp_genome_guard =
+        disk_usage::TempDirGuard::new(temp_genome_dir.clone(), no_cleanup || index_dir.is_some());
 
     if !quiet {
+        let kind = if index_dir.is_some() { "index" } else { "temp" };
         timing.log(
             "split",
-            &format!("Using temp directory: {}", temp_genome_dir.display()),
+            &format!("Using {kind} directory: {}", temp_genome_dir.display()),
         );
     }
 
+    if let Some(dir) = checkpoint_dir {
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("Failed to create checkpoint directory {}", dir.display()))?;
+        let settings = format!(
+            "aligner={aligner_name}\nfrequency={frequency}\nmin_alignment_length={}\n\
+             map_pct_identity={}\n",
+            min_alignment_length.unwrap_or(0),
+            map_pct_identity.as_deref().unwrap_or("")
+        );
+        check_checkpoint_settings(dir, &settings)?;
+    }
+
     // Collect all genome groups from all input files
+    // File name stem of a genome prefix
+    let genome_stem = |genome: &str| genome.trim_end_matches(delimiter).replace(delimiter, "_");
     let mut all_groups: Vec<(String, String)> = Vec::new(); // (genome_prefix, source_fasta)
 
     for fasta_file in fasta_files {
         let path = Path::new(fasta_file);
-        let groups = detect_genome_groups(path)?;
+        let groups = detect_genome_groups(path, delimiter, prefix_depth)?;
 
         for group in groups {
             all_groups.push((group, fasta_file.clone()));
@@ -2061,16 +2564,62 @@ fn align_all_pairs_mode(
 
     // Write per-genome FASTA files
     let mut genome_files: HashMap<String, std::path::PathBuf> = HashMap::new();
+    // --index-dir: stamps of the genomes to (re)build, and those whose FASTA
+    // and index are still current
+    let stamp_path = |genome: &str| temp_genome_dir.join(format!("{}.stamp", genome_stem(genome)));
+    let mut stale_stamps: HashMap<String, String> = HashMap::new();
 
     for (genome_prefix, source_file) in &all_groups {
         if genome_files.contains_key(genome_prefix) {
             continue; // Already processed
         }
 
-        let genome_name = genome_prefix.trim_end_matches('#').replace('#', "_");
-        let genome_path = temp_genome_dir.join(format!("{genome_name}.fa"));
+        let genome_path = temp_genome_dir.join(format!("{}.fa", genome_stem(genome_prefix)));
+        let current = if index_dir.is_some() {
+            let stamp = index_stamp(Path::new(source_file), frequency)?;
+            let recorded = std::fs::read_to_string(stamp_path(genome_prefix)).ok();
+            if genome_path.exists() && recorded.as_deref() == Some(stamp.as_str()) {
+                true
+            } else {
+                if genome_path.exists() && !quiet {
+                    timing.log(
+                        "split",
+                        &format!(
+                            "Rebuilding {genome_prefix}: its source or k-mer frequency changed"
+                        ),
+                    );
+                }
+                // Dropped until the genome is rebuilt, so an interrupted
+                // rebuild is never taken as current
+                let _ = std::fs::remove_file(stamp_path(genome_prefix));
+                stale_stamps.insert(genome_prefix.clone(), stamp);
+                false
+            }
+        } else {
+            false
+        };
+        if current {
+            if !quiet {
+                timing.log(
+                    "split",
+                    &format!("Reusing {} for {genome_prefix}", genome_path.display()),
+                );
+            }
+            genome_files.insert(genome_prefix.clone(), genome_path);
+            continue;
+        }
 
-        let seq_count = write_genome_fasta(Path::new(source_file), &genome_path, genome_prefix)?;
+        // Written under another name first, so an interrupted write is never
+        // reused from --index-dir
+        let partial_path = genome_path.with_extension("fa.partial");
+        let seq_count = write_genome_fasta(
+            Path::new(source_file),
+            &partial_path,
+            genome_prefix,
+            delimiter,
+            prefix_depth,
+        )?;
+        std::fs::rename(&partial_path, &genome_path)?;
 
         if !quiet {
             timing.log(
@@ -2091,6 +2640,86 @@ fn align_all_pairs_mode(
     let mut genome_prefixes: Vec<String> = genome_files.keys().cloned().collect();
     genome_prefixes.sort();
 
+    // With --add-to, pairs of two already-aligned genomes are skipped
+    let is_existing = |genome: &str| existing_genomes.is_some_and(|e| e.contains(genome));
+    if let Some(existing) = existing_genomes {
+        if !quiet {
+            timing.log(
+                "align",
+                &format!(
+                    "Adding {} new genomes to {} already aligned",
+                    genome_prefixes.len().saturating_sub(existing.len()),
+                    existing.len()
+                ),
+            );
+        }
+    }
+
+    // With --sparsify-pairs only the selected genome pairs are aligned, and
+    // with --max-mash-distance only those close enough
+    let sparsify_selects = *sparsify_pairs != knn_graph::SparsificationStrategy::None;
+    let mut selected_pairs: Option<std::collections::HashSet<(usize, usize)>> = None;
+    // (i, j) with i < j -> mash distance, for pairs skipped as too divergent
+    let mut distant_pairs: std::collections::BTreeMap<(usize, usize), f64> =
+        std::collections::BTreeMap::new();
+    if sparsify_selects || max_mash_distance.is_some() {
+        let sketches = genome_prefixes
+            .iter()
+            .map(|genome| sketch_genome_fasta(&genome_files[genome], mash_params))
+            .collect::<Result<Vec<_>>>()?;
+        let n = genome_prefixes.len();
+        if sparsify_selects {
+            let selected: std::collections::HashSet<(usize, usize)> =
+                knn_graph::select_pairs_from_sketches_with_seed(&sketches, sparsify_pairs, seed)
+                    .into_iter()
+                    .map(|(i, j)| (i.min(j), i.max(j)))
+                    .collect();
+            if !quiet {
+                timing.log(
+                    "pairs",
+                    &format!(
+                        "Sparsification {sparsify_pairs}: aligning {} of {} genome pairs",
+                        selected.len(),
+                        n * n.saturating_sub(1) / 2
+                    ),
+                );
+            }
+            selected_pairs = Some(selected);
+        }
+        if let Some(max_distance) = max_mash_distance {
+            for i in 0..n {
+                for j in i + 1..n {
+                    let aligned = !(is_existing(&genome_prefixes[i])
+                        && is_existing(&genome_prefixes[j]))
+                        && selected_pairs.as_ref().is_none_or(|s| s.contains(&(i, j)));
+                    if !aligned {
+                        continue;
+                    }
+                    let distance = sketches[i].mash_distance(&sketches[j]);
+                    if distance > max_distance {
+                        distant_pairs.insert((i, j), distance);
+                    }
+                }
+            }
+            if !quiet {
+                timing.log(
+                    "pairs",
+                    &format!(
+                        "Skipping {} genome pairs with mash distance > {max_distance}",
+                        distant_pairs.len()
+                    ),
+                );
+            }
+        }
+    }
+    let is_selected = |i: usize, j: usize| {
+        let pair = (i.min(j), i.max(j));
+        selected_pairs
+            .as_ref()
+            .is_none_or(|selected| selected.contains(&pair))
+            && !distant_pairs.contains_key(&pair)
+    };
+
     if !quiet {
         timing.log(
             "index",
@@ -2100,7 +2729,7 @@ fn align_all_pairs_mode(
 
     // Create aligner with adaptive parameters
     let avg_seq = avg_seq_len_from_fai(Path::new(&fasta_files[0]))?;
-    let num_genomes = count_total_genomes(fasta_files)?;
+    let num_genomes = count_total_genomes(fasta_files, delimiter, prefix_depth)?;
     let wfmash_density = orchestrator::resolve_wfmash_density(sparsify, num_genomes);
     let aligner = create_aligner(
         aligner_name,
@@ -2111,6 +2740,9 @@ fn align_all_pairs_mode(
         tempdir.map(String::from),
         Some(avg_seq),
         wfmash_density,
+        fastga_args,
+        minimap2_preset,
+        delimiter,
     )?;
 
     // Step 1: Build GDB and GIX indices for all genomes (FastGA only)
@@ -2120,14 +2752,42 @@ fn align_all_pairs_mode(
             threads,
             min_alignment_length.unwrap_or(0),
             tempdir.map(String::from),
+            fastga_args,
         )?;
         for genome_prefix in &genome_prefixes {
             let fasta_path = &genome_files[genome_prefix];
 
+            // FastGA finds a .1gdb/.gix pair next to the FASTA by itself
+            let index_base = temp_genome_dir.join(genome_stem(genome_prefix));
+            let indexed = ["1gdb", "gix"]
+                .iter()
+                .all(|ext| Path::new(&format!("{}.{ext}", index_base.display())).exists());
+            if index_dir.is_some() && indexed && !stale_stamps.contains_key(genome_prefix) {
+                if !quiet {
+                    timing.log(
+                        "index",
+                        &format!(
+                            "Reusing index for {}",
+                            genome_prefix.trim_end_matches(delimiter)
+                        ),
+                    );
+                }
+                continue;
+            }
+            if stale_stamps.contains_key(genome_prefix) {
+                // An index of the previous FASTA must not be picked up
+                for ext in ["1gdb", "gix"] {
+                    let _ = std::fs::remove_file(format!("{}.{ext}", index_base.display()));
+                }
+            }
+
             if !quiet {
                 timing.log(
                     "index",
-                    &format!("Building index for {}", genome_prefix.trim_end_matches('#')),
+                    &format!(
+                        "Building index for {}",
+                        genome_prefix.trim_end_matches(delimiter)
+                    ),
                 );
             }
 
@@ -2149,6 +2809,11 @@ fn align_all_pairs_mode(
             }
         }
     }
+    for (genome_prefix, stamp) in &stale_stamps {
+        let path = stamp_path(genome_prefix);
+        std::fs::write(&path, stamp)
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+    }
 
     if !quiet {
         timing.log(
@@ -2157,76 +2822,140 @@ fn align_all_pairs_mode(
         );
     }
 
-    // Create merged PAF output file
+    // Create merged PAF output file. Each pair's PAF is fully written before
+    // it is appended, so a single buffered writer flushed at the end is enough.
     let merged_paf = tempfile::NamedTempFile::with_suffix(".paf")?;
-    let mut merged_output = File::create(merged_paf.path())?;
+    let mut merged_output =
+        std::io::BufWriter::with_capacity(io_buffer, File::create(merged_paf.path())?);
+
+    // Leading comment lines mark the pairs skipped by --max-mash-distance, so
+    // they read as intentionally empty; filtering always keeps them
+    for (&(i, j), distance) in &distant_pairs {
+        writeln!(
+            merged_output,
+            "{}\t{}\t{}\tmash_distance:f:{distance:.4}",
+            paf::SKIPPED_PAIR_MARKER,
+            genome_prefixes[i].trim_end_matches(delimiter),
+            genome_prefixes[j].trim_end_matches(delimiter)
+        )?;
+    }
+
+    // Align one pair and append its PAF to the merged output. With
+    // --checkpoint-dir each finished pair is kept there as
+    // {query}_vs_{target}.paf, and a pair whose file exists is not realigned.
+    let mut align_pair = |query: &str, target: &str| -> Result<usize> {
+        let (query_fasta, target_fasta) = (&genome_files[query], &genome_files[target]);
+        let Some(dir) = checkpoint_dir else {
+            // FastGA uses pre-built GDB/GIX indices, wfmash handles internally
+            let temp_paf = aligner.align_to_temp_paf(query_fasta, target_fasta)?;
+            return batch_align::append_paf_file(temp_paf.path(), &mut merged_output);
+        };
+        let checkpoint = dir.join(format!(
+            "{}_vs_{}.paf",
+            genome_stem(query),
+            genome_stem(target)
+        ));
+        if checkpoint.exists() {
+            if !quiet {
+                timing.log(
+                    "align",
+                    &format!("Reusing checkpoint {}", checkpoint.display()),
+                );
+            }
+        } else {
+            let temp_paf = aligner.align_to_temp_paf(query_fasta, target_fasta)?;
+            // Copied under another name first, so an interrupted copy is
+            // never taken for a finished pair
+            let partial = checkpoint.with_extension("paf.partial");
+            std::fs::copy(temp_paf.path(), &partial)
+                .with_context(|| format!("Failed to write checkpoint {}", partial.display()))?;
+            std::fs::rename(&partial, &checkpoint)?;
+        }
+        batch_align::append_paf_file(&checkpoint, &mut merged_output)
+    };
+
+    // Whether (i, j) is aligned: self-alignments only with keep_self, and
+    // neither pairs aligned before nor unselected pairs
+    let n = genome_prefixes.len();
+    let is_aligned = |i: usize, j: usize| {
+        if i == j {
+            keep_self && !is_existing(&genome_prefixes[i])
+        } else {
+            !(is_existing(&genome_prefixes[i]) && is_existing(&genome_prefixes[j]))
+                && is_selected(i, j)
+        }
+    };
+    let pair_count = (0..n)
+        .flat_map(|i| (0..n).map(move |j| (i, j)))
+        .filter(|&(i, j)| is_aligned(i, j))
+        .count();
+
+    // `pair 12/90 (13.3%) ETA 4h12m` for the pair after `done` finished ones,
+    // extrapolating linearly from the time spent on those
+    let align_start = timing.elapsed();
+    let progress = |done: usize| {
+        let percent = 100.0 * (done + 1) as f64 / pair_count as f64;
+        let eta = if done == 0 {
+            String::new()
+        } else {
+            let per_pair = (timing.elapsed() - align_start) / done as f64;
+            format!(" ETA {}", format_eta(per_pair * (pair_count - done) as f64))
+        };
+        format!("pair {}/{pair_count} ({percent:.1}%){eta}", done + 1)
+    };
 
     // Align all pairs in both directions (complete matrix)
     let mut total_pairs = 0;
     let mut total_alignments = 0;
 
-    for i in 0..genome_prefixes.len() {
-        for j in 0..genome_prefixes.len() {
-            // Skip self-alignments
-            if i == j {
-                continue;
-            }
-
+    for i in 0..n {
+        for j in 0..n {
             let genome_i = &genome_prefixes[i];
             let genome_j = &genome_prefixes[j];
 
-            let fasta_i = &genome_files[genome_i];
-            let fasta_j = &genome_files[genome_j];
+            if i == j || !is_aligned(i, j) {
+                continue;
+            }
 
             if !quiet {
                 timing.log(
                     "align",
                     &format!(
-                        "Aligning {} vs {}",
-                        genome_i.trim_end_matches('#'),
-                        genome_j.trim_end_matches('#')
+                        "{}: aligning {} vs {}",
+                        progress(total_pairs),
+                        genome_i.trim_end_matches(delimiter),
+                        genome_j.trim_end_matches(delimiter)
                     ),
                 );
             }
 
-            // Align (FastGA uses pre-built GDB/GIX indices, wfmash handles internally)
-            let temp_paf = aligner.align_to_temp_paf(fasta_i, fasta_j)?;
-
-            // Append to merged output
-            let paf_content = std::fs::read_to_string(temp_paf.path())?;
-            merged_output.write_all(paf_content.as_bytes())?;
-            merged_output.flush()?; // Flush after each alignment to prevent corruption
-
-            let alignment_count = paf_content.lines().count();
+            let alignment_count = align_pair(genome_i, genome_j)?;
             total_alignments += alignment_count;
             total_pairs += 1;
         }
 
         // Handle self-alignments if requested
-        if keep_self {
+        if is_aligned(i, i) {
             let genome_i = &genome_prefixes[i];
-            let fasta_i = &genome_files[genome_i];
 
             if !quiet {
                 timing.log(
                     "align",
-                    &format!("Self-aligning {}", genome_i.trim_end_matches('#')),
+                    &format!(
+                        "{}: self-aligning {}",
+                        progress(total_pairs),
+                        genome_i.trim_end_matches(delimiter)
+                    ),
                 );
             }
 
-            let temp_paf = aligner.align_to_temp_paf(fasta_i, fasta_i)?;
-
-            let paf_content = std::fs::read_to_string(temp_paf.path())?;
-            merged_output.write_all(paf_content.as_bytes())?;
-            merged_output.flush()?; // Flush after each alignment to prevent corruption
-
-            let alignment_count = paf_content.lines().count();
+            let alignment_count = align_pair(genome_i, genome_i)?;
             total_alignments += alignment_count;
             total_pairs += 1;
         }
     }
 
-    // Final flush (redundant but safe)
+    // Single flush once all pairs are appended
     merged_output.flush()?;
 
     if !quiet {
@@ -2238,22 +2967,17 @@ fn align_all_pairs_mode(
         );
     }
 
-    // Cleanup temp genome files and indices
-    for genome_path in genome_files.values() {
-        // Remove FASTA file
-        let _ = std::fs::remove_file(genome_path);
+    Ok(merged_paf)
+}
 
-        // Remove FastGA GIX files if present
-        if aligner_name == "fastga" {
-            let gdb_base = genome_path.with_extension("");
-            let _ = std::fs::remove_file(format!("{}.gix", gdb_base.display()));
-            let _ = std::fs::remove_file(format!("{}.ktab", gdb_base.display()));
-        }
+/// Compact duration for progress ETAs: `4h12m`, `3m05s` or `42s`
+fn format_eta(seconds: f64) -> String {
+    let seconds = seconds.max(0.0).round() as u64;
+    match (seconds / 3600, seconds % 3600 / 60, seconds % 60) {
+        (0, 0, s) => format!("{s}s"),
+        (0, m, s) => format!("{m}m{s:02}s"),
+        (h, m, _) => format!("{h}h{m:02}m"),
     }
-
-    let _ = std::fs::remove_dir(&temp_genome_dir);
-
-    Ok(merged_paf)
 }
 
 /// Resolve the effective FastGA k-mer frequency from user args and the input FASTA paths.
@@ -2262,23 +2986,209 @@ fn resolve_fastga_freq<P: AsRef<Path>>(args: &Args, fastas: &[P]) -> Result<usiz
         args.aln.frequency,
         args.aln.fastga_frequency_multiplier,
         fastas,
+        args.aln.prefix_delimiter,
     )
 }
 
+/// MAPQ assumed for records without one (`--default-mapq`)
+fn default_mapq(aln: &AlnArgs) -> u8 {
+    aln.default_mapq.unwrap_or(paf::MAPQ_UNAVAILABLE)
+}
+
+/// Split `--fastga-args` into the raw arguments passed to every FastGA run.
+fn fastga_args(aln: &AlnArgs) -> Result<Vec<String>> {
+    aln.fastga_args
+        .as_deref()
+        .map(fastga_integration::parse_extra_args)
+        .transpose()
+        .map(Option::unwrap_or_default)
+}
+
 /// Construct a `FastGAIntegration` with a fully-resolved k-mer frequency.
 fn create_fastga_integration(
     frequency: usize,
     num_threads: usize,
     min_alignment_length: u64,
     temp_dir: Option<String>,
+    extra_args: &[String],
 ) -> Result<fastga_integration::FastGAIntegration> {
     use crate::fastga_integration::FastGAIntegration;
-    Ok(FastGAIntegration::new(
-        frequency,
-        num_threads,
-        min_alignment_length,
-        temp_dir,
-    ))
+    Ok(
+        FastGAIntegration::new(frequency, num_threads, min_alignment_length, temp_dir)
+            .with_extra_args(extra_args.to_vec()),
+    )
+}
+
+/// Construct a `Minimap2Integration` for the `--minimap2-preset` preset.
+fn create_minimap2_integration(
+    num_threads: usize,
+    temp_dir: Option<String>,
+    preset: &str,
+) -> Result<minimap2_integration::Minimap2Integration> {
+    minimap2_integration::Minimap2Integration::new(num_threads, temp_dir, preset)
+}
+
+/// Scoring function selected by `--scoring`
+fn scoring_function(aln: &AlnArgs) -> Result<ScoringFunction> {
+    aln.scoring
+        .parse()
+        .map_err(|e: String| anyhow::anyhow!("--scoring: {e}"))
+}
+
+/// Sparsity fraction applied to already-computed alignments (`--sparsify`).
+/// Tree sampling is applied to the PAF separately, so it keeps everything here.
+fn sparsity_fraction(aln: &AlnArgs) -> Result<f64> {
+    use knn_graph::SparsificationStrategy;
+    match &aln.sparsify {
+        SparsificationStrategy::None => Ok(1.0),
+        SparsificationStrategy::Random(f) => Ok(*f),
+        SparsificationStrategy::TreeSampling(_, _, _) => Ok(1.0),
+        SparsificationStrategy::Auto
+        | SparsificationStrategy::Connectivity(_)
+        | SparsificationStrategy::WfmashDensity(_) => Err(anyhow::anyhow!(
+            "--sparsify '{}' is not valid for post-alignment PAF/1aln filtering; \
+             use `none`, `random:<f>`, a bare float, or `tree:<n>:<n>:<f>`",
+            aln.sparsify
+        )),
+    }
+}
+
+/// Scaffold jump and mass after adaptive clamping. We typically don't know
+/// the underlying average sequence length; this best-effort probe reads the
+/// first input if it happens to be a FASTA with a .fai sidecar.
+fn effective_scaffold_params(args: &Args, timing: &TimingContext) -> (u64, u64) {
+    let avg_seq_len_for_adaptive: Option<u64> = if !args.aln.no_adaptive_scaffolds {
+        args.files
+            .first()
+            .and_then(|p| avg_seq_len_from_fai(Path::new(p)).ok())
+    } else {
+        None
+    };
+    let (effective_scaffold_jump, effective_scaffold_mass) = pansn::clamp_scaffold_params(
+        args.aln.scaffold_jump,
+        args.aln.scaffold_mass,
+        avg_seq_len_for_adaptive,
+        !args.aln.no_adaptive_scaffolds,
+    );
+    if !args.aln.no_adaptive_scaffolds
+        && !args.quiet
+        && (effective_scaffold_jump != args.aln.scaffold_jump
+            || effective_scaffold_mass != args.aln.scaffold_mass)
+    {
+        timing.log(
+            "adaptive",
+            &format!(
+                "Scaffold thresholds clamped (jump {} -> {}, mass {} -> {})",
+                args.aln.scaffold_jump,
+                effective_scaffold_jump,
+                args.aln.scaffold_mass,
+                effective_scaffold_mass,
+            ),
+        );
+    }
+    (effective_scaffold_jump, effective_scaffold_mass)
+}
+
+/// Build the PAF filter shared by the main filtering path and `--serve`.
+/// Options that write extra report files are added by the caller.
+fn build_paf_filter(args: &Args, config: FilterConfig) -> PafFilter {
+    // Note: -f (no_filter) implies --self (keep self-mappings)
+    PafFilter::new(config)
+        .with_keep_self(args.aln.keep_self || args.aln.no_filter)
+        .with_self_genome(args.aln.self_genome)
+        .with_pair_selection(pair_selection(&args.aln))
+        .with_canonical_pairs(args.aln.canonical_pairs)
+        .with_strand(args.aln.strand)
+        .with_scaffolds_only(args.aln.scaffolds_only)
+        .with_no_rescue(args.aln.no_rescue)
+        .with_min_relative_score(args.aln.min_relative_score)
+        .with_split_overlapping_members(args.aln.split_overlapping_members)
+        .with_member_overlap_tolerance(args.aln.member_overlap_tolerance)
+        .with_allow_inversions(args.aln.allow_inversions)
+        .with_max_diagonal_deviation(args.aln.max_diagonal_deviation)
+        .with_block_length_from(args.aln.block_length_from)
+        .with_chain_identity_lis(args.aln.chain_identity_lis)
+        .with_reference_mode(args.aln.reference_mode)
+        .with_max_rescued_per_scaffold(args.aln.max_rescued_per_scaffold)
+        .with_rescue_metric(args.aln.rescue_metric)
+        .with_overlap_axis(args.aln.overlap_axis)
+        .with_coord_base(args.aln.coord_base, args.aln.output_coord_base)
+        .with_minus_strand_coords(args.aln.minus_strand_coords)
+        .with_trim_overlaps(args.aln.trim_overlaps)
+        .with_validate_output(args.aln.validate_output)
+        .with_component_tag(args.aln.component_tag)
+        .with_preserve_headers(args.aln.preserve_headers)
+        .with_min_pair_coverage(args.aln.min_pair_coverage)
+        .with_min_query_coverage(args.aln.min_query_coverage)
+        .with_min_target_coverage(args.aln.min_target_coverage)
+        .with_scaffold_keep_fraction(cli::scaffold_filter_fraction(&args.aln.scaffold_filter))
+        .with_repair_input(args.aln.repair_input)
+        .with_strict(args.aln.strict)
+        .with_group_by_chain(args.aln.group_by_chain)
+        .with_sort_output(args.aln.sort_output)
+        .with_no_plane_sweep(args.aln.no_plane_sweep)
+        .with_identity_transform(args.aln.identity_transform)
+        .with_max_output_bp(args.aln.max_output_bp)
+        .with_tag_sweep_rank(args.aln.tag_sweep_rank)
+        .with_mark_secondary(args.aln.mark_secondary)
+}
+
+/// Add the scaffold chain reports written while filtering, which apply to
+/// .1aln filtering as well as PAF
+fn with_chain_reports(filter: PafFilter, aln: &AlnArgs) -> PafFilter {
+    filter
+        .with_inversions_report(aln.inversions.as_ref().map(std::path::PathBuf::from))
+        .with_breakpoints_report(aln.breakpoints.as_ref().map(std::path::PathBuf::from))
+        .with_scaffold_bed(aln.scaffold_bed.as_ref().map(std::path::PathBuf::from))
+        .with_bridges_report(aln.report_bridges.as_ref().map(std::path::PathBuf::from))
+        .with_chain_report(aln.chain_report.as_ref().map(std::path::PathBuf::from))
+}
+
+/// Build the base filter config for `--serve` from the command line.
+/// There is no single input up front, so ANI-relative identity thresholds
+/// are not available.
+fn serve_base_config(args: &Args, timing: &TimingContext) -> Result<FilterConfig> {
+    let (mapping_filter_mode, mapping_max_per_query, mapping_max_per_target) =
+        parse_filter_mode(&args.aln.num_mappings, "plane sweep");
+    let (scaffold_filter_mode, scaffold_max_per_query, scaffold_max_per_target) =
+        parse_filter_mode(&args.aln.scaffold_filter, "scaffold");
+
+    let min_identity = parse_identity_value(&args.aln.min_identity, None)
+        .context("--serve does not support ANI-relative --min-aln-identity")?;
+    let min_scaffold_identity = if args.aln.min_scaffold_identity.is_empty() {
+        min_identity
+    } else {
+        parse_identity_value(&args.aln.min_scaffold_identity, None)
+            .context("--serve does not support ANI-relative --min-scaffold-identity")?
+    };
+    let (scaffold_jump, scaffold_mass) = effective_scaffold_params(args, timing);
+
+    Ok(FilterConfig {
+        chain_gap: scaffold_jump,
+        min_block_length: args.aln.block_length.unwrap_or(0),
+        mapping_filter_mode,
+        mapping_max_per_query,
+        mapping_max_per_target,
+        plane_sweep_secondaries: 0,
+        scaffold_filter_mode,
+        scaffold_max_per_query,
+        scaffold_max_per_target,
+        overlap_threshold: args.aln.overlap,
+        sparsity: sparsity_fraction(&args.aln)?,
+        no_merge: true,
+        scaffold_gap: scaffold_jump,
+        min_scaffold_length: scaffold_mass,
+        scaffold_overlap_threshold: args.aln.scaffold_overlap,
+        scaffold_max_deviation: args.aln.scaffold_dist,
+        prefix_delimiter: args.aln.prefix_delimiter,
+        prefix_depth: prefix_depth(&args.aln),
+        skip_prefix: args.aln.skip_prefix,
+        default_mapq: default_mapq(&args.aln),
+        scoring_function: scoring_function(&args.aln)?,
+        min_identity,
+        min_scaffold_identity,
+        min_scaffold_count: args.aln.min_scaffold_count,
+    })
 }
 
 /// Create an aligner backend based on the --aligner flag.
@@ -2310,6 +3220,7 @@ fn avg_seq_len_from_fai(path: &Path) -> Result<u64> {
     Ok(total_bases / num_seqs)
 }
 
+#[allow(clippy::too_many_arguments)]
 fn create_aligner(
     aligner_name: &str,
     frequency: usize,
@@ -2319,6 +3230,9 @@ fn create_aligner(
     temp_dir: Option<String>,
     avg_seq_len: Option<u64>,
     wfmash_density: Option<f64>,
+    fastga_args: &[String],
+    minimap2_preset: &str,
+    prefix_delimiter: char,
 ) -> Result<Box<dyn aligner::Aligner>> {
     // Invalid (aligner, flag) combinations are rejected at the CLI entry
     // point; by the time we reach here, `map_pct_identity` is guaranteed
@@ -2330,6 +3244,7 @@ fn create_aligner(
                 num_threads,
                 min_alignment_length.unwrap_or(0),
                 temp_dir,
+                fastga_args,
             )?;
             Ok(Box::new(fastga))
         }
@@ -2344,13 +3259,356 @@ fn create_aligner(
                 wfmash_density,
                 None, // num_mappings
                 None, // pairs_file
+                prefix_delimiter,
             )?;
             Ok(Box::new(wfmash))
         }
+        "minimap2" => {
+            let minimap2 = create_minimap2_integration(num_threads, temp_dir, minimap2_preset)?;
+            Ok(Box::new(minimap2))
+        }
         _ => anyhow::bail!("Unknown aligner: {aligner_name}"),
     }
 }
 
+/// Check the temp directory has room for FastGA's indexes before aligning
+/// FASTA input, sized from the input files (gzipped FASTA counted at ~4x).
+/// Batched runs (--max-disk / --batch-bytes) do their own budgeting.
+fn preflight_temp_space(args: &Args, input_file_types: &[FileType]) -> Result<()> {
+    let aligns_fasta = !input_file_types.is_empty()
+        && input_file_types.iter().all(|t| *t == FileType::Fasta);
+    if !aligns_fasta
+        || args.aln.aligner != "fastga"
+        || args.aln.max_disk.is_some()
+        || args.aln.batch_bytes.is_some()
+    {
+        return Ok(());
+    }
+
+    let total_bp = fasta_input_bytes(&args.files)?;
+    // Where FastGA will put its indexes: --temp-dir, $TMPDIR, else "."
+    let temp_dir = fastga_integration::get_temp_dir(args.aln.tempdir.as_deref());
+    let minimum = batch_align::estimate_min_disk(total_bp);
+    let estimated = batch_align::estimate_peak_disk(
+        total_bp,
+        args.files.len(),
+        None,
+        args.threads,
+        args.aln.zstd_compress,
+    );
+    disk_usage::check_temp_space(Path::new(&temp_dir), minimum, estimated)
+}
+
+/// Uncompressed size of the FASTA `files`, gzipped files counted at ~4x
+fn fasta_input_bytes(files: &[String]) -> Result<u64> {
+    let mut total = 0u64;
+    for file in files {
+        let size = std::fs::metadata(file)
+            .with_context(|| format!("Failed to stat {file}"))?
+            .len();
+        let compressed = file.ends_with(".gz") || file.ends_with(".bgz");
+        total += if compressed { size * 4 } else { size };
+    }
+    Ok(total)
+}
+
+/// Check there is room next to the filtered PAF `paf_path` for the .1aln
+/// PAFtoALN writes there. Trace points make the .1aln much smaller than a
+/// PAF with CIGARs, so a quarter of the PAF size is required and its full
+/// size is the estimate.
+fn preflight_paf_to_aln_space(paf_path: &Path) -> Result<()> {
+    let paf_bytes = std::fs::metadata(paf_path)
+        .with_context(|| format!("Failed to stat {}", paf_path.display()))?
+        .len();
+    // A bare file name has an empty parent, which statvfs cannot query
+    let dir = paf_path
+        .parent()
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+    disk_usage::check_temp_space(dir, paf_bytes / 4, paf_bytes)
+}
+
+/// Apply `--on-empty` once the number of output records is known.
+/// Returns whether output should be written.
+fn check_empty_output(records: usize, on_empty: OnEmpty) -> Result<bool> {
+    if records > 0 {
+        return Ok(true);
+    }
+    match on_empty {
+        OnEmpty::EmptyFile => Ok(true),
+        OnEmpty::NoFile => Ok(false),
+        OnEmpty::Error => anyhow::bail!("Filtering kept no records (--on-empty error)"),
+    }
+}
+
+/// Genomes already aligned in the `--add-to` PAF, after checking that the
+/// FASTA input holds all of them plus at least one new genome
+fn resolve_add_to(args: &Args, input_file_types: &[FileType]) -> Result<Option<BTreeSet<String>>> {
+    let Some(ref existing_paf) = args.aln.add_to else {
+        return Ok(None);
+    };
+    if input_file_types.is_empty() || input_file_types.iter().any(|ft| *ft != FileType::Fasta) {
+        anyhow::bail!("--add-to needs FASTA input holding the existing and the new genomes");
+    }
+    if args.aln.batch_bytes.is_some()
+        || args.aln.batch_size.is_some()
+        || args.aln.max_disk.is_some()
+    {
+        anyhow::bail!("--add-to aligns genome pairs one by one and cannot be batched");
+    }
+
+    let existing = detect_paf_genome_groups(
+        Path::new(existing_paf),
+        args.aln.prefix_delimiter,
+        prefix_depth(&args.aln),
+    )
+    .with_context(|| format!("Failed to read --add-to PAF {existing_paf}"))?;
+    anyhow::ensure!(
+        !existing.is_empty(),
+        "No PanSN genomes (sample#hap#contig names) found in {existing_paf}"
+    );
+    let mut input_genomes = BTreeSet::new();
+    for file in &args.files {
+        input_genomes.extend(detect_genome_groups(
+            Path::new(file),
+            args.aln.prefix_delimiter,
+            prefix_depth(&args.aln),
+        )?);
+    }
+    if let Some(missing) = existing.difference(&input_genomes).next() {
+        anyhow::bail!(
+            "Genome {missing} of {existing_paf} is not in the FASTA input; \
+             --add-to needs the sequences of the existing genomes too"
+        );
+    }
+    anyhow::ensure!(
+        input_genomes.len() > existing.len(),
+        "No new genomes to add: every genome of the FASTA input is already in {existing_paf}"
+    );
+    Ok(Some(existing))
+}
+
+/// Put the records of the `--add-to` PAF in front of the filtered new
+/// records in `output_path`. Returns the number of existing records.
+fn prepend_existing_records(existing_paf: &Path, output_path: &Path) -> Result<usize> {
+    use std::io::Write;
+
+    let merged_path = output_path.with_extension("merged.paf");
+    let mut writer = std::io::BufWriter::new(File::create(&merged_path)?);
+    let mut existing = 0;
+    for line in paf::open_paf_input(existing_paf)?.lines() {
+        let line = line?;
+        if !line.is_empty() && !paf::is_header_line(&line) {
+            existing += 1;
+        }
+        writeln!(writer, "{line}")?;
+    }
+    std::io::copy(&mut File::open(output_path)?, &mut writer)?;
+    writer.flush()?;
+    drop(writer);
+    std::fs::rename(&merged_path, output_path)?;
+    Ok(existing)
+}
+
+/// Compress the file at `path` into `output` as BGZF (`--bgzip`)
+fn write_bgzf<W: std::io::Write>(path: &Path, output: W) -> std::io::Result<()> {
+    let mut writer = noodles::bgzf::io::Writer::new(output);
+    std::io::copy(&mut File::open(path)?, &mut writer)?;
+    writer.finish()?;
+    Ok(())
+}
+
+/// Run `write` on locked stdout and flush it. A reader that closed the pipe
+/// early (`sweepga ... | head`) ends the output cleanly instead of failing.
+fn write_stdout(
+    write: impl FnOnce(&mut std::io::StdoutLock<'static>) -> std::io::Result<()>,
+) -> Result<()> {
+    use std::io::Write;
+
+    let mut handle = std::io::stdout().lock();
+    match write(&mut handle).and_then(|()| handle.flush()) {
+        Err(e) if e.kind() == std::io::ErrorKind::BrokenPipe => Ok(()),
+        result => Ok(result?),
+    }
+}
+
+/// Write the `--profile` folded stacks, if requested
+fn write_profile(args: &Args) -> Result<()> {
+    if let Some(ref path) = args.profile {
+        profile::disable();
+        let stacks = profile::write_folded(Path::new(path))?;
+        log::info!("[sweepga] Wrote {stacks} profile stacks to {path}");
+    }
+    Ok(())
+}
+
+/// The FASTA files among the inputs, which --axt and --maf take bases from
+fn input_fastas(args: &Args, input_file_types: &[FileType]) -> Vec<std::path::PathBuf> {
+    args.files
+        .iter()
+        .zip(input_file_types)
+        .filter(|(_, ft)| **ft == FileType::Fasta)
+        .map(|(f, _)| std::path::PathBuf::from(f))
+        .collect()
+}
+
+/// `--split-strand` output files for `output_file`: `out.paf` becomes
+/// `out.fwd.paf` and `out.rev.paf`
+fn split_strand_paths(output_file: &str) -> (String, String) {
+    let base = output_file.strip_suffix(".paf").unwrap_or(output_file);
+    (format!("{base}.fwd.paf"), format!("{base}.rev.paf"))
+}
+
+/// Split the filtered PAF at `paf_path` into `dir/<query>__<target>.paf`,
+/// one file per genome pair with records or a skipped-pair marker
+/// (`--output-dir`); genome names are the PanSN prefixes with `_` for the
+/// delimiter. Each line goes straight to its pair's file. Returns the files
+/// written, sorted.
+fn write_pair_outputs(
+    paf_path: &Path,
+    dir: &Path,
+    delimiter: char,
+    prefix_depth: usize,
+) -> Result<Vec<std::path::PathBuf>> {
+    use std::collections::hash_map::Entry;
+    use std::io::Write;
+
+    std::fs::create_dir_all(dir)
+        .with_context(|| format!("Failed to create output directory {}", dir.display()))?;
+    let stem = |genome: &str| genome.trim_end_matches(delimiter).replace(delimiter, "_");
+    let mut writers: HashMap<(String, String), std::io::BufWriter<File>> = HashMap::new();
+    let mut files = Vec::new();
+    for line in BufReader::new(File::open(paf_path)?).lines() {
+        let line = line?;
+        // A skipped pair gets a file holding just its marker line
+        let key = if let Some((query, target)) = paf::skipped_pair(&line) {
+            (stem(query), stem(target))
+        } else {
+            if line.is_empty() || paf::is_header_line(&line) {
+                continue;
+            }
+            let fields: Vec<&str> = line.splitn(7, '\t').collect();
+            let (Some(query), Some(target)) = (fields.first(), fields.get(5)) else {
+                continue;
+            };
+            (
+                stem(pansn::genome_prefix(query, delimiter, prefix_depth)),
+                stem(pansn::genome_prefix(target, delimiter, prefix_depth)),
+            )
+        };
+        let writer = match writers.entry(key) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => {
+                let (query, target) = entry.key();
+                let path = dir.join(format!("{query}__{target}.paf"));
+                let file = File::create(&path)
+                    .with_context(|| format!("Failed to create {}", path.display()))?;
+                files.push(path);
+                entry.insert(std::io::BufWriter::new(file))
+            }
+        };
+        writeln!(writer, "{line}")?;
+    }
+    for writer in writers.values_mut() {
+        writer.flush()?;
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Write the `--manifest` JSON, if requested, once all outputs are in place.
+/// `config` is `None` for runs that filter nothing (--no-filter, --joblist).
+fn write_run_manifest(
+    args: &Args,
+    config: Option<&FilterConfig>,
+    stats: Option<&paf_filter::FilterStats>,
+    pair_outputs: &[std::path::PathBuf],
+    timing: &TimingContext,
+    alignment_time: Option<f64>,
+) -> Result<()> {
+    if args.manifest.is_none() {
+        return Ok(());
+    }
+
+    let mut outputs = match (&args.output_file, args.aln.split_strand) {
+        (Some(output_file), true) => {
+            let (forward_file, reverse_file) = split_strand_paths(output_file);
+            [forward_file, reverse_file]
+                .into_iter()
+                .map(std::path::PathBuf::from)
+                .collect()
+        }
+        _ => match &args.output_dir {
+            Some(_) => pair_outputs.to_vec(),
+            None => vec![std::path::PathBuf::from(
+                args.output_file.as_deref().unwrap_or("-"),
+            )],
+        },
+    };
+    outputs.extend(
+        [
+            &args.aln.inversions,
+            &args.aln.breakpoints,
+            &args.aln.scaffold_bed,
+            &args.aln.report_bridges,
+            &args.aln.chain_report,
+            &args.aln.chain,
+            &args.aln.axt,
+            &args.aln.histogram,
+            &args.aln.dump_registry,
+            &args.aln.ani_matrix,
+            &args.aln.ani_matrix_counts,
+            &args.aln.ani_per_chrom,
+        ]
+        .into_iter()
+        .flatten()
+        .map(std::path::PathBuf::from),
+    );
+    if let Some(stats) = stats {
+        outputs.extend(stats.scaffold_bundles.iter().cloned());
+    }
+    let inputs = args.files.iter().map(std::path::PathBuf::from).collect();
+
+    write_manifest(args, config, stats, inputs, outputs, timing, alignment_time)
+}
+
+/// Write the `--manifest` JSON, if requested, describing `inputs` and `outputs`
+fn write_manifest(
+    args: &Args,
+    config: Option<&FilterConfig>,
+    stats: Option<&paf_filter::FilterStats>,
+    inputs: Vec<std::path::PathBuf>,
+    mut outputs: Vec<std::path::PathBuf>,
+    timing: &TimingContext,
+    alignment_time: Option<f64>,
+) -> Result<()> {
+    let Some(path) = &args.manifest else {
+        return Ok(());
+    };
+
+    // --on-empty no-file leaves no output to describe
+    outputs.retain(|p| p.as_os_str() == "-" || p.exists());
+
+    let (total_elapsed, _) = timing.stats();
+    let mut timings = vec![("total".to_string(), total_elapsed)];
+    if let Some(align_time) = alignment_time {
+        timings.push(("alignment".to_string(), align_time));
+    }
+
+    manifest::RunManifest {
+        argv: std::env::args().collect(),
+        aligner: args.aln.aligner.clone(),
+        config: config.map_or(serde_json::Value::Null, |config| {
+            manifest::filter_config_json(config, &args.aln)
+        }),
+        stats: stats.map_or(serde_json::Value::Null, manifest::filter_stats_json),
+        inputs,
+        outputs,
+        timings,
+    }
+    .write(Path::new(path))
+}
+
 fn main() -> Result<()> {
     // Default to `info` level; users can override via RUST_LOG.
     env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info"))
@@ -2466,17 +3724,79 @@ fn main() -> Result<()> {
                  wfmash does not produce .1aln format."
             );
         }
-        if args.aln.frequency.is_some() {
+        if args.aln.frequency.is_some() {
+            anyhow::bail!(
+                "--fastga-frequency is only supported with --aligner fastga; \
+                 wfmash uses its own frequency estimation."
+            );
+        }
+    }
+    if args.aln.aligner == "fastga" && args.aln.map_pct_identity.is_some() {
+        anyhow::bail!(
+            "--map-pct-identity is only supported with --aligner wfmash; \
+             FastGA has no mapping-identity knob."
+        );
+    }
+    if args.aln.aligner == "minimap2" {
+        if args.output_1aln
+            || args
+                .output_file
+                .as_ref()
+                .is_some_and(|f| f.ends_with(".1aln"))
+        {
+            anyhow::bail!(".1aln output is only supported with --aligner fastga.");
+        }
+        if args.aln.frequency.is_some() || args.aln.map_pct_identity.is_some() {
+            anyhow::bail!(
+                "--fastga-frequency and --map-pct-identity do not apply to --aligner minimap2; \
+                 use --minimap2-preset."
+            );
+        }
+        if args.aln.batch_size.is_some()
+            || args.aln.batch_bytes.is_some()
+            || args.aln.max_disk.is_some()
+        {
             anyhow::bail!(
-                "--fastga-frequency is only supported with --aligner fastga; \
-                 wfmash uses its own frequency estimation."
+                "--aligner minimap2 does not support batched alignment \
+                 (--batch-size, --batch-bytes, --max-disk)"
             );
         }
     }
-    if args.aln.aligner == "fastga" && args.aln.map_pct_identity.is_some() {
-        anyhow::bail!(
-            "--map-pct-identity is only supported with --aligner wfmash; \
-             FastGA has no mapping-identity knob."
+
+    if let Some(fraction) = args.aln.min_pair_coverage {
+        anyhow::ensure!(
+            (0.0..=1.0).contains(&fraction),
+            "--min-pair-coverage must be between 0 and 1 (got {fraction})"
+        );
+    }
+    for (flag, fraction) in [
+        ("--min-query-coverage", args.aln.min_query_coverage),
+        ("--min-target-coverage", args.aln.min_target_coverage),
+    ] {
+        anyhow::ensure!(
+            (0.0..=1.0).contains(&fraction),
+            "{flag} must be between 0 and 1 (got {fraction})"
+        );
+    }
+
+    if let Some(distance) = args.aln.max_mash_distance {
+        anyhow::ensure!(
+            (0.0..=1.0).contains(&distance),
+            "--max-mash-distance must be between 0 and 1 (got {distance})"
+        );
+    }
+    if args.profile.is_some() {
+        profile::enable();
+    }
+
+    // Raw FastGA pass-through arguments
+    if args.aln.fastga_args.is_some() {
+        if args.aln.aligner != "fastga" {
+            anyhow::bail!("--fastga-args is only supported with --aligner fastga");
+        }
+        log::warn!(
+            "[sweepga] Passing unvalidated arguments to FastGA: {}",
+            fastga_args(&args.aln)?.join(" ")
         );
     }
 
@@ -2528,6 +3848,8 @@ fn main() -> Result<()> {
             None => Box::new(std::io::stdout()),
         };
         joblist::write_pair_commands(&pairs, &cfg, &mut out)?;
+        drop(out);
+        write_run_manifest(&args, None, None, &[], &timing, None)?;
         return Ok(());
     }
 
@@ -2545,6 +3867,186 @@ fn main() -> Result<()> {
             .as_ref()
             .is_some_and(|f| f.ends_with(".1aln"));
     let use_1aln_workflow = !input_is_paf && want_1aln_output;
+    let bgzip_output = args.bgzip
+        || args
+            .output_file
+            .as_ref()
+            .is_some_and(|f| f.ends_with(".gz"));
+
+    // Coordinate bases only describe PAF text; aligner output and .1aln are always 0-based
+    if args.aln.coord_base != 0 && !input_is_paf {
+        anyhow::bail!("--coord-base 1 only applies to PAF input");
+    }
+    if args.aln.output_coord_base != 0 && want_1aln_output {
+        anyhow::bail!("--output-coord-base 1 only applies to PAF output");
+    }
+    if args.aln.minus_strand_coords && want_1aln_output {
+        anyhow::bail!("--minus-strand-coords only applies to PAF output");
+    }
+    if args.aln.chain.is_some() && want_1aln_output {
+        anyhow::bail!("--chain only applies to PAF output");
+    }
+    if args.aln.scaffold_bundle.is_some() && want_1aln_output {
+        anyhow::bail!("--scaffold-bundle only applies to PAF output");
+    }
+    if args.aln.tag_sweep_rank && want_1aln_output {
+        anyhow::bail!("--tag-sweep-rank only applies to PAF output");
+    }
+    if args.aln.axt.is_some() && want_1aln_output {
+        anyhow::bail!("--axt only applies to PAF output");
+    }
+    // .1aln has no comment lines to mark the skipped pairs with
+    if args.aln.max_mash_distance.is_some() && want_1aln_output {
+        anyhow::bail!("--max-mash-distance only applies to PAF output");
+    }
+    if args.aln.axt.is_some() && !input_file_types.contains(&FileType::Fasta) {
+        anyhow::bail!("--axt needs FASTA input to take the aligned sequences from");
+    }
+    if args.aln.split_strand && (args.output_file.is_none() || want_1aln_output) {
+        anyhow::bail!("--split-strand requires a PAF --output-file");
+    }
+    if bgzip_output && (want_1aln_output || args.aln.split_strand || args.aln.no_filter) {
+        anyhow::bail!(
+            "--bgzip cannot be combined with .1aln output, --split-strand or --no-filter"
+        );
+    }
+    if args.aln.dump_registry.is_some() && use_1aln_workflow {
+        anyhow::bail!("--dump-registry only applies to PAF filtering");
+    }
+    if args.aln.identity_transform != IdentityTransform::None && use_1aln_workflow {
+        anyhow::bail!("--identity-transform only applies to PAF filtering");
+    }
+    if args.aln.max_output_bp.is_some() && use_1aln_workflow {
+        anyhow::bail!("--max-output-bp only applies to PAF filtering");
+    }
+    // The .1aln workflow takes only the record selection from the PAF filter
+    // (`unified_filter::filter_file_with`)
+    let paf_only_options = [
+        (args.aln.min_pair_coverage.is_some(), "--min-pair-coverage"),
+        (
+            args.aln.block_length_from != paf_filter::BlockLengthSource::Column,
+            "--block-length-from",
+        ),
+        (args.aln.trim_overlaps, "--trim-overlaps"),
+        (args.aln.component_tag, "--component-tag"),
+        (args.aln.group_by_chain, "--group-by-chain"),
+    ];
+    if use_1aln_workflow {
+        if let Some((_, flag)) = paf_only_options.iter().find(|(set, _)| *set) {
+            anyhow::bail!("{flag} only applies to PAF filtering");
+        }
+    }
+    if args.aln.low_memory
+        && (use_1aln_workflow
+            || args.aln.scaffold_jump != 0
+            || !(args.aln.reference_mode || args.aln.no_plane_sweep))
+    {
+        anyhow::bail!(
+            "--low-memory filters PAF one query at a time and needs --scaffold-jump 0 with \
+             --reference-mode or --no-plane-sweep"
+        );
+    }
+    if args.aln.ani_matrix.is_some() && use_1aln_workflow {
+        anyhow::bail!("--ani-matrix only applies to PAF filtering");
+    }
+    if args.aln.ani_per_chrom.is_some() && use_1aln_workflow {
+        anyhow::bail!("--ani-per-chrom only applies to PAF filtering");
+    }
+    if args.aln.sort_output.is_some() && (use_1aln_workflow || args.aln.no_filter) {
+        anyhow::bail!("--sort-output only applies to PAF filtering");
+    }
+    if args.aln.mark_secondary && (use_1aln_workflow || args.aln.no_filter) {
+        anyhow::bail!("--mark-secondary only applies to PAF filtering");
+    }
+    if args.aln.mark_secondary
+        && (args.aln.max_output_bp.is_some() || args.aln.min_pair_coverage.is_some())
+    {
+        anyhow::bail!(
+            "--mark-secondary cannot be combined with --max-output-bp or --min-pair-coverage"
+        );
+    }
+    if args.aln.max_memory.is_some() && use_1aln_workflow {
+        anyhow::bail!("--max-memory only applies to PAF filtering");
+    }
+    if args.aln.strict && use_1aln_workflow {
+        anyhow::bail!("--strict only applies to PAF filtering");
+    }
+    if args.aln.allow_inversions && use_1aln_workflow {
+        anyhow::bail!("--allow-inversions only applies to PAF filtering");
+    }
+    if args.aln.validate_output && (use_1aln_workflow || args.aln.no_filter) {
+        anyhow::bail!("--validate-output only applies to PAF filtering");
+    }
+    if args.aln.histogram.is_some() && (use_1aln_workflow || args.aln.no_filter) {
+        anyhow::bail!("--histogram only applies to PAF filtering");
+    }
+    if args.output_sam && (want_1aln_output || args.aln.split_strand || args.aln.no_filter) {
+        anyhow::bail!("--sam cannot be combined with .1aln output, --split-strand or --no-filter");
+    }
+    if args.output_maf && (want_1aln_output || args.aln.split_strand || args.aln.no_filter) {
+        anyhow::bail!("--maf cannot be combined with .1aln output, --split-strand or --no-filter");
+    }
+    if args.output_maf && (args.aln.output_coord_base != 0 || args.aln.minus_strand_coords) {
+        anyhow::bail!(
+            "--maf cannot be combined with --output-coord-base 1 or --minus-strand-coords"
+        );
+    }
+    if args.output_maf && !input_file_types.contains(&FileType::Fasta) {
+        anyhow::bail!("--maf needs FASTA input to take the aligned sequences from");
+    }
+    if args.aln.add_to.is_some() && (want_1aln_output || args.aln.split_strand) {
+        anyhow::bail!("--add-to only applies to single-file PAF output");
+    }
+    if args.output_dir.is_some()
+        && (want_1aln_output
+            || args.aln.split_strand
+            || args.aln.no_filter
+            || args.aln.add_to.is_some())
+    {
+        anyhow::bail!(
+            "--output-dir writes PAF only and cannot be combined with .1aln output, \
+             --split-strand, --no-filter or --add-to"
+        );
+    }
+    // Long-lived filter server: handle stdin requests until EOF, then exit
+    if args.serve {
+        if want_1aln_output || args.aln.no_filter {
+            anyhow::bail!("--serve writes PAF and cannot be combined with .1aln or --no-filter");
+        }
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(args.threads)
+            .build_global()?;
+
+        let config = serve_base_config(&args, &timing)?;
+        if !args.quiet {
+            timing.log("serve", "Waiting for filter requests on stdin");
+        }
+        let summary = serve::serve(
+            std::io::stdin().lock(),
+            std::io::stdout().lock(),
+            &config,
+            |config| build_paf_filter(&args, config),
+        )?;
+        if !args.quiet {
+            timing.log("serve", &format!("Handled {} requests", summary.handled));
+        }
+        // The manifest describes the files actually filtered, not argv
+        let (inputs, outputs) = summary.filtered.into_iter().unzip();
+        write_manifest(&args, Some(&config), None, inputs, outputs, &timing, None)?;
+        return Ok(());
+    }
+
+    let add_to_genomes = resolve_add_to(&args, &input_file_types)?;
+
+    // Fail before any alignment starts rather than on the first pair
+    if args.aln.aligner == "minimap2"
+        && input_file_types
+            .iter()
+            .any(|t| matches!(t, FileType::Fasta | FileType::Agc))
+    {
+        minimap2_integration::find_binary()?;
+    }
+    preflight_temp_space(&args, &input_file_types)?;
 
     if use_1aln_workflow {
         // PURE .1ALN WORKFLOW - FastGA produces .1aln, filter as .1aln, output .1aln
@@ -2562,30 +4064,80 @@ fn main() -> Result<()> {
                 (None, args.files[0].clone())
             } else if !input_file_types.is_empty() && input_file_types[0] == FileType::Fasta {
                 // FASTA input - use FastGA to produce .1aln
-                if args.files.len() > 1 {
-                    // log::info!(
-                    //     "[sweepga] ERROR: Multiple FASTA files not supported in .1aln workflow yet"
-                    // );
-                    // log::info!("[sweepga] Use --paf flag to use PAF workflow for multiple files");
-                    std::process::exit(1);
+                if input_file_types.iter().any(|ft| *ft != FileType::Fasta) {
+                    anyhow::bail!("The .1aln workflow cannot mix FASTA with other inputs");
                 }
 
                 let alignment_start = Instant::now();
-                // Canonicalize path to avoid empty parent directory issues in fastga-rs
-                let path = std::fs::canonicalize(&args.files[0])
-                    .with_context(|| format!("Failed to resolve path: {}", args.files[0]))?;
+                // Two single-genome FASTAs are aligned one against the other,
+                // as in the PAF workflow; FastGA's .1aln then refers to the
+                // GDBs of both files. More FASTAs are aligned all-vs-all as one
+                // file: per-pair .1aln files have their own GDBs, which
+                // AlnWriter cannot merge. The directory also collects FastGA's
+                // indices of the combined FASTA, and is removed once the .1aln
+                // has been copied out.
+                let single_genome = |file: &String| -> Result<bool> {
+                    let groups = detect_genome_groups(
+                        Path::new(file),
+                        args.aln.prefix_delimiter,
+                        prefix_depth(&args.aln),
+                    )?;
+                    Ok(groups.len() <= 1)
+                };
+                let mut combined_dir = None;
+                let (target, query) = if args.files.len() == 2
+                    && single_genome(&args.files[0])?
+                    && single_genome(&args.files[1])?
+                {
+                    // Canonicalize paths to avoid empty parent directory issues in fastga-rs
+                    let target = std::fs::canonicalize(&args.files[0])
+                        .with_context(|| format!("Failed to resolve path: {}", args.files[0]))?;
+                    let query = std::fs::canonicalize(&args.files[1])
+                        .with_context(|| format!("Failed to resolve path: {}", args.files[1]))?;
+                    (target, query)
+                } else if args.files.len() > 1 {
+                    let mut builder = tempfile::Builder::new();
+                    builder.prefix("sweepga_combined_");
+                    let dir = match &args.aln.tempdir {
+                        Some(base) => builder.tempdir_in(base)?,
+                        None => builder.tempdir()?,
+                    };
+                    let path = dir.path().join("combined.fa");
+                    let seq_count = concatenate_fastas(&args.files, &path)?;
+                    if !args.quiet {
+                        timing.log(
+                            "detect",
+                            &format!(
+                                "Combined {} FASTA files ({seq_count} sequences)",
+                                args.files.len()
+                            ),
+                        );
+                    }
+                    combined_dir = Some(dir);
+                    (path.clone(), path)
+                } else {
+                    // Canonicalize path to avoid empty parent directory issues in fastga-rs
+                    let path = std::fs::canonicalize(&args.files[0])
+                        .with_context(|| format!("Failed to resolve path: {}", args.files[0]))?;
+                    (path.clone(), path)
+                };
                 let fastga = create_fastga_integration(
                     resolve_fastga_freq(&args, &args.files)?,
                     args.threads,
                     args.aln.block_length.unwrap_or(0),
                     args.aln.tempdir.clone(),
+                    &fastga_args(&args.aln)?,
                 )?;
 
                 if !args.quiet {
-                    timing.log("align", &format!("Running FastGA on {}", args.files[0]));
+                    timing.log(
+                        "align",
+                        &format!("Running FastGA on {}", args.files.join(", ")),
+                    );
                 }
 
-                let temp_1aln = fastga.align_to_temp_1aln(&path, &path)?;
+                let temp_1aln = fastga.align_to_temp_1aln(&target, &query)?;
+                drop(combined_dir);
                 alignment_time = Some(alignment_start.elapsed().as_secs_f64());
 
                 if !args.quiet {
@@ -2616,65 +4168,10 @@ fn main() -> Result<()> {
             parse_filter_mode(&args.aln.num_mappings, "plane sweep");
         let (scaffold_filter_mode, scaffold_max_per_query, scaffold_max_per_target) =
             parse_filter_mode(&args.aln.scaffold_filter, "scaffold");
-        let scoring_function = match args.aln.scoring.as_str() {
-            "ani" | "identity" => ScoringFunction::Identity,
-            "length" => ScoringFunction::Length,
-            "length-ani" | "length-identity" => ScoringFunction::LengthIdentity,
-            "matches" => ScoringFunction::Matches,
-            "log-length-ani" | "log-length-identity" => ScoringFunction::LogLengthIdentity,
-            _ => ScoringFunction::LogLengthIdentity,
-        };
-
-        // Sparsification strategy (already parsed by clap via FromStr).
-        use knn_graph::SparsificationStrategy;
-        let sparsity_fraction = match &args.aln.sparsify {
-            SparsificationStrategy::None => 1.0,
-            SparsificationStrategy::Random(f) => *f,
-            SparsificationStrategy::TreeSampling(_, _, _) => 1.0, // Tree filtering handled separately
-            SparsificationStrategy::Auto
-            | SparsificationStrategy::Connectivity(_)
-            | SparsificationStrategy::WfmashDensity(_) => {
-                return Err(anyhow::anyhow!(
-                    "--sparsify '{}' is not valid for post-alignment PAF/1aln filtering; \
-                     use `none`, `random:<f>`, a bare float, or `tree:<n>:<n>:<f>`",
-                    args.aln.sparsify
-                ));
-            }
-        };
-
-        // If the user requested --adaptive-scaffolds and we have a FASTA
-        // input with an available .fai, clamp scaffold thresholds to the
-        // sequence-length scale. For .1aln-only input we have no FASTA to
-        // inspect, so clamping is a no-op.
-        let avg_seq_len_for_adaptive: Option<u64> = if !args.aln.no_adaptive_scaffolds {
-            args.files
-                .first()
-                .and_then(|p| avg_seq_len_from_fai(Path::new(p)).ok())
-        } else {
-            None
-        };
-        let (effective_scaffold_jump, effective_scaffold_mass) = pansn::clamp_scaffold_params(
-            args.aln.scaffold_jump,
-            args.aln.scaffold_mass,
-            avg_seq_len_for_adaptive,
-            !args.aln.no_adaptive_scaffolds,
-        );
-        if !args.aln.no_adaptive_scaffolds
-            && !args.quiet
-            && (effective_scaffold_jump != args.aln.scaffold_jump
-                || effective_scaffold_mass != args.aln.scaffold_mass)
-        {
-            timing.log(
-                "adaptive",
-                &format!(
-                    "Scaffold thresholds clamped (jump {} -> {}, mass {} -> {})",
-                    args.aln.scaffold_jump,
-                    effective_scaffold_jump,
-                    args.aln.scaffold_mass,
-                    effective_scaffold_mass,
-                ),
-            );
-        }
+        let scoring_function = scoring_function(&args.aln)?;
+        let sparsity_fraction = sparsity_fraction(&args.aln)?;
+        let (effective_scaffold_jump, effective_scaffold_mass) =
+            effective_scaffold_params(&args, &timing);
 
         let filter_config = FilterConfig {
             chain_gap: effective_scaffold_jump,
@@ -2693,14 +4190,18 @@ fn main() -> Result<()> {
             min_scaffold_length: effective_scaffold_mass,
             scaffold_overlap_threshold: args.aln.scaffold_overlap,
             scaffold_max_deviation: args.aln.scaffold_dist,
-            prefix_delimiter: '#',
-            skip_prefix: false,
+            prefix_delimiter: args.aln.prefix_delimiter,
+            prefix_depth: prefix_depth(&args.aln),
+            skip_prefix: args.aln.skip_prefix,
+            default_mapq: default_mapq(&args.aln),
             scoring_function,
             min_identity: 0.0,
             min_scaffold_identity: 0.0,
+            min_scaffold_count: args.aln.min_scaffold_count,
         };
 
         // Step 2.6: Apply tree filtering if requested (natively on .1aln format)
+        use knn_graph::SparsificationStrategy;
         let tree_filtered_input =
             if let &SparsificationStrategy::TreeSampling(k_nearest, k_farthest, random_fraction) =
                 &args.aln.sparsify
@@ -2718,6 +4219,8 @@ fn main() -> Result<()> {
                     k_nearest,
                     k_farthest,
                     random_fraction,
+                    filter_config.prefix_delimiter,
+                    filter_config.prefix_depth,
                     args.quiet,
                 )?;
 
@@ -2734,33 +4237,40 @@ fn main() -> Result<()> {
         };
 
         // Step 3: Filter .1aln directly using unified_filter (format-preserving)
-        use crate::unified_filter::filter_file;
-        if let Some(ref output_file) = args.output_file {
-            filter_file(
-                final_filter_input,
-                output_file,
-                &filter_config,
-                false,
-                args.aln.keep_self,
-            )?;
+        use crate::unified_filter::filter_file_with;
+        let filter = with_chain_reports(build_paf_filter(&args, filter_config.clone()), &args.aln);
+        let stats = if let Some(ref output_file) = args.output_file {
+            let (records, stats) =
+                filter_file_with(final_filter_input, output_file, &filter, false)?;
+            let write_output = check_empty_output(records, args.on_empty);
+            if !matches!(write_output, Ok(true)) {
+                let _ = std::fs::remove_file(output_file);
+            }
+            write_output?;
+            stats
         } else {
             // Write to temp file then copy to stdout
             let temp_output = tempfile::NamedTempFile::with_suffix(".1aln")?;
-            filter_file(
-                final_filter_input,
-                temp_output.path(),
-                &filter_config,
-                false,
-                args.aln.keep_self,
-            )?;
+            let (records, stats) =
+                filter_file_with(final_filter_input, temp_output.path(), &filter, false)?;
 
-            // Copy binary to stdout
-            use std::io::copy;
-            let mut file = std::fs::File::open(temp_output.path())?;
-            let stdout = std::io::stdout();
-            let mut handle = stdout.lock();
-            copy(&mut file, &mut handle)?;
-        }
+            if check_empty_output(records, args.on_empty)? {
+                // Copy binary to stdout
+                let mut file = std::fs::File::open(temp_output.path())?;
+                write_stdout(|out| std::io::copy(&mut file, out).map(drop))?;
+            }
+            stats
+        };
+
+        write_run_manifest(
+            &args,
+            Some(&filter_config),
+            Some(&stats),
+            &[],
+            &timing,
+            alignment_time,
+        )?;
+        write_profile(&args)?;
 
         if !args.quiet {
             let (total_elapsed, _) = timing.stats();
@@ -2774,6 +4284,11 @@ fn main() -> Result<()> {
         return Ok(());
     }
 
+    // .1aln input converted to PAF keeps its differences as cg:Z: CIGARs when
+    // matches scoring reads them or the filtered PAF becomes .1aln or SAM
+    let need_cigar =
+        args.aln.scoring == "matches" || want_1aln_output || args.output_sam || args.output_maf;
+
     // Detect file types and route accordingly
     let (_temp_paf, input_path) = if !args.files.is_empty() {
         let file_types = &input_file_types;
@@ -2800,7 +4315,11 @@ fn main() -> Result<()> {
                 // Canonicalize path to avoid empty parent directory issues in fastga-rs
                 let path = std::fs::canonicalize(&args.files[0])
                     .with_context(|| format!("Failed to resolve path: {}", args.files[0]))?;
-                let groups = detect_genome_groups(&path)?;
+                let groups = detect_genome_groups(
+                    &path,
+                    args.aln.prefix_delimiter,
+                    prefix_depth(&args.aln),
+                )?;
 
                 if groups.len() > 1 {
                     // Multiple genomes in single FASTA - use pairwise alignment
@@ -2839,7 +4358,12 @@ fn main() -> Result<()> {
                         args.aln.zstd_compress,
                         args.aln.zstd_level,
                         &args.aln.aligner,
+                        &fastga_args(&args.aln)?,
+                        &args.aln.minimap2_preset,
                         &args.aln.sparsify,
+                        &PairOptions::from_args(&args.aln, add_to_genomes.as_ref()),
+                        args.aln.io_buffer as usize,
+                        args.no_cleanup,
                     )?;
 
                     alignment_time = Some(alignment_start.elapsed().as_secs_f64());
@@ -2870,7 +4394,11 @@ fn main() -> Result<()> {
                     }
 
                     let avg_seq = avg_seq_len_from_fai(&path)?;
-                    let num_genomes = count_total_genomes(&[&path])?;
+                    let num_genomes = count_total_genomes(
+                        &[&path],
+                        args.aln.prefix_delimiter,
+                        prefix_depth(&args.aln),
+                    )?;
                     let wfmash_density =
                         orchestrator::resolve_wfmash_density(&args.aln.sparsify, num_genomes);
                     let aligner = create_aligner(
@@ -2882,6 +4410,9 @@ fn main() -> Result<()> {
                         args.aln.tempdir.clone(),
                         Some(avg_seq),
                         wfmash_density,
+                        &fastga_args(&args.aln)?,
+                        &args.aln.minimap2_preset,
+                        args.aln.prefix_delimiter,
                     )?;
                     let temp_paf = aligner.align_to_temp_paf(&path, &path)?;
 
@@ -2903,11 +4434,19 @@ fn main() -> Result<()> {
             }
             (2, true) => {
                 // Two FASTAs - check if they're the same or different
-                let groups1 = detect_genome_groups(Path::new(&args.files[0]))?;
-                let groups2 = detect_genome_groups(Path::new(&args.files[1]))?;
+                let groups1 = detect_genome_groups(
+                    Path::new(&args.files[0]),
+                    args.aln.prefix_delimiter,
+                    prefix_depth(&args.aln),
+                )?;
+                let groups2 = detect_genome_groups(
+                    Path::new(&args.files[1]),
+                    args.aln.prefix_delimiter,
+                    prefix_depth(&args.aln),
+                )?;
 
-                if groups1.len() > 1 || groups2.len() > 1 {
-                    // Multiple genomes across files - use pairwise alignment
+                if groups1.len() > 1 || groups2.len() > 1 || add_to_genomes.is_some() {
+                    // Multiple genomes across files (or --add-to) - use pairwise alignment
                     if !args.quiet {
                         timing.log(
                             "detect",
@@ -2943,7 +4482,12 @@ fn main() -> Result<()> {
                         args.aln.zstd_compress,
                         args.aln.zstd_level,
                         &args.aln.aligner,
+                        &fastga_args(&args.aln)?,
+                        &args.aln.minimap2_preset,
                         &args.aln.sparsify,
+                        &PairOptions::from_args(&args.aln, add_to_genomes.as_ref()),
+                        args.aln.io_buffer as usize,
+                        args.no_cleanup,
                     )?;
 
                     alignment_time = Some(alignment_start.elapsed().as_secs_f64());
@@ -2981,7 +4525,11 @@ fn main() -> Result<()> {
                     let avg_seq = avg_seq_len_from_fai(&target)?;
                     // Count genomes across both input FASTAs so `wfmash:auto`
                     // adapts to the full 2-file cohort size.
-                    let num_genomes = count_total_genomes(&args.files)?;
+                    let num_genomes = count_total_genomes(
+                        &args.files,
+                        args.aln.prefix_delimiter,
+                        prefix_depth(&args.aln),
+                    )?;
                     let wfmash_density =
                         orchestrator::resolve_wfmash_density(&args.aln.sparsify, num_genomes);
                     let aligner = create_aligner(
@@ -2993,6 +4541,9 @@ fn main() -> Result<()> {
                         args.aln.tempdir.clone(),
                         Some(avg_seq),
                         wfmash_density,
+                        &fastga_args(&args.aln)?,
+                        &args.aln.minimap2_preset,
+                        args.aln.prefix_delimiter,
                     )?;
                     let temp_paf = aligner.align_to_temp_paf(&target, &query)?;
 
@@ -3047,8 +4598,13 @@ fn main() -> Result<()> {
                     args.aln.zstd_compress,
                     args.aln.zstd_level,
                     &args.aln.aligner,
-                        &args.aln.sparsify,
-                    )?;
+                    &fastga_args(&args.aln)?,
+                    &args.aln.minimap2_preset,
+                    &args.aln.sparsify,
+                    &PairOptions::from_args(&args.aln, add_to_genomes.as_ref()),
+                    args.aln.io_buffer as usize,
+                    args.no_cleanup,
+                )?;
 
                 alignment_time = Some(alignment_start.elapsed().as_secs_f64());
                 if !args.quiet {
@@ -3077,7 +4633,13 @@ fn main() -> Result<()> {
                     );
                 }
 
-                let temp_paf = aln_to_paf(&args.files[0], args.threads)?;
+                let temp_paf = aln_to_paf(
+                    &args.files[0],
+                    args.threads,
+                    need_cigar,
+                    args.aln.aln_identity,
+                    args.aln.default_mapq,
+                )?;
                 let paf_path = temp_paf.path().to_str().unwrap().to_string();
 
                 if !args.quiet {
@@ -3155,7 +4717,11 @@ fn main() -> Result<()> {
                 let path = std::fs::canonicalize(&temp_path)
                     .with_context(|| format!("Failed to resolve path: {temp_path}"))?;
                 let avg_seq = avg_seq_len_from_fai(&path)?;
-                let num_genomes = count_total_genomes(&[&path])?;
+                let num_genomes = count_total_genomes(
+                    &[&path],
+                    args.aln.prefix_delimiter,
+                    prefix_depth(&args.aln),
+                )?;
                 let wfmash_density =
                     orchestrator::resolve_wfmash_density(&args.aln.sparsify, num_genomes);
                 let aligner = create_aligner(
@@ -3167,6 +4733,9 @@ fn main() -> Result<()> {
                     args.aln.tempdir.clone(),
                     Some(avg_seq),
                     wfmash_density,
+                    &fastga_args(&args.aln)?,
+                    &args.aln.minimap2_preset,
+                    args.aln.prefix_delimiter,
                 )?;
                 let temp_paf = aligner.align_to_temp_paf(&path, &path)?;
 
@@ -3197,7 +4766,13 @@ fn main() -> Result<()> {
                     timing.log("convert", "Converting .1aln from stdin to PAF");
                 }
 
-                let temp_paf = aln_to_paf(&temp_path, args.threads)?;
+                let temp_paf = aln_to_paf(
+                    &temp_path,
+                    args.threads,
+                    need_cigar,
+                    args.aln.aln_identity,
+                    args.aln.default_mapq,
+                )?;
                 let paf_path = temp_paf.path().to_str().unwrap().to_string();
 
                 if !args.quiet {
@@ -3226,13 +4801,14 @@ fn main() -> Result<()> {
         use std::io::{BufRead, Write};
 
         let input = crate::paf::open_paf_input(&input_path)?;
-        let stdout = std::io::stdout();
-        let mut output = stdout.lock();
-
-        for line in input.lines() {
-            writeln!(output, "{}", line?)?;
-        }
+        write_stdout(|out| {
+            for line in input.lines() {
+                writeln!(out, "{}", line?)?;
+            }
+            Ok(())
+        })?;
 
+        write_run_manifest(&args, None, None, &[], &timing, alignment_time)?;
         return Ok(());
     }
 
@@ -3244,66 +4820,10 @@ fn main() -> Result<()> {
     let (scaffold_filter_mode, scaffold_max_per_query, scaffold_max_per_target) =
         parse_filter_mode(&args.aln.scaffold_filter, "scaffold");
 
-    // Parse scoring function
-    let scoring_function = match args.aln.scoring.as_str() {
-        "ani" | "identity" => ScoringFunction::Identity,
-        "length" => ScoringFunction::Length,
-        "length-ani" | "length-identity" => ScoringFunction::LengthIdentity,
-        "matches" => ScoringFunction::Matches,
-        "log-length-ani" | "log-length-identity" => ScoringFunction::LogLengthIdentity,
-        _ => ScoringFunction::LogLengthIdentity,
-    };
-
-    // Sparsification strategy (already parsed by clap via FromStr).
-    use knn_graph::SparsificationStrategy;
-    let sparsity_fraction = match &args.aln.sparsify {
-        SparsificationStrategy::None => 1.0,
-        SparsificationStrategy::Random(f) => *f,
-        SparsificationStrategy::TreeSampling(_, _, _) => 1.0, // Tree filtering will be applied to PAF
-        SparsificationStrategy::Auto
-        | SparsificationStrategy::Connectivity(_)
-        | SparsificationStrategy::WfmashDensity(_) => {
-            return Err(anyhow::anyhow!(
-                "--sparsify '{}' is not valid for post-alignment PAF/1aln filtering; \
-                 use `none`, `random:<f>`, a bare float, or `tree:<n>:<n>:<f>`",
-                args.aln.sparsify
-            ));
-        }
-    };
-
-    // Adaptive scaffold clamping for PAF workflow. When input is a PAF we
-    // typically don't know the underlying average sequence length; this
-    // best-effort probe reads the first input if it happens to be a FASTA
-    // with a .fai sidecar (common for --paf runs over the same assembly).
-    let avg_seq_len_for_adaptive: Option<u64> = if !args.aln.no_adaptive_scaffolds {
-        args.files
-            .first()
-            .and_then(|p| avg_seq_len_from_fai(Path::new(p)).ok())
-    } else {
-        None
-    };
-    let (effective_scaffold_jump, effective_scaffold_mass) = pansn::clamp_scaffold_params(
-        args.aln.scaffold_jump,
-        args.aln.scaffold_mass,
-        avg_seq_len_for_adaptive,
-        !args.aln.no_adaptive_scaffolds,
-    );
-    if !args.aln.no_adaptive_scaffolds
-        && !args.quiet
-        && (effective_scaffold_jump != args.aln.scaffold_jump
-            || effective_scaffold_mass != args.aln.scaffold_mass)
-    {
-        timing.log(
-            "adaptive",
-            &format!(
-                "Scaffold thresholds clamped (jump {} -> {}, mass {} -> {})",
-                args.aln.scaffold_jump,
-                effective_scaffold_jump,
-                args.aln.scaffold_mass,
-                effective_scaffold_mass,
-            ),
-        );
-    }
+    let scoring_function = scoring_function(&args.aln)?;
+    let sparsity_fraction = sparsity_fraction(&args.aln)?;
+    let (effective_scaffold_jump, effective_scaffold_mass) =
+        effective_scaffold_params(&args, &timing);
 
     // Placeholder for identity values - will be calculated after we have input path
     let temp_config = FilterConfig {
@@ -3323,15 +4843,30 @@ fn main() -> Result<()> {
         min_scaffold_length: effective_scaffold_mass,
         scaffold_overlap_threshold: args.aln.scaffold_overlap,
         scaffold_max_deviation: args.aln.scaffold_dist,
-        prefix_delimiter: '#',
-        skip_prefix: false,
+        prefix_delimiter: args.aln.prefix_delimiter,
+        prefix_depth: prefix_depth(&args.aln),
+        skip_prefix: args.aln.skip_prefix,
+        default_mapq: default_mapq(&args.aln),
         scoring_function,
         min_identity: 0.0,          // Will be set later
         min_scaffold_identity: 0.0, // Will be set later
+        min_scaffold_count: args.aln.min_scaffold_count,
     };
 
     // Parse ANI calculation method
-    let ani_method = parse_ani_method(&args.aln.ani_method);
+    let score_weights = AniScoreWeights {
+        identity_weight: args.aln.ani_score_weight,
+        log_base: args.aln.ani_score_log_base.unwrap_or(std::f64::consts::E),
+    };
+    anyhow::ensure!(
+        score_weights.identity_weight >= 0.0,
+        "--ani-score-weight must not be negative"
+    );
+    anyhow::ensure!(
+        score_weights.log_base > 0.0 && score_weights.log_base != 1.0,
+        "--ani-score-log-base must be positive and not 1"
+    );
+    let ani_method = parse_ani_method(&args.aln.ani_method, score_weights);
     if ani_method.is_none() {
         // log::info!(
         //     "[sweepga] WARNING: Unknown ANI method '{}', using 'n50-identity'",
@@ -3341,20 +4876,62 @@ fn main() -> Result<()> {
     let ani_method = ani_method.unwrap_or(AniMethod::NPercentile(50.0, NSort::Identity));
 
     // Now calculate ANI if needed for identity thresholds
-    let ani_percentile = if args.aln.min_identity.to_lowercase().contains("ani")
+    let ani_values = if args.aln.min_identity.to_lowercase().contains("ani")
         || args.aln.min_scaffold_identity.to_lowercase().contains("ani")
     {
-        Some(calculate_ani_stats(&input_path, ani_method, args.quiet)?)
+        Some(calculate_ani_stats(
+            &input_path,
+            &pair_selection(&args.aln),
+            temp_config.prefix_delimiter,
+            temp_config.prefix_depth,
+            ani_method,
+            args.aln.identity_transform,
+            args.quiet,
+        )?)
     } else {
         None
     };
 
+    if let Some(ref matrix_path) = args.aln.ani_matrix {
+        let genomes = write_ani_matrix(
+            &input_path,
+            &pair_selection(&args.aln),
+            temp_config.prefix_delimiter,
+            temp_config.prefix_depth,
+            args.aln.identity_transform,
+            matrix_path,
+            args.aln.ani_matrix_counts.as_deref(),
+        )?;
+        if !args.quiet {
+            timing.log(
+                "ani",
+                &format!("Wrote ANI matrix of {genomes} genomes to {matrix_path}"),
+            );
+        }
+    }
+    if let Some(ref per_chrom_path) = args.aln.ani_per_chrom {
+        let pairs = write_ani_per_chrom(
+            &input_path,
+            &pair_selection(&args.aln),
+            temp_config.prefix_delimiter,
+            temp_config.prefix_depth,
+            args.aln.identity_transform,
+            per_chrom_path,
+        )?;
+        if !args.quiet {
+            timing.log(
+                "ani",
+                &format!("Wrote ANI of {pairs} chromosome pairs to {per_chrom_path}"),
+            );
+        }
+    }
+
     // Parse identity thresholds
-    let min_identity = parse_identity_value(&args.aln.min_identity, ani_percentile)?;
+    let min_identity = parse_identity_value(&args.aln.min_identity, ani_values.as_deref())?;
     let min_scaffold_identity = if args.aln.min_scaffold_identity.is_empty() {
         min_identity // If empty string, use min_identity
     } else {
-        parse_identity_value(&args.aln.min_scaffold_identity, ani_percentile)?
+        parse_identity_value(&args.aln.min_scaffold_identity, ani_values.as_deref())?
     };
 
     // Only report thresholds if they're non-zero
@@ -3400,8 +4977,11 @@ fn main() -> Result<()> {
     let (output_file, output_path_buf) = output_temp.keep()?;
     let output_path = output_path_buf.to_str().unwrap().to_string();
     drop(output_file); // Close the file handle so filter_paf can open it
+    // --split-strand: reverse-strand records go to a second temp file
+    let reverse_output_path = args.aln.split_strand.then(|| format!("{output_path}.rev"));
 
     // Apply tree-based sparsification if requested
+    use knn_graph::SparsificationStrategy;
     let tree_filtered_path = if let &SparsificationStrategy::TreeSampling(k_nearest, k_farthest, rand_frac) =
         &args.aln.sparsify
     {
@@ -3438,6 +5018,8 @@ fn main() -> Result<()> {
             k_nearest,
             k_farthest,
             rand_frac,
+            config.prefix_delimiter,
+            config.prefix_depth,
         )?;
 
         Some(tree_path)
@@ -3448,24 +5030,38 @@ fn main() -> Result<()> {
     // Use tree-filtered PAF if available, otherwise use original input
     let filter_input_path = tree_filtered_path.as_ref().unwrap_or(&input_path);
 
-    // Note: -f (no_filter) implies --self (keep self-mappings)
-    let filter = PafFilter::new(config)
-        .with_keep_self(args.aln.keep_self || args.aln.no_filter)
-        .with_scaffolds_only(args.aln.scaffolds_only);
-    filter.filter_paf(filter_input_path, &output_path)?;
+    let filter = with_chain_reports(build_paf_filter(&args, config.clone()), &args.aln)
+        .with_histogram(args.aln.histogram.as_ref().map(std::path::PathBuf::from))
+        .with_chain_output(args.aln.chain.as_ref().map(std::path::PathBuf::from))
+        .with_axt_output(
+            args.aln.axt.as_ref().map(std::path::PathBuf::from),
+            input_fastas(&args, &input_file_types),
+        )
+        .with_scaffold_bundle(args.aln.scaffold_bundle.as_ref().map(std::path::PathBuf::from))
+        .with_low_memory(args.aln.low_memory)
+        .with_max_memory(args.aln.max_memory)
+        .with_registry_output(args.aln.dump_registry.as_ref().map(std::path::PathBuf::from))
+        .with_reverse_output(reverse_output_path.as_ref().map(std::path::PathBuf::from));
+    let (mut records, stats) = filter.filter_paf_with_stats(filter_input_path, &output_path)?;
+    if let Some(ref existing_paf) = args.aln.add_to {
+        let existing = prepend_existing_records(Path::new(existing_paf), Path::new(&output_path))?;
+        if !args.quiet {
+            timing.log(
+                "merge",
+                &format!("Added {records} new records to the {existing} of {existing_paf}"),
+            );
+        }
+        records += existing;
+    }
+    let write_output = check_empty_output(records, args.on_empty).inspect_err(|_| {
+        let _ = std::fs::remove_file(&output_path);
+        if let Some(ref path) = reverse_output_path {
+            let _ = std::fs::remove_file(path);
+        }
+    })?;
 
     // Convert output format if requested
-    // Note: PAFtoALN automatically appends .paf to the input filename, so we need to strip it
-    let paftoaln_input_path = if output_1aln {
-        output_path
-            .strip_suffix(".paf")
-            .unwrap_or(&output_path)
-            .to_string()
-    } else {
-        output_path.clone()
-    };
-
-    let (final_output_path, _aln_temp) = if output_1aln {
+    let (final_output_path, _aln_temp) = if output_1aln && write_output {
         // Convert PAF to 1aln using PAFtoALN
         // Requires the original FASTA or .1aln file(s) for sequence metadata
         if input_file_types.is_empty() {
@@ -3486,51 +5082,31 @@ fn main() -> Result<()> {
                 (output_path.clone(), None::<tempfile::NamedTempFile>)
             } else {
                 // Convert filtered PAF to .1aln using PAFtoALN
-                // Find PAFtoALN binary (embedded)
-                let paftoaln_bin = sweepga::binary_paths::get_embedded_binary_path("PAFtoALN")
-                    .map_err(|_| anyhow::anyhow!("PAFtoALN tool not found. Cannot convert PAF to .1aln. Use --paf flag to output PAF format instead."))?;
-
                 if !args.quiet {
                     timing.log("convert", "Converting filtered PAF to .1aln");
                 }
 
-                // PAFtoALN usage: PAFtoALN [-T<int>] <alignments> <source1.fa|source1.1gdb> [<source2.fa|source2.1gdb>]
-                // Note: PAFtoALN automatically appends .paf to the alignments filename for input
-                // and creates <alignments>.1aln for output
-                let mut cmd = std::process::Command::new(&paftoaln_bin);
-                cmd.arg(format!("-T{}", args.threads))
-                    .arg(&paftoaln_input_path);
-
                 // Pass FASTA or .1gdb file(s) for sequence metadata
                 // If input is .1aln, pass matching .1gdb; if FASTA, pass FASTA
-                for input_file in &args.files {
-                    if input_file.ends_with(".1aln") {
-                        // Input is .1aln - pass matching .1gdb
-                        let gdb_path = input_file
-                            .strip_suffix(".1aln")
-                            .unwrap_or(input_file)
-                            .to_string()
-                            + ".1gdb";
-                        cmd.arg(&gdb_path);
-                    } else {
-                        // Input is FASTA - pass it directly
-                        cmd.arg(input_file);
-                    }
-                }
-
-                // Suppress PAFtoALN's normal output unless in verbose mode
-                if args.quiet {
-                    cmd.stdout(std::process::Stdio::null())
-                        .stderr(std::process::Stdio::null());
-                }
-
-                let status = cmd.status()?;
-                if !status.success() {
-                    anyhow::bail!("PAFtoALN conversion failed");
-                }
-
-                // PAFtoALN creates the output file as <input_path>.1aln
-                let aln_path = format!("{paftoaln_input_path}.1aln");
+                let sources: Vec<std::path::PathBuf> = args
+                    .files
+                    .iter()
+                    .map(|input_file| match input_file.strip_suffix(".1aln") {
+                        Some(base) => std::path::PathBuf::from(format!("{base}.1gdb")),
+                        None => std::path::PathBuf::from(input_file),
+                    })
+                    .collect();
+
+                preflight_paf_to_aln_space(Path::new(&output_path))?;
+                // cg:Z: CIGARs in the filtered PAF become trace points in the .1aln
+                let aln_path = fastga_integration::paf_to_aln(
+                    Path::new(&output_path),
+                    &sources,
+                    args.threads,
+                    args.quiet,
+                )?
+                .to_string_lossy()
+                .into_owned();
 
                 if !args.quiet {
                     timing.log("convert", "PAF to .1aln conversion complete");
@@ -3539,6 +5115,21 @@ fn main() -> Result<()> {
                 (aln_path, None::<tempfile::NamedTempFile>)
             }
         }
+    } else if args.output_sam && write_output {
+        let sam = tempfile::NamedTempFile::with_suffix(".sam")?;
+        let sam_records = sam::write_sam(Path::new(&output_path), sam.path())?;
+        if !args.quiet {
+            timing.log("convert", &format!("Wrote {sam_records} SAM records"));
+        }
+        (sam.path().to_string_lossy().into_owned(), Some(sam))
+    } else if args.output_maf && write_output {
+        let maf = tempfile::NamedTempFile::with_suffix(".maf")?;
+        let fastas = input_fastas(&args, &input_file_types);
+        let maf_blocks = maf::write_maf(Path::new(&output_path), maf.path(), &fastas)?;
+        if !args.quiet {
+            timing.log("convert", &format!("Wrote {maf_blocks} MAF blocks"));
+        }
+        (maf.path().to_string_lossy().into_owned(), Some(maf))
     } else {
         (output_path.clone(), None::<tempfile::NamedTempFile>)
     };
@@ -3547,33 +5138,77 @@ fn main() -> Result<()> {
     use std::io::{BufRead, BufReader, Write};
 
     // Write output (PAF or .1aln) to file or stdout
-    if let Some(output_file) = &args.output_file {
+    // Per-pair files written under --output-dir, for the run manifest
+    let mut pair_outputs = Vec::new();
+    if !write_output {
+        // --on-empty no-file: nothing to write
+    } else if let (Some(output_file), Some(reverse_path)) =
+        (&args.output_file, &reverse_output_path)
+    {
+        let (forward_file, reverse_file) = split_strand_paths(output_file);
+        std::fs::copy(&final_output_path, forward_file)?;
+        std::fs::copy(reverse_path, reverse_file)?;
+    } else if bgzip_output {
+        // BGZF is binary - compress straight into the file or raw stdout
+        match &args.output_file {
+            Some(output_file) => {
+                write_bgzf(Path::new(&final_output_path), File::create(output_file)?)?
+            }
+            None => write_stdout(|out| write_bgzf(Path::new(&final_output_path), out))?,
+        }
+    } else if let Some(output_file) = &args.output_file {
         std::fs::copy(&final_output_path, output_file)?;
+    } else if let Some(output_dir) = &args.output_dir {
+        pair_outputs = write_pair_outputs(
+            Path::new(&final_output_path),
+            Path::new(output_dir),
+            config.prefix_delimiter,
+            config.prefix_depth,
+        )?;
+        if !args.quiet {
+            timing.log(
+                "output",
+                &format!(
+                    "Wrote {} genome-pair PAF files to {output_dir}",
+                    pair_outputs.len()
+                ),
+            );
+        }
     } else if output_1aln {
         // .1aln is binary - copy bytes directly to stdout
-        use std::io::copy;
         let mut file = std::fs::File::open(&final_output_path)?;
-        let stdout = std::io::stdout();
-        let mut handle = stdout.lock();
-        copy(&mut file, &mut handle)?;
+        write_stdout(|out| std::io::copy(&mut file, out).map(drop))?;
     } else {
         // PAF is text - write line by line to stdout
         let file = std::fs::File::open(&final_output_path)?;
         let reader = BufReader::new(file);
-        let stdout = std::io::stdout();
-        let mut handle = stdout.lock();
-
-        for line in reader.lines() {
-            writeln!(handle, "{}", line?)?;
-        }
+        write_stdout(|out| {
+            for line in reader.lines() {
+                writeln!(out, "{}", line?)?;
+            }
+            Ok(())
+        })?;
     }
 
     // Clean up temp files
     let _ = std::fs::remove_file(&output_path);
+    if let Some(ref path) = reverse_output_path {
+        let _ = std::fs::remove_file(path);
+    }
     if output_1aln {
         let _ = std::fs::remove_file(&final_output_path);
     }
 
+    write_run_manifest(
+        &args,
+        Some(&config),
+        Some(&stats),
+        &pair_outputs,
+        &timing,
+        alignment_time,
+    )?;
+    write_profile(&args)?;
+
     if !args.quiet {
         let (total_elapsed, cpu_ratio) = timing.stats();
         let filtering_time = if let Some(align_time) = alignment_time {
@@ -0,0 +1,262 @@
+//! Run manifest (`--manifest`)
+//!
+//! A JSON record of one invocation for provenance tracking in workflow
+//! managers (Snakemake, Nextflow): the command line, sweepga and aligner
+//! versions, the resolved filter configuration, the filtering stage counts,
+//! input and output files with sizes and SHA-256 checksums, and timings.
+
+use anyhow::{Context, Result};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::fmt::Write as _;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+
+use crate::cli::AlnArgs;
+use crate::paf_filter::{FilterConfig, FilterStats};
+
+/// Aligner crate revisions pinned at build time (see build.rs)
+const FASTGA_REV: Option<&str> = option_env!("SWEEPGA_FASTGA_REV");
+const WFMASH_REV: Option<&str> = option_env!("SWEEPGA_WFMASH_REV");
+
+/// Everything recorded about one run. Checksums are computed when the
+/// manifest is serialized, so build it after all outputs are written.
+#[derive(Debug, Clone)]
+pub struct RunManifest {
+    pub argv: Vec<String>,
+    pub aligner: String,
+    pub config: Value,
+    /// Filtering stage counts ([`filter_stats_json`]), null without filtering
+    pub stats: Value,
+    pub inputs: Vec<PathBuf>,
+    /// Output files; `-` stands for stdout and gets no checksum
+    pub outputs: Vec<PathBuf>,
+    /// `(step, seconds)` pairs
+    pub timings: Vec<(String, f64)>,
+}
+
+impl RunManifest {
+    /// Serialize to JSON, hashing every input and output file
+    pub fn to_json(&self) -> Result<Value> {
+        let aligner_version = match self.aligner.as_str() {
+            "fastga" => FASTGA_REV.map(str::to_string),
+            "wfmash" => WFMASH_REV.map(str::to_string),
+            "minimap2" => crate::minimap2_integration::version().ok(),
+            _ => None,
+        };
+        let timings: serde_json::Map<String, Value> = self
+            .timings
+            .iter()
+            .map(|(step, secs)| (step.clone(), json!(secs)))
+            .collect();
+
+        Ok(json!({
+            "sweepga_version": env!("CARGO_PKG_VERSION"),
+            "aligner": self.aligner,
+            "aligner_version": aligner_version,
+            "argv": self.argv,
+            "config": self.config,
+            "stats": self.stats,
+            "inputs": file_entries(&self.inputs)?,
+            "outputs": file_entries(&self.outputs)?,
+            "timings_seconds": timings,
+        }))
+    }
+
+    /// Write the manifest as pretty-printed JSON to `path`
+    pub fn write(&self, path: &Path) -> Result<()> {
+        let json = serde_json::to_string_pretty(&self.to_json()?)?;
+        std::fs::write(path, json + "\n")
+            .with_context(|| format!("Failed to write manifest {}", path.display()))
+    }
+}
+
+fn file_entries(paths: &[PathBuf]) -> Result<Vec<Value>> {
+    paths.iter().map(PathBuf::as_path).map(file_entry).collect()
+}
+
+/// `{path, size, sha256}` for a file, or just `{path}` for stdout (`-`)
+fn file_entry(path: &Path) -> Result<Value> {
+    if path == Path::new("-") {
+        return Ok(json!({ "path": "-" }));
+    }
+    let size = std::fs::metadata(path)
+        .with_context(|| format!("Failed to stat {}", path.display()))?
+        .len();
+    Ok(json!({
+        "path": path.display().to_string(),
+        "size": size,
+        "sha256": sha256_file(path)?,
+    }))
+}
+
+/// Hex-encoded SHA-256 of a file's contents
+pub fn sha256_file(path: &Path) -> Result<String> {
+    let mut file =
+        File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut file, &mut hasher)?;
+    Ok(hasher.finalize().iter().fold(String::new(), |mut hex, b| {
+        let _ = write!(hex, "{b:02x}");
+        hex
+    }))
+}
+
+/// The resolved filter configuration, with the PAF filter options given by
+/// `aln`, as one JSON object
+pub fn filter_config_json(config: &FilterConfig, aln: &AlnArgs) -> Value {
+    let mut json = json!({
+        "mapping_filter_mode": format!("{:?}", config.mapping_filter_mode),
+        "mapping_max_per_query": config.mapping_max_per_query,
+        "mapping_max_per_target": config.mapping_max_per_target,
+        "scaffold_filter_mode": format!("{:?}", config.scaffold_filter_mode),
+        "scaffold_max_per_query": config.scaffold_max_per_query,
+        "scaffold_max_per_target": config.scaffold_max_per_target,
+        "min_block_length": config.min_block_length,
+        "overlap_threshold": config.overlap_threshold,
+        "sparsity": config.sparsity,
+        "scaffold_gap": config.scaffold_gap,
+        "min_scaffold_length": config.min_scaffold_length,
+        "scaffold_overlap_threshold": config.scaffold_overlap_threshold,
+        "scaffold_max_deviation": config.scaffold_max_deviation,
+        "scoring_function": format!("{:?}", config.scoring_function),
+        "min_identity": config.min_identity,
+        "min_scaffold_identity": config.min_scaffold_identity,
+        "min_scaffold_count": config.min_scaffold_count,
+        "prefix_delimiter": config.prefix_delimiter.to_string(),
+        "prefix_depth": config.prefix_depth,
+        "skip_prefix": config.skip_prefix,
+        "default_mapq": config.default_mapq,
+    });
+    if let (Value::Object(fields), Value::Object(options)) = (&mut json, filter_options_json(aln)) {
+        fields.extend(options);
+    }
+    json
+}
+
+/// The counts of each filtering stage and the output summary as one JSON
+/// object
+pub fn filter_stats_json(stats: &FilterStats) -> Value {
+    json!({
+        "plane_sweep_before": stats.plane_sweep_before,
+        "plane_sweep_after": stats.plane_sweep_after,
+        "chains_merged": stats.chains_merged,
+        "chains_after_length_filter": stats.chains_after_length_filter,
+        "scaffolds": stats.scaffolds,
+        "anchors": stats.anchors,
+        "rescued": stats.rescued,
+        "output_mappings": stats.output_mappings,
+        "output_mb": stats.output_mb,
+        "avg_identity": stats.avg_identity,
+        "identity_p10": stats.identity_percentiles.map(|p| p[0]),
+        "identity_p50": stats.identity_percentiles.map(|p| p[1]),
+        "identity_p90": stats.identity_percentiles.map(|p| p[2]),
+        "malformed_lines": stats.malformed_lines,
+    })
+}
+
+/// The PAF filter options outside [`FilterConfig`] (record selection,
+/// rescue, chaining and output shaping) as JSON
+fn filter_options_json(aln: &AlnArgs) -> Value {
+    json!({
+        "keep_self": aln.keep_self || aln.no_filter,
+        "self_genome": aln.self_genome,
+        "keep_pairs": aln.keep_pairs,
+        "exclude_pairs": aln.exclude_pairs,
+        "canonical_pairs": aln.canonical_pairs,
+        "strand": format!("{:?}", aln.strand),
+        "min_relative_score": aln.min_relative_score,
+        "min_pair_coverage": aln.min_pair_coverage,
+        "min_query_coverage": aln.min_query_coverage,
+        "min_target_coverage": aln.min_target_coverage,
+        "scaffold_keep_fraction": crate::cli::scaffold_filter_fraction(&aln.scaffold_filter),
+        "scaffolds_only": aln.scaffolds_only,
+        "no_rescue": aln.no_rescue,
+        "max_rescued_per_scaffold": aln.max_rescued_per_scaffold,
+        "rescue_metric": format!("{:?}", aln.rescue_metric),
+        "overlap_axis": format!("{:?}", aln.overlap_axis),
+        "no_plane_sweep": aln.no_plane_sweep,
+        "reference_mode": aln.reference_mode,
+        "split_overlapping_members": aln.split_overlapping_members,
+        "member_overlap_tolerance": aln.member_overlap_tolerance,
+        "allow_inversions": aln.allow_inversions,
+        "max_diagonal_deviation": aln.max_diagonal_deviation,
+        "chain_identity_lis": aln.chain_identity_lis,
+        "block_length_from": format!("{:?}", aln.block_length_from),
+        "identity_transform": format!("{:?}", aln.identity_transform),
+        "trim_overlaps": aln.trim_overlaps,
+        "max_output_bp": aln.max_output_bp,
+        "coord_base": aln.coord_base,
+        "output_coord_base": aln.output_coord_base,
+        "minus_strand_coords": aln.minus_strand_coords,
+        "repair_input": aln.repair_input,
+        "strict": aln.strict,
+        "mark_secondary": aln.mark_secondary,
+        "sort_output": aln.sort_output.map(|sort| format!("{sort:?}")),
+        "group_by_chain": aln.group_by_chain,
+        "tag_sweep_rank": aln.tag_sweep_rank,
+        "component_tag": aln.component_tag,
+        "preserve_headers": aln.preserve_headers,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sha256_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("hello.txt");
+        std::fs::write(&path, "hello\n").unwrap();
+        assert_eq!(
+            sha256_file(&path).unwrap(),
+            "5891b5b522d5df086d0ff0b110fbd9d21bb4fc7163af34d08286a2e846f6be03"
+        );
+    }
+
+    #[test]
+    fn test_stdout_output_has_no_checksum() {
+        let manifest = RunManifest {
+            argv: vec!["sweepga".to_string()],
+            aligner: "fastga".to_string(),
+            config: Value::Null,
+            stats: Value::Null,
+            inputs: vec![],
+            outputs: vec![PathBuf::from("-")],
+            timings: vec![("total".to_string(), 1.5)],
+        };
+        let json = manifest.to_json().unwrap();
+        assert_eq!(json["outputs"][0], json!({ "path": "-" }));
+        assert_eq!(json["timings_seconds"]["total"], json!(1.5));
+    }
+
+    #[test]
+    fn test_filter_options_record_paf_filter_flags() {
+        use clap::Parser;
+
+        #[derive(Parser)]
+        struct Cli {
+            #[clap(flatten)]
+            aln: AlnArgs,
+        }
+        let cli = Cli::parse_from(["sweepga", "--self", "--min-relative-score", "0.5"]);
+        let json = filter_options_json(&cli.aln);
+        assert_eq!(json["keep_self"], json!(true));
+        assert_eq!(json["min_relative_score"], json!(0.5));
+        assert_eq!(json["no_rescue"], json!(false));
+    }
+
+    #[test]
+    fn test_filter_stats_record_identity_percentiles() {
+        let stats = FilterStats {
+            output_mappings: 3,
+            identity_percentiles: Some([0.9, 0.95, 0.99]),
+            ..FilterStats::default()
+        };
+        let json = filter_stats_json(&stats);
+        assert_eq!(json["output_mappings"], json!(3));
+        assert_eq!(json["identity_p50"], json!(0.95));
+        assert!(filter_stats_json(&FilterStats::default())["identity_p10"].is_null());
+    }
+}
@@ -98,6 +98,9 @@ pub struct MappingAux {
     pub chain_id: String,          // Chain identifier (e.g., "1.1.1")
     pub chain_status: ChainStatus, // Scaffold or rescued
     pub ref_len: u32,              // Reference sequence length
+    /// Optional tags of the source record as (`TAG:TYPE`, value), `cg:Z`
+    /// first, carried opaquely so records rebuilt from mappings keep them
+    pub extra_tags: Vec<(String, String)>,
 }
 
 impl Default for MappingAux {
@@ -111,6 +114,7 @@ impl Default for MappingAux {
             chain_id: String::new(),
             chain_status: ChainStatus::Unassigned,
             ref_len: 0,
+            extra_tags: Vec::new(),
         }
     }
 }
@@ -0,0 +1,108 @@
+//! Minimap2 integration module
+//!
+//! This module runs the `minimap2` binary from PATH as an alignment backend,
+//! for inputs too divergent for FastGA's seeds. Alignments are computed base
+//! by base (`-c --eqx`), so the PAF carries `cg:Z:` CIGARs with `=`/`X`
+//! operations like FastGA's.
+
+use crate::aligner::Aligner;
+use anyhow::{Context, Result};
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use tempfile::NamedTempFile;
+
+/// Locate the `minimap2` binary on PATH
+pub fn find_binary() -> Result<PathBuf> {
+    std::env::var_os("PATH")
+        .iter()
+        .flat_map(std::env::split_paths)
+        .map(|dir| dir.join("minimap2"))
+        .find(|path| path.is_file())
+        .context("minimap2 not found on PATH; install it or choose another --aligner")
+}
+
+/// Version reported by the minimap2 on PATH (`minimap2 --version`)
+pub fn version() -> Result<String> {
+    let output = Command::new(find_binary()?)
+        .arg("--version")
+        .output()
+        .context("Failed to run minimap2 --version")?;
+    anyhow::ensure!(output.status.success(), "minimap2 --version failed");
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Minimap2 alignment backend
+pub struct Minimap2Integration {
+    binary: PathBuf,
+    preset: String,
+    num_threads: usize,
+    temp_dir: Option<String>,
+}
+
+impl Minimap2Integration {
+    /// Create a backend using the minimap2 on PATH with the given preset
+    /// (`-x`), e.g. `asm5` or `asm20`
+    pub fn new(num_threads: usize, temp_dir: Option<String>, preset: &str) -> Result<Self> {
+        Ok(Minimap2Integration {
+            binary: find_binary()?,
+            preset: preset.to_string(),
+            num_threads,
+            temp_dir,
+        })
+    }
+
+    /// `minimap2` invocation aligning `queries` to `targets`, PAF on stdout
+    fn command(&self, queries: &Path, targets: &Path) -> Command {
+        let mut cmd = Command::new(&self.binary);
+        cmd.arg("-x")
+            .arg(&self.preset)
+            .args(["-c", "--eqx"])
+            .arg("-t")
+            .arg(self.num_threads.to_string())
+            .arg(targets)
+            .arg(queries)
+            .stderr(Stdio::piped());
+        cmd
+    }
+
+    /// Run `cmd` with its PAF streamed straight into `paf`; only stderr is
+    /// collected, for the error message
+    fn run(&self, mut cmd: Command, paf: File) -> Result<()> {
+        let output = cmd
+            .stdout(paf)
+            .spawn()
+            .and_then(|child| child.wait_with_output())
+            .with_context(|| format!("Failed to run {}", self.binary.display()))?;
+        if !output.status.success() {
+            anyhow::bail!(
+                "minimap2 failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+        Ok(())
+    }
+}
+
+impl Aligner for Minimap2Integration {
+    fn align_to_temp_paf(&self, queries: &Path, targets: &Path) -> Result<NamedTempFile> {
+        let mut builder = tempfile::Builder::new();
+        builder.prefix("minimap2_").suffix(".paf");
+        let temp_paf = match &self.temp_dir {
+            Some(dir) => builder.tempfile_in(dir)?,
+            None => builder.tempfile()?,
+        };
+        self.run(self.command(queries, targets), temp_paf.reopen()?)?;
+        Ok(temp_paf)
+    }
+
+    fn align_direct_paf(&self, queries: &Path, targets: &Path) -> Result<Vec<u8>> {
+        let temp_paf = self.align_to_temp_paf(queries, targets)?;
+        std::fs::read(temp_paf.path())
+            .with_context(|| format!("Failed to read {}", temp_paf.path().display()))
+    }
+
+    fn name(&self) -> &str {
+        "minimap2"
+    }
+}
@@ -1,32 +1,136 @@
 use crate::mapping::{Mapping, MappingAux, PafRecord};
 use anyhow::{bail, Result};
-use noodles::bgzf;
 use std::collections::HashMap;
 use std::fs::File;
 use std::io::{BufRead, BufReader, Read};
 use std::path::Path;
 
-/// Open a file and auto-detect bgzip compression, returning a boxed BufRead
+/// MAPQ value meaning "mapping quality unavailable" (SAM/PAF convention),
+/// assumed for records without one unless `--default-mapq` says otherwise
+pub const MAPQ_UNAVAILABLE: u8 = 255;
+
+/// MAPQ written by the native .1aln to PAF conversion unless
+/// `--default-mapq` is given
+pub const CONVERTED_MAPQ: u8 = 60;
+
+/// First two bytes of every gzip member, bgzip blocks included
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// Open a file and auto-detect gzip/bgzip compression by its magic bytes
+/// (whatever the extension), returning a boxed BufRead over the text
 pub fn open_paf_input<P: AsRef<Path>>(path: P) -> Result<Box<dyn BufRead>> {
-    let path = path.as_ref();
-    let file = File::open(path)?;
-
-    // Check by file extension (faster than reading magic bytes)
-    let is_compressed = path
-        .extension()
-        .and_then(|ext| ext.to_str())
-        .map(|ext| ext == "gz" || ext == "bgz")
-        .unwrap_or(false);
-
-    if is_compressed {
-        Ok(Box::new(BufReader::new(bgzf::io::reader::Reader::new(
-            file,
-        ))))
+    let mut reader = BufReader::new(File::open(path)?);
+
+    // MultiGzDecoder reads every member, so plain gzip and bgzip (a series of
+    // gzip blocks) both decode as one stream
+    if reader.fill_buf()?.starts_with(&GZIP_MAGIC) {
+        Ok(Box::new(BufReader::new(
+            flate2::bufread::MultiGzDecoder::new(reader),
+        )))
     } else {
-        Ok(Box::new(BufReader::new(file)))
+        Ok(Box::new(reader))
+    }
+}
+
+/// Sequence lengths seen in PAF length columns (2 and 7), each sequence
+/// counted once, summed into genome sizes on demand
+#[derive(Debug, Default, Clone)]
+pub struct GenomeSizes {
+    sequence_lengths: HashMap<String, u64>,
+}
+
+impl GenomeSizes {
+    /// Collect the lengths of every query and target sequence in a PAF file
+    pub fn from_paf<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let mut sizes = GenomeSizes::default();
+        for line in open_paf_input(path)?.lines() {
+            let line = line?;
+            if is_header_line(&line) {
+                continue;
+            }
+            let fields: Vec<&str> = line.split('\t').collect();
+            if fields.len() < 11 {
+                continue;
+            }
+            sizes.add(fields[0], fields[1].parse().unwrap_or(0));
+            sizes.add(fields[5], fields[6].parse().unwrap_or(0));
+        }
+        Ok(sizes)
+    }
+
+    /// Record a sequence length (the first length seen for a name wins)
+    pub fn add(&mut self, sequence: &str, length: u64) {
+        if !self.sequence_lengths.contains_key(sequence) {
+            self.sequence_lengths.insert(sequence.to_string(), length);
+        }
+    }
+
+    /// Total length of all distinct sequences
+    pub fn total(&self) -> u64 {
+        self.sequence_lengths.values().sum()
+    }
+
+    /// Size of each genome, grouping sequences by `genome_of(name)`
+    pub fn per_genome(&self, genome_of: impl Fn(&str) -> String) -> HashMap<String, u64> {
+        let mut sizes: HashMap<String, u64> = HashMap::new();
+        for (name, length) in &self.sequence_lengths {
+            *sizes.entry(genome_of(name)).or_default() += length;
+        }
+        sizes
     }
 }
 
+/// The last line of a PAF file if it is not newline-terminated, as left by a
+/// run that crashed mid-write. Returns its 0-based line number and contents.
+pub fn truncated_final_line<P: AsRef<Path>>(path: P) -> Result<Option<(usize, String)>> {
+    let mut reader = open_paf_input(path)?;
+    let mut buf = Vec::new();
+    let mut last = None;
+    let mut line_number = 0;
+    loop {
+        buf.clear();
+        if reader.read_until(b'\n', &mut buf)? == 0 {
+            break;
+        }
+        if buf.last() != Some(&b'\n') {
+            last = Some((line_number, String::from_utf8_lossy(&buf).into_owned()));
+        }
+        line_number += 1;
+    }
+    Ok(last)
+}
+
+/// Number of lines in a PAF file, counted without keeping them
+pub fn count_lines<P: AsRef<Path>>(path: P) -> Result<usize> {
+    let mut reader = open_paf_input(path)?;
+    let mut buf = Vec::new();
+    let mut lines = 0;
+    while reader.read_until(b'\n', &mut buf)? > 0 {
+        buf.clear();
+        lines += 1;
+    }
+    Ok(lines)
+}
+
+/// Whether a PAF line is a `#` comment or `@` header line rather than a record
+pub fn is_header_line(line: &str) -> bool {
+    line.starts_with('#') || line.starts_with('@')
+}
+
+/// Leading field of the comment line that marks a genome pair skipped by
+/// `--max-mash-distance`: `#sweepga:skipped<TAB>query<TAB>target<TAB>...`
+pub const SKIPPED_PAIR_MARKER: &str = "#sweepga:skipped";
+
+/// The query and target genomes of a skipped-pair marker line. Filtering
+/// keeps these lines even without --preserve-headers.
+pub fn skipped_pair(line: &str) -> Option<(&str, &str)> {
+    let mut fields = line.split('\t');
+    if fields.next() != Some(SKIPPED_PAIR_MARKER) {
+        return None;
+    }
+    Some((fields.next()?, fields.next()?))
+}
+
 /// Parse CIGAR string to count exact matches (= operations)
 /// Returns (matches, mismatches, insertions, deletions)
 pub fn parse_cigar_counts(cigar: &str) -> Result<(u64, u64, u64, u64)> {
@@ -63,6 +167,32 @@ pub fn parse_cigar_counts(cigar: &str) -> Result<(u64, u64, u64, u64)> {
     Ok((matches, mismatches, insertions, deletions))
 }
 
+/// Gap-compressed identity of a CIGAR alignment, as minimap2's `de:f:`
+/// defines it: matches / (matches + mismatches + gap opens), every run of
+/// `I` or `D` counting once. `M` operations hold `paf_matches` (PAF column
+/// 10) minus the `=` bases as matches and the rest as mismatches.
+pub fn gap_compressed_identity(cigar: &str, paf_matches: u64) -> Result<f64> {
+    let (mut exact, mut mismatches, mut aligned, mut gap_opens) = (0u64, 0u64, 0u64, 0u64);
+    for (len, op) in crate::trim_overlaps::parse_cigar_ops(cigar)? {
+        match op {
+            '=' => exact += len,
+            'X' => mismatches += len,
+            'M' => aligned += len,
+            'I' | 'D' => gap_opens += 1,
+            _ => {}
+        }
+    }
+    let m_matches = aligned.min(paf_matches.saturating_sub(exact));
+    let matches = exact + m_matches;
+    let mismatches = mismatches + aligned - m_matches;
+    let columns = matches + mismatches + gap_opens;
+    Ok(if columns > 0 {
+        matches as f64 / columns as f64
+    } else {
+        0.0
+    })
+}
+
 #[allow(dead_code)]
 pub struct PafReader<R: Read> {
     reader: BufReader<R>,
@@ -126,6 +256,12 @@ impl<R: Read> PafReader<R> {
             query_seq_id: query_id,
             query_len: paf.query_len,
             ref_len: paf.ref_len,
+            extra_tags: paf
+                .cigar
+                .iter()
+                .map(|cigar| ("cg:Z".to_string(), cigar.clone()))
+                .chain(paf.tags.iter().cloned())
+                .collect(),
             ..Default::default()
         };
 
@@ -1,18 +1,24 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
 use indexmap::{IndexMap, IndexSet};
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
 use std::fs::File;
+use std::hash::{Hash, Hasher};
 use std::io::{BufRead, BufWriter, Write};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
+use crate::chain_format::ChainMember;
 use crate::mapping::ChainStatus;
 use crate::paf::open_paf_input;
 use crate::plane_sweep_exact::PlaneSweepMapping;
 use crate::plane_sweep_scaffold::{plane_sweep_scaffolds, ScaffoldLike};
+use crate::scaffold_bundle::Bundle;
 use crate::sequence_index::SequenceIndex;
 
 // Re-export filter types for backwards compatibility
-pub use crate::filter_types::{FilterMode, ScoringFunction};
+pub use crate::filter_types::{
+    BlockLengthSource, FilterMode, IdentityTransform, OutputSort, OverlapAxis, RescueMetric,
+    ScoringFunction, StrandFilter,
+};
 
 /// Filter configuration
 #[derive(Clone)]
@@ -39,99 +45,395 @@ pub struct FilterConfig {
     pub min_scaffold_length: u64, // -S/--scaffold-mass
     pub scaffold_overlap_threshold: f64,
     pub scaffold_max_deviation: u64, // -D/--scaffold-dist
-    pub prefix_delimiter: char,
-    pub skip_prefix: bool,
+    pub prefix_delimiter: char,      // --prefix-delimiter
+    pub prefix_depth: usize,         // --prefix-depth (0 = up to the last delimiter)
+    pub skip_prefix: bool,           // --skip-prefix
+    pub default_mapq: u8,            // --default-mapq (MAPQ of records without one)
 
     // Scoring and identity filtering
     pub scoring_function: ScoringFunction,
     pub min_identity: f64, // Minimum block identity threshold (0.0-1.0)
     pub min_scaffold_identity: f64, // Minimum scaffold identity threshold (0.0-1.0)
+    pub min_scaffold_count: usize, // Minimum member mappings per scaffold chain
 }
 
+#[allow(dead_code)]
+impl FilterConfig {
+    /// A [`FilterConfigBuilder`] starting from the command line defaults
+    pub fn builder() -> FilterConfigBuilder {
+        FilterConfigBuilder::default()
+    }
+}
+
+/// The command line defaults, unvalidated; fixtures override the fields
+/// they care about with `..FilterConfig::default()`
+impl Default for FilterConfig {
+    fn default() -> Self {
+        FilterConfig {
+            chain_gap: 50_000,
+            min_block_length: 0,
+            mapping_filter_mode: FilterMode::ManyToMany,
+            mapping_max_per_query: None,
+            mapping_max_per_target: None,
+            plane_sweep_secondaries: 0,
+            scaffold_filter_mode: FilterMode::ManyToMany,
+            scaffold_max_per_query: None,
+            scaffold_max_per_target: None,
+            overlap_threshold: 0.95,
+            sparsity: 1.0,
+            no_merge: true,
+            scaffold_gap: 50_000,
+            min_scaffold_length: 10_000,
+            scaffold_overlap_threshold: 0.5,
+            scaffold_max_deviation: 0,
+            prefix_delimiter: '#',
+            prefix_depth: 0,
+            skip_prefix: false,
+            default_mapq: crate::paf::MAPQ_UNAVAILABLE,
+            scoring_function: ScoringFunction::LogLengthIdentity,
+            min_identity: 0.0,
+            min_scaffold_identity: 0.0,
+            min_scaffold_count: 1,
+        }
+    }
+}
+
+/// Chainable construction of a [`FilterConfig`] for library users.
+///
+/// Starts from the command line defaults (`-n many:many`, `-j 50k`, `-S 10k`,
+/// `--scaffold-filter many:many`, `--scaffold-dist 0`, `--overlap 0.95`,
+/// `--scaffold-overlap 0.5`, log-length-ANI scoring, no identity cutoff);
+/// [`build`](Self::build) rejects out-of-range and contradictory settings.
+///
+/// ```
+/// use sweepga::FilterConfig;
+///
+/// let config = FilterConfig::builder()
+///     .num_mappings(1, 1)
+///     .scaffold_jump(10_000)
+///     .scaffold_dist(20_000)
+///     .min_identity(0.95)
+///     .build()
+///     .unwrap();
+/// assert_eq!(config.scaffold_gap, 10_000);
+/// ```
+#[derive(Clone)]
+#[allow(dead_code)]
+pub struct FilterConfigBuilder {
+    config: FilterConfig,
+    /// `--min-scaffold-identity`; follows `min_identity` until set
+    min_scaffold_identity: Option<f64>,
+}
+
+impl Default for FilterConfigBuilder {
+    fn default() -> Self {
+        FilterConfigBuilder {
+            config: FilterConfig::default(),
+            min_scaffold_identity: None,
+        }
+    }
+}
+
+#[allow(dead_code)]
+impl FilterConfigBuilder {
+    /// Filter mode and limits for per-query/per-target counts (0 = many),
+    /// as `-n` parses them
+    fn limits(per_query: usize, per_target: usize) -> (FilterMode, Option<usize>, Option<usize>) {
+        let per_query = (per_query > 0).then_some(per_query);
+        let per_target = (per_target > 0).then_some(per_target);
+        let mode = match (per_query, per_target) {
+            (Some(1), Some(1)) => FilterMode::OneToOne,
+            (Some(1), _) | (_, Some(1)) => FilterMode::OneToMany,
+            _ => FilterMode::ManyToMany,
+        };
+        (mode, per_query, per_target)
+    }
+
+    /// Mappings kept per query and per target by the plane sweep, like
+    /// `-n 1:1` (0 = many)
+    pub fn num_mappings(mut self, per_query: usize, per_target: usize) -> Self {
+        (
+            self.config.mapping_filter_mode,
+            self.config.mapping_max_per_query,
+            self.config.mapping_max_per_target,
+        ) = Self::limits(per_query, per_target);
+        self
+    }
+
+    /// Scaffolds kept per query and per target, like `--scaffold-filter 1:1`
+    /// (0 = many)
+    pub fn scaffold_filter(mut self, per_query: usize, per_target: usize) -> Self {
+        (
+            self.config.scaffold_filter_mode,
+            self.config.scaffold_max_per_query,
+            self.config.scaffold_max_per_target,
+        ) = Self::limits(per_query, per_target);
+        self
+    }
+
+    /// Maximum gap (bp) bridged when chaining mappings into scaffolds
+    /// (`-j`; 0 disables scaffolding)
+    pub fn scaffold_jump(mut self, gap: u64) -> Self {
+        self.config.chain_gap = gap;
+        self.config.scaffold_gap = gap;
+        self
+    }
+
+    /// Minimum scaffold length in bp (`-S`)
+    pub fn scaffold_mass(mut self, length: u64) -> Self {
+        self.config.min_scaffold_length = length;
+        self
+    }
+
+    /// Rescue mappings within this distance (bp) of a kept scaffold
+    /// (`--scaffold-dist`; 0 disables rescue)
+    pub fn scaffold_dist(mut self, distance: u64) -> Self {
+        self.config.scaffold_max_deviation = distance;
+        self
+    }
+
+    /// Overlap fraction above which scaffolds compete (`--scaffold-overlap`)
+    pub fn scaffold_overlap(mut self, fraction: f64) -> Self {
+        self.config.scaffold_overlap_threshold = fraction;
+        self
+    }
+
+    /// Overlap fraction above which mappings compete in the plane sweep (`--overlap`)
+    pub fn overlap(mut self, fraction: f64) -> Self {
+        self.config.overlap_threshold = fraction;
+        self
+    }
+
+    /// Minimum mapping block length in bp (`-l`)
+    pub fn block_length(mut self, length: u64) -> Self {
+        self.config.min_block_length = length;
+        self
+    }
+
+    /// Minimum mapping identity (0-1); also the scaffold identity cutoff
+    /// unless [`min_scaffold_identity`](Self::min_scaffold_identity) is set
+    pub fn min_identity(mut self, identity: f64) -> Self {
+        self.config.min_identity = identity;
+        self
+    }
+
+    /// Minimum scaffold identity (0-1)
+    pub fn min_scaffold_identity(mut self, identity: f64) -> Self {
+        self.min_scaffold_identity = Some(identity);
+        self
+    }
+
+    /// Minimum number of member mappings per scaffold chain
+    /// (`--min-scaffold-count`)
+    pub fn min_scaffold_count(mut self, count: usize) -> Self {
+        self.config.min_scaffold_count = count;
+        self
+    }
+
+    /// Scoring function ranking mappings and scaffolds (`--scoring`)
+    pub fn scoring(mut self, scoring: ScoringFunction) -> Self {
+        self.config.scoring_function = scoring;
+        self
+    }
+
+    /// Fraction of mappings kept by random sparsification (1 = all)
+    pub fn sparsity(mut self, fraction: f64) -> Self {
+        self.config.sparsity = fraction;
+        self
+    }
+
+    /// PanSN delimiter separating the genome prefix from the sequence name
+    pub fn prefix_delimiter(mut self, delimiter: char) -> Self {
+        self.config.prefix_delimiter = delimiter;
+        self
+    }
+
+    /// Name fields forming a genome group, like `--prefix-depth` (0 = up to
+    /// the last delimiter)
+    pub fn prefix_depth(mut self, depth: usize) -> Self {
+        self.config.prefix_depth = depth;
+        self
+    }
+
+    /// MAPQ assumed for records without one, like `--default-mapq`
+    pub fn default_mapq(mut self, mapq: u8) -> Self {
+        self.config.default_mapq = mapq;
+        self
+    }
+
+    /// Validate the settings and return the configuration
+    pub fn build(self) -> Result<FilterConfig> {
+        let mut config = self.config;
+        config.min_scaffold_identity = self.min_scaffold_identity.unwrap_or(config.min_identity);
+
+        for (name, fraction) in [
+            ("overlap", config.overlap_threshold),
+            ("scaffold_overlap", config.scaffold_overlap_threshold),
+            ("min_identity", config.min_identity),
+            ("min_scaffold_identity", config.min_scaffold_identity),
+        ] {
+            anyhow::ensure!(
+                (0.0..=1.0).contains(&fraction),
+                "{name} must be between 0 and 1 (got {fraction})"
+            );
+        }
+        anyhow::ensure!(
+            config.sparsity > 0.0 && config.sparsity <= 1.0,
+            "sparsity must be in (0, 1] (got {})",
+            config.sparsity
+        );
+        if config.scaffold_gap == 0 {
+            // Without scaffolds there is nothing to rescue around or to limit
+            anyhow::ensure!(
+                config.scaffold_max_deviation == 0,
+                "scaffold_dist needs scaffolding; set scaffold_jump above 0"
+            );
+            anyhow::ensure!(
+                config.scaffold_filter_mode == FilterMode::ManyToMany
+                    && config.scaffold_max_per_query.is_none()
+                    && config.scaffold_max_per_target.is_none(),
+                "scaffold_filter needs scaffolding; set scaffold_jump above 0"
+            );
+        }
+        Ok(config)
+    }
+}
+
+/// Default query overlap (bp) allowed between members of one chain before the
+/// chain is reported as a collapsed tandem duplication (`--member-overlap-tolerance`)
+const MEMBER_OVERLAP_TOLERANCE: u64 = 100;
+
 /// Record metadata for filtering without modifying records
 #[derive(Debug, Clone)]
 #[allow(dead_code)]
 pub struct RecordMeta {
-    pub rank: usize, // 0-based index in original file
-    pub query_name: String,
-    pub target_name: String,
+    pub rank: usize,    // 0-based index in original file
+    pub query_id: u32,  // Query sequence ID in the input's SequenceIndex
+    pub target_id: u32, // Target sequence ID in the input's SequenceIndex
     pub query_start: u64,
     pub query_end: u64,
     pub target_start: u64,
     pub target_end: u64,
     pub block_length: u64,
-    pub identity: f64,         // Block identity: matches / alignment_length
-    pub matches: u64,          // Number of matching bases
+    pub identity: f64,                // Block identity: matches / alignment_length
+    pub gap_compressed_identity: f64, // Indel runs counted once (cg:Z:/de:f:), else identity
+    pub mapq: u8,     // PAF column 12 (255 = unavailable, see paf::MAPQ_UNAVAILABLE)
+    pub matches: u64, // Number of matching bases
     pub alignment_length: u64, // Total alignment length (including gaps)
     pub strand: char,
     pub chain_id: Option<String>,
     pub chain_status: ChainStatus,
     pub discard: bool,
     pub overlapped: bool,
+    pub sweep_rank: Option<usize>, // Position among kept competitors in the plane sweep
+    pub secondary: bool,           // Filtered out, but kept for --mark-secondary
+    pub inversions: Option<String>, // Inverted query ranges joined into its chain (iv:Z:)
 }
 
-/// Compact record metadata using sequence IDs instead of strings
-#[derive(Debug, Clone)]
+/// Estimated memory per input record when filtering in memory, for
+/// `--max-memory`: the parsed metadata, the copy kept for rescue, the
+/// plane-swept and passing sets
+const RECORD_MEMORY_BYTES: u64 = 4 * std::mem::size_of::<RecordMeta>() as u64;
+
+/// Counts from one [`PafFilter::apply_filters`] run
+#[derive(Debug, Clone, Default, PartialEq)]
 #[allow(dead_code)]
-struct CompactRecordMeta {
-    rank: usize, // 0-based index in original file
-    query_id: u32,
-    target_id: u32,
-    query_start: u64,
-    query_end: u64,
-    target_start: u64,
-    target_end: u64,
-    block_length: u64,
-    identity: f64,
-    strand: char,
-    chain_id: Option<String>, // Still a string for now
-    chain_status: ChainStatus,
-    discard: bool,
-    overlapped: bool,
+pub struct FilterStats {
+    /// Mappings entering the plane sweep, after the per-record filters
+    pub plane_sweep_before: usize,
+    /// Mappings left by the plane sweep (all of them with `--no-plane-sweep`)
+    pub plane_sweep_after: usize,
+    /// Chains merged from the plane-swept mappings (0 without scaffolding)
+    pub chains_merged: usize,
+    /// Chains passing the minimum scaffold length and identity
+    pub chains_after_length_filter: usize,
+    /// Chains kept by the scaffold plane sweep
+    pub scaffolds: usize,
+    /// Passing mappings that are members of a kept scaffold
+    pub anchors: usize,
+    /// Passing mappings rescued near an anchor
+    pub rescued: usize,
+    /// Mappings passing all filters
+    pub output_mappings: usize,
+    /// Total block length of the passing mappings, in Mb
+    pub output_mb: f64,
+    /// Mean identity of the passing mappings (0 if none)
+    pub avg_identity: f64,
+    /// p10/p50/p90 identity of the passing mappings ([`sorted_percentile`],
+    /// `None` if none)
+    pub identity_percentiles: Option<[f64; 3]>,
+    /// Input lines skipped as malformed PAF (an error with `--strict`)
+    pub malformed_lines: usize,
+    /// Per-chain files written to the `--scaffold-bundle` directory
+    pub scaffold_bundles: Vec<PathBuf>,
 }
 
-impl CompactRecordMeta {
-    /// Convert from regular RecordMeta using a sequence index
-    #[allow(dead_code)]
-    fn from_record_meta(meta: &RecordMeta, seq_index: &mut SequenceIndex) -> Self {
-        Self {
-            rank: meta.rank,
-            query_id: seq_index.get_or_insert(&meta.query_name),
-            target_id: seq_index.get_or_insert(&meta.target_name),
-            query_start: meta.query_start,
-            query_end: meta.query_end,
-            target_start: meta.target_start,
-            target_end: meta.target_end,
-            block_length: meta.block_length,
-            identity: meta.identity,
-            strand: meta.strand,
-            chain_id: meta.chain_id.clone(),
-            chain_status: meta.chain_status.clone(),
-            discard: meta.discard,
-            overlapped: meta.overlapped,
+impl FilterStats {
+    /// Fill in the output counts from the passing mappings
+    fn record_output<'a>(&mut self, passing: impl Iterator<Item = &'a RecordMeta>) {
+        let mut bases = 0;
+        let mut identities = Vec::new();
+        for m in passing {
+            bases += m.block_length;
+            identities.push(m.identity);
         }
+        self.record_identities(&identities, bases);
     }
 
-    /// Convert back to RecordMeta for output
-    #[allow(dead_code)]
-    fn to_record_meta(&self, seq_index: &SequenceIndex) -> RecordMeta {
-        RecordMeta {
-            rank: self.rank,
-            query_name: seq_index.name(self.query_id).to_string(),
-            target_name: seq_index.name(self.target_id).to_string(),
-            query_start: self.query_start,
-            query_end: self.query_end,
-            target_start: self.target_start,
-            target_end: self.target_end,
-            block_length: self.block_length,
-            identity: self.identity,
-            matches: (self.identity * self.block_length as f64) as u64, // Estimate matches from identity
-            alignment_length: self.block_length, // Use block_length as alignment length
-            strand: self.strand,
-            chain_id: self.chain_id.clone(),
-            chain_status: self.chain_status.clone(),
-            discard: self.discard,
-            overlapped: self.overlapped,
+    /// Fill in the output counts from the identities and total block length
+    /// of the passing mappings
+    fn record_identities(&mut self, identities: &[f64], bases: u64) {
+        self.output_mappings = identities.len();
+        self.output_mb = bases as f64 / 1_000_000.0;
+        self.avg_identity = if identities.is_empty() {
+            0.0
+        } else {
+            identities.iter().sum::<f64>() / identities.len() as f64
+        };
+        self.identity_percentiles =
+            identity_percentiles(identities, &[10.0, 50.0, 90.0]).map(|p| [p[0], p[1], p[2]]);
+    }
+
+    /// Log the filtering summary
+    pub fn log_summary(&self) {
+        log::info!(
+            "[sweepga] Summary: {} → {} mappings ({:.1}% kept)",
+            self.plane_sweep_before,
+            self.output_mappings,
+            (self.output_mappings as f64 / self.plane_sweep_before.max(1) as f64) * 100.0
+        );
+        if self.malformed_lines > 0 {
+            log::warn!(
+                "[sweepga]   Skipped {} malformed input lines (--strict rejects them)",
+                self.malformed_lines
+            );
+        }
+        if self.chains_merged > 0 {
+            log::info!(
+                "[sweepga]   Scaffolds: {} chains merged, {} after length/identity filter, \
+                 {} after sweep",
+                self.chains_merged,
+                self.chains_after_length_filter,
+                self.scaffolds
+            );
+            log::info!(
+                "[sweepga]   Anchors: {}, rescued: {}",
+                self.anchors,
+                self.rescued
+            );
+        }
+        log::info!(
+            "[sweepga]   Output: {:.1} Mb total, {:.1}% avg identity",
+            self.output_mb,
+            self.avg_identity * 100.0
+        );
+        if let Some(p) = self.identity_percentiles {
+            log::info!(
+                "[sweepga]   Identity p10/p50/p90: {:.1}% / {:.1}% / {:.1}%",
+                p[0] * 100.0,
+                p[1] * 100.0,
+                p[2] * 100.0
+            );
         }
     }
 }
@@ -140,8 +442,8 @@ impl CompactRecordMeta {
 #[derive(Debug, Clone)]
 #[allow(dead_code)]
 struct MergedChain {
-    query_name: String,
-    target_name: String,
+    query_id: u32,
+    target_id: u32,
     query_start: u64,
     query_end: u64,
     target_start: u64,
@@ -149,12 +451,30 @@ struct MergedChain {
     strand: char,
     total_length: u64,
     weighted_identity: f64, // Average identity of mapped regions (matches/mapped_length)
+    gap_compressed_identity: f64, // Member gap-compressed identities weighted by block length
     sum_matches: u64,       // Sum of matches from all mappings
     sum_block_lengths: u64, // Sum of actual mapped lengths
+    mapq: u8,               // Highest member MAPQ
     member_indices: Vec<usize>, // Indices of original mappings in this chain
+    inversions: Vec<(u64, u64)>, // Query ranges of inverted segments joined in (--allow-inversions)
+    member_overlap: bool,   // Members overlapped on the query (--split-overlapping-members)
 }
 
 impl MergedChain {
+    /// `iv:Z:` value listing the joined inverted segments as comma-separated
+    /// `start-end` query ranges, if there are any
+    fn inversions_tag(&self) -> Option<String> {
+        if self.inversions.is_empty() {
+            return None;
+        }
+        let ranges: Vec<String> = self
+            .inversions
+            .iter()
+            .map(|(start, end)| format!("{start}-{end}"))
+            .collect();
+        Some(ranges.join(","))
+    }
+
     /// Calculate score for this chain based on the scoring function
     fn score(&self, scoring: ScoringFunction) -> f64 {
         match scoring {
@@ -182,47 +502,93 @@ impl MergedChain {
                 // Total matches only - more matches = better
                 self.sum_matches as f64
             }
+            ScoringFunction::MapqIdentity => {
+                // Weighted identity scaled by the best member's MAPQ
+                self.weighted_identity * crate::plane_sweep_exact::mapq_weight(self.mapq)
+            }
+            ScoringFunction::GapCompressedIdentity => self.gap_compressed_identity,
+            ScoringFunction::Weighted { identity, length } => {
+                // Unnormalized blend; the plane sweeps normalize per group
+                identity * self.weighted_identity
+                    + length * (self.sum_block_lengths.max(1) as f64).ln()
+            }
+            ScoringFunction::Custom {
+                len_weight,
+                id_weight,
+                use_log,
+            } => {
+                let length = if use_log {
+                    (self.sum_block_lengths.max(1) as f64).ln()
+                } else {
+                    self.sum_block_lengths as f64
+                };
+                len_weight * length + id_weight * self.weighted_identity
+            }
+        }
+    }
+
+    /// Diagonal of the chain start, as for members (anti-diagonal on '-')
+    fn diagonal(&self) -> i64 {
+        if self.strand == '+' {
+            self.target_start as i64 - self.query_start as i64
+        } else {
+            self.target_end as i64 + self.query_start as i64
+        }
+    }
+
+    /// Target position where the chain ends along the query
+    fn target_exit(&self) -> u64 {
+        if self.strand == '+' {
+            self.target_end
+        } else {
+            self.target_start
+        }
+    }
+
+    /// Target position where the chain starts along the query
+    fn target_entry(&self) -> u64 {
+        if self.strand == '+' {
+            self.target_start
+        } else {
+            self.target_end
         }
     }
 }
 
-impl ScaffoldLike for MergedChain {
+/// A merged chain with its sequence names, for the shared scaffold sweep
+struct NamedChain<'a> {
+    chain: &'a MergedChain,
+    names: &'a SequenceIndex,
+}
+
+impl ScaffoldLike for NamedChain<'_> {
     fn query_name(&self) -> &str {
-        &self.query_name
+        self.names.name(self.chain.query_id)
     }
     fn target_name(&self) -> &str {
-        &self.target_name
+        self.names.name(self.chain.target_id)
     }
     fn query_start(&self) -> u64 {
-        self.query_start
+        self.chain.query_start
     }
     fn query_end(&self) -> u64 {
-        self.query_end
+        self.chain.query_end
     }
     fn target_start(&self) -> u64 {
-        self.target_start
+        self.chain.target_start
     }
     fn target_end(&self) -> u64 {
-        self.target_end
+        self.chain.target_end
     }
     fn identity(&self) -> f64 {
-        self.weighted_identity
+        self.chain.weighted_identity
+    }
+    fn mapq(&self) -> u8 {
+        self.chain.mapq
+    }
+    fn gap_compressed_identity(&self) -> f64 {
+        self.chain.gap_compressed_identity
     }
-}
-
-/// Compact merged chain using sequence IDs
-#[derive(Debug, Clone)]
-#[allow(dead_code)]
-struct CompactMergedChain {
-    query_id: u32,
-    target_id: u32,
-    query_start: u64,
-    query_end: u64,
-    target_start: u64,
-    target_end: u64,
-    strand: char,
-    total_length: u64,
-    member_indices: Vec<usize>,
 }
 
 /// PAF filter that preserves original records
@@ -231,7 +597,62 @@ pub struct PafFilter {
     #[allow(dead_code)]
     temp_dir: Option<String>,
     keep_self: bool,
+    /// Treat mappings between haplotypes of one sample as self (`--self-genome`)
+    self_genome: bool,
+    /// Genomes a record must (not) involve (`--keep-pairs` / `--exclude-pairs`)
+    pair_selection: crate::pansn::PairSelection,
+    /// Keep only the query-genome < target-genome direction (`--canonical-pairs`)
+    canonical_pairs: bool,
+    strand: StrandFilter,
     scaffolds_only: bool,
+    no_rescue: bool,
+    min_relative_score: f64,
+    split_overlapping_members: bool,
+    member_overlap_tolerance: u64,
+    allow_inversions: bool,
+    max_diagonal_deviation: u64,
+    block_length_source: BlockLengthSource,
+    chain_identity_lis: bool,
+    reference_mode: bool,
+    max_rescued_per_scaffold: Option<usize>,
+    rescue_metric: RescueMetric,
+    overlap_axis: OverlapAxis,
+    input_coord_base: u8,
+    output_coord_base: u8,
+    minus_strand_coords: bool,
+    trim_overlaps: bool,
+    inversions_report: Option<PathBuf>,
+    breakpoints_report: Option<PathBuf>,
+    scaffold_bed: Option<PathBuf>,
+    histogram: Option<PathBuf>,
+    validate_output: bool,
+    bridges_report: Option<PathBuf>,
+    chain_report: Option<PathBuf>,
+    chain_output: Option<PathBuf>,
+    axt_output: Option<PathBuf>,
+    /// FASTA files holding the aligned sequences, for --axt
+    axt_sequences: Vec<PathBuf>,
+    component_tag: bool,
+    preserve_headers: bool,
+    min_pair_coverage: Option<f64>,
+    min_query_coverage: f64,
+    min_target_coverage: f64,
+    /// Keep this fraction of each chromosome pair's scaffolds (`--scaffold-filter 0.8f`)
+    scaffold_keep_fraction: Option<f64>,
+    repair_input: bool,
+    strict: bool,
+    reverse_output: Option<PathBuf>,
+    group_by_chain: bool,
+    sort_output: Option<OutputSort>,
+    registry_output: Option<PathBuf>,
+    no_plane_sweep: bool,
+    scaffold_bundle: Option<PathBuf>,
+    identity_transform: IdentityTransform,
+    max_output_bp: Option<u64>,
+    tag_sweep_rank: bool,
+    mark_secondary: bool,
+    low_memory: bool,
+    max_memory: Option<u64>,
 }
 
 #[allow(dead_code)]
@@ -243,10 +664,65 @@ impl PafFilter {
                 .ok()
                 .or_else(|| Some("/tmp".to_string())),
             keep_self: false, // Exclude self-mappings by default
+            self_genome: false,
+            pair_selection: crate::pansn::PairSelection::default(),
+            canonical_pairs: false,
+            strand: StrandFilter::Both,
             scaffolds_only: false,
+            no_rescue: false,
+            min_relative_score: 0.0,
+            split_overlapping_members: false,
+            member_overlap_tolerance: MEMBER_OVERLAP_TOLERANCE,
+            allow_inversions: false,
+            max_diagonal_deviation: 0,
+            block_length_source: BlockLengthSource::Column,
+            chain_identity_lis: false,
+            reference_mode: false,
+            max_rescued_per_scaffold: None,
+            rescue_metric: RescueMetric::Euclidean,
+            overlap_axis: OverlapAxis::Both,
+            input_coord_base: 0,
+            output_coord_base: 0,
+            minus_strand_coords: false,
+            trim_overlaps: false,
+            inversions_report: None,
+            breakpoints_report: None,
+            scaffold_bed: None,
+            histogram: None,
+            validate_output: false,
+            bridges_report: None,
+            chain_report: None,
+            chain_output: None,
+            axt_output: None,
+            axt_sequences: Vec::new(),
+            component_tag: false,
+            preserve_headers: false,
+            min_pair_coverage: None,
+            min_query_coverage: 0.0,
+            min_target_coverage: 0.0,
+            scaffold_keep_fraction: None,
+            repair_input: false,
+            strict: false,
+            reverse_output: None,
+            group_by_chain: false,
+            sort_output: None,
+            registry_output: None,
+            no_plane_sweep: false,
+            scaffold_bundle: None,
+            identity_transform: IdentityTransform::None,
+            max_output_bp: None,
+            tag_sweep_rank: false,
+            mark_secondary: false,
+            low_memory: false,
+            max_memory: None,
         }
     }
 
+    /// The thresholds this filter applies
+    pub fn config(&self) -> &FilterConfig {
+        &self.config
+    }
+
     /// Extract prefix from sequence name for grouping
     fn extract_prefix(&self, name: &str) -> String {
         if self.config.skip_prefix {
@@ -254,14 +730,17 @@ impl PafFilter {
             return name.to_string();
         }
 
-        // Extract prefix: take everything up to and including the last delimiter
+        // Extract prefix: everything up to and including the last delimiter, or
+        // the first --prefix-depth fields
         // e.g., "r#1#2" -> "r#1#", "Rabacal-1#Chr1" -> "Rabacal-1#", "r#1" -> "r#"
-        if let Some(pos) = name.rfind(self.config.prefix_delimiter) {
-            name[..=pos].to_string() // Include the delimiter itself
-        } else {
-            // No delimiter found, use full name
-            name.to_string()
-        }
+        crate::pansn::genome_prefix(name, self.config.prefix_delimiter, self.config.prefix_depth)
+            .to_string()
+    }
+
+    /// PanSN genome of a sequence name, regardless of `skip_prefix`: genome
+    /// selections name genomes even when filtering is per sequence
+    fn genome_of<'a>(&self, name: &'a str) -> &'a str {
+        crate::pansn::genome_prefix(name, self.config.prefix_delimiter, self.config.prefix_depth)
     }
 
     pub fn with_keep_self(mut self, keep_self: bool) -> Self {
@@ -269,1491 +748,5117 @@ impl PafFilter {
         self
     }
 
+    /// Count mappings between sequences of the same sample (first PanSN
+    /// field, e.g. HG002#1#chr1 and HG002#2#chr1) as self-mappings, instead
+    /// of only those between identically named sequences
+    pub fn with_self_genome(mut self, self_genome: bool) -> Self {
+        self.self_genome = self_genome;
+        self
+    }
+
+    /// Whether `m` is a self-mapping: same sequence, or with `self_genome`
+    /// the same sample
+    fn is_self_mapping(&self, m: &RecordMeta, names: &SequenceIndex) -> bool {
+        if !self.self_genome {
+            return m.query_id == m.target_id;
+        }
+        let sample =
+            |id| crate::pansn::genome_prefix(names.name(id), self.config.prefix_delimiter, 1);
+        sample(m.query_id) == sample(m.target_id)
+    }
+
+    /// Keep only records between the genomes chosen by `selection`
+    pub fn with_pair_selection(mut self, selection: crate::pansn::PairSelection) -> Self {
+        self.pair_selection = selection;
+        self
+    }
+
+    /// Keep each genome pair in one direction only, with the query genome
+    /// sorting before the target genome; self-pairs are unaffected
+    pub fn with_canonical_pairs(mut self, canonical_pairs: bool) -> Self {
+        self.canonical_pairs = canonical_pairs;
+        self
+    }
+
+    /// Keep only the records on `strand`, before the plane sweep and
+    /// scaffolding
+    pub fn with_strand(mut self, strand: StrandFilter) -> Self {
+        self.strand = strand;
+        self
+    }
+
     pub fn with_scaffolds_only(mut self, scaffolds_only: bool) -> Self {
         self.scaffolds_only = scaffolds_only;
         self
     }
 
-    /// Main filtering pipeline using record ranks
-    pub fn filter_paf<P: AsRef<Path>>(&self, input_path: P, output_path: P) -> Result<()> {
-        // First pass: extract metadata for all records
-        let metadata = self.extract_metadata(&input_path)?;
+    /// Keep the scaffold anchors only, rescuing nothing whatever the
+    /// configured `scaffold_max_deviation`: the same output as setting it to 0
+    pub fn with_no_rescue(mut self, no_rescue: bool) -> Self {
+        self.no_rescue = no_rescue;
+        self
+    }
 
-        // Apply filters to get passing record ranks
-        let passing_ranks = self.apply_filters(metadata)?;
+    /// Drop mappings scoring below `fraction` × the best score of their query (0 = off)
+    pub fn with_min_relative_score(mut self, fraction: f64) -> Self {
+        self.min_relative_score = fraction;
+        self
+    }
 
-        // Second pass: write passing records with annotations
-        self.write_filtered_output(&input_path, &output_path, passing_ranks)?;
+    /// Break scaffold chains whose members overlap on the query (tandem collapse)
+    pub fn with_split_overlapping_members(mut self, split: bool) -> Self {
+        self.split_overlapping_members = split;
+        self
+    }
 
-        Ok(())
+    /// Query overlap (bp) allowed between chain members before the chain is
+    /// flagged as a collapsed tandem duplication (default 100)
+    pub fn with_member_overlap_tolerance(mut self, tolerance: u64) -> Self {
+        self.member_overlap_tolerance = tolerance;
+        self
     }
 
-    /// Extract metadata from PAF without modifying records (private implementation)
-    fn extract_metadata<P: AsRef<Path>>(&self, path: P) -> Result<Vec<RecordMeta>> {
-        let reader = open_paf_input(path.as_ref())?;
-        let mut metadata = Vec::new();
-        let mut has_cigar = false;
-        let mut checked_cigar = false;
+    /// Join a chain, an opposite-strand chain and a chain continuing the first
+    /// one into a single chain when they are consecutive on the query within
+    /// the scaffold gap, so an inversion does not split its scaffold
+    pub fn with_allow_inversions(mut self, allow_inversions: bool) -> Self {
+        self.allow_inversions = allow_inversions;
+        self
+    }
 
-        for (rank, line) in reader.lines().enumerate() {
-            let line = line?;
-            let fields: Vec<&str> = line.split('\t').collect();
+    /// Drop chain members more than `max_deviation` bp off the chain's median diagonal (0 = off)
+    pub fn with_max_diagonal_deviation(mut self, max_deviation: u64) -> Self {
+        self.max_diagonal_deviation = max_deviation;
+        self
+    }
 
-            if fields.len() < 11 {
-                continue;
-            }
+    /// Choose how each record's block length is defined (default: PAF column 11)
+    pub fn with_block_length_from(mut self, source: BlockLengthSource) -> Self {
+        self.block_length_source = source;
+        self
+    }
 
-            // Parse essential fields
-            let query_name = fields[0].to_string();
-            let _query_len = fields[1].parse::<u64>().unwrap_or(0);
-            let query_start = fields[2].parse::<u64>().unwrap_or(0);
-            let query_end = fields[3].parse::<u64>().unwrap_or(0);
-            let strand = if fields[4] == "+" { '+' } else { '-' };
-            let target_name = fields[5].to_string();
-            let _target_len = fields[6].parse::<u64>().unwrap_or(0);
-            let target_start = fields[7].parse::<u64>().unwrap_or(0);
-            let target_end = fields[8].parse::<u64>().unwrap_or(0);
-            let matches = fields[9].parse::<u64>().unwrap_or(0);
-            let block_length = fields[10].parse::<u64>().unwrap_or(1);
-
-            // Calculate block identity: matches / alignment_length
-            // alignment_length is the block_length (denominator in PAF format)
-            let alignment_length = block_length;
-            let mut identity = matches as f64 / alignment_length.max(1) as f64;
-            let mut exact_matches = matches; // Default to PAF matches field
-
-            // Look for tags: divergence (dv:f:) and CIGAR (cg:Z:)
-            for field in &fields[11..] {
-                if let Some(div_str) = field.strip_prefix("dv:f:") {
-                    if let Ok(div) = div_str.parse::<f64>() {
-                        identity = 1.0 - div;
-                    }
-                } else if let Some(cigar_str) = field.strip_prefix("cg:Z:") {
-                    // Parse CIGAR to get exact match count
-                    if let Ok((cigar_matches, _, _, _)) = crate::paf::parse_cigar_counts(cigar_str)
-                    {
-                        if cigar_matches > 0 {
-                            exact_matches = cigar_matches;
-                            // Also update identity based on CIGAR matches
-                            identity = cigar_matches as f64 / alignment_length.max(1) as f64;
-                            has_cigar = true;
-                        }
-                    }
-                }
-            }
+    /// Compute chain identity from collinear members only (see `collinear_matches`)
+    pub fn with_chain_identity_lis(mut self, chain_identity_lis: bool) -> Self {
+        self.chain_identity_lis = chain_identity_lis;
+        self
+    }
 
-            // Check CIGAR availability on first record when using matches scoring
-            if !checked_cigar && self.config.scoring_function == ScoringFunction::Matches {
-                checked_cigar = true;
-                if !has_cigar {
-                    // log::info!("[sweepga] WARNING: Using 'matches' scoring but input lacks CIGAR strings (cg:Z: tags).");
-                    // log::info!("[sweepga] WARNING: Match counts will be estimated from PAF matches field (column 10).");
-                    // log::info!("[sweepga] WARNING: For exact match counts, use aligners that output extended CIGAR (e.g., wfmash).");
-                }
-            }
+    /// Keep at most `max` rescued mappings per chain, the ones closest to its anchors
+    pub fn with_max_rescued_per_scaffold(mut self, max: Option<usize>) -> Self {
+        self.max_rescued_per_scaffold = max;
+        self
+    }
 
-            metadata.push(RecordMeta {
-                rank,
-                query_name,
-                target_name,
-                query_start,
-                query_end,
-                target_start,
-                target_end,
-                block_length,
-                identity,
-                matches: exact_matches, // Use exact matches from CIGAR if available
-                alignment_length,
-                strand,
-                chain_id: None,
-                chain_status: ChainStatus::Unassigned,
-                discard: false,
-                overlapped: false,
-            });
-        }
+    /// Distance between a mapping and an anchor when rescuing
+    pub fn with_rescue_metric(mut self, metric: RescueMetric) -> Self {
+        self.rescue_metric = metric;
+        self
+    }
 
-        Ok(metadata)
+    /// Run the mapping plane sweep along the query axis, the target axis, or
+    /// both (keeping the intersection)
+    pub fn with_overlap_axis(mut self, axis: OverlapAxis) -> Self {
+        self.overlap_axis = axis;
+        self
     }
 
-    /// Apply filtering pipeline following wfmash's algorithm
-    pub fn apply_filters(
-        &self,
-        mut metadata: Vec<RecordMeta>,
-    ) -> Result<HashMap<usize, RecordMeta>> {
-        // 1. Filter by minimum block length, self-mappings, and minimum identity
-        metadata.retain(|m| {
-            m.block_length >= self.config.min_block_length
-                && (self.keep_self || m.query_name != m.target_name)
-                && m.identity >= self.config.min_identity
-        });
+    /// Sweep each query against all targets at once, keeping its best hit(s)
+    /// per region whichever reference sequence they land on
+    pub fn with_reference_mode(mut self, reference_mode: bool) -> Self {
+        self.reference_mode = reference_mode;
+        self
+    }
 
-        // Keep all original mappings for rescue phase (before any plane sweep)
-        let all_original_mappings = metadata.clone();
+    /// Read and write PAF start coordinates as 0-based (standard) or 1-based.
+    /// Internally everything stays 0-based half-open.
+    pub fn with_coord_base(mut self, input_base: u8, output_base: u8) -> Self {
+        self.input_coord_base = input_base;
+        self.output_coord_base = output_base;
+        self
+    }
 
-        // 2. Apply plane sweep as the default filtering method
-        // IMPORTANT: Plane sweep must be applied PER QUERY SEQUENCE, not per query-target pair!
-        // This is how wfmash does it - all mappings from a query compete regardless of target
+    /// Write reverse-strand target coordinates on the minus strand
+    /// (`target_len - end`, `target_len - start`), as in chain/axt files
+    pub fn with_minus_strand_coords(mut self, minus_strand_coords: bool) -> Self {
+        self.minus_strand_coords = minus_strand_coords;
+        self
+    }
 
-        // The plane sweep is already correctly implemented in apply_plane_sweep_to_mappings
-        // which groups by query sequence internally. We just need to pass ALL mappings to it.
-        let before_plane_sweep = metadata.len();
-        metadata = self.apply_plane_sweep_to_mappings(&metadata)?;
-        let after_plane_sweep = metadata.len();
+    /// Clip kept mappings so none overlap on the query (modifies coordinates)
+    pub fn with_trim_overlaps(mut self, trim_overlaps: bool) -> Self {
+        self.trim_overlaps = trim_overlaps;
+        self
+    }
 
-        // Report plane sweep if it filtered anything
-        if before_plane_sweep != after_plane_sweep {
-            log::info!("[sweepga] Plane sweep: {before_plane_sweep} → {after_plane_sweep} mappings");
-        }
+    /// Write surviving reverse-strand scaffold chains per genome pair to `path`
+    pub fn with_inversions_report(mut self, path: Option<PathBuf>) -> Self {
+        self.inversions_report = path;
+        self
+    }
 
-        // If no scaffolding (scaffold_gap == 0), we're done - return the plane-swept mappings
-        if self.config.scaffold_gap == 0 {
-            log::info!("[sweepga] Plane sweep filtering (no scaffolding)");
-            let total_kept_bases: u64 = metadata.iter().map(|m| m.block_length).sum();
-            let avg_identity = if !metadata.is_empty() {
-                metadata.iter().map(|m| m.identity).sum::<f64>() / metadata.len() as f64
-            } else {
-                0.0
-            };
-            log::info!(
-                "[sweepga] Summary: {} → {} mappings ({:.1}% kept)",
-                all_original_mappings.len(),
-                metadata.len(),
-                (metadata.len() as f64 / all_original_mappings.len().max(1) as f64) * 100.0
-            );
-            log::info!(
-                "[sweepga]   Output: {:.1} Mb total, {:.1}% avg identity",
-                total_kept_bases as f64 / 1_000_000.0,
-                avg_identity * 100.0
-            );
+    /// Write rearrangement breakpoints between consecutive scaffold chains
+    /// (along the query) on different diagonals to `path`
+    pub fn with_breakpoints_report(mut self, path: Option<PathBuf>) -> Self {
+        self.breakpoints_report = path;
+        self
+    }
 
-            let mut result = HashMap::new();
-            for m in metadata {
-                result.insert(m.rank, m);
-            }
-            return Ok(result);
-        }
+    /// Write the query span of each kept scaffold chain to `path` as BED
+    pub fn with_scaffold_bed(mut self, path: Option<PathBuf>) -> Self {
+        self.scaffold_bed = path;
+        self
+    }
 
-        // 3. Apply scaffold filtering (wfmash's filterByScaffolds)
-        // According to CLAUDE.md: create scaffolds from the PLANE-SWEPT mappings,
-        // then rescue from ALL ORIGINAL mappings
+    /// Write identity and block length histograms of the output mappings to
+    /// `path`
+    pub fn with_histogram(mut self, path: Option<PathBuf>) -> Self {
+        self.histogram = path;
+        self
+    }
 
-        // Use scaffold_gap for merging into scaffolds
-        let merged_chains = self.merge_mappings_into_chains(&metadata, self.config.scaffold_gap)?;
-        // log::info!(
-        //     "[sweepga]   Merged into {} chains (gap ≤ {})",
-        //     merged_chains.len(),
-        //     self.config.scaffold_gap
-        // );
+    /// Re-read the written output and check that its records are exactly the
+    /// passing input records, each once and with its input coordinates
+    pub fn with_validate_output(mut self, validate_output: bool) -> Self {
+        self.validate_output = validate_output;
+        self
+    }
 
-        // Step 2: Filter chains by minimum scaffold length and identity
-        let mut filtered_chains: Vec<MergedChain> = merged_chains
-            .into_iter()
-            .filter(|chain| {
-                chain.total_length >= self.config.min_scaffold_length
-                    && chain.weighted_identity >= self.config.min_scaffold_identity
-            })
-            .collect();
-        // log::info!(
-        //     "[sweepga]   Length/identity filter: {} chains kept, {} removed",
-        //     filtered_chains.len(),
-        //     filtered_out
-        // );
-        if self.config.min_scaffold_length > 0 || self.config.min_scaffold_identity > 0.0 {
-            // log::info!(
-            //     "[sweepga]   Min length: {}, min identity: {:.1}%",
-            //     self.config.min_scaffold_length,
-            //     self.config.min_scaffold_identity * 100.0
-            // );
-        }
+    /// Write rescued mappings lying near anchors of two or more chains to `path`
+    pub fn with_bridges_report(mut self, path: Option<PathBuf>) -> Self {
+        self.bridges_report = path;
+        self
+    }
 
-        // Step 3: Apply plane sweep to scaffolds
-        // Track which mappings are in scaffolds BEFORE plane sweep
-        let mut pre_sweep_scaffold_members: HashSet<usize> = HashSet::new();
-        for chain in &filtered_chains {
-            for &member_rank in &chain.member_indices {
-                pre_sweep_scaffold_members.insert(member_rank);
-            }
-        }
+    /// Write one row of statistics per kept scaffold chain (span, anchor and
+    /// rescued mapping counts, identity) to `path`
+    pub fn with_chain_report(mut self, path: Option<PathBuf>) -> Self {
+        self.chain_report = path;
+        self
+    }
 
-        filtered_chains = self.apply_scaffold_plane_sweep(filtered_chains)?;
-        // log::info!(
-        //     "[sweepga]   Scaffold sweep: {} → {} scaffolds",
-        //     before_sweep,
-        //     filtered_chains.len()
-        // );
+    /// Also write the output chains in UCSC chain format to `path`
+    pub fn with_chain_output(mut self, path: Option<PathBuf>) -> Self {
+        self.chain_output = path;
+        self
+    }
 
-        // If scaffolds_only mode, return the actual mappings that form scaffolds
-        if self.scaffolds_only {
-            let mut scaffold_mappings = HashMap::new();
+    /// Also write output records with CIGARs as AXT blocks to `path`, taking
+    /// the aligned bases from the `sequences` FASTA files
+    pub fn with_axt_output(mut self, path: Option<PathBuf>, sequences: Vec<PathBuf>) -> Self {
+        self.axt_output = path;
+        self.axt_sequences = sequences;
+        self
+    }
 
-            // Build a map from rank to original metadata for quick lookup
-            // Use all_original_mappings since member_indices refers to original ranks
-            let mut rank_to_meta: HashMap<usize, &RecordMeta> = HashMap::new();
-            for meta in &all_original_mappings {
-                rank_to_meta.insert(meta.rank, meta);
-            }
+    /// Tag output records with `cc:i:`, the connected component of the genome
+    /// graph (genomes linked by surviving mappings) their genome pair is in
+    pub fn with_component_tag(mut self, component_tag: bool) -> Self {
+        self.component_tag = component_tag;
+        self
+    }
 
-            // Collect all member mappings from scaffold chains
-            for (chain_idx, chain) in filtered_chains.iter().enumerate() {
-                // Create a unique chain ID
-                let chain_id = format!("chain_{}", chain_idx + 1);
+    /// Copy leading `#`/`@` header lines of the input to the top of the output
+    pub fn with_preserve_headers(mut self, preserve_headers: bool) -> Self {
+        self.preserve_headers = preserve_headers;
+        self
+    }
 
-                for &member_rank in &chain.member_indices {
-                    // member_indices contains ranks of original mappings
-                    if let Some(meta) = rank_to_meta.get(&member_rank) {
-                        let mut scaffold_meta = (*meta).clone();
-                        scaffold_meta.chain_status = ChainStatus::Scaffold;
-                        scaffold_meta.chain_id = Some(chain_id.clone());
-                        scaffold_mappings.insert(member_rank, scaffold_meta);
-                    }
-                }
-            }
+    /// Drop every mapping of a genome pair whose kept mappings cover less than
+    /// `fraction` of the smaller genome of the pair
+    pub fn with_min_pair_coverage(mut self, fraction: Option<f64>) -> Self {
+        self.min_pair_coverage = fraction;
+        self
+    }
 
-            return Ok(scaffold_mappings);
-        }
+    /// Drop mappings covering less than `fraction` of their query sequence (0 = off)
+    pub fn with_min_query_coverage(mut self, fraction: f64) -> Self {
+        self.min_query_coverage = fraction;
+        self
+    }
 
-        // Step 4: Identify anchors - use the actual member mappings of scaffold chains
-        // NOTE: member_indices actually contains ranks, not array indices!
-        let mut anchor_ranks = HashSet::new();
-        let mut rank_to_chain_id: HashMap<usize, String> = HashMap::new();
+    /// Drop mappings covering less than `fraction` of their target sequence (0 = off)
+    pub fn with_min_target_coverage(mut self, fraction: f64) -> Self {
+        self.min_target_coverage = fraction;
+        self
+    }
 
-        for (chain_idx, chain) in filtered_chains.iter().enumerate() {
-            let chain_id = format!("chain_{}", chain_idx + 1);
+    /// Keep `ceil(fraction × n)` of the n scaffolds on each chromosome pair,
+    /// per query and target position, instead of the M:N scaffold limits
+    pub fn with_scaffold_keep_fraction(mut self, fraction: Option<f64>) -> Self {
+        self.scaffold_keep_fraction = fraction;
+        self
+    }
 
-            // The member_indices field contains the ranks of mappings in this chain
-            for &member_rank in &chain.member_indices {
-                anchor_ranks.insert(member_rank);
-                rank_to_chain_id.insert(member_rank, chain_id.clone());
-            }
-        }
+    /// Drop a truncated (not newline-terminated) final input line with a
+    /// warning instead of parsing it, e.g. after a crashed run
+    pub fn with_repair_input(mut self, repair_input: bool) -> Self {
+        self.repair_input = repair_input;
+        self
+    }
 
-        // Step 4b: Include inversions that are on the scaffold's diagonal.
-        // For each forward scaffold, check if any reverse alignments would be "on the diagonal"
-        // if they were flipped. This is equivalent to checking perpendicular distance to the
-        // diagonal line: for a forward scaffold with t = q + offset, the perpendicular distance
-        // from point (q, t) is |t - q - offset| / sqrt(2).
-        let max_diagonal_distance = self.config.scaffold_gap;
+    /// Fail on the first malformed PAF line (too few columns, non-numeric
+    /// coordinates) instead of skipping and counting it
+    pub fn with_strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
 
-        // Pre-group reverse-strand mappings by (query_name, target_name) to avoid
-        // scanning all mappings for every chain (was O(chains * all_mappings)).
-        let mut reverse_by_chr_pair: HashMap<(&str, &str), Vec<usize>> = HashMap::new();
-        for (idx, mapping) in all_original_mappings.iter().enumerate() {
-            if mapping.strand == '-' {
-                reverse_by_chr_pair
-                    .entry((&mapping.query_name, &mapping.target_name))
-                    .or_default()
-                    .push(idx);
-            }
-        }
+    /// Write reverse-strand records to `path` instead of the main output
+    pub fn with_reverse_output(mut self, path: Option<PathBuf>) -> Self {
+        self.reverse_output = path;
+        self
+    }
 
-        for (chain_idx, chain) in filtered_chains.iter().enumerate() {
-            // Only process forward scaffolds - inversions belong to forward diagonals
-            if chain.strand != '+' {
-                continue;
-            }
+    /// Write records grouped by chain, chains by descending total matches,
+    /// instead of in input order (unchained records form their own groups)
+    pub fn with_group_by_chain(mut self, group_by_chain: bool) -> Self {
+        self.group_by_chain = group_by_chain;
+        self
+    }
 
-            let chain_id = format!("chain_{}", chain_idx + 1);
-            let diagonal_offset = chain.target_start as i64 - chain.query_start as i64;
+    /// Write records sorted by `sort` instead of in input order
+    pub fn with_sort_output(mut self, sort: Option<OutputSort>) -> Self {
+        self.sort_output = sort;
+        self
+    }
 
-            // Only check reverse mappings on the same chromosome pair
-            let candidates =
-                reverse_by_chr_pair.get(&(chain.query_name.as_str(), chain.target_name.as_str()));
-            let empty_vec = Vec::new();
-            let candidates = candidates.unwrap_or(&empty_vec);
+    /// Write the input's sequence names, their IDs (in order of first
+    /// appearance) and lengths to `path` as TSV
+    pub fn with_registry_output(mut self, path: Option<PathBuf>) -> Self {
+        self.registry_output = path;
+        self
+    }
 
-            for &idx in candidates {
-                let mapping = &all_original_mappings[idx];
+    /// Skip the mapping plane sweep so scaffolds are built from all raw
+    /// mappings and only the scaffold sweep arbitrates between them
+    pub fn with_no_plane_sweep(mut self, no_plane_sweep: bool) -> Self {
+        self.no_plane_sweep = no_plane_sweep;
+        self
+    }
 
-                // Skip if already an anchor
-                if anchor_ranks.contains(&mapping.rank) {
-                    continue;
-                }
+    /// Also write each output scaffold chain, with a summary header, to its
+    /// own `<chain id>.paf` in directory `dir`
+    pub fn with_scaffold_bundle(mut self, dir: Option<PathBuf>) -> Self {
+        self.scaffold_bundle = dir;
+        self
+    }
 
-                // Check if near the scaffold's query range (within scaffold_gap distance)
-                // This allows inversions just before/after the scaffold to be captured
-                let extended_query_start = chain.query_start.saturating_sub(max_diagonal_distance);
-                let extended_query_end = chain.query_end.saturating_add(max_diagonal_distance);
-                if mapping.query_end < extended_query_start
-                    || mapping.query_start > extended_query_end
-                {
-                    continue;
-                }
+    /// Correct each record's observed identity with an evolutionary model
+    /// before identity thresholds and scoring see it
+    pub fn with_identity_transform(mut self, transform: IdentityTransform) -> Self {
+        self.identity_transform = transform;
+        self
+    }
 
-                // Calculate perpendicular distance to the scaffold's diagonal
-                // Diagonal line: t = q + offset
-                // Perpendicular distance = |t - q - offset| / sqrt(2)
-                let mapping_q_center = (mapping.query_start + mapping.query_end) / 2;
-                let mapping_t_center = (mapping.target_start + mapping.target_end) / 2;
-                let deviation =
-                    (mapping_t_center as i64 - mapping_q_center as i64 - diagonal_offset)
-                        .unsigned_abs();
-                let perpendicular_distance = (deviation as f64 / std::f64::consts::SQRT_2) as u64;
+    /// Keep only the best-scoring passing mappings whose block lengths add
+    /// up to at most `max_bp`
+    pub fn with_max_output_bp(mut self, max_bp: Option<u64>) -> Self {
+        self.max_output_bp = max_bp;
+        self
+    }
 
-                if perpendicular_distance <= max_diagonal_distance {
-                    anchor_ranks.insert(mapping.rank);
-                    rank_to_chain_id.insert(mapping.rank, chain_id.clone());
-                }
+    /// Tag output records with `sr:i:`, their rank among the competitors kept
+    /// by the mapping plane sweep (0 = primary; untagged if not swept)
+    pub fn with_tag_sweep_rank(mut self, tag_sweep_rank: bool) -> Self {
+        self.tag_sweep_rank = tag_sweep_rank;
+        self
+    }
+
+    /// Keep the mappings lost to the plane sweep and scaffold filtering,
+    /// tagging output records `tp:A:P` (kept) or `tp:A:S` (filtered out)
+    pub fn with_mark_secondary(mut self, mark_secondary: bool) -> Self {
+        self.mark_secondary = mark_secondary;
+        self
+    }
+
+    /// Filter one query sequence at a time in a single streaming pass, so
+    /// memory is bounded by the largest query group instead of the whole
+    /// input. The input must be grouped by query sequence (e.g. `sort -k1,1`)
+    /// and the filter must decide per query: no scaffolding, and reference
+    /// mode or no plane sweep.
+    pub fn with_low_memory(mut self, low_memory: bool) -> Self {
+        self.low_memory = low_memory;
+        self
+    }
+
+    /// Memory budget in bytes for in-memory filtering. Larger inputs are
+    /// filtered as with `with_low_memory`, or rejected up front if the
+    /// settings need all records at once.
+    pub fn with_max_memory(mut self, max_memory: Option<u64>) -> Self {
+        self.max_memory = max_memory;
+        self
+    }
+
+    /// Main filtering pipeline using record ranks. Returns the number of records written.
+    pub fn filter_paf<P: AsRef<Path>>(&self, input_path: P, output_path: P) -> Result<usize> {
+        self.filter_paf_with_stats(input_path, output_path)
+            .map(|(written, _stats)| written)
+    }
+
+    /// [`Self::filter_paf`], also returning the counts of each filtering stage
+    pub fn filter_paf_with_stats<P: AsRef<Path>>(
+        &self,
+        input_path: P,
+        output_path: P,
+    ) -> Result<(usize, FilterStats)> {
+        anyhow::ensure!(
+            !self.mark_secondary
+                || (self.max_output_bp.is_none() && self.min_pair_coverage.is_none()),
+            "--mark-secondary cannot be combined with --max-output-bp or --min-pair-coverage"
+        );
+        if self.low_memory || self.exceeds_max_memory(input_path.as_ref())? {
+            return self.filter_paf_streaming(input_path.as_ref(), output_path.as_ref());
+        }
+        let _profile = crate::profile::scope("filter_paf");
+
+        // First pass: extract metadata for all records
+        let mut names = SequenceIndex::new();
+        let (mut metadata, fingerprint, malformed) =
+            self.extract_metadata(&input_path, &mut names)?;
+        self.write_registry(&names)?;
+
+        if self.repair_input {
+            if let Some((rank, line)) = crate::paf::truncated_final_line(&input_path)? {
+                log::warn!(
+                    "[sweepga] --repair-input: dropping truncated final line {}: {line}",
+                    rank + 1
+                );
+                metadata.retain(|meta| meta.rank != rank);
             }
         }
 
-        // Identify mappings that were in filtered-out scaffolds
-        // These should NOT be rescued even if they're near surviving anchors
-        let filtered_scaffold_members: HashSet<usize> = pre_sweep_scaffold_members
-            .difference(&anchor_ranks)
-            .copied()
-            .collect();
+        // Apply filters to get passing record ranks
+        let (mut passing_ranks, mut stats, swept_out) =
+            self.apply_filters_with_swept_out(metadata, &names)?;
+        stats.malformed_lines = malformed;
 
-        // log::info!("[sweepga] Rescue phase");
-        // log::info!(
-        //     "[sweepga]   Anchors: {} mappings in {} scaffolds",
-        //     anchor_ranks.len(),
-        //     filtered_chains.len()
-        // );
+        if let Some(min_coverage) = self.min_pair_coverage {
+            let sizes = crate::paf::GenomeSizes::from_paf(&input_path)?;
+            self.drop_low_coverage_pairs(&mut passing_ranks, &names, &sizes, min_coverage);
+        }
 
-        // Map ranks to indices in all_original_mappings
-        let mut rank_to_idx = HashMap::new();
-        for (idx, meta) in all_original_mappings.iter().enumerate() {
-            rank_to_idx.insert(meta.rank, idx);
+        if let Some(max_bp) = self.max_output_bp {
+            self.cap_output_bp(&mut passing_ranks, max_bp);
+        }
+        // Summarize what is written, after the whole-input cutoffs
+        stats.record_output(passing_ranks.values());
+        stats.log_summary();
+        if let Some(ref path) = self.histogram {
+            crate::histogram::write_histogram(
+                path,
+                passing_ranks.values().map(|m| (m.identity, m.block_length)),
+            )?;
         }
 
-        // Step 5: Rescue mappings within scaffold_max_deviation of anchors
-        // OPTIMIZED: Group by chromosome pair and sort for efficient rescue
+        // Optional post-processing: clip kept mappings to a non-overlapping query tiling
+        let trims = if self.trim_overlaps {
+            self.trim_query_overlaps(&mut passing_ranks, &names)
+        } else {
+            HashMap::new()
+        };
 
-        // First, group all mappings by (query_chr, target_chr) pair.
-        // IndexMap: iteration order = first-encounter (PAF input order),
-        // making the kept_mappings output order deterministic across runs.
-        let mut mappings_by_chr_pair: IndexMap<(String, String), Vec<usize>> = IndexMap::new();
-        for (idx, mapping) in all_original_mappings.iter().enumerate() {
-            let key = (mapping.query_name.clone(), mapping.target_name.clone());
-            mappings_by_chr_pair.entry(key).or_default().push(idx);
+        if self.mark_secondary {
+            let added = Self::add_secondaries(&mut passing_ranks, swept_out);
+            log::info!("[sweepga]   Kept {added} filtered-out mappings as secondary (tp:A:S)");
         }
 
-        // Sort each chromosome pair's mappings by query position for binary search
-        for indices in mappings_by_chr_pair.values_mut() {
-            indices.sort_by_key(|&idx| all_original_mappings[idx].query_start);
+        if let Some(ref dir) = self.scaffold_bundle {
+            let chain_ids: BTreeSet<&str> = passing_ranks
+                .values()
+                .filter_map(|m| m.chain_id.as_deref())
+                .collect();
+            stats.scaffold_bundles = chain_ids
+                .into_iter()
+                .map(|chain_id| crate::scaffold_bundle::bundle_path(dir, chain_id))
+                .collect();
         }
 
-        // Collect anchors by chromosome pair for efficient lookup.
-        let mut anchors_by_chr_pair: HashMap<(String, String), Vec<usize>> = HashMap::new();
-        for &anchor_rank in &anchor_ranks {
-            if let Some(&anchor_idx) = rank_to_idx.get(&anchor_rank) {
-                let anchor = &all_original_mappings[anchor_idx];
-                let key = (anchor.query_name.clone(), anchor.target_name.clone());
-                anchors_by_chr_pair.entry(key).or_default().push(anchor_idx);
-            }
+        // Second pass: write passing records with annotations
+        let written = self.write_filtered_output(
+            &input_path,
+            &output_path,
+            passing_ranks,
+            &names,
+            &trims,
+            fingerprint,
+        )?;
+        Ok((written, stats))
+    }
+
+    /// Why these settings cannot be filtered one query at a time
+    /// (`filter_paf_streaming`), if they can't
+    fn low_memory_conflict(&self) -> Option<String> {
+        if self.config.scaffold_gap != 0 || !(self.reference_mode || self.no_plane_sweep) {
+            return Some(
+                "--low-memory filters one query at a time and needs --scaffold-jump 0 with \
+                 --reference-mode or --no-plane-sweep"
+                    .to_string(),
+            );
         }
+        let whole_input_options = [
+            (self.trim_overlaps, "--trim-overlaps"),
+            (self.min_pair_coverage.is_some(), "--min-pair-coverage"),
+            (self.max_output_bp.is_some(), "--max-output-bp"),
+            (self.component_tag, "--component-tag"),
+            (self.group_by_chain, "--group-by-chain"),
+            (self.sort_output.is_some(), "--sort-output"),
+            (self.mark_secondary, "--mark-secondary"),
+            (self.histogram.is_some(), "--histogram"),
+            (self.validate_output, "--validate-output"),
+            (self.chain_output.is_some(), "--chain"),
+            (self.axt_output.is_some(), "--axt"),
+            (self.scaffold_bundle.is_some(), "--scaffold-bundle"),
+        ];
+        whole_input_options
+            .iter()
+            .find(|(set, _)| *set)
+            .map(|(_, flag)| {
+                format!("{flag} needs all records at once and cannot be used with --low-memory")
+            })
+    }
 
-        let mut kept_mappings = Vec::new();
-        let mut kept_status = HashMap::new();
-        let max_deviation = self.config.scaffold_max_deviation;
+    /// Whether filtering `input_path` in memory would exceed `--max-memory`,
+    /// estimated from its line count. Errors if it would and the settings
+    /// cannot be filtered one query at a time instead.
+    fn exceeds_max_memory(&self, input_path: &Path) -> Result<bool> {
+        let Some(limit) = self.max_memory else {
+            return Ok(false);
+        };
+        let records = crate::paf::count_lines(input_path)?;
+        let estimate = (records as u64).saturating_mul(RECORD_MEMORY_BYTES);
+        if estimate <= limit {
+            return Ok(false);
+        }
+        let needed = crate::disk_usage::format_bytes(estimate);
+        let limit = crate::disk_usage::format_bytes(limit);
+        if let Some(conflict) = self.low_memory_conflict() {
+            anyhow::bail!(
+                "Filtering {records} records in memory needs about {needed}, over \
+                 --max-memory {limit}, and they cannot be streamed: {conflict}"
+            );
+        }
+        log::warn!(
+            "[sweepga] Filtering {records} records in memory needs about {needed}, over \
+             --max-memory {limit}; filtering one query at a time (--low-memory)"
+        );
+        Ok(true)
+    }
 
-        // Process each chromosome pair independently (can be parallelized)
-        for ((query_chr, target_chr), mapping_indices) in &mappings_by_chr_pair {
-            let chr_key = (query_chr.clone(), target_chr.clone());
-            let chr_anchors = anchors_by_chr_pair
-                .get(&chr_key)
-                .map(|v| v.as_slice())
-                .unwrap_or(&[]);
+    /// Single-pass `filter_paf` for `with_low_memory`: each query sequence's
+    /// records are filtered and written as soon as the next query starts
+    fn filter_paf_streaming(
+        &self,
+        input_path: &Path,
+        output_path: &Path,
+    ) -> Result<(usize, FilterStats)> {
+        let _profile = crate::profile::scope("filter_paf");
+        if let Some(conflict) = self.low_memory_conflict() {
+            anyhow::bail!(conflict);
+        }
 
-            if chr_anchors.is_empty() {
-                continue; // No anchors on this chromosome pair, skip
+        let mut truncated_rank = None;
+        if self.repair_input {
+            if let Some((rank, line)) = crate::paf::truncated_final_line(input_path)? {
+                log::warn!(
+                    "[sweepga] --repair-input: dropping truncated final line {}: {line}",
+                    rank + 1
+                );
+                truncated_rank = Some(rank);
             }
+        }
 
-            // For each mapping on this chromosome pair
-            for &mapping_idx in mapping_indices {
-                let mapping = &all_original_mappings[mapping_idx];
+        let mut names = SequenceIndex::new();
+        let mut writer = BufWriter::new(File::create(output_path)?);
+        let mut reverse_writer = self.create_reverse_writer()?;
 
-                if anchor_ranks.contains(&mapping.rank) {
-                    // This is an anchor (member of a scaffold chain) - keep it with its chain ID
-                    let mut anchor_mapping = mapping.clone();
-                    if let Some(chain_id) = rank_to_chain_id.get(&mapping.rank) {
-                        anchor_mapping.chain_id = Some(chain_id.clone());
+        let reader = open_paf_input(input_path)?;
+        // Records of the current query sequence, with their input lines
+        let mut block: Vec<(RecordMeta, String)> = Vec::new();
+        let mut finished_queries: HashSet<u32> = HashSet::new();
+        let mut total = 0;
+        // Identities and total block length of the written records
+        let mut identities = Vec::new();
+        let mut bases = 0;
+        let mut malformed = 0;
+        let mut in_header = self.preserve_headers;
+        for (rank, line) in reader.lines().enumerate() {
+            let line = line?;
+            // Skipped-pair markers always pass through, so the pairs read as
+            // intentionally empty rather than lost
+            if crate::paf::skipped_pair(&line).is_some() {
+                writeln!(writer, "{line}")?;
+                if let Some(ref mut reverse_writer) = reverse_writer {
+                    writeln!(reverse_writer, "{line}")?;
+                }
+                continue;
+            }
+            // Leading header lines pass through untouched with --preserve-headers
+            if in_header {
+                if crate::paf::is_header_line(&line) {
+                    writeln!(writer, "{line}")?;
+                    if let Some(ref mut reverse_writer) = reverse_writer {
+                        writeln!(reverse_writer, "{line}")?;
                     }
-                    kept_mappings.push(anchor_mapping);
-                    // All anchors are scaffold members by definition
-                    kept_status.insert(mapping.rank, ChainStatus::Scaffold);
-                } else if filtered_scaffold_members.contains(&mapping.rank) {
-                    // This mapping was part of a plane-sweep-filtered scaffold
-                    // Do NOT rescue it, even if it's near an anchor
                     continue;
-                } else if max_deviation > 0 {
-                    // Only attempt rescue if max_deviation > 0
-                    // Check if within deviation distance of any anchor
-                    // Use binary search to find anchors within query range
-                    let mapping_q_center = (mapping.query_start + mapping.query_end) / 2;
-                    let mapping_t_center = (mapping.target_start + mapping.target_end) / 2;
+                }
+                in_header = false;
+            }
+            if truncated_rank == Some(rank) {
+                continue;
+            }
+            let Some(meta) = self.parse_record(rank, &line, &mut names, &mut malformed)? else {
+                continue;
+            };
+            total += 1;
+
+            if let Some((first, _)) = block.first() {
+                if first.query_id != meta.query_id {
+                    finished_queries.insert(first.query_id);
+                    let block = std::mem::take(&mut block);
+                    let kept =
+                        self.write_query_block(block, &names, &mut writer, &mut reverse_writer)?;
+                    for m in kept {
+                        identities.push(m.identity);
+                        bases += m.block_length;
+                    }
+                }
+            }
+            anyhow::ensure!(
+                !finished_queries.contains(&meta.query_id),
+                "--low-memory needs the input grouped by query sequence (e.g. sort -k1,1): \
+                 {} reappears on line {}",
+                names.name(meta.query_id),
+                rank + 1
+            );
+            block.push((meta, line));
+        }
+        let kept = self.write_query_block(block, &names, &mut writer, &mut reverse_writer)?;
+        for m in kept {
+            identities.push(m.identity);
+            bases += m.block_length;
+        }
 
-                    let mut min_distance = u64::MAX;
-                    let mut closest_anchor_rank = None;
+        writer.flush()?;
+        if let Some(ref mut reverse_writer) = reverse_writer {
+            reverse_writer.flush()?;
+        }
+        self.write_registry(&names)?;
 
-                    // Only check anchors that are within max_deviation in query space
-                    for &anchor_idx in chr_anchors {
-                        let anchor = &all_original_mappings[anchor_idx];
-                        let anchor_q_center = (anchor.query_start + anchor.query_end) / 2;
+        // No scaffolding here, so only the record counts apply
+        let mut stats = FilterStats {
+            plane_sweep_before: total,
+            malformed_lines: malformed,
+            ..FilterStats::default()
+        };
+        stats.record_identities(&identities, bases);
+        stats.log_summary();
+        Ok((stats.output_mappings, stats))
+    }
 
-                        // Early exit if anchor is too far in query space
-                        let q_diff =
-                            (mapping_q_center as i64 - anchor_q_center as i64).unsigned_abs();
-                        if q_diff > max_deviation {
-                            continue; // Too far in query dimension alone
-                        }
+    /// Filter the records of one query sequence like `apply_filters` does
+    /// without scaffolding, and write the passing ones in input order.
+    /// Returns the written records.
+    fn write_query_block(
+        &self,
+        block: Vec<(RecordMeta, String)>,
+        names: &SequenceIndex,
+        writer: &mut BufWriter<File>,
+        reverse_writer: &mut Option<BufWriter<File>>,
+    ) -> Result<Vec<RecordMeta>> {
+        let mut lines = HashMap::with_capacity(block.len());
+        let mut metadata = Vec::with_capacity(block.len());
+        for (meta, line) in block {
+            lines.insert(meta.rank, line);
+            metadata.push(meta);
+        }
 
-                        let anchor_t_center = (anchor.target_start + anchor.target_end) / 2;
-                        let t_diff =
-                            (mapping_t_center as i64 - anchor_t_center as i64).unsigned_abs();
+        metadata.retain(|m| self.passes_record_filters(m, names));
+        if self.min_relative_score > 0.0 {
+            metadata = self.filter_by_relative_score(metadata);
+        }
+        if !self.no_plane_sweep {
+            metadata = self.apply_plane_sweep_to_mappings(&metadata, names)?;
+        }
+        metadata.sort_by_key(|m| m.rank);
+
+        for meta in &metadata {
+            let line = lines
+                .remove(&meta.rank)
+                .expect("passing records come from the block");
+            let line = self.output_line(self.internal_line(line, None)?, meta, None)?;
+            write_stranded(writer, reverse_writer, meta.strand, &line)?;
+        }
+        Ok(metadata)
+    }
 
-                        // Euclidean distance
-                        let distance = ((q_diff * q_diff + t_diff * t_diff) as f64).sqrt() as u64;
-                        if distance < min_distance {
-                            min_distance = distance;
-                            closest_anchor_rank = Some(anchor.rank);
-                        }
+    /// Write the `--dump-registry` TSV, if requested
+    fn write_registry(&self, registry: &SequenceIndex) -> Result<()> {
+        let Some(ref path) = self.registry_output else {
+            return Ok(());
+        };
+        let file = File::create(path)
+            .with_context(|| format!("Failed to create registry {}", path.display()))?;
+        let mut writer = BufWriter::new(file);
+        registry.write_tsv(&mut writer)?;
+        writer.flush()?;
+        log::info!(
+            "[sweepga] Wrote {} sequences to {}",
+            registry.len(),
+            path.display()
+        );
+        Ok(())
+    }
 
-                        // Early exit if we found a close enough anchor
-                        if min_distance <= max_deviation {
-                            break;
-                        }
+    /// Writer for reverse-strand records, when they go to a separate file
+    fn create_reverse_writer(&self) -> Result<Option<BufWriter<File>>> {
+        let Some(ref path) = self.reverse_output else {
+            return Ok(None);
+        };
+        let file =
+            File::create(path).with_context(|| format!("Failed to create {}", path.display()))?;
+        Ok(Some(BufWriter::new(file)))
+    }
+
+    /// Extract metadata from PAF without modifying records (private implementation),
+    /// registering sequence names in `names`
+    fn extract_metadata<P: AsRef<Path>>(
+        &self,
+        path: P,
+        names: &mut SequenceIndex,
+    ) -> Result<(Vec<RecordMeta>, InputFingerprint, usize)> {
+        let _profile = crate::profile::scope("parse");
+        let reader = open_paf_input(path.as_ref())?;
+        let mut metadata = Vec::new();
+        let mut fingerprint = InputFingerprint::default();
+        let mut malformed = 0;
+
+        for (rank, line) in reader.lines().enumerate() {
+            let line = line?;
+            fingerprint.add(&line);
+            if let Some(meta) = self.parse_record(rank, &line, names, &mut malformed)? {
+                metadata.push(meta);
+            }
+        }
+
+        Ok((metadata, fingerprint, malformed))
+    }
+
+    /// Error on malformed input line `rank` with `--strict`; otherwise count
+    /// it in `malformed`, logging the first one, and skip it
+    fn skip_malformed(
+        &self,
+        rank: usize,
+        reason: &str,
+        malformed: &mut usize,
+    ) -> Result<Option<RecordMeta>> {
+        anyhow::ensure!(!self.strict, "Malformed PAF line {}: {reason}", rank + 1);
+        if *malformed == 0 {
+            log::warn!(
+                "[sweepga] Skipping malformed PAF line {}: {reason} (--strict makes this an error)",
+                rank + 1
+            );
+        }
+        *malformed += 1;
+        Ok(None)
+    }
+
+    /// Metadata of input line `rank`, or None for empty, header and malformed
+    /// lines (see [`PafFilter::skip_malformed`])
+    fn parse_record(
+        &self,
+        rank: usize,
+        line: &str,
+        names: &mut SequenceIndex,
+        malformed: &mut usize,
+    ) -> Result<Option<RecordMeta>> {
+        if line.is_empty() || crate::paf::is_header_line(line) {
+            return Ok(None);
+        }
+        let fields: Vec<&str> = line.split('\t').collect();
+
+        if let Some(reason) = malformed_reason(&fields) {
+            return self.skip_malformed(rank, &reason, malformed);
+        }
+
+        // Parse essential fields
+        let query_len = fields[1].parse::<u64>().unwrap_or(0);
+        let query_start = self.to_zero_based(fields[2].parse::<u64>().unwrap_or(0), rank)?;
+        let query_end = fields[3].parse::<u64>().unwrap_or(0);
+        let strand = if fields[4] == "+" { '+' } else { '-' };
+        let target_len = fields[6].parse::<u64>().unwrap_or(0);
+        let target_start = self.to_zero_based(fields[7].parse::<u64>().unwrap_or(0), rank)?;
+        let target_end = fields[8].parse::<u64>().unwrap_or(0);
+        if let Some(reason) = inconsistent_range("query", query_start, query_end, query_len)
+            .or_else(|| inconsistent_range("target", target_start, target_end, target_len))
+        {
+            return self.skip_malformed(rank, &reason, malformed);
+        }
+        let matches = fields[9].parse::<u64>().unwrap_or(0);
+        let block_length = self.block_length_source.block_length(
+            fields[10].parse::<u64>().unwrap_or(1),
+            query_end.saturating_sub(query_start),
+            target_end.saturating_sub(target_start),
+        );
+        let query_id = names.get_or_insert_with_length(fields[0], query_len);
+        let target_id = names.get_or_insert_with_length(fields[5], target_len);
+        let mapq = fields
+            .get(11)
+            .and_then(|f| f.parse::<u8>().ok())
+            .unwrap_or(self.config.default_mapq);
+
+        // Calculate block identity: matches / alignment_length
+        // alignment_length is the block_length (denominator in PAF format)
+        let alignment_length = block_length;
+        let mut identity = matches as f64 / alignment_length.max(1) as f64;
+        let mut exact_matches = matches; // Default to PAF matches field
+        let mut gap_compressed_divergence = None;
+        let mut cigar_gap_compressed_identity = None;
+
+        // Look for tags: divergence (dv:f:, de:f:) and CIGAR (cg:Z:)
+        for field in &fields[11..] {
+            if let Some(div_str) = field.strip_prefix("dv:f:") {
+                if let Ok(div) = div_str.parse::<f64>() {
+                    identity = 1.0 - div;
+                }
+            } else if let Some(div_str) = field.strip_prefix("de:f:") {
+                gap_compressed_divergence = div_str.parse::<f64>().ok();
+            } else if let Some(cigar_str) = field.strip_prefix("cg:Z:") {
+                cigar_gap_compressed_identity =
+                    crate::paf::gap_compressed_identity(cigar_str, matches).ok();
+                // Parse CIGAR to get exact match count
+                if let Ok((cigar_matches, _, _, _)) = crate::paf::parse_cigar_counts(cigar_str) {
+                    if cigar_matches > 0 {
+                        exact_matches = cigar_matches;
+                        // Also update identity based on CIGAR matches
+                        identity = cigar_matches as f64 / alignment_length.max(1) as f64;
                     }
+                }
+            }
+        }
+        let identity = self.identity_transform.apply(identity);
+        // The CIGAR, else the aligner's de:f:, else block identity
+        let gap_compressed_identity = cigar_gap_compressed_identity
+            .or(gap_compressed_divergence.map(|div| 1.0 - div))
+            .map_or(identity, |gci| self.identity_transform.apply(gci));
+
+        Ok(Some(RecordMeta {
+            rank,
+            query_id,
+            target_id,
+            query_start,
+            query_end,
+            target_start,
+            target_end,
+            block_length,
+            identity,
+            gap_compressed_identity,
+            mapq,
+            matches: exact_matches, // Use exact matches from CIGAR if available
+            alignment_length,
+            strand,
+            chain_id: None,
+            chain_status: ChainStatus::Unassigned,
+            discard: false,
+            overlapped: false,
+            sweep_rank: None,
+            secondary: false,
+            inversions: None,
+        }))
+    }
 
-                    if min_distance <= max_deviation {
-                        // This mapping is rescued - assign it to the same chain as its closest anchor
-                        let mut rescued_mapping = mapping.clone();
-                        if let Some(anchor_rank) = closest_anchor_rank {
-                            if let Some(chain_id) = rank_to_chain_id.get(&anchor_rank) {
-                                rescued_mapping.chain_id = Some(chain_id.clone());
-                            }
+    /// Convert an input start coordinate to 0-based. 1-based inclusive
+    /// `[s, e]` is 0-based half-open `[s - 1, e)`, so only starts shift.
+    fn to_zero_based(&self, start: u64, rank: usize) -> Result<u64> {
+        start
+            .checked_sub(u64::from(self.input_coord_base))
+            .with_context(|| {
+                format!(
+                    "PAF line {}: start coordinate 0 is invalid with --coord-base 1",
+                    rank + 1
+                )
+            })
+    }
+
+    /// Apply filtering pipeline following wfmash's algorithm. `names` holds the
+    /// sequence IDs of `metadata`. Returns the passing mappings by rank, with
+    /// the counts of each stage.
+    pub fn apply_filters(
+        &self,
+        metadata: Vec<RecordMeta>,
+        names: &SequenceIndex,
+    ) -> Result<(HashMap<usize, RecordMeta>, FilterStats)> {
+        let (passing, stats, _swept_out) = self.apply_filters_with_swept_out(metadata, names)?;
+        Ok((passing, stats))
+    }
+
+    /// [`Self::apply_filters`], also returning the mappings dropped by the
+    /// plane sweep or with a chain dropped by the scaffold sweep (the
+    /// `--mark-secondary` candidates; none unless it is set)
+    fn apply_filters_with_swept_out(
+        &self,
+        mut metadata: Vec<RecordMeta>,
+        names: &SequenceIndex,
+    ) -> Result<(HashMap<usize, RecordMeta>, FilterStats, Vec<RecordMeta>)> {
+        // 1. Filter by minimum block length, self-mappings, minimum identity and
+        //    minimum query/target coverage
+        metadata.retain(|m| self.passes_record_filters(m, names));
+
+        // 1b. Per-query relative score cutoff (drops weak paralogous hits early)
+        if self.min_relative_score > 0.0 {
+            let before = metadata.len();
+            metadata = self.filter_by_relative_score(metadata);
+            if before != metadata.len() {
+                log::info!(
+                    "[sweepga] Relative score filter ({}): {before} → {} mappings",
+                    self.min_relative_score,
+                    metadata.len()
+                );
+            }
+        }
+
+        // Keep all original mappings for rescue phase (before any plane sweep)
+        let all_original_mappings = metadata.clone();
+
+        // 2. Apply plane sweep as the default filtering method
+        // IMPORTANT: Plane sweep must be applied PER QUERY SEQUENCE, not per query-target pair!
+        // This is how wfmash does it - all mappings from a query compete regardless of target
+
+        // The plane sweep is already correctly implemented in apply_plane_sweep_to_mappings
+        // which groups by query sequence internally. We just need to pass ALL mappings to it.
+        let mut stats = FilterStats {
+            plane_sweep_before: metadata.len(),
+            ..FilterStats::default()
+        };
+        metadata = self.scaffolding_input(metadata, names)?;
+        stats.plane_sweep_after = metadata.len();
+
+        // Mappings the plane sweep dropped, for --mark-secondary
+        let mut swept_out: HashSet<usize> = HashSet::new();
+        if self.mark_secondary {
+            swept_out.extend(all_original_mappings.iter().map(|m| m.rank));
+            for m in &metadata {
+                swept_out.remove(&m.rank);
+            }
+        }
+
+        // If no scaffolding (scaffold_gap == 0), we're done - return the plane-swept mappings
+        if self.config.scaffold_gap == 0 {
+            if self.no_plane_sweep {
+                log::info!("[sweepga] No plane sweep or scaffolding: mappings kept unfiltered");
+            } else {
+                log::info!("[sweepga] Plane sweep filtering (no scaffolding)");
+            }
+            if self.inversions_report.is_some() {
+                log::warn!(
+                    "[sweepga] --inversions needs scaffolding (--scaffold-jump > 0); \
+                     no report written"
+                );
+            }
+            if self.breakpoints_report.is_some() {
+                log::warn!(
+                    "[sweepga] --breakpoints needs scaffolding (--scaffold-jump > 0); \
+                     no report written"
+                );
+            }
+            if self.bridges_report.is_some() {
+                log::warn!(
+                    "[sweepga] --report-bridges needs scaffolding (--scaffold-jump > 0); \
+                     no report written"
+                );
+            }
+            if self.scaffold_bed.is_some() {
+                log::warn!(
+                    "[sweepga] --scaffold-bed needs scaffolding (--scaffold-jump > 0); \
+                     no file written"
+                );
+            }
+            if self.chain_report.is_some() {
+                log::warn!(
+                    "[sweepga] --chain-report needs scaffolding (--scaffold-jump > 0); \
+                     no report written"
+                );
+            }
+            stats.record_output(metadata.iter());
+
+            let mut result = HashMap::new();
+            for m in metadata {
+                result.insert(m.rank, m);
+            }
+            let swept_out = Self::select_ranks(&all_original_mappings, &swept_out);
+            return Ok((result, stats, swept_out));
+        }
+
+        // Scaffold members are taken from the unswept mappings below; keep
+        // the sweep ranks of those that survived the plane sweep
+        let sweep_ranks: HashMap<usize, usize> = metadata
+            .iter()
+            .filter_map(|m| Some((m.rank, m.sweep_rank?)))
+            .collect();
+
+        // 3. Apply scaffold filtering (wfmash's filterByScaffolds)
+        // According to CLAUDE.md: create scaffolds from the PLANE-SWEPT mappings,
+        // then rescue from ALL ORIGINAL mappings
+
+        // Use scaffold_gap for merging into scaffolds
+        let merged_chains = self.merge_mappings_into_chains(&metadata, self.config.scaffold_gap)?;
+        stats.chains_merged = merged_chains.len();
+        // log::info!(
+        //     "[sweepga]   Merged into {} chains (gap ≤ {})",
+        //     merged_chains.len(),
+        //     self.config.scaffold_gap
+        // );
+
+        // Step 2: Filter chains by minimum scaffold length, identity and
+        // member count
+        let mut filtered_chains: Vec<MergedChain> = merged_chains
+            .into_iter()
+            .filter(|chain| {
+                chain.total_length >= self.config.min_scaffold_length
+                    && chain.weighted_identity >= self.config.min_scaffold_identity
+                    && chain.member_indices.len() >= self.config.min_scaffold_count
+            })
+            .collect();
+        stats.chains_after_length_filter = filtered_chains.len();
+        // log::info!(
+        //     "[sweepga]   Length/identity filter: {} chains kept, {} removed",
+        //     filtered_chains.len(),
+        //     filtered_out
+        // );
+        if self.config.min_scaffold_length > 0 || self.config.min_scaffold_identity > 0.0 {
+            // log::info!(
+            //     "[sweepga]   Min length: {}, min identity: {:.1}%",
+            //     self.config.min_scaffold_length,
+            //     self.config.min_scaffold_identity * 100.0
+            // );
+        }
+
+        // Step 3: Apply plane sweep to scaffolds
+        // Track which mappings are in scaffolds BEFORE plane sweep
+        let mut pre_sweep_scaffold_members: HashSet<usize> = HashSet::new();
+        for chain in &filtered_chains {
+            for &member_rank in &chain.member_indices {
+                pre_sweep_scaffold_members.insert(member_rank);
+            }
+        }
+
+        filtered_chains = self.apply_scaffold_plane_sweep(filtered_chains, names)?;
+        stats.scaffolds = filtered_chains.len();
+
+        // ... and the members of the chains the scaffold sweep dropped
+        if self.mark_secondary {
+            let kept_members: HashSet<usize> = filtered_chains
+                .iter()
+                .flat_map(|chain| chain.member_indices.iter().copied())
+                .collect();
+            swept_out.extend(pre_sweep_scaffold_members.difference(&kept_members));
+        }
+
+        // Chain IDs are only assigned to the chains kept here
+        let overlap_ids: Vec<String> = filtered_chains
+            .iter()
+            .enumerate()
+            .filter(|(_, chain)| chain.member_overlap)
+            .map(|(chain_idx, _)| format!("chain_{}", chain_idx + 1))
+            .collect();
+        if !overlap_ids.is_empty() {
+            let action = if self.split_overlapping_members {
+                "split at"
+            } else {
+                "with"
+            };
+            log::info!(
+                "[sweepga] Scaffold chains {action} overlapping members: {}",
+                overlap_ids.join(", ")
+            );
+        }
+
+        if let Some(ref path) = self.inversions_report {
+            let reported = self.write_inversions_report(&filtered_chains, names, path)?;
+            log::info!(
+                "[sweepga] Reported {reported} candidate inversions to {}",
+                path.display()
+            );
+        }
+        if let Some(ref path) = self.breakpoints_report {
+            let reported = self.write_breakpoints_report(&filtered_chains, names, path)?;
+            log::info!(
+                "[sweepga] Reported {reported} rearrangement breakpoints to {}",
+                path.display()
+            );
+        }
+        if let Some(ref path) = self.scaffold_bed {
+            Self::write_scaffold_bed(&filtered_chains, names, path)?;
+            log::info!(
+                "[sweepga] Wrote {} scaffold chains to {}",
+                filtered_chains.len(),
+                path.display()
+            );
+        }
+        // log::info!(
+        //     "[sweepga]   Scaffold sweep: {} → {} scaffolds",
+        //     before_sweep,
+        //     filtered_chains.len()
+        // );
+
+        // If scaffolds_only mode, return the actual mappings that form scaffolds
+        if self.scaffolds_only {
+            let mut scaffold_mappings = HashMap::new();
+
+            // Build a map from rank to original metadata for quick lookup
+            // Use all_original_mappings since member_indices refers to original ranks
+            let mut rank_to_meta: HashMap<usize, &RecordMeta> = HashMap::new();
+            for meta in &all_original_mappings {
+                rank_to_meta.insert(meta.rank, meta);
+            }
+
+            // Collect all member mappings from scaffold chains
+            for (chain_idx, chain) in filtered_chains.iter().enumerate() {
+                // Create a unique chain ID
+                let chain_id = format!("chain_{}", chain_idx + 1);
+                let inversions = chain.inversions_tag();
+
+                for &member_rank in &chain.member_indices {
+                    // member_indices contains ranks of original mappings
+                    if let Some(meta) = rank_to_meta.get(&member_rank) {
+                        let mut scaffold_meta = (*meta).clone();
+                        scaffold_meta.chain_status = ChainStatus::Scaffold;
+                        scaffold_meta.chain_id = Some(chain_id.clone());
+                        scaffold_meta.sweep_rank = sweep_ranks.get(&member_rank).copied();
+                        scaffold_meta.inversions = inversions.clone();
+                        scaffold_mappings.insert(member_rank, scaffold_meta);
+                    }
+                }
+            }
+
+            stats.anchors = scaffold_mappings.len();
+            stats.record_output(scaffold_mappings.values());
+            if let Some(ref path) = self.chain_report {
+                Self::write_chain_report(
+                    &filtered_chains,
+                    scaffold_mappings.values(),
+                    names,
+                    path,
+                )?;
+                log::info!(
+                    "[sweepga] Wrote statistics of {} chains to {}",
+                    filtered_chains.len(),
+                    path.display()
+                );
+            }
+            let swept_out = Self::select_ranks(&all_original_mappings, &swept_out);
+            return Ok((scaffold_mappings, stats, swept_out));
+        }
+
+        // Step 4: Identify anchors - use the actual member mappings of scaffold chains
+        // NOTE: member_indices actually contains ranks, not array indices!
+        let mut anchor_ranks = HashSet::new();
+        let mut rank_to_chain_id: HashMap<usize, String> = HashMap::new();
+        // Inverted segments of each chain, by chain ID (--allow-inversions)
+        let mut chain_inversions: HashMap<String, String> = HashMap::new();
+
+        for (chain_idx, chain) in filtered_chains.iter().enumerate() {
+            let chain_id = format!("chain_{}", chain_idx + 1);
+            if let Some(inversions) = chain.inversions_tag() {
+                chain_inversions.insert(chain_id.clone(), inversions);
+            }
+
+            // The member_indices field contains the ranks of mappings in this chain
+            for &member_rank in &chain.member_indices {
+                anchor_ranks.insert(member_rank);
+                rank_to_chain_id.insert(member_rank, chain_id.clone());
+            }
+        }
+
+        // Step 4b: Include inversions that are on the scaffold's diagonal.
+        // For each forward scaffold, check if any reverse alignments would be "on the diagonal"
+        // if they were flipped. This is equivalent to checking perpendicular distance to the
+        // diagonal line: for a forward scaffold with t = q + offset, the perpendicular distance
+        // from point (q, t) is |t - q - offset| / sqrt(2).
+        let max_diagonal_distance = self.config.scaffold_gap;
+
+        // Pre-group reverse-strand mappings by (query_id, target_id) to avoid
+        // scanning all mappings for every chain (was O(chains * all_mappings)).
+        let mut reverse_by_chr_pair: HashMap<(u32, u32), Vec<usize>> = HashMap::new();
+        for (idx, mapping) in all_original_mappings.iter().enumerate() {
+            if mapping.strand == '-' {
+                reverse_by_chr_pair
+                    .entry((mapping.query_id, mapping.target_id))
+                    .or_default()
+                    .push(idx);
+            }
+        }
+
+        for (chain_idx, chain) in filtered_chains.iter().enumerate() {
+            // Only process forward scaffolds - inversions belong to forward diagonals
+            if chain.strand != '+' {
+                continue;
+            }
+
+            let chain_id = format!("chain_{}", chain_idx + 1);
+            let diagonal_offset = chain.target_start as i64 - chain.query_start as i64;
+
+            // Only check reverse mappings on the same chromosome pair
+            let candidates = reverse_by_chr_pair.get(&(chain.query_id, chain.target_id));
+            let empty_vec = Vec::new();
+            let candidates = candidates.unwrap_or(&empty_vec);
+
+            for &idx in candidates {
+                let mapping = &all_original_mappings[idx];
+
+                // Skip if already an anchor
+                if anchor_ranks.contains(&mapping.rank) {
+                    continue;
+                }
+
+                // Check if near the scaffold's query range (within scaffold_gap distance)
+                // This allows inversions just before/after the scaffold to be captured
+                let extended_query_start = chain.query_start.saturating_sub(max_diagonal_distance);
+                let extended_query_end = chain.query_end.saturating_add(max_diagonal_distance);
+                if mapping.query_end < extended_query_start
+                    || mapping.query_start > extended_query_end
+                {
+                    continue;
+                }
+
+                // Calculate perpendicular distance to the scaffold's diagonal
+                // Diagonal line: t = q + offset
+                // Perpendicular distance = |t - q - offset| / sqrt(2)
+                let mapping_q_center = (mapping.query_start + mapping.query_end) / 2;
+                let mapping_t_center = (mapping.target_start + mapping.target_end) / 2;
+                let deviation =
+                    (mapping_t_center as i64 - mapping_q_center as i64 - diagonal_offset)
+                        .unsigned_abs();
+                let perpendicular_distance = (deviation as f64 / std::f64::consts::SQRT_2) as u64;
+
+                if perpendicular_distance <= max_diagonal_distance {
+                    anchor_ranks.insert(mapping.rank);
+                    rank_to_chain_id.insert(mapping.rank, chain_id.clone());
+                }
+            }
+        }
+
+        // Identify mappings that were in filtered-out scaffolds
+        // These should NOT be rescued even if they're near surviving anchors
+        let filtered_scaffold_members: HashSet<usize> = pre_sweep_scaffold_members
+            .difference(&anchor_ranks)
+            .copied()
+            .collect();
+
+        // log::info!("[sweepga] Rescue phase");
+        // log::info!(
+        //     "[sweepga]   Anchors: {} mappings in {} scaffolds",
+        //     anchor_ranks.len(),
+        //     filtered_chains.len()
+        // );
+
+        // Map ranks to indices in all_original_mappings
+        let mut rank_to_idx = HashMap::new();
+        for (idx, meta) in all_original_mappings.iter().enumerate() {
+            rank_to_idx.insert(meta.rank, idx);
+        }
+
+        // Step 5: Rescue mappings within scaffold_max_deviation of anchors
+        let _profile = crate::profile::scope("rescue");
+        // OPTIMIZED: Group by chromosome pair and sort for efficient rescue
+
+        // First, group all mappings by (query_chr, target_chr) pair.
+        // IndexMap: iteration order = first-encounter (PAF input order),
+        // making the kept_mappings output order deterministic across runs.
+        let mut mappings_by_chr_pair: IndexMap<(u32, u32), Vec<usize>> = IndexMap::new();
+        for (idx, mapping) in all_original_mappings.iter().enumerate() {
+            let key = (mapping.query_id, mapping.target_id);
+            mappings_by_chr_pair.entry(key).or_default().push(idx);
+        }
+
+        // Sort each chromosome pair's mappings by query position for binary search
+        for indices in mappings_by_chr_pair.values_mut() {
+            indices.sort_by_key(|&idx| all_original_mappings[idx].query_start);
+        }
+
+        // Collect anchors by chromosome pair for efficient lookup.
+        let mut anchors_by_chr_pair: HashMap<(u32, u32), Vec<usize>> = HashMap::new();
+        for &anchor_rank in &anchor_ranks {
+            if let Some(&anchor_idx) = rank_to_idx.get(&anchor_rank) {
+                let anchor = &all_original_mappings[anchor_idx];
+                let key = (anchor.query_id, anchor.target_id);
+                anchors_by_chr_pair.entry(key).or_default().push(anchor_idx);
+            }
+        }
+
+        let mut kept_mappings = Vec::new();
+        let mut kept_status = HashMap::new();
+        let max_deviation = self.config.scaffold_max_deviation;
+        let mut bridges: Vec<(&RecordMeta, Vec<&str>)> = Vec::new();
+        // (chain ID, distance to its anchor, rank) of every rescued mapping
+        let mut rescued: Vec<(Option<String>, u64, usize)> = Vec::new();
+        let rank_by_distance = self.max_rescued_per_scaffold.is_some();
+
+        // Process each chromosome pair independently (can be parallelized)
+        for (chr_key, mapping_indices) in &mappings_by_chr_pair {
+            let chr_anchors = anchors_by_chr_pair
+                .get(chr_key)
+                .map(|v| v.as_slice())
+                .unwrap_or(&[]);
+
+            if chr_anchors.is_empty() {
+                continue; // No anchors on this chromosome pair, skip
+            }
+
+            // For each mapping on this chromosome pair
+            for &mapping_idx in mapping_indices {
+                let mapping = &all_original_mappings[mapping_idx];
+
+                if anchor_ranks.contains(&mapping.rank) {
+                    // This is an anchor (member of a scaffold chain) - keep it with its chain ID
+                    let mut anchor_mapping = mapping.clone();
+                    if let Some(chain_id) = rank_to_chain_id.get(&mapping.rank) {
+                        anchor_mapping.chain_id = Some(chain_id.clone());
+                    }
+                    kept_mappings.push(anchor_mapping);
+                    // All anchors are scaffold members by definition
+                    kept_status.insert(mapping.rank, ChainStatus::Scaffold);
+                } else if self.no_rescue {
+                    // --no-rescue: every mapping that is not an anchor is dropped
+                    continue;
+                } else if filtered_scaffold_members.contains(&mapping.rank) {
+                    // This mapping was part of a plane-sweep-filtered scaffold
+                    // Do NOT rescue it, even if it's near an anchor
+                    continue;
+                } else if max_deviation > 0 {
+                    // Only attempt rescue if max_deviation > 0
+                    // Check if within deviation distance of any anchor
+                    // Use binary search to find anchors within query range
+                    let mapping_q_center = (mapping.query_start + mapping.query_end) / 2;
+                    let mapping_t_center = (mapping.target_start + mapping.target_end) / 2;
+
+                    let mut min_distance = u64::MAX;
+                    let mut closest_anchor_rank = None;
+
+                    // Only check anchors that are within max_deviation in query space
+                    // (no metric is below the query distance)
+                    for &anchor_idx in chr_anchors {
+                        let anchor = &all_original_mappings[anchor_idx];
+                        let anchor_q_center = (anchor.query_start + anchor.query_end) / 2;
+
+                        // Early exit if anchor is too far in query space
+                        let q_diff =
+                            (mapping_q_center as i64 - anchor_q_center as i64).unsigned_abs();
+                        if q_diff > max_deviation {
+                            continue; // Too far in query dimension alone
+                        }
+
+                        let anchor_t_center = (anchor.target_start + anchor.target_end) / 2;
+                        let t_diff =
+                            (mapping_t_center as i64 - anchor_t_center as i64).unsigned_abs();
+
+                        let distance = self.rescue_metric.distance(q_diff, t_diff);
+                        if distance < min_distance {
+                            min_distance = distance;
+                            closest_anchor_rank = Some(anchor.rank);
+                        }
+
+                        // Early exit if we found a close enough anchor (unless rescue is
+                        // capped, where the true closest distance ranks the candidates)
+                        if min_distance <= max_deviation && !rank_by_distance {
+                            break;
+                        }
+                    }
+
+                    if min_distance <= max_deviation {
+                        // This mapping is rescued - assign it to the same chain as its closest anchor
+                        let mut rescued_mapping = mapping.clone();
+                        if let Some(anchor_rank) = closest_anchor_rank {
+                            if let Some(chain_id) = rank_to_chain_id.get(&anchor_rank) {
+                                rescued_mapping.chain_id = Some(chain_id.clone());
+                            }
+                        }
+                        let chain_id = rescued_mapping.chain_id.clone();
+                        rescued.push((chain_id, min_distance, mapping.rank));
+                        kept_mappings.push(rescued_mapping);
+                        kept_status.insert(mapping.rank, ChainStatus::Rescued);
+
+                        // A rescued mapping near anchors of several chains is
+                        // evidence that those chains belong to one scaffold
+                        if self.bridges_report.is_some() {
+                            let chain_ids = Self::nearby_chain_ids(
+                                mapping,
+                                chr_anchors,
+                                &all_original_mappings,
+                                &rank_to_chain_id,
+                                max_deviation,
+                                self.rescue_metric,
+                            );
+                            if chain_ids.len() >= 2 {
+                                bridges.push((mapping, chain_ids));
+                            }
+                        }
+                    }
+                }
+                // If max_deviation == 0 and not an anchor, mapping is not kept
+            }
+        }
+
+        if let Some(max_rescued) = self.max_rescued_per_scaffold {
+            let dropped = Self::excess_rescued(rescued, max_rescued);
+            if !dropped.is_empty() {
+                kept_mappings.retain(|m| !dropped.contains(&m.rank));
+                kept_status.retain(|rank, _| !dropped.contains(rank));
+                bridges.retain(|(m, _)| !dropped.contains(&m.rank));
+                log::info!(
+                    "[sweepga] Dropped {} rescued mappings beyond {} per scaffold",
+                    dropped.len(),
+                    max_rescued
+                );
+            }
+        }
+
+        if let Some(ref path) = self.bridges_report {
+            if max_deviation == 0 || self.no_rescue {
+                log::warn!(
+                    "[sweepga] --report-bridges needs rescue (--scaffold-dist > 0, no \
+                     --no-rescue); writing an empty report"
+                );
+            }
+            bridges.sort_by_key(|(mapping, _)| mapping.rank);
+            Self::write_bridges_report(&bridges, names, path)?;
+            log::info!(
+                "[sweepga] Reported {} bridge candidates to {}",
+                bridges.len(),
+                path.display()
+            );
+        }
+
+        // Build result map directly from kept mappings
+        let mut passing = HashMap::new();
+        for meta in kept_mappings {
+            let mut result = meta.clone();
+            // Set the chain status based on what we determined earlier
+            result.chain_status = kept_status
+                .get(&meta.rank)
+                .cloned()
+                .unwrap_or(ChainStatus::Scaffold);
+            result.sweep_rank = sweep_ranks.get(&meta.rank).copied();
+            result.inversions = result
+                .chain_id
+                .as_ref()
+                .and_then(|chain_id| chain_inversions.get(chain_id))
+                .cloned();
+            passing.insert(meta.rank, result);
+        }
+
+        for meta in passing.values() {
+            match meta.chain_status {
+                ChainStatus::Rescued => stats.rescued += 1,
+                _ => stats.anchors += 1,
+            }
+        }
+        stats.record_output(passing.values());
+        if let Some(ref path) = self.chain_report {
+            Self::write_chain_report(&filtered_chains, passing.values(), names, path)?;
+            log::info!(
+                "[sweepga] Wrote statistics of {} chains to {}",
+                filtered_chains.len(),
+                path.display()
+            );
+        }
+        let swept_out = Self::select_ranks(&all_original_mappings, &swept_out);
+        Ok((passing, stats, swept_out))
+    }
+
+    /// The mappings whose rank is in `ranks`, in input order
+    fn select_ranks(mappings: &[RecordMeta], ranks: &HashSet<usize>) -> Vec<RecordMeta> {
+        mappings
+            .iter()
+            .filter(|m| ranks.contains(&m.rank))
+            .cloned()
+            .collect()
+    }
+
+    /// Add the swept-out `candidates` missing from `passing` (not rescued),
+    /// flagged as secondary. Returns the number added.
+    fn add_secondaries(
+        passing: &mut HashMap<usize, RecordMeta>,
+        candidates: Vec<RecordMeta>,
+    ) -> usize {
+        let mut added = 0;
+        for meta in candidates {
+            if !passing.contains_key(&meta.rank) {
+                passing.insert(
+                    meta.rank,
+                    RecordMeta {
+                        secondary: true,
+                        ..meta
+                    },
+                );
+                added += 1;
+            }
+        }
+        added
+    }
+
+    /// Minimum block length, self-mapping, genome pair, pair direction,
+    /// minimum identity and minimum query/target coverage checks
+    fn passes_record_filters(&self, m: &RecordMeta, names: &SequenceIndex) -> bool {
+        m.block_length >= self.config.min_block_length
+            && self.strand.accepts(m.strand)
+            && (self.keep_self || !self.is_self_mapping(m, names))
+            && (!self.canonical_pairs
+                || self.genome_of(names.name(m.query_id))
+                    <= self.genome_of(names.name(m.target_id)))
+            && (self.pair_selection.is_all()
+                || self.pair_selection.accepts(
+                    self.genome_of(names.name(m.query_id)),
+                    self.genome_of(names.name(m.target_id)),
+                    self.config.prefix_delimiter,
+                ))
+            && m.identity >= self.config.min_identity
+            && covers(
+                names,
+                m.query_id,
+                m.query_end - m.query_start,
+                self.min_query_coverage,
+            )
+            && covers(
+                names,
+                m.target_id,
+                m.target_end - m.target_start,
+                self.min_target_coverage,
+            )
+    }
+
+    /// The mappings scaffolds are built from: the plane-swept mappings, or all
+    /// of them with `--no-plane-sweep`
+    fn scaffolding_input(
+        &self,
+        metadata: Vec<RecordMeta>,
+        names: &SequenceIndex,
+    ) -> Result<Vec<RecordMeta>> {
+        let before_plane_sweep = metadata.len();
+        if self.no_plane_sweep {
+            log::info!(
+                "[sweepga] Plane sweep: skipped (--no-plane-sweep), \
+                 {before_plane_sweep} mappings enter scaffolding"
+            );
+            return Ok(metadata);
+        }
+
+        let swept = self.apply_plane_sweep_to_mappings(&metadata, names)?;
+        // Report plane sweep if it filtered anything
+        if before_plane_sweep != swept.len() {
+            log::info!(
+                "[sweepga] Plane sweep: {before_plane_sweep} → {} mappings",
+                swept.len()
+            );
+        }
+        Ok(swept)
+    }
+
+    /// Ranks of rescued mappings beyond the `max` closest (ties by input order)
+    /// for each chain
+    fn excess_rescued(rescued: Vec<(Option<String>, u64, usize)>, max: usize) -> HashSet<usize> {
+        let mut by_chain: HashMap<Option<String>, Vec<(u64, usize)>> = HashMap::new();
+        for (chain_id, distance, rank) in rescued {
+            by_chain.entry(chain_id).or_default().push((distance, rank));
+        }
+
+        let mut dropped = HashSet::new();
+        for mut candidates in by_chain.into_values() {
+            if candidates.len() > max {
+                candidates.sort_unstable();
+                dropped.extend(candidates[max..].iter().map(|&(_, rank)| rank));
+            }
+        }
+        dropped
+    }
+
+    /// Chain IDs (in first-seen order) with an anchor within `max_deviation`
+    /// of `mapping`, using the same center-to-center distance as rescue
+    fn nearby_chain_ids<'a>(
+        mapping: &RecordMeta,
+        anchors: &[usize],
+        all_mappings: &[RecordMeta],
+        rank_to_chain_id: &'a HashMap<usize, String>,
+        max_deviation: u64,
+        metric: RescueMetric,
+    ) -> Vec<&'a str> {
+        let q_center = (mapping.query_start + mapping.query_end) / 2;
+        let t_center = (mapping.target_start + mapping.target_end) / 2;
+
+        let mut chain_ids: Vec<&str> = Vec::new();
+        for &anchor_idx in anchors {
+            let anchor = &all_mappings[anchor_idx];
+            let q_diff = q_center.abs_diff((anchor.query_start + anchor.query_end) / 2);
+            let t_diff = t_center.abs_diff((anchor.target_start + anchor.target_end) / 2);
+            if metric.distance(q_diff, t_diff) > max_deviation {
+                continue;
+            }
+            if let Some(chain_id) = rank_to_chain_id.get(&anchor.rank) {
+                if !chain_ids.contains(&chain_id.as_str()) {
+                    chain_ids.push(chain_id);
+                }
+            }
+        }
+        chain_ids
+    }
+
+    /// Write the `--scaffold-bed` file: per kept chain, its query span, the
+    /// chain ID its members carry in `ch:Z:`, span length and strand
+    fn write_scaffold_bed(
+        chains: &[MergedChain],
+        names: &SequenceIndex,
+        path: &Path,
+    ) -> Result<()> {
+        let file =
+            File::create(path).with_context(|| format!("Failed to create {}", path.display()))?;
+        let mut writer = BufWriter::new(file);
+        for (chain_idx, chain) in chains.iter().enumerate() {
+            writeln!(
+                writer,
+                "{}\t{}\t{}\tchain_{}\t{}\t{}",
+                names.name(chain.query_id),
+                chain.query_start,
+                chain.query_end,
+                chain_idx + 1,
+                chain.total_length,
+                chain.strand
+            )?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Write the `--chain-report` file: per kept chain, its ID (as in `ch:Z:`),
+    /// spans, strand, the numbers of anchor and rescued mappings among the
+    /// kept `mappings`, its weighted identity and summed matches
+    fn write_chain_report<'a>(
+        chains: &[MergedChain],
+        mappings: impl Iterator<Item = &'a RecordMeta>,
+        names: &SequenceIndex,
+        path: &Path,
+    ) -> Result<()> {
+        // (anchors, rescued) per chain ID
+        let mut counts: HashMap<&str, (usize, usize)> = HashMap::new();
+        for meta in mappings {
+            if let Some(chain_id) = meta.chain_id.as_deref() {
+                let entry = counts.entry(chain_id).or_default();
+                match meta.chain_status {
+                    ChainStatus::Rescued => entry.1 += 1,
+                    _ => entry.0 += 1,
+                }
+            }
+        }
+
+        let file =
+            File::create(path).with_context(|| format!("Failed to create {}", path.display()))?;
+        let mut writer = BufWriter::new(file);
+        writeln!(
+            writer,
+            "#chain\tquery\tquery_start\tquery_end\ttarget\ttarget_start\ttarget_end\tstrand\t\
+             anchors\trescued\tidentity\tmatches"
+        )?;
+        for (chain_idx, chain) in chains.iter().enumerate() {
+            let chain_id = format!("chain_{}", chain_idx + 1);
+            let (anchors, rescued) = counts.get(chain_id.as_str()).copied().unwrap_or_default();
+            writeln!(
+                writer,
+                "{chain_id}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{anchors}\t{rescued}\t{:.4}\t{}",
+                names.name(chain.query_id),
+                chain.query_start,
+                chain.query_end,
+                names.name(chain.target_id),
+                chain.target_start,
+                chain.target_end,
+                chain.strand,
+                chain.weighted_identity,
+                chain.sum_matches,
+            )?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Write bridge candidates: rescued mappings near anchors of two or more
+    /// chains, listed with the chain IDs they connect (as in the `ch:Z:` tags)
+    fn write_bridges_report(
+        bridges: &[(&RecordMeta, Vec<&str>)],
+        names: &SequenceIndex,
+        path: &Path,
+    ) -> Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        writeln!(
+            writer,
+            "#query\tquery_start\tquery_end\tstrand\ttarget\ttarget_start\ttarget_end\tchains"
+        )?;
+        for (mapping, chain_ids) in bridges {
+            writeln!(
+                writer,
+                "{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
+                names.name(mapping.query_id),
+                mapping.query_start,
+                mapping.query_end,
+                mapping.strand,
+                names.name(mapping.target_id),
+                mapping.target_start,
+                mapping.target_end,
+                chain_ids.join(","),
+            )?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Report reverse-strand scaffold chains as candidate inversions, grouped by
+    /// genome pair, with the nearest forward chains on either side (by query
+    /// position, same sequence pair) as flanking context. Returns the count.
+    fn write_inversions_report(
+        &self,
+        chains: &[MergedChain],
+        names: &SequenceIndex,
+        path: &Path,
+    ) -> Result<usize> {
+        let mut by_genome_pair: IndexMap<(String, String), Vec<&MergedChain>> = IndexMap::new();
+        for chain in chains {
+            let key = (
+                self.extract_prefix(names.name(chain.query_id)),
+                self.extract_prefix(names.name(chain.target_id)),
+            );
+            by_genome_pair.entry(key).or_default().push(chain);
+        }
+        by_genome_pair.sort_keys();
+
+        let fmt_flank = |chain: Option<&&MergedChain>| match chain {
+            Some(c) => format!(
+                "{}-{}:{}-{}",
+                c.query_start, c.query_end, c.target_start, c.target_end
+            ),
+            None => ".".to_string(),
+        };
+
+        let mut writer = BufWriter::new(File::create(path)?);
+        writeln!(
+            writer,
+            "#query_genome\ttarget_genome\tquery\tquery_start\tquery_end\ttarget\t\
+             target_start\ttarget_end\tlength\tidentity\tleft_flank\tright_flank"
+        )?;
+
+        let mut reported = 0;
+        for ((query_genome, target_genome), mut pair_chains) in by_genome_pair {
+            pair_chains.sort_by_key(|c| {
+                (
+                    names.name(c.query_id),
+                    names.name(c.target_id),
+                    c.query_start,
+                )
+            });
+
+            for inv in pair_chains.iter().filter(|c| c.strand == '-') {
+                let forward = pair_chains.iter().filter(|c| {
+                    c.strand == '+' && c.query_id == inv.query_id && c.target_id == inv.target_id
+                });
+                let left = forward
+                    .clone()
+                    .filter(|c| c.query_end <= inv.query_start)
+                    .max_by_key(|c| c.query_end);
+                let right = forward
+                    .filter(|c| c.query_start >= inv.query_end)
+                    .min_by_key(|c| c.query_start);
+
+                writeln!(
+                    writer,
+                    "{query_genome}\t{target_genome}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{:.4}\t{}\t{}",
+                    names.name(inv.query_id),
+                    inv.query_start,
+                    inv.query_end,
+                    names.name(inv.target_id),
+                    inv.target_start,
+                    inv.target_end,
+                    inv.total_length,
+                    inv.weighted_identity,
+                    fmt_flank(left),
+                    fmt_flank(right),
+                )?;
+                reported += 1;
+            }
+        }
+
+        writer.flush()?;
+        Ok(reported)
+    }
+
+    /// Report rearrangement breakpoints: per genome pair, consecutive scaffold
+    /// chains along each query sequence that continue on another diagonal,
+    /// target sequence or strand. Each row gives where the left chain leaves
+    /// the query and target and where the right chain enters them; the
+    /// diagonal shift is `.` when the chains are not on the same target and
+    /// strand. Returns the count.
+    fn write_breakpoints_report(
+        &self,
+        chains: &[MergedChain],
+        names: &SequenceIndex,
+        path: &Path,
+    ) -> Result<usize> {
+        let mut by_query: IndexMap<(String, String, &str), Vec<&MergedChain>> = IndexMap::new();
+        for chain in chains {
+            let key = (
+                self.extract_prefix(names.name(chain.query_id)),
+                self.extract_prefix(names.name(chain.target_id)),
+                names.name(chain.query_id),
+            );
+            by_query.entry(key).or_default().push(chain);
+        }
+        by_query.sort_keys();
+
+        let mut writer = BufWriter::new(File::create(path)?);
+        writeln!(
+            writer,
+            "#query_genome\ttarget_genome\tquery\tleft_query_end\tright_query_start\t\
+             left_target\tleft_target_end\tleft_strand\tright_target\tright_target_start\t\
+             right_strand\tdiagonal_shift"
+        )?;
+
+        let mut reported = 0;
+        for ((query_genome, target_genome, query), mut query_chains) in by_query {
+            query_chains.sort_by_key(|c| (c.query_start, c.query_end));
+            for pair in query_chains.windows(2) {
+                let (left, right) = (pair[0], pair[1]);
+                let colinear = left.target_id == right.target_id && left.strand == right.strand;
+                if colinear && left.diagonal() == right.diagonal() {
+                    continue;
+                }
+                let shift = if colinear {
+                    (right.diagonal() - left.diagonal()).to_string()
+                } else {
+                    ".".to_string()
+                };
+                writeln!(
+                    writer,
+                    "{query_genome}\t{target_genome}\t{query}\t{}\t{}\t{}\t{}\t{}\t\
+                     {}\t{}\t{}\t{shift}",
+                    left.query_end,
+                    right.query_start,
+                    names.name(left.target_id),
+                    left.target_exit(),
+                    left.strand,
+                    names.name(right.target_id),
+                    right.target_entry(),
+                    right.strand,
+                )?;
+                reported += 1;
+            }
+        }
+
+        writer.flush()?;
+        Ok(reported)
+    }
+
+    /// Merge mappings into chains using wfmash's union-find approach
+    fn merge_mappings_into_chains(
+        &self,
+        metadata: &[RecordMeta],
+        max_gap: u64,
+    ) -> Result<Vec<MergedChain>> {
+        use crate::union_find::UnionFind;
+        let _profile = crate::profile::scope("merge");
+
+        // Group by (query, target, strand) - this is like wfmash's refSeqId grouping
+        // Store the original rank, not the position in metadata array.
+        // IndexMap: iteration order = PAF input order, so all_chains is built
+        // deterministically (otherwise scaffold idx assignment drifts run-to-run).
+        let mut groups: IndexMap<(u32, u32, char), Vec<(usize, usize)>> = IndexMap::new();
+
+        for (idx, meta) in metadata.iter().enumerate() {
+            let key = (meta.query_id, meta.target_id, meta.strand);
+            groups.entry(key).or_default().push((meta.rank, idx));
+        }
+
+        let mut all_chains = Vec::new();
+        let mut overlapping_chains = 0usize;
+        let mut off_diagonal_members = 0usize;
+
+        for ((query, target, strand), indices) in groups {
+            // Sort by query start position (like wfmash's sort)
+            let mut sorted_indices = indices.clone();
+            sorted_indices.sort_by_key(|&(_rank, idx)| metadata[idx].query_start);
+
+            // Phase 1: Find all best-buddy relationships
+            let spans: Vec<ChainSpan> = sorted_indices
+                .iter()
+                .map(|&(_rank, idx)| ChainSpan::of(&metadata[idx]))
+                .collect();
+            let best_pred_idx = best_buddy_predecessors(&spans, strand, max_gap);
+
+            // Phase 2: Create chains using union-find on best-buddy pairs only
+            let mut uf = UnionFind::new(sorted_indices.len());
+            for (j, pred) in best_pred_idx.iter().enumerate() {
+                if let Some(i) = pred {
+                    uf.union(*i, j);
+                }
+            }
+
+            // Extract chains from union-find sets
+            let sets = uf.get_sets();
+            let chains: Vec<Vec<(usize, usize)>> = sets
+                .into_iter()
+                .map(|set_indices| set_indices.into_iter().map(|i| sorted_indices[i]).collect())
+                .collect();
+
+            // Create merged chains from groups of mappings
+            for mut chain_indices in chains {
+                if chain_indices.is_empty() {
+                    continue;
+                }
+
+                // Members overlapping on the query (collapsed tandem copies) would
+                // double-count bases in the chain's matches and identity
+                chain_indices.sort_by_key(|&(_rank, idx)| metadata[idx].query_start);
+
+                // Members linked in through spurious best-buddy pairs sit off the
+                // chain's diagonal; they are chained on their own instead
+                if self.max_diagonal_deviation > 0 {
+                    let dropped = Self::drop_off_diagonal_members(
+                        &mut chain_indices,
+                        strand,
+                        metadata,
+                        self.max_diagonal_deviation,
+                    );
+                    off_diagonal_members += dropped.len();
+                    for member in dropped {
+                        all_chains.push(self.build_merged_chain(
+                            query,
+                            target,
+                            strand,
+                            &[member],
+                            metadata,
+                        ));
+                    }
+                }
+
+                let tolerance = self.member_overlap_tolerance;
+                let member_overlap =
+                    Self::has_member_query_overlap(&chain_indices, metadata, tolerance);
+                if member_overlap {
+                    overlapping_chains += 1;
+                    if self.split_overlapping_members {
+                        let parts =
+                            Self::split_at_member_overlaps(chain_indices, metadata, tolerance);
+                        for part in parts {
+                            let mut chain =
+                                self.build_merged_chain(query, target, strand, &part, metadata);
+                            chain.member_overlap = true;
+                            all_chains.push(chain);
+                        }
+                        continue;
+                    }
+                }
+
+                let mut chain =
+                    self.build_merged_chain(query, target, strand, &chain_indices, metadata);
+                chain.member_overlap = member_overlap;
+                all_chains.push(chain);
+            }
+        }
+
+        if self.allow_inversions {
+            let before = all_chains.len();
+            all_chains = self.join_inversions(all_chains, metadata, max_gap);
+            log::info!(
+                "[sweepga] Joined {} inverted segments into their flanking chains",
+                (before - all_chains.len()) / 2
+            );
+        }
+
+        if off_diagonal_members > 0 {
+            log::info!(
+                "[sweepga] Removed {off_diagonal_members} chain members deviating more than \
+                 {}bp from their chain's median diagonal",
+                self.max_diagonal_deviation
+            );
+        }
+
+        if overlapping_chains > 0 {
+            if self.split_overlapping_members {
+                log::info!(
+                    "[sweepga] Split {overlapping_chains} chains with members overlapping \
+                     on the query by >{}bp",
+                    self.member_overlap_tolerance
+                );
+            } else {
+                log::info!(
+                    "[sweepga] {overlapping_chains} chains have members overlapping on the \
+                     query by >{}bp; see --split-overlapping-members",
+                    self.member_overlap_tolerance
+                );
+            }
+        }
+
+        Ok(all_chains)
+    }
+
+    /// Join each chain followed on the query by an opposite-strand chain and
+    /// then a chain continuing it (`flanks_inversion`) into one chain on the
+    /// flanks' strand, recording the inverted segment's query range
+    fn join_inversions(
+        &self,
+        chains: Vec<MergedChain>,
+        metadata: &[RecordMeta],
+        max_gap: u64,
+    ) -> Vec<MergedChain> {
+        let rank_to_idx: HashMap<usize, usize> = metadata
+            .iter()
+            .enumerate()
+            .map(|(idx, meta)| (meta.rank, idx))
+            .collect();
+        let mut by_pair: IndexMap<(u32, u32), Vec<MergedChain>> = IndexMap::new();
+        for chain in chains {
+            by_pair
+                .entry((chain.query_id, chain.target_id))
+                .or_default()
+                .push(chain);
+        }
+
+        let mut joined = Vec::new();
+        for ((query, target), mut pair_chains) in by_pair {
+            pair_chains.sort_by_key(|c| (c.query_start, c.query_end));
+            let mut pair_chains: VecDeque<MergedChain> = pair_chains.into();
+            while let Some(mut current) = pair_chains.pop_front() {
+                while pair_chains.len() >= 2
+                    && self.flanks_inversion(&current, &pair_chains[0], &pair_chains[1], max_gap)
+                {
+                    let inv = pair_chains.pop_front().unwrap();
+                    let right = pair_chains.pop_front().unwrap();
+                    let members: Vec<(usize, usize)> = [&current, &inv, &right]
+                        .iter()
+                        .flat_map(|c| c.member_indices.iter())
+                        .map(|&rank| (rank, rank_to_idx[&rank]))
+                        .collect();
+                    let mut inversions = std::mem::take(&mut current.inversions);
+                    inversions.push((inv.query_start, inv.query_end));
+                    inversions.extend(right.inversions);
+                    let member_overlap =
+                        current.member_overlap || inv.member_overlap || right.member_overlap;
+                    current =
+                        self.build_merged_chain(query, target, current.strand, &members, metadata);
+                    current.inversions = inversions;
+                    current.member_overlap = member_overlap;
+                }
+                joined.push(current);
+            }
+        }
+        joined
+    }
+
+    /// Whether `inv`, on the other strand, sits between `left` and `right`,
+    /// which continue each other on one strand: the three follow each other
+    /// on the query within `max_gap`, overlapping by at most the member
+    /// overlap tolerance, and `inv` lies between the flanks on the target,
+    /// which are at most its span plus `2 * max_gap` apart
+    fn flanks_inversion(
+        &self,
+        left: &MergedChain,
+        inv: &MergedChain,
+        right: &MergedChain,
+        max_gap: u64,
+    ) -> bool {
+        if left.strand != right.strand || inv.strand == left.strand {
+            return false;
+        }
+        let query_follows = |a: &MergedChain, b: &MergedChain| {
+            b.query_start + self.member_overlap_tolerance >= a.query_end
+                && b.query_start.saturating_sub(a.query_end) <= max_gap
+        };
+        if !query_follows(left, inv) || !query_follows(inv, right) {
+            return false;
+        }
+        // Flanks in target order
+        let (low, high) = if left.strand == '+' {
+            (left, right)
+        } else {
+            (right, left)
+        };
+        let inv_span = inv.target_end - inv.target_start;
+        low.target_end <= high.target_start + max_gap
+            && inv.target_start + max_gap >= low.target_end
+            && inv.target_end <= high.target_start + max_gap
+            && high.target_start.saturating_sub(low.target_end) <= inv_span + 2 * max_gap
+    }
+
+    /// Build a merged chain from `(rank, metadata index)` members
+    fn build_merged_chain(
+        &self,
+        query: u32,
+        target: u32,
+        strand: char,
+        chain_indices: &[(usize, usize)],
+        metadata: &[RecordMeta],
+    ) -> MergedChain {
+        // Calculate merged chain boundaries and weighted identity
+        let mut q_min = u64::MAX;
+        let mut q_max = 0;
+        let mut t_min = u64::MAX;
+        let mut t_max = 0;
+        let mut member_ranks = Vec::new();
+        let mut sum_matches = 0u64;
+        let mut sum_block_lengths = 0u64;
+        let mut gap_compressed_mass = 0.0;
+        let mut mapq = 0u8;
+
+        for &(rank, idx) in chain_indices {
+            let meta = &metadata[idx];
+            q_min = q_min.min(meta.query_start);
+            q_max = q_max.max(meta.query_end);
+            t_min = t_min.min(meta.target_start);
+            t_max = t_max.max(meta.target_end);
+            member_ranks.push(rank); // Store the original rank
+
+            // Sum up matches and block lengths for weighted identity
+            sum_matches += meta.matches;
+            sum_block_lengths += meta.block_length;
+            gap_compressed_mass += meta.gap_compressed_identity * meta.block_length as f64;
+            mapq = mapq.max(meta.mapq);
+        }
+
+        let total_length = q_max - q_min;
+
+        // Calculate identity with log-compressed gaps
+        // Gaps between alignments are penalized logarithmically
+        // This gives a middle ground: gaps hurt but not linearly
+        let gap_length = total_length.saturating_sub(sum_block_lengths);
+        let log_compressed_gap = if gap_length > 0 {
+            (gap_length as f64).ln().max(0.0)
+        } else {
+            0.0
+        };
+        let effective_length = sum_block_lengths as f64 + log_compressed_gap;
+
+        // With --chain-identity-lis, out-of-order members add no matches
+        let identity_matches = if self.chain_identity_lis {
+            Self::collinear_matches(chain_indices, strand, metadata)
+        } else {
+            sum_matches
+        };
+        let weighted_identity = if effective_length > 0.0 {
+            identity_matches as f64 / effective_length
+        } else {
+            0.0
+        };
+        let gap_compressed_identity = if sum_block_lengths > 0 {
+            gap_compressed_mass / sum_block_lengths as f64
+        } else {
+            0.0
+        };
+
+        MergedChain {
+            query_id: query,
+            target_id: target,
+            query_start: q_min,
+            query_end: q_max,
+            target_start: t_min,
+            target_end: t_max,
+            strand,
+            total_length,
+            weighted_identity,
+            gap_compressed_identity,
+            sum_matches,
+            sum_block_lengths,
+            mapq,
+            member_indices: member_ranks, // Now storing ranks, not indices
+            inversions: Vec::new(),
+            member_overlap: false,
+        }
+    }
+
+    /// Matches of the heaviest collinear subset of members: a longest increasing
+    /// subsequence (weighted by matches) of member target positions in query
+    /// order, descending on '-'. Members out of order with the rest of the
+    /// chain (rearrangements, misplaced repeats) contribute nothing. A Fenwick
+    /// tree of prefix maxima over target positions keeps this O(n log n).
+    fn collinear_matches(
+        chain_indices: &[(usize, usize)],
+        strand: char,
+        metadata: &[RecordMeta],
+    ) -> u64 {
+        let mut members: Vec<&RecordMeta> = chain_indices
+            .iter()
+            .map(|&(_rank, idx)| &metadata[idx])
+            .collect();
+        members.sort_by_key(|m| (m.query_start, m.query_end));
+
+        // A member can follow any earlier member whose key is at most its own:
+        // target start on '+', reversed target end on '-'
+        let key = |m: &RecordMeta| {
+            if strand == '+' {
+                m.target_start
+            } else {
+                u64::MAX - m.target_end
+            }
+        };
+        let mut keys: Vec<u64> = members.iter().map(|m| key(m)).collect();
+        keys.sort_unstable();
+        keys.dedup();
+
+        // tree[k]: matches of the heaviest collinear subset ending at a member
+        // whose 1-based key rank falls in the Fenwick range of k
+        let mut tree = vec![0u64; keys.len() + 1];
+        let mut heaviest = 0;
+        for member in members {
+            let rank = keys.partition_point(|&k| k < key(member)) + 1;
+            let mut pred = 0;
+            let mut i = rank;
+            while i > 0 {
+                pred = pred.max(tree[i]);
+                i &= i - 1;
+            }
+            let best = pred + member.matches;
+            heaviest = heaviest.max(best);
+            let mut i = rank;
+            while i < tree.len() {
+                tree[i] = tree[i].max(best);
+                i += i & i.wrapping_neg();
+            }
+        }
+        heaviest
+    }
+
+    /// True if any member starts more than `tolerance` bp before the end of an
+    /// earlier member. Members must be sorted by query start.
+    fn has_member_query_overlap(
+        chain_indices: &[(usize, usize)],
+        metadata: &[RecordMeta],
+        tolerance: u64,
+    ) -> bool {
+        let mut max_end = 0u64;
+        for &(_rank, idx) in chain_indices {
+            let meta = &metadata[idx];
+            if meta.query_start + tolerance < max_end {
+                return true;
+            }
+            max_end = max_end.max(meta.query_end);
+        }
+        false
+    }
+
+    /// Diagonal of a mapping: constant along a colinear alignment, so members of
+    /// a true chain share it up to indels. On '-' the target runs backwards and
+    /// the anti-diagonal (target end + query start) is used instead.
+    fn member_diagonal(meta: &RecordMeta, strand: char) -> i64 {
+        if strand == '+' {
+            meta.target_start as i64 - meta.query_start as i64
+        } else {
+            meta.target_end as i64 + meta.query_start as i64
+        }
+    }
+
+    /// Remove members whose diagonal is more than `max_deviation` from the
+    /// chain's median diagonal, recomputing the median until no member is
+    /// removed. Chains with fewer than three members have no meaningful
+    /// median and are left alone. Returns the removed members.
+    fn drop_off_diagonal_members(
+        chain_indices: &mut Vec<(usize, usize)>,
+        strand: char,
+        metadata: &[RecordMeta],
+        max_deviation: u64,
+    ) -> Vec<(usize, usize)> {
+        let mut dropped = Vec::new();
+
+        while chain_indices.len() >= 3 {
+            let mut diagonals: Vec<i64> = chain_indices
+                .iter()
+                .map(|&(_rank, idx)| Self::member_diagonal(&metadata[idx], strand))
+                .collect();
+            diagonals.sort_unstable();
+            let median = diagonals[diagonals.len() / 2];
+
+            let before = chain_indices.len();
+            chain_indices.retain(|&member| {
+                let diagonal = Self::member_diagonal(&metadata[member.1], strand);
+                let keep = diagonal.abs_diff(median) <= max_deviation;
+                if !keep {
+                    dropped.push(member);
+                }
+                keep
+            });
+            if chain_indices.len() == before {
+                break;
+            }
+        }
+        dropped
+    }
+
+    /// Break a chain (members sorted by query start) wherever a member overlaps
+    /// the preceding members on the query by more than `tolerance` bp.
+    fn split_at_member_overlaps(
+        chain_indices: Vec<(usize, usize)>,
+        metadata: &[RecordMeta],
+        tolerance: u64,
+    ) -> Vec<Vec<(usize, usize)>> {
+        let mut parts: Vec<Vec<(usize, usize)>> = Vec::new();
+        let mut current: Vec<(usize, usize)> = Vec::new();
+        let mut max_end = 0u64;
+
+        for (rank, idx) in chain_indices {
+            let meta = &metadata[idx];
+            if !current.is_empty() && meta.query_start + tolerance < max_end {
+                parts.push(std::mem::take(&mut current));
+                max_end = 0;
+            }
+            max_end = max_end.max(meta.query_end);
+            current.push((rank, idx));
+        }
+        if !current.is_empty() {
+            parts.push(current);
+        }
+        parts
+    }
+
+    /// Apply plane sweep to filter overlapping chains
+    /// Calculate overlap fraction between two ranges
+    fn calculate_overlap(start1: u32, end1: u32, start2: u32, end2: u32) -> f64 {
+        let overlap_start = start1.max(start2);
+        let overlap_end = end1.min(end2);
+
+        if overlap_start >= overlap_end {
+            return 0.0; // No overlap
+        }
+
+        let overlap_len = overlap_end - overlap_start;
+        let len1 = end1 - start1;
+        let len2 = end2 - start2;
+        let min_len = len1.min(len2);
+
+        if min_len == 0 {
+            return 0.0;
+        }
+
+        overlap_len as f64 / min_len as f64
+    }
+
+    /// Apply 1:1 filtering within a prefix group
+    fn filter_one_to_one_within_group(&self, mappings: Vec<RecordMeta>) -> Result<Vec<RecordMeta>> {
+        if mappings.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        // Find best mapping (longest)
+        let best = mappings.into_iter().max_by_key(|m| m.block_length).unwrap();
+
+        Ok(vec![best])
+    }
+
+    /// Compute non-overlapping query spans for the kept mappings. Mappings fully
+    /// covered by better ones are removed from `passing`; the returned map holds
+    /// the new span of every mapping that needs clipping.
+    fn trim_query_overlaps(
+        &self,
+        passing: &mut HashMap<usize, RecordMeta>,
+        names: &SequenceIndex,
+    ) -> HashMap<usize, (u64, u64)> {
+        use crate::trim_overlaps::{compute_query_trims, TrimCandidate};
+
+        let candidates: Vec<TrimCandidate> = passing
+            .values()
+            .map(|m| TrimCandidate {
+                rank: m.rank,
+                query_name: names.name(m.query_id),
+                query_start: m.query_start,
+                query_end: m.query_end,
+                score: self.mapping_score(m),
+            })
+            .collect();
+        let spans = compute_query_trims(&candidates);
+
+        let mut trims = HashMap::new();
+        let mut dropped = Vec::new();
+        for (rank, span) in spans {
+            let meta = &passing[&rank];
+            match span {
+                None => dropped.push(rank),
+                Some(span) if span != (meta.query_start, meta.query_end) => {
+                    trims.insert(rank, span);
+                }
+                Some(_) => {}
+            }
+        }
+        for rank in &dropped {
+            passing.remove(rank);
+        }
+
+        if !trims.is_empty() || !dropped.is_empty() {
+            log::info!(
+                "[sweepga] Trim overlaps: {} mappings clipped, {} fully covered and removed",
+                trims.len(),
+                dropped.len()
+            );
+        }
+        trims
+    }
+
+    /// Stable-sort output records by `sort`, so ties keep input order
+    fn sort_records(
+        &self,
+        records: &mut [(&RecordMeta, String)],
+        sort: OutputSort,
+        names: &SequenceIndex,
+    ) {
+        match sort {
+            OutputSort::Query => records.sort_by(|(a, _), (b, _)| {
+                (names.name(a.query_id), a.query_start, a.query_end).cmp(&(
+                    names.name(b.query_id),
+                    b.query_start,
+                    b.query_end,
+                ))
+            }),
+            OutputSort::Target => records.sort_by(|(a, _), (b, _)| {
+                (names.name(a.target_id), a.target_start, a.target_end).cmp(&(
+                    names.name(b.target_id),
+                    b.target_start,
+                    b.target_end,
+                ))
+            }),
+            OutputSort::Score => {
+                records.sort_by(|(a, _), (b, _)| {
+                    self.mapping_score(b).total_cmp(&self.mapping_score(a))
+                });
+            }
+        }
+    }
+
+    /// Score a mapping with the configured scoring function (same as the plane sweep)
+    fn mapping_score(&self, meta: &RecordMeta) -> f64 {
+        PlaneSweepMapping {
+            idx: 0,
+            query_start: meta.query_start,
+            query_end: meta.query_end,
+            target_start: meta.target_start,
+            target_end: meta.target_end,
+            identity: self
+                .config
+                .scoring_function
+                .scored_identity(meta.identity, meta.gap_compressed_identity),
+            mapq: meta.mapq,
+            flags: 0,
+        }
+        .score_with_function(self.config.scoring_function)
+    }
+
+    /// Keep only mappings whose score is at least `min_relative_score` times
+    /// the best score among all mappings of the same query sequence.
+    /// Queries whose best score is not positive are left untouched.
+    fn filter_by_relative_score(&self, mappings: Vec<RecordMeta>) -> Vec<RecordMeta> {
+        let scores: Vec<f64> = mappings.iter().map(|m| self.mapping_score(m)).collect();
+
+        let mut best_per_query: HashMap<u32, f64> = HashMap::new();
+        for (m, &score) in mappings.iter().zip(&scores) {
+            let best = best_per_query
+                .entry(m.query_id)
+                .or_insert(f64::NEG_INFINITY);
+            if score > *best {
+                *best = score;
+            }
+        }
+
+        let keep: Vec<bool> = mappings
+            .iter()
+            .zip(&scores)
+            .map(|(m, &score)| {
+                let best = best_per_query[&m.query_id];
+                best <= 0.0 || score >= self.min_relative_score * best
+            })
+            .collect();
+
+        mappings
+            .into_iter()
+            .zip(keep)
+            .filter_map(|(m, keep)| keep.then_some(m))
+            .collect()
+    }
+
+    /// Apply plane sweep to raw mappings (before scaffold filtering)
+    /// This implements the wfmash plane sweep algorithm exactly
+    /// CRITICAL: Plane sweep must run PER QUERY SEQUENCE, not globally
+    fn apply_plane_sweep_to_mappings(
+        &self,
+        mappings: &[RecordMeta],
+        names: &SequenceIndex,
+    ) -> Result<Vec<RecordMeta>> {
+        let _profile = crate::profile::scope("plane_sweep");
+        if mappings.is_empty() || mappings.len() <= 1 {
+            return Ok(mappings
+                .iter()
+                .map(|m| RecordMeta {
+                    sweep_rank: Some(0),
+                    ..m.clone()
+                })
+                .collect());
+        }
+
+        // Convert RecordMeta to PlaneSweepMapping with grouping keys
+        let scoring = self.config.scoring_function;
+        let plane_sweep_mappings: Vec<(PlaneSweepMapping, u32, u32)> = mappings
+            .iter()
+            .enumerate()
+            .map(|(idx, meta)| {
+                let mapping = PlaneSweepMapping {
+                    idx, // Store original index
+                    query_start: meta.query_start,
+                    query_end: meta.query_end,
+                    target_start: meta.target_start,
+                    target_end: meta.target_end,
+                    identity: scoring.scored_identity(meta.identity, meta.gap_compressed_identity),
+                    mapq: meta.mapq,
+                    flags: 0,
+                };
+                // For 1:1 filtering with prefix grouping:
+                // We want to keep the best alignment for each chromosome WITHIN each genome pair
+                // So we group by full chromosome names (which include the genome prefix)
+                (mapping, meta.query_id, meta.target_id)
+            })
+            .collect();
+
+        let overlap_threshold = self.config.overlap_threshold;
+
+        use crate::plane_sweep_exact::{plane_sweep_query_ranked, plane_sweep_target_ranked};
+
+        let query_limit = match self.config.mapping_filter_mode {
+            FilterMode::OneToOne => 1,
+            FilterMode::OneToMany => self.config.mapping_max_per_query.unwrap_or(1),
+            FilterMode::ManyToMany => self.config.mapping_max_per_query.unwrap_or(usize::MAX),
+        };
+
+        let target_limit = match self.config.mapping_filter_mode {
+            FilterMode::OneToOne => 1,
+            FilterMode::OneToMany => self.config.mapping_max_per_target.unwrap_or(usize::MAX),
+            FilterMode::ManyToMany => self.config.mapping_max_per_target.unwrap_or(usize::MAX),
+        };
+
+        if self.reference_mode {
+            return Ok(self.reference_plane_sweep(mappings, &plane_sweep_mappings));
+        }
+
+        // CRITICAL: Group by (query_genome_prefix, target_genome_prefix) pairs FIRST.
+        // This ensures plane sweep runs independently for each genome pair.
+        // IndexMap: insertion-order iteration, so every per-group plane sweep
+        // sees the same input order across runs (without this, the group-iteration
+        // order drives `idx` assignment in `enumerate()`, breaking tie-breaks).
+        let mut genome_pair_groups: IndexMap<(String, String), Vec<usize>> = IndexMap::new();
+
+        for (i, &(_, q, t)) in plane_sweep_mappings.iter().enumerate() {
+            // Genome prefix ("SGDref#1#chrI" -> "SGDref#1#"), or the full
+            // name with --skip-prefix; names without a delimiter are their
+            // own group
+            let query_genome = self.extract_prefix(names.name(q));
+            let target_genome = self.extract_prefix(names.name(t));
+            genome_pair_groups
+                .entry((query_genome, target_genome))
+                .or_default()
+                .push(i);
+        }
+
+        // Process each genome pair independently
+        let mut all_kept_indices = Vec::new();
+        // Worse of each mapping's query- and target-axis sweep ranks
+        let mut sweep_ranks = vec![0; plane_sweep_mappings.len()];
+
+        for (_genome_pair, genome_pair_indices) in genome_pair_groups {
+            let _profile = crate::profile::scope("genome_pair");
+            // Within this genome pair, apply the query and/or target sweeps
+            // (--overlap-axis), intersecting them when both run
+
+            // Query axis sweep: group by query chr within this genome pair
+            let mut query_kept_set: IndexSet<usize> = IndexSet::new();
+            if self.overlap_axis.sweeps_query() {
+                let mut by_query: IndexMap<u32, Vec<usize>> = IndexMap::new();
+
+                for &idx in &genome_pair_indices {
+                    let (_, q, _t) = plane_sweep_mappings[idx];
+                    by_query.entry(q).or_default().push(idx);
+                }
+
+                for (_q_chr, indices) in by_query {
+                    let mut query_mappings: Vec<_> =
+                        indices.iter().map(|&i| plane_sweep_mappings[i].0).collect();
+
+                    let (kept, ranks) = plane_sweep_query_ranked(
+                        &mut query_mappings,
+                        query_limit,
+                        overlap_threshold,
+                        self.config.scoring_function,
+                    );
+                    for k in kept {
+                        query_kept_set.insert(indices[k]);
+                        sweep_ranks[indices[k]] = sweep_ranks[indices[k]].max(ranks[k]);
+                    }
+                }
+            }
+
+            // Target axis sweep: group by target chr within this genome pair
+            let mut target_kept_set: IndexSet<usize> = IndexSet::new();
+            if self.overlap_axis.sweeps_target() {
+                let mut by_target: IndexMap<u32, Vec<usize>> = IndexMap::new();
+
+                for &idx in &genome_pair_indices {
+                    let (_, _q, t) = plane_sweep_mappings[idx];
+                    by_target.entry(t).or_default().push(idx);
+                }
+
+                for (_t_chr, indices) in by_target {
+                    let mut target_mappings: Vec<_> =
+                        indices.iter().map(|&i| plane_sweep_mappings[i].0).collect();
+
+                    let (kept, ranks) = plane_sweep_target_ranked(
+                        &mut target_mappings,
+                        target_limit,
+                        overlap_threshold,
+                        self.config.scoring_function,
+                    );
+                    for k in kept {
+                        target_kept_set.insert(indices[k]);
+                        sweep_ranks[indices[k]] = sweep_ranks[indices[k]].max(ranks[k]);
+                    }
+                }
+            }
+
+            // IndexSet preserves insertion order, so the kept indices are
+            // deterministic
+            let mut kept: Vec<usize> = match self.overlap_axis {
+                OverlapAxis::Query => query_kept_set.into_iter().collect(),
+                OverlapAxis::Target => target_kept_set.into_iter().collect(),
+                OverlapAxis::Both => query_kept_set
+                    .iter()
+                    .filter(|i| target_kept_set.contains(*i))
+                    .copied()
+                    .collect(),
+            };
+            kept.sort_unstable();
+            all_kept_indices.extend(kept);
+        }
+
+        let kept_indices = all_kept_indices;
+
+        // Convert back to RecordMeta
+        let result: Vec<RecordMeta> = kept_indices
+            .iter()
+            .map(|&idx| RecordMeta {
+                sweep_rank: Some(sweep_ranks[idx]),
+                ..mappings[idx].clone()
+            })
+            .collect();
+
+        Ok(result)
+    }
+
+    /// Reference-mode plane sweep: mappings are grouped by query sequence only,
+    /// so hits to every target (all reference chromosomes) compete on the
+    /// query axis. Keeps `mapping_max_per_query` (default 1) per region. There
+    /// is no target-axis sweep since all queries may share a reference region.
+    fn reference_plane_sweep(
+        &self,
+        mappings: &[RecordMeta],
+        plane_sweep_mappings: &[(PlaneSweepMapping, u32, u32)],
+    ) -> Vec<RecordMeta> {
+        use crate::plane_sweep_exact::plane_sweep_query_ranked;
+
+        let query_limit = self.config.mapping_max_per_query.unwrap_or(1);
+
+        let mut by_query: IndexMap<u32, Vec<usize>> = IndexMap::new();
+        for (i, &(_, q, _t)) in plane_sweep_mappings.iter().enumerate() {
+            by_query.entry(q).or_default().push(i);
+        }
+
+        let mut kept_indices = Vec::new();
+        for indices in by_query.into_values() {
+            let mut query_mappings: Vec<_> =
+                indices.iter().map(|&i| plane_sweep_mappings[i].0).collect();
+            let (kept, ranks) = plane_sweep_query_ranked(
+                &mut query_mappings,
+                query_limit,
+                self.config.overlap_threshold,
+                self.config.scoring_function,
+            );
+            kept_indices.extend(kept.into_iter().map(|k| (indices[k], ranks[k])));
+        }
+        kept_indices.sort_unstable();
+
+        kept_indices
+            .iter()
+            .map(|&(i, rank)| RecordMeta {
+                sweep_rank: Some(rank),
+                ..mappings[i].clone()
+            })
+            .collect()
+    }
+
+    /// Apply scaffold plane sweep - SAME ALGORITHM as regular mappings, just different params
+    fn apply_scaffold_plane_sweep(
+        &self,
+        chains: Vec<MergedChain>,
+        names: &SequenceIndex,
+    ) -> Result<Vec<MergedChain>> {
+        let _profile = crate::profile::scope("scaffold_sweep");
+        if chains.is_empty() || chains.len() <= 1 {
+            return Ok(chains);
+        }
+
+        // Use shared plane sweep module
+        let named: Vec<NamedChain> = chains
+            .iter()
+            .map(|chain| NamedChain { chain, names })
+            .collect();
+        let kept_indices = plane_sweep_scaffolds(
+            &named,
+            self.config.scaffold_filter_mode,
+            self.config.scaffold_max_per_query,
+            self.config.scaffold_max_per_target,
+            self.scaffold_keep_fraction,
+            self.config.scaffold_overlap_threshold,
+            self.config.scoring_function,
+            self.config.prefix_delimiter,
+            self.config.prefix_depth,
+        )?;
+
+        // Return the filtered chains
+        Ok(kept_indices
+            .iter()
+            .map(|&idx| chains[idx].clone())
+            .collect())
+    }
+
+    // Original implementation kept for reference - can be deleted after testing
+    #[allow(dead_code)]
+    fn apply_scaffold_plane_sweep_original(
+        &self,
+        chains: Vec<MergedChain>,
+    ) -> Result<Vec<MergedChain>> {
+        if chains.is_empty() || chains.len() <= 1 {
+            return Ok(chains);
+        }
+
+        // Convert MergedChain to PlaneSweepMapping - same as regular mappings
+        let scoring = self.config.scoring_function;
+        let plane_sweep_mappings: Vec<(PlaneSweepMapping, u32, u32)> = chains
+            .iter()
+            .enumerate()
+            .map(|(idx, chain)| {
+                let mapping = PlaneSweepMapping {
+                    idx,
+                    query_start: chain.query_start,
+                    query_end: chain.query_end,
+                    target_start: chain.target_start,
+                    target_end: chain.target_end,
+                    identity: scoring
+                        .scored_identity(chain.weighted_identity, chain.gap_compressed_identity),
+                    mapq: chain.mapq,
+                    flags: 0,
+                };
+                (mapping, chain.query_id, chain.target_id)
+            })
+            .collect();
+
+        // Get the scaffold filter params (vs mapping filter params)
+        let overlap_threshold = self.config.scaffold_overlap_threshold;
+
+        // Apply plane sweep - EXACT SAME FUNCTION as regular mappings
+        // Always use independent query and target sweeps
+        use crate::plane_sweep_exact::{plane_sweep_query, plane_sweep_target};
+
+        let kept_indices: Vec<usize> = match self.config.scaffold_filter_mode {
+            FilterMode::OneToOne => {
+                // True 1:1: Apply plane sweep independently on query and target axes
+                // 1. Sweep across each query chromosome (scaffolds to different targets compete)
+                // 2. Sweep across each target chromosome (scaffolds from different queries compete)
+                // 3. Keep only scaffolds that pass BOTH sweeps
+
+                // Query axis sweep: group by query chr
+                let mut query_kept_set = HashSet::new();
+                let mut by_query: std::collections::HashMap<u32, Vec<usize>> =
+                    std::collections::HashMap::new();
+
+                for (i, &(_, q, _t)) in plane_sweep_mappings.iter().enumerate() {
+                    by_query.entry(q).or_default().push(i);
+                }
+
+                for (_q_chr, indices) in by_query {
+                    let mut query_mappings: Vec<_> =
+                        indices.iter().map(|&i| plane_sweep_mappings[i].0).collect();
+
+                    let kept = plane_sweep_query(
+                        &mut query_mappings,
+                        1,
+                        overlap_threshold,
+                        self.config.scoring_function,
+                    );
+                    for k in kept {
+                        query_kept_set.insert(indices[k]);
+                    }
+                }
+
+                // Target axis sweep: group by target chr
+                let mut target_kept_set = HashSet::new();
+                let mut by_target: std::collections::HashMap<u32, Vec<usize>> =
+                    std::collections::HashMap::new();
+
+                for (i, &(_, _q, t)) in plane_sweep_mappings.iter().enumerate() {
+                    by_target.entry(t).or_default().push(i);
+                }
+
+                for (_t_chr, indices) in by_target {
+                    let mut target_mappings: Vec<_> =
+                        indices.iter().map(|&i| plane_sweep_mappings[i].0).collect();
+
+                    let kept = plane_sweep_target(
+                        &mut target_mappings,
+                        1,
+                        overlap_threshold,
+                        self.config.scoring_function,
+                    );
+                    for k in kept {
+                        target_kept_set.insert(indices[k]);
+                    }
+                }
+
+                // Intersection: keep only indices that passed both sweeps
+                query_kept_set
+                    .intersection(&target_kept_set)
+                    .copied()
+                    .collect()
+            }
+            FilterMode::OneToMany | FilterMode::ManyToMany => {
+                // Same independent sweep pattern as 1:1, just different limits
+                let query_limit = match self.config.scaffold_filter_mode {
+                    FilterMode::OneToMany => self.config.scaffold_max_per_query.unwrap_or(1),
+                    FilterMode::ManyToMany => {
+                        self.config.scaffold_max_per_query.unwrap_or(usize::MAX)
+                    }
+                    _ => unreachable!(),
+                };
+
+                let target_limit = match self.config.scaffold_filter_mode {
+                    FilterMode::OneToMany => {
+                        self.config.scaffold_max_per_target.unwrap_or(usize::MAX)
+                    }
+                    FilterMode::ManyToMany => {
+                        self.config.scaffold_max_per_target.unwrap_or(usize::MAX)
+                    }
+                    _ => unreachable!(),
+                };
+
+                // Query axis sweep: group by query chr
+                let mut query_kept_set = HashSet::new();
+                let mut by_query: std::collections::HashMap<u32, Vec<usize>> =
+                    std::collections::HashMap::new();
+
+                for (i, &(_, q, _t)) in plane_sweep_mappings.iter().enumerate() {
+                    by_query.entry(q).or_default().push(i);
+                }
+
+                for (_q_chr, indices) in by_query {
+                    let mut query_mappings: Vec<_> =
+                        indices.iter().map(|&i| plane_sweep_mappings[i].0).collect();
+
+                    let kept = plane_sweep_query(
+                        &mut query_mappings,
+                        query_limit,
+                        overlap_threshold,
+                        self.config.scoring_function,
+                    );
+                    for k in kept {
+                        query_kept_set.insert(indices[k]);
+                    }
+                }
+
+                // Target axis sweep: group by target chr
+                let mut target_kept_set = HashSet::new();
+                let mut by_target: std::collections::HashMap<u32, Vec<usize>> =
+                    std::collections::HashMap::new();
+
+                for (i, &(_, _q, t)) in plane_sweep_mappings.iter().enumerate() {
+                    by_target.entry(t).or_default().push(i);
+                }
+
+                for (_t_chr, indices) in by_target {
+                    let mut target_mappings: Vec<_> =
+                        indices.iter().map(|&i| plane_sweep_mappings[i].0).collect();
+
+                    let kept = plane_sweep_target(
+                        &mut target_mappings,
+                        target_limit,
+                        overlap_threshold,
+                        self.config.scoring_function,
+                    );
+                    for k in kept {
+                        target_kept_set.insert(indices[k]);
+                    }
+                }
+
+                // Intersection: keep only indices that passed both sweeps
+                query_kept_set
+                    .intersection(&target_kept_set)
+                    .copied()
+                    .collect()
+            }
+        };
+
+        // Convert back to MergedChain
+        let result: Vec<MergedChain> = kept_indices
+            .iter()
+            .map(|&idx| chains[idx].clone())
+            .collect();
+
+        Ok(result)
+    }
+
+    /// DEPRECATED - Now using unified apply_scaffold_plane_sweep
+    #[allow(dead_code)]
+    fn scaffold_one_to_one_filter(&self, chains: Vec<MergedChain>) -> Result<Vec<MergedChain>> {
+        use crate::plane_sweep_exact::{plane_sweep_both, PlaneSweepMapping};
+
+        // Group by (query_chr, target_chr) pairs
+        let mut by_chr_pair: HashMap<(u32, u32), Vec<MergedChain>> = HashMap::new();
+
+        for chain in chains {
+            let key = (chain.query_id, chain.target_id);
+            by_chr_pair.entry(key).or_default().push(chain);
+        }
+
+        let mut filtered_chains = Vec::new();
+
+        // Apply plane sweep within each chromosome pair
+        for (_pair, pair_chains) in by_chr_pair {
+            if pair_chains.is_empty() {
+                continue;
+            }
+
+            // Convert chains to mappings for plane sweep
+            let scoring = self.config.scoring_function;
+            let mut mappings: Vec<PlaneSweepMapping> = pair_chains
+                .iter()
+                .enumerate()
+                .map(|(idx, chain)| PlaneSweepMapping {
+                    idx,
+                    query_start: chain.query_start,
+                    query_end: chain.query_end,
+                    target_start: chain.target_start,
+                    target_end: chain.target_end,
+                    identity: scoring
+                        .scored_identity(chain.weighted_identity, chain.gap_compressed_identity),
+                    mapq: chain.mapq,
+                    flags: 0,
+                })
+                .collect();
+
+            // Apply plane sweep with 1:1 filtering on both query and target axes
+            // This keeps all non-overlapping scaffolds plus best overlapping ones
+            let kept_indices = plane_sweep_both(
+                &mut mappings,
+                1, // max per query position
+                1, // max per target position
+                self.config.scaffold_overlap_threshold,
+                self.config.scoring_function,
+            );
+
+            // Collect the kept chains
+            for idx in kept_indices {
+                filtered_chains.push(pair_chains[idx].clone());
+            }
+        }
+
+        Ok(filtered_chains)
+    }
+
+    /// DEPRECATED - Now using unified apply_scaffold_plane_sweep
+    #[allow(dead_code)]
+    fn scaffold_one_to_many_filter(&self, chains: Vec<MergedChain>) -> Result<Vec<MergedChain>> {
+        use crate::plane_sweep_exact::{plane_sweep_query, PlaneSweepMapping};
+
+        // Group by (query_chr, target_chr) pairs
+        let mut by_chr_pair: HashMap<(u32, u32), Vec<MergedChain>> = HashMap::new();
+
+        for chain in chains {
+            let key = (chain.query_id, chain.target_id);
+            by_chr_pair.entry(key).or_default().push(chain);
+        }
+
+        let mut filtered_chains = Vec::new();
+
+        // Apply plane sweep within each chromosome pair
+        for (_pair, pair_chains) in by_chr_pair {
+            if pair_chains.is_empty() {
+                continue;
+            }
+
+            // Convert chains to mappings for plane sweep
+            let scoring = self.config.scoring_function;
+            let mut mappings: Vec<PlaneSweepMapping> = pair_chains
+                .iter()
+                .enumerate()
+                .map(|(idx, chain)| PlaneSweepMapping {
+                    idx,
+                    query_start: chain.query_start,
+                    query_end: chain.query_end,
+                    target_start: chain.target_start,
+                    target_end: chain.target_end,
+                    identity: scoring
+                        .scored_identity(chain.weighted_identity, chain.gap_compressed_identity),
+                    mapq: chain.mapq,
+                    flags: 0,
+                })
+                .collect();
+
+            // Apply plane sweep with 1:∞ filtering (limit on query axis only)
+            let max_per_query = self.config.scaffold_max_per_query.unwrap_or(1);
+            let kept_indices = plane_sweep_query(
+                &mut mappings,
+                max_per_query,
+                self.config.scaffold_overlap_threshold,
+                self.config.scoring_function,
+            );
+
+            // Collect the kept chains
+            for idx in kept_indices {
+                filtered_chains.push(pair_chains[idx].clone());
+            }
+        }
+
+        Ok(filtered_chains)
+    }
+
+    /// Keep all non-overlapping chains (for scaffold filtering with -n inf behavior)
+    fn plane_sweep_keep_all_non_overlapping(
+        &self,
+        chains: Vec<MergedChain>,
+    ) -> Result<Vec<MergedChain>> {
+        // Group chains by query
+        let mut by_query: HashMap<u32, Vec<MergedChain>> = HashMap::new();
+        for chain in chains {
+            by_query.entry(chain.query_id).or_default().push(chain);
+        }
+
+        let mut filtered = Vec::new();
+
+        for (_query, mut query_chains) in by_query {
+            // Sort by chain score (descending) to prioritize better chains
+            let scoring = self.config.scoring_function;
+            query_chains.sort_by(|a, b| {
+                let score_a = a.score(scoring);
+                let score_b = b.score(scoring);
+                score_b
+                    .partial_cmp(&score_a)
+                    .unwrap_or(std::cmp::Ordering::Equal)
+            });
+
+            let mut kept: Vec<MergedChain> = Vec::new();
+
+            for chain in query_chains {
+                let mut has_significant_overlap = false;
+
+                // Check overlap with already kept chains
+                for existing in &kept {
+                    let overlap_start = chain.query_start.max(existing.query_start);
+                    let overlap_end = chain.query_end.min(existing.query_end);
+
+                    if overlap_start < overlap_end {
+                        let overlap_len = overlap_end - overlap_start;
+                        let chain_len = chain.query_end - chain.query_start;
+                        let existing_len = existing.query_end - existing.query_start;
+                        let min_len = chain_len.min(existing_len);
+
+                        // Use scaffold_overlap_threshold to determine significant overlap
+                        let overlap_frac = overlap_len as f64 / min_len as f64;
+                        if overlap_frac > self.config.scaffold_overlap_threshold {
+                            has_significant_overlap = true;
+                            break;
                         }
-                        kept_mappings.push(rescued_mapping);
-                        kept_status.insert(mapping.rank, ChainStatus::Rescued);
                     }
                 }
-                // If max_deviation == 0 and not an anchor, mapping is not kept
+
+                // Keep chain if it doesn't significantly overlap with any existing
+                if !has_significant_overlap {
+                    kept.push(chain);
+                }
             }
-        }
 
-        // Build result map directly from kept mappings
-        let mut passing = HashMap::new();
-        for meta in kept_mappings {
-            let mut result = meta.clone();
-            // Set the chain status based on what we determined earlier
-            result.chain_status = kept_status
-                .get(&meta.rank)
-                .cloned()
-                .unwrap_or(ChainStatus::Scaffold);
-            passing.insert(meta.rank, result);
+            filtered.extend(kept);
         }
 
-        Ok(passing)
+        Ok(filtered)
     }
 
-    /// Merge mappings into chains using wfmash's union-find approach
-    fn merge_mappings_into_chains(
-        &self,
-        metadata: &[RecordMeta],
-        max_gap: u64,
-    ) -> Result<Vec<MergedChain>> {
-        use crate::union_find::UnionFind;
-
-        // Group by (query, target, strand) - this is like wfmash's refSeqId grouping
-        // Store the original rank, not the position in metadata array.
-        // IndexMap: iteration order = PAF input order, so all_chains is built
-        // deterministically (otherwise scaffold idx assignment drifts run-to-run).
-        let mut groups: IndexMap<(String, String, char), Vec<(usize, usize)>> = IndexMap::new();
-
-        for (idx, meta) in metadata.iter().enumerate() {
-            let key = (
-                meta.query_name.clone(),
-                meta.target_name.clone(),
-                meta.strand,
-            );
-            groups.entry(key).or_default().push((meta.rank, idx));
+    fn plane_sweep_filter_chains(&self, chains: Vec<MergedChain>) -> Result<Vec<MergedChain>> {
+        // Group chains by query
+        let mut by_query: HashMap<u32, Vec<MergedChain>> = HashMap::new();
+        for chain in chains {
+            by_query.entry(chain.query_id).or_default().push(chain);
         }
 
-        let mut all_chains = Vec::new();
-
-        for ((query, target, strand), indices) in groups {
-            // Sort by query start position (like wfmash's sort)
-            let mut sorted_indices = indices.clone();
-            sorted_indices.sort_by_key(|&(_rank, idx)| metadata[idx].query_start);
-
-            // Best-buddy chaining: track best predecessor for each mapping
-            let mut best_pred_score: Vec<u64> = vec![u64::MAX; sorted_indices.len()];
-            let mut best_pred_idx: Vec<Option<usize>> = vec![None; sorted_indices.len()];
+        let mut filtered = Vec::new();
 
-            // Phase 1: Find all best-buddy relationships
-            for i in 0..sorted_indices.len() {
-                let (_rank_i, idx_i) = sorted_indices[i];
-                let search_bound = metadata[idx_i].query_end + max_gap;
+        for (_query, mut query_chains) in by_query {
+            // Sort by query start
+            query_chains.sort_by_key(|c| c.query_start);
 
-                let mut best_j = None;
-                let mut best_score = u64::MAX;
+            // Plane sweep to remove overlaps
+            let mut kept: Vec<MergedChain> = Vec::new();
+            let scoring_func = self.config.scoring_function;
 
-                for j in (i + 1)..sorted_indices.len() {
-                    let (_rank_j, idx_j) = sorted_indices[j];
+            for chain in query_chains {
+                // Check overlap with already kept chains
+                let mut has_significant_overlap = false;
 
-                    if metadata[idx_j].query_start > search_bound {
-                        break;
-                    }
+                kept.retain(|existing| {
+                    let overlap_start = chain.query_start.max(existing.query_start);
+                    let overlap_end = chain.query_end.min(existing.query_end);
 
-                    // Calculate query distance/overlap (using actual overlap as distance, not 0)
-                    let q_gap = if metadata[idx_j].query_start >= metadata[idx_i].query_end {
-                        // Normal gap
-                        metadata[idx_j].query_start - metadata[idx_i].query_end
-                    } else {
-                        // Overlap: use overlap amount as distance, penalize if too large
-                        let overlap = metadata[idx_i].query_end - metadata[idx_j].query_start;
-                        if overlap <= max_gap / 5 {
-                            overlap // Small overlap: use actual overlap distance
-                        } else {
-                            max_gap + 1 // Large overlap: reject
-                        }
-                    };
+                    if overlap_start < overlap_end {
+                        let overlap_len = overlap_end - overlap_start;
+                        let chain_len = chain.query_end - chain.query_start;
+                        let existing_len = existing.query_end - existing.query_start;
+                        let min_len = chain_len.min(existing_len);
 
-                    // Calculate target distance/overlap
-                    let r_gap = if strand == '+' {
-                        if metadata[idx_j].target_start >= metadata[idx_i].target_end {
-                            metadata[idx_j].target_start - metadata[idx_i].target_end
-                        } else {
-                            let overlap = metadata[idx_i].target_end - metadata[idx_j].target_start;
-                            if overlap <= max_gap / 5 {
-                                overlap
+                        // If >50% overlap (wfmash uses scaffold_overlap_threshold)
+                        if overlap_len as f64 / min_len as f64
+                            > self.config.scaffold_overlap_threshold
+                        {
+                            // Keep the better scoring chain
+                            if existing.score(scoring_func) > chain.score(scoring_func) {
+                                has_significant_overlap = true;
+                                true // Keep existing
                             } else {
-                                max_gap + 1
+                                false // Remove existing, will add current
                             }
-                        }
-                    } else if metadata[idx_i].target_start >= metadata[idx_j].target_end {
-                        metadata[idx_i].target_start - metadata[idx_j].target_end
-                    } else {
-                        let overlap = metadata[idx_j].target_end - metadata[idx_i].target_start;
-                        if overlap <= max_gap / 5 {
-                            overlap
                         } else {
-                            max_gap + 1
-                        }
-                    };
-
-                    if q_gap <= max_gap && r_gap <= max_gap {
-                        let dist_sq = q_gap * q_gap + r_gap * r_gap;
-
-                        // Best-buddy: only link if i is the best predecessor for j
-                        if dist_sq < best_score && dist_sq < best_pred_score[j] {
-                            best_score = dist_sq;
-                            best_j = Some(j);
+                            true // No significant overlap, keep both
                         }
+                    } else {
+                        true // No overlap, keep
                     }
-                }
-
-                // Record best-buddy relationship for j
-                if let Some(j) = best_j {
-                    best_pred_score[j] = best_score;
-                    best_pred_idx[j] = Some(i);
-                }
-            }
+                });
 
-            // Phase 2: Create chains using union-find on best-buddy pairs only
-            let mut uf = UnionFind::new(sorted_indices.len());
-            for (j, pred) in best_pred_idx.iter().enumerate() {
-                if let Some(i) = pred {
-                    uf.union(*i, j);
+                if !has_significant_overlap {
+                    kept.push(chain);
                 }
             }
 
-            // Extract chains from union-find sets
-            let sets = uf.get_sets();
-            let chains: Vec<Vec<(usize, usize)>> = sets
-                .into_iter()
-                .map(|set_indices| set_indices.into_iter().map(|i| sorted_indices[i]).collect())
-                .collect();
-
-            // Create merged chains from groups of mappings
-            for chain_indices in chains {
-                if chain_indices.is_empty() {
-                    continue;
-                }
-
-                // Calculate merged chain boundaries and weighted identity
-                let mut q_min = u64::MAX;
-                let mut q_max = 0;
-                let mut t_min = u64::MAX;
-                let mut t_max = 0;
-                let mut member_ranks = Vec::new();
-                let mut sum_matches = 0u64;
-                let mut sum_block_lengths = 0u64;
-
-                for &(rank, idx) in &chain_indices {
-                    let meta = &metadata[idx];
-                    q_min = q_min.min(meta.query_start);
-                    q_max = q_max.max(meta.query_end);
-                    t_min = t_min.min(meta.target_start);
-                    t_max = t_max.max(meta.target_end);
-                    member_ranks.push(rank); // Store the original rank
-
-                    // Sum up matches and block lengths for weighted identity
-                    sum_matches += meta.matches;
-                    sum_block_lengths += meta.block_length;
-                }
-
-                let total_length = q_max - q_min;
-
-                // Calculate identity with log-compressed gaps
-                // Gaps between alignments are penalized logarithmically
-                // This gives a middle ground: gaps hurt but not linearly
-                let gap_length = total_length.saturating_sub(sum_block_lengths);
-                let log_compressed_gap = if gap_length > 0 {
-                    (gap_length as f64).ln().max(0.0)
-                } else {
-                    0.0
-                };
-                let effective_length = sum_block_lengths as f64 + log_compressed_gap;
-
-                let weighted_identity = if effective_length > 0.0 {
-                    sum_matches as f64 / effective_length
-                } else {
-                    0.0
-                };
-
-                all_chains.push(MergedChain {
-                    query_name: query.clone(),
-                    target_name: target.clone(),
-                    query_start: q_min,
-                    query_end: q_max,
-                    target_start: t_min,
-                    target_end: t_max,
-                    strand,
-                    total_length,
-                    weighted_identity,
-                    sum_matches,
-                    sum_block_lengths,
-                    member_indices: member_ranks, // Now storing ranks, not indices
-                });
-            }
+            filtered.extend(kept);
         }
 
-        Ok(all_chains)
+        Ok(filtered)
     }
 
-    /// Apply plane sweep to filter overlapping chains
-    /// Calculate overlap fraction between two ranges
-    fn calculate_overlap(start1: u32, end1: u32, start2: u32, end2: u32) -> f64 {
-        let overlap_start = start1.max(start2);
-        let overlap_end = end1.min(end2);
-
-        if overlap_start >= overlap_end {
-            return 0.0; // No overlap
+    /// Apply final filtering mode (1:1, 1:N, N:N)
+    fn apply_filtering_mode(&self, metadata: Vec<RecordMeta>) -> Result<Vec<RecordMeta>> {
+        match self.config.mapping_filter_mode {
+            FilterMode::OneToOne => self.one_to_one_filter(metadata),
+            FilterMode::OneToMany => self.one_to_many_filter(metadata),
+            FilterMode::ManyToMany => self.many_to_many_filter(metadata),
         }
+    }
 
-        let overlap_len = overlap_end - overlap_start;
-        let len1 = end1 - start1;
-        let len2 = end2 - start2;
-        let min_len = len1.min(len2);
+    /// One-to-one filtering
+    fn one_to_one_filter(&self, metadata: Vec<RecordMeta>) -> Result<Vec<RecordMeta>> {
+        let mut best_per_query: HashMap<u32, RecordMeta> = HashMap::new();
 
-        if min_len == 0 {
-            return 0.0;
+        // Find best mapping per query
+        for meta in metadata {
+            best_per_query
+                .entry(meta.query_id)
+                .and_modify(|e| {
+                    if meta.block_length > e.block_length {
+                        *e = meta.clone();
+                    }
+                })
+                .or_insert(meta);
         }
 
-        overlap_len as f64 / min_len as f64
-    }
-
-    /// Apply 1:1 filtering within a prefix group
-    fn filter_one_to_one_within_group(&self, mappings: Vec<RecordMeta>) -> Result<Vec<RecordMeta>> {
-        if mappings.is_empty() {
-            return Ok(Vec::new());
+        // From those, keep best per target
+        let mut best_per_target: HashMap<u32, RecordMeta> = HashMap::new();
+        for meta in best_per_query.into_values() {
+            best_per_target
+                .entry(meta.target_id)
+                .and_modify(|e| {
+                    if meta.block_length > e.block_length {
+                        *e = meta.clone();
+                    }
+                })
+                .or_insert(meta);
         }
 
-        // Find best mapping (longest)
-        let best = mappings.into_iter().max_by_key(|m| m.block_length).unwrap();
-
-        Ok(vec![best])
+        Ok(best_per_target.into_values().collect())
     }
 
-    /// Apply plane sweep to raw mappings (before scaffold filtering)
-    /// This implements the wfmash plane sweep algorithm exactly
-    /// CRITICAL: Plane sweep must run PER QUERY SEQUENCE, not globally
-    fn apply_plane_sweep_to_mappings(&self, mappings: &[RecordMeta]) -> Result<Vec<RecordMeta>> {
-        if mappings.is_empty() || mappings.len() <= 1 {
-            return Ok(mappings.to_vec());
-        }
-
-        // Convert RecordMeta to PlaneSweepMapping with grouping keys
-        let plane_sweep_mappings: Vec<(PlaneSweepMapping, String, String)> = mappings
-            .iter()
-            .enumerate()
-            .map(|(idx, meta)| {
-                let mapping = PlaneSweepMapping {
-                    idx, // Store original index
-                    query_start: meta.query_start,
-                    query_end: meta.query_end,
-                    target_start: meta.target_start,
-                    target_end: meta.target_end,
-                    identity: meta.identity, // Use actual identity from PAF
-                    flags: 0,
-                };
-                // For 1:1 filtering with prefix grouping:
-                // We want to keep the best alignment for each chromosome WITHIN each genome pair
-                // So we group by full chromosome names (which include the genome prefix)
-                let query_group = meta.query_name.clone();
-                let target_group = meta.target_name.clone();
-                (mapping, query_group, target_group)
-            })
-            .collect();
-
-        let overlap_threshold = self.config.overlap_threshold;
-
-        use crate::plane_sweep_exact::{plane_sweep_query, plane_sweep_target};
+    /// One-to-many filtering
+    fn one_to_many_filter(&self, metadata: Vec<RecordMeta>) -> Result<Vec<RecordMeta>> {
+        let mut result = Vec::new();
 
-        let query_limit = match self.config.mapping_filter_mode {
-            FilterMode::OneToOne => 1,
-            FilterMode::OneToMany => self.config.mapping_max_per_query.unwrap_or(1),
-            FilterMode::ManyToMany => self.config.mapping_max_per_query.unwrap_or(usize::MAX),
-        };
+        // Group by query
+        let mut by_query: HashMap<u32, Vec<RecordMeta>> = HashMap::new();
+        for meta in metadata {
+            by_query.entry(meta.query_id).or_default().push(meta);
+        }
 
-        let target_limit = match self.config.mapping_filter_mode {
-            FilterMode::OneToOne => 1,
-            FilterMode::OneToMany => self.config.mapping_max_per_target.unwrap_or(usize::MAX),
-            FilterMode::ManyToMany => self.config.mapping_max_per_target.unwrap_or(usize::MAX),
-        };
+        // Keep best per query, optionally limit per target
+        for (_query, mut metas) in by_query {
+            metas.sort_by_key(|m| std::cmp::Reverse(m.block_length));
 
-        // Helper function to extract genome prefix from sequence name
-        // For "SGDref#1#chrI", returns "SGDref#1#"
-        // For sequences without '#', behavior depends on filter mode:
-        // Extract genome prefix:
-        // - If PanSN format (has #): prefix up to and including last #
-        // - If no #: use full sequence name (treat as single-sequence genome)
-        let extract_genome_prefix = |seq_name: &str| -> String {
-            if let Some(last_pos) = seq_name.rfind('#') {
-                seq_name[..=last_pos].to_string()
-            } else {
-                // No # separator: treat full name as the group prefix
-                // This handles non-PanSN genomes (e.g., "genome_x" instead of "genome_x#1#chr1")
-                seq_name.to_string()
+            if let Some(limit) = self.config.mapping_max_per_target {
+                metas.truncate(limit);
             }
-        };
-
-        // CRITICAL: Group by (query_genome_prefix, target_genome_prefix) pairs FIRST.
-        // This ensures plane sweep runs independently for each genome pair.
-        // IndexMap: insertion-order iteration, so every per-group plane sweep
-        // sees the same input order across runs (without this, the group-iteration
-        // order drives `idx` assignment in `enumerate()`, breaking tie-breaks).
-        let mut genome_pair_groups: IndexMap<(String, String), Vec<usize>> = IndexMap::new();
 
-        for (i, (_, q, t)) in plane_sweep_mappings.iter().enumerate() {
-            let query_genome = extract_genome_prefix(q);
-            let target_genome = extract_genome_prefix(t);
-            genome_pair_groups
-                .entry((query_genome, target_genome))
-                .or_default()
-                .push(i);
+            result.extend(metas);
         }
 
-        // Process each genome pair independently
-        let mut all_kept_indices = Vec::new();
+        Ok(result)
+    }
+
+    /// Many-to-many filtering
+    fn many_to_many_filter(&self, metadata: Vec<RecordMeta>) -> Result<Vec<RecordMeta>> {
+        // For scaffold filtering, we typically don't need additional limiting
+        // but we can apply per-query/target limits if specified
+
+        if self.config.mapping_max_per_query.is_none()
+            && self.config.mapping_max_per_target.is_none()
+        {
+            return Ok(metadata);
+        }
 
-        for (_genome_pair, genome_pair_indices) in genome_pair_groups {
-            // Within this genome pair, apply query and target sweeps with intersection
+        let mut result = metadata;
 
-            // Query axis sweep: group by query chr within this genome pair
-            let mut query_kept_set: IndexSet<usize> = IndexSet::new();
-            let mut by_query: IndexMap<String, Vec<usize>> = IndexMap::new();
+        // Apply per-query limit
+        if let Some(limit) = self.config.mapping_max_per_query {
+            let mut by_query: HashMap<u32, Vec<RecordMeta>> = HashMap::new();
+            for meta in result {
+                by_query.entry(meta.query_id).or_default().push(meta);
+            }
 
-            for &idx in &genome_pair_indices {
-                let (_, q, _t) = &plane_sweep_mappings[idx];
-                by_query.entry(q.clone()).or_default().push(idx);
+            result = Vec::new();
+            for (_query, mut metas) in by_query {
+                metas.sort_by_key(|m| std::cmp::Reverse(m.block_length));
+                metas.truncate(limit);
+                result.extend(metas);
             }
+        }
 
-            for (_q_chr, indices) in by_query {
-                let mut query_mappings: Vec<_> =
-                    indices.iter().map(|&i| plane_sweep_mappings[i].0).collect();
+        // Apply per-target limit
+        if let Some(limit) = self.config.mapping_max_per_target {
+            let mut by_target: HashMap<u32, Vec<RecordMeta>> = HashMap::new();
+            for meta in result {
+                by_target.entry(meta.target_id).or_default().push(meta);
+            }
 
-                let kept = plane_sweep_query(
-                    &mut query_mappings,
-                    query_limit,
-                    overlap_threshold,
-                    self.config.scoring_function,
-                );
-                for k in kept {
-                    query_kept_set.insert(indices[k]);
-                }
+            result = Vec::new();
+            for (_target, mut metas) in by_target {
+                metas.sort_by_key(|m| std::cmp::Reverse(m.block_length));
+                metas.truncate(limit);
+                result.extend(metas);
             }
+        }
 
-            // Target axis sweep: group by target chr within this genome pair
-            let mut target_kept_set: IndexSet<usize> = IndexSet::new();
-            let mut by_target: IndexMap<String, Vec<usize>> = IndexMap::new();
+        Ok(result)
+    }
 
-            for &idx in &genome_pair_indices {
-                let (_, _q, t) = &plane_sweep_mappings[idx];
-                by_target.entry(t.clone()).or_default().push(idx);
+    /// Remove all passing mappings of genome pairs whose mappings cover less
+    /// than `min_coverage` of the smaller genome (by merged intervals on that
+    /// genome's sequences). Self pairs are left alone.
+    fn drop_low_coverage_pairs(
+        &self,
+        passing: &mut HashMap<usize, RecordMeta>,
+        names: &SequenceIndex,
+        sizes: &crate::paf::GenomeSizes,
+        min_coverage: f64,
+    ) {
+        let genome_sizes = sizes.per_genome(|name| self.extract_prefix(name));
+
+        // (genome A, genome B) with A < B -> genome -> sequence -> intervals
+        type Intervals = HashMap<u32, Vec<(u64, u64)>>;
+        let mut pairs: HashMap<(String, String), HashMap<String, Intervals>> = HashMap::new();
+        for meta in passing.values() {
+            let query_genome = self.extract_prefix(names.name(meta.query_id));
+            let target_genome = self.extract_prefix(names.name(meta.target_id));
+            if query_genome == target_genome {
+                continue;
             }
+            let key = if query_genome < target_genome {
+                (query_genome.clone(), target_genome.clone())
+            } else {
+                (target_genome.clone(), query_genome.clone())
+            };
+            let sides = pairs.entry(key).or_default();
+            sides
+                .entry(query_genome)
+                .or_default()
+                .entry(meta.query_id)
+                .or_default()
+                .push((meta.query_start, meta.query_end));
+            sides
+                .entry(target_genome)
+                .or_default()
+                .entry(meta.target_id)
+                .or_default()
+                .push((meta.target_start, meta.target_end));
+        }
 
-            for (_t_chr, indices) in by_target {
-                let mut target_mappings: Vec<_> =
-                    indices.iter().map(|&i| plane_sweep_mappings[i].0).collect();
-
-                let kept = plane_sweep_target(
-                    &mut target_mappings,
-                    target_limit,
-                    overlap_threshold,
-                    self.config.scoring_function,
+        let mut dropped_pairs: HashSet<(String, String)> = HashSet::new();
+        for ((a, b), mut sides) in pairs {
+            let size_of = |genome: &str| genome_sizes.get(genome).copied().unwrap_or(0);
+            let smaller = if size_of(&a) <= size_of(&b) { &a } else { &b };
+            let smaller_size = size_of(smaller);
+            if smaller_size == 0 {
+                continue;
+            }
+            let covered: u64 = sides
+                .remove(smaller)
+                .unwrap_or_default()
+                .into_values()
+                .map(merged_length)
+                .sum();
+            let coverage = covered as f64 / smaller_size as f64;
+            if coverage < min_coverage {
+                log::debug!(
+                    "[sweepga] {a} vs {b}: pair coverage {:.2}% below minimum, dropping",
+                    coverage * 100.0
                 );
-                for k in kept {
-                    target_kept_set.insert(indices[k]);
-                }
+                dropped_pairs.insert((a, b));
             }
-
-            // Intersection: keep only indices that passed both sweeps within this genome pair.
-            // IndexSet preserves insertion order, so the intersection iteration is
-            // deterministic.
-            let mut intersect: Vec<usize> = query_kept_set
-                .iter()
-                .filter(|i| target_kept_set.contains(*i))
-                .copied()
-                .collect();
-            intersect.sort_unstable();
-            all_kept_indices.extend(intersect);
         }
 
-        let kept_indices = all_kept_indices;
+        if dropped_pairs.is_empty() {
+            return;
+        }
+        let before = passing.len();
+        passing.retain(|_, meta| {
+            let query_genome = self.extract_prefix(names.name(meta.query_id));
+            let target_genome = self.extract_prefix(names.name(meta.target_id));
+            let key = if query_genome < target_genome {
+                (query_genome, target_genome)
+            } else {
+                (target_genome, query_genome)
+            };
+            !dropped_pairs.contains(&key)
+        });
+        log::info!(
+            "[sweepga] --min-pair-coverage: dropped {} genome pairs ({} mappings)",
+            dropped_pairs.len(),
+            before - passing.len()
+        );
+    }
 
-        // Convert back to RecordMeta
-        let result: Vec<RecordMeta> = kept_indices
-            .iter()
-            .map(|&idx| mappings[idx].clone())
+    /// Keep passing mappings in descending score order (ties by input order)
+    /// while their total block length stays within `max_bp`; the first one
+    /// that would exceed it and all lower-scoring ones are dropped.
+    fn cap_output_bp(&self, passing: &mut HashMap<usize, RecordMeta>, max_bp: u64) {
+        let mut ranked: Vec<(f64, usize, u64)> = passing
+            .values()
+            .map(|meta| (self.mapping_score(meta), meta.rank, meta.block_length))
             .collect();
+        ranked.sort_by(|a, b| b.0.total_cmp(&a.0).then(a.1.cmp(&b.1)));
 
-        Ok(result)
+        let (mut kept, mut kept_bp) = (0, 0u64);
+        for &(_, _, length) in &ranked {
+            if kept_bp + length > max_bp {
+                break;
+            }
+            kept += 1;
+            kept_bp += length;
+        }
+        for &(_, rank, _) in &ranked[kept..] {
+            passing.remove(&rank);
+        }
+        log::info!(
+            "[sweepga] --max-output-bp: kept {kept_bp} bp in {kept} mappings, \
+             dropped {} mappings",
+            ranked.len() - kept
+        );
     }
 
-    /// Apply scaffold plane sweep - SAME ALGORITHM as regular mappings, just different params
-    fn apply_scaffold_plane_sweep(&self, chains: Vec<MergedChain>) -> Result<Vec<MergedChain>> {
-        if chains.is_empty() || chains.len() <= 1 {
-            return Ok(chains);
+    /// Connected components of the genome graph whose edges are the passing
+    /// mappings' genome pairs. Returns `rank -> component ID`, numbering
+    /// components from 1 in input order.
+    fn genome_components(
+        &self,
+        passing: &HashMap<usize, RecordMeta>,
+        names: &SequenceIndex,
+    ) -> HashMap<usize, usize> {
+        use crate::union_find::UnionFind;
+
+        let mut ranks: Vec<usize> = passing.keys().copied().collect();
+        ranks.sort_unstable();
+
+        let mut genomes: IndexMap<String, usize> = IndexMap::new();
+        let mut edges = Vec::with_capacity(ranks.len());
+        for &rank in &ranks {
+            let meta = &passing[&rank];
+            let mut genome_id = |id: u32| {
+                let next = genomes.len();
+                *genomes
+                    .entry(self.extract_prefix(names.name(id)))
+                    .or_insert(next)
+            };
+            let query = genome_id(meta.query_id);
+            let target = genome_id(meta.target_id);
+            edges.push((rank, query, target));
         }
 
-        // Use shared plane sweep module
-        let kept_indices = plane_sweep_scaffolds(
-            &chains,
-            self.config.scaffold_filter_mode,
-            self.config.scaffold_max_per_query,
-            self.config.scaffold_max_per_target,
-            self.config.scaffold_overlap_threshold,
-            self.config.scoring_function,
-        )?;
+        let mut uf = UnionFind::new(genomes.len());
+        for &(_rank, query, target) in &edges {
+            uf.union(query, target);
+        }
 
-        // Return the filtered chains
-        Ok(kept_indices
-            .iter()
-            .map(|&idx| chains[idx].clone())
-            .collect())
+        let mut component_ids: HashMap<usize, usize> = HashMap::new();
+        edges
+            .into_iter()
+            .map(|(rank, query, _target)| {
+                let root = uf.find(query);
+                let next = component_ids.len() + 1;
+                (rank, *component_ids.entry(root).or_insert(next))
+            })
+            .collect()
     }
 
-    // Original implementation kept for reference - can be deleted after testing
-    #[allow(dead_code)]
-    fn apply_scaffold_plane_sweep_original(
-        &self,
-        chains: Vec<MergedChain>,
-    ) -> Result<Vec<MergedChain>> {
-        if chains.is_empty() || chains.len() <= 1 {
-            return Ok(chains);
+    /// Write filtered output with annotations
+    /// A passing input line in 0-based coordinates, clipped to `trim` if set
+    fn internal_line(&self, mut line: String, trim: Option<(u64, u64)>) -> Result<String> {
+        // Trimming works on 0-based coordinates, as does everything internal
+        if self.input_coord_base != 0 {
+            line = shift_start_coords(&line, -i64::from(self.input_coord_base))?;
         }
+        if let Some((start, end)) = trim {
+            line = crate::trim_overlaps::trim_paf_line(&line, start, end)?;
+        }
+        Ok(line)
+    }
 
-        // Convert MergedChain to PlaneSweepMapping - same as regular mappings
-        let plane_sweep_mappings: Vec<(PlaneSweepMapping, String, String)> = chains
-            .iter()
-            .enumerate()
-            .map(|(idx, chain)| {
-                let mapping = PlaneSweepMapping {
-                    idx,
-                    query_start: chain.query_start,
-                    query_end: chain.query_end,
-                    target_start: chain.target_start,
-                    target_end: chain.target_end,
-                    identity: chain.weighted_identity, // Use weighted identity for scoring
-                    flags: 0,
-                };
-                let query_group = chain.query_name.clone();
-                let target_group = chain.target_name.clone();
-                (mapping, query_group, target_group)
-            })
-            .collect();
+    /// An internal line in output coordinates, with our annotation tags
+    fn output_line(
+        &self,
+        mut line: String,
+        meta: &RecordMeta,
+        component: Option<usize>,
+    ) -> Result<String> {
+        if self.minus_strand_coords {
+            line = reflect_reverse_target_coords(&line)?;
+        }
+        if self.output_coord_base != 0 {
+            line = shift_start_coords(&line, i64::from(self.output_coord_base))?;
+        }
 
-        // Get the scaffold filter params (vs mapping filter params)
-        let overlap_threshold = self.config.scaffold_overlap_threshold;
+        // Add our annotations as tags
+        if let Some(ref chain_id) = meta.chain_id {
+            line.push_str(&format!("\tch:Z:{chain_id}"));
+        }
+        // Output status tag like wfmash does
+        let status_str = match meta.chain_status {
+            ChainStatus::Scaffold => "scaffold",
+            ChainStatus::Rescued => "rescued",
+            ChainStatus::Unassigned => "unassigned",
+        };
+        line.push_str(&format!("\tst:Z:{status_str}"));
+        if let Some(component) = component {
+            line.push_str(&format!("\tcc:i:{component}"));
+        }
+        if let Some(ref inversions) = meta.inversions {
+            line.push_str(&format!("\tiv:Z:{inversions}"));
+        }
+        if let (true, Some(sweep_rank)) = (self.tag_sweep_rank, meta.sweep_rank) {
+            line.push_str(&format!("\tsr:i:{sweep_rank}"));
+        }
+        if self.mark_secondary {
+            // Replace the aligner's own type tag (minimap2 writes tp:A:)
+            line = line
+                .split('\t')
+                .filter(|field| !field.starts_with("tp:A:"))
+                .collect::<Vec<_>>()
+                .join("\t");
+            line.push_str(if meta.secondary {
+                "\ttp:A:S"
+            } else {
+                "\ttp:A:P"
+            });
+        }
+        Ok(line)
+    }
 
-        // Apply plane sweep - EXACT SAME FUNCTION as regular mappings
-        // Always use independent query and target sweeps
-        use crate::plane_sweep_exact::{plane_sweep_query, plane_sweep_target};
+    fn write_filtered_output<P: AsRef<Path>>(
+        &self,
+        input_path: P,
+        output_path: P,
+        passing: HashMap<usize, RecordMeta>,
+        names: &SequenceIndex,
+        trims: &HashMap<usize, (u64, u64)>,
+        first_pass: InputFingerprint,
+    ) -> Result<usize> {
+        let _profile = crate::profile::scope("write");
+        let input_path = input_path.as_ref();
+        let output_path = output_path.as_ref();
+        let output_file = File::create(output_path)?;
+        let mut writer = BufWriter::new(output_file);
+        let mut reverse_writer = self.create_reverse_writer()?;
 
-        let kept_indices: Vec<usize> = match self.config.scaffold_filter_mode {
-            FilterMode::OneToOne => {
-                // True 1:1: Apply plane sweep independently on query and target axes
-                // 1. Sweep across each query chromosome (scaffolds to different targets compete)
-                // 2. Sweep across each target chromosome (scaffolds from different queries compete)
-                // 3. Keep only scaffolds that pass BOTH sweeps
+        // Scaffolds_only mode is now handled the same as normal mode
+        // since we're returning actual mappings, not synthetic records
 
-                // Query axis sweep: group by query chr
-                let mut query_kept_set = HashSet::new();
-                let mut by_query: std::collections::HashMap<String, Vec<usize>> =
-                    std::collections::HashMap::new();
+        // Normal mode - read input and filter
+        let reader = open_paf_input(input_path)?;
+        let mut written = 0;
+        // Members of each output chain, for --chain (unchained records stand alone)
+        let mut chains: IndexMap<String, Vec<ChainMember>> = IndexMap::new();
+        // Output lines of each chain with its total matches, for --group-by-chain
+        let mut grouped: IndexMap<String, (u64, Vec<(char, String)>)> = IndexMap::new();
+        // Output lines with their records in input order, for --sort-output
+        let mut sorted: Vec<(&RecordMeta, String)> = Vec::new();
+        // Members and output lines of each scaffold chain, for --scaffold-bundle
+        let mut bundles: IndexMap<String, Bundle> = IndexMap::new();
+        // 0-based output records, for --axt
+        let mut axt_lines: Vec<String> = Vec::new();
+        let components = if self.component_tag {
+            self.genome_components(&passing, names)
+        } else {
+            HashMap::new()
+        };
 
-                for (i, (_, q, _t)) in plane_sweep_mappings.iter().enumerate() {
-                    by_query.entry(q.clone()).or_default().push(i);
+        let mut fingerprint = InputFingerprint::default();
+        let mut in_header = self.preserve_headers;
+        for (rank, line) in reader.lines().enumerate() {
+            let mut line = line?;
+            fingerprint.add(&line);
+            // Skipped-pair markers always pass through, so the pairs read as
+            // intentionally empty rather than lost
+            if crate::paf::skipped_pair(&line).is_some() {
+                writeln!(writer, "{line}")?;
+                if let Some(ref mut reverse_writer) = reverse_writer {
+                    writeln!(reverse_writer, "{line}")?;
                 }
-
-                for (_q_chr, indices) in by_query {
-                    let mut query_mappings: Vec<_> =
-                        indices.iter().map(|&i| plane_sweep_mappings[i].0).collect();
-
-                    let kept = plane_sweep_query(
-                        &mut query_mappings,
-                        1,
-                        overlap_threshold,
-                        self.config.scoring_function,
-                    );
-                    for k in kept {
-                        query_kept_set.insert(indices[k]);
+                continue;
+            }
+            // Leading header lines pass through untouched with --preserve-headers
+            if in_header {
+                if crate::paf::is_header_line(&line) {
+                    writeln!(writer, "{line}")?;
+                    if let Some(ref mut reverse_writer) = reverse_writer {
+                        writeln!(reverse_writer, "{line}")?;
                     }
+                    continue;
                 }
-
-                // Target axis sweep: group by target chr
-                let mut target_kept_set = HashSet::new();
-                let mut by_target: std::collections::HashMap<String, Vec<usize>> =
-                    std::collections::HashMap::new();
-
-                for (i, (_, _q, t)) in plane_sweep_mappings.iter().enumerate() {
-                    by_target.entry(t.clone()).or_default().push(i);
+                in_header = false;
+            }
+            if let Some(meta) = passing.get(&rank) {
+                line = self.internal_line(line, trims.get(&rank).copied())?;
+                let chain_key = meta
+                    .chain_id
+                    .clone()
+                    .unwrap_or_else(|| format!("record_{rank}"));
+                let member = if self.chain_output.is_some() || self.scaffold_bundle.is_some() {
+                    Some(ChainMember::from_paf_line(&line)?)
+                } else {
+                    None
+                };
+                if let (Some(_), Some(member)) = (&self.chain_output, &member) {
+                    chains
+                        .entry(chain_key.clone())
+                        .or_default()
+                        .push(member.clone());
                 }
+                if self.axt_output.is_some() {
+                    axt_lines.push(line.clone());
+                }
+                line = self.output_line(line, meta, components.get(&rank).copied())?;
 
-                for (_t_chr, indices) in by_target {
-                    let mut target_mappings: Vec<_> =
-                        indices.iter().map(|&i| plane_sweep_mappings[i].0).collect();
-
-                    let kept = plane_sweep_target(
-                        &mut target_mappings,
-                        1,
-                        overlap_threshold,
-                        self.config.scoring_function,
-                    );
-                    for k in kept {
-                        target_kept_set.insert(indices[k]);
-                    }
+                if let (Some(_), Some(chain_id), Some(member)) =
+                    (&self.scaffold_bundle, &meta.chain_id, member)
+                {
+                    bundles
+                        .entry(chain_id.clone())
+                        .or_default()
+                        .push(member, line.clone());
+                }
+                if self.group_by_chain {
+                    let group = grouped.entry(chain_key).or_default();
+                    group.0 += meta.matches;
+                    group.1.push((meta.strand, line));
+                } else if self.sort_output.is_some() {
+                    sorted.push((meta, line));
+                } else {
+                    write_stranded(&mut writer, &mut reverse_writer, meta.strand, &line)?;
                 }
+                written += 1;
+            }
+        }
 
-                // Intersection: keep only indices that passed both sweeps
-                query_kept_set
-                    .intersection(&target_kept_set)
-                    .copied()
-                    .collect()
+        // Ranks index lines of the first pass; if the file changed in between
+        // (e.g. on a networked filesystem) they annotated the wrong records, so
+        // remove what was written and leave the side outputs alone
+        if fingerprint != first_pass {
+            drop(writer);
+            drop(reverse_writer);
+            let _ = std::fs::remove_file(output_path);
+            if let Some(ref path) = self.reverse_output {
+                let _ = std::fs::remove_file(path);
             }
-            FilterMode::OneToMany | FilterMode::ManyToMany => {
-                // Same independent sweep pattern as 1:1, just different limits
-                let query_limit = match self.config.scaffold_filter_mode {
-                    FilterMode::OneToMany => self.config.scaffold_max_per_query.unwrap_or(1),
-                    FilterMode::ManyToMany => {
-                        self.config.scaffold_max_per_query.unwrap_or(usize::MAX)
-                    }
-                    _ => unreachable!(),
-                };
+            anyhow::bail!(
+                "Input {} changed between the two filtering passes (read {} lines, then {} \
+                 with different contents); re-run on a stable copy of the input",
+                input_path.display(),
+                first_pass.lines,
+                fingerprint.lines
+            );
+        }
 
-                let target_limit = match self.config.scaffold_filter_mode {
-                    FilterMode::OneToMany => {
-                        self.config.scaffold_max_per_target.unwrap_or(usize::MAX)
-                    }
-                    FilterMode::ManyToMany => {
-                        self.config.scaffold_max_per_target.unwrap_or(usize::MAX)
-                    }
-                    _ => unreachable!(),
-                };
+        // Stable sort: equally scored chains keep the order of their first member
+        let mut groups: Vec<(u64, Vec<(char, String)>)> = grouped.into_values().collect();
+        groups.sort_by(|a, b| b.0.cmp(&a.0));
+        for (strand, line) in groups.into_iter().flat_map(|(_, lines)| lines) {
+            write_stranded(&mut writer, &mut reverse_writer, strand, &line)?;
+        }
+        if let Some(sort) = self.sort_output {
+            self.sort_records(&mut sorted, sort, names);
+            for (meta, line) in sorted {
+                write_stranded(&mut writer, &mut reverse_writer, meta.strand, &line)?;
+            }
+        }
 
-                // Query axis sweep: group by query chr
-                let mut query_kept_set = HashSet::new();
-                let mut by_query: std::collections::HashMap<String, Vec<usize>> =
-                    std::collections::HashMap::new();
+        writer.flush()?;
+        if let Some(ref mut reverse_writer) = reverse_writer {
+            reverse_writer.flush()?;
+        }
 
-                for (i, (_, q, _t)) in plane_sweep_mappings.iter().enumerate() {
-                    by_query.entry(q.clone()).or_default().push(i);
-                }
+        if let Some(ref path) = self.chain_output {
+            let chains: Vec<Vec<ChainMember>> = chains.into_values().collect();
+            let count = crate::chain_format::write_chain_file(path, &chains)?;
+            log::info!("[sweepga] Wrote {count} chains to {}", path.display());
+        }
+        if let Some(ref path) = self.axt_output {
+            let count = crate::axt::write_axt_file(path, &axt_lines, &self.axt_sequences)?;
+            log::info!("[sweepga] Wrote {count} AXT blocks to {}", path.display());
+        }
+        if let Some(ref dir) = self.scaffold_bundle {
+            let bundles: Vec<(String, Bundle)> = bundles.into_iter().collect();
+            let count = crate::scaffold_bundle::write_bundle_dir(dir, &bundles)?;
+            log::info!(
+                "[sweepga] Wrote {count} scaffold bundles to {}",
+                dir.display()
+            );
+        }
 
-                for (_q_chr, indices) in by_query {
-                    let mut query_mappings: Vec<_> =
-                        indices.iter().map(|&i| plane_sweep_mappings[i].0).collect();
+        if self.validate_output {
+            self.validate_written_output(output_path, &passing, names, trims)?;
+        }
 
-                    let kept = plane_sweep_query(
-                        &mut query_mappings,
-                        query_limit,
-                        overlap_threshold,
-                        self.config.scoring_function,
-                    );
-                    for k in kept {
-                        query_kept_set.insert(indices[k]);
-                    }
-                }
+        Ok(written)
+    }
 
-                // Target axis sweep: group by target chr
-                let mut target_kept_set = HashSet::new();
-                let mut by_target: std::collections::HashMap<String, Vec<usize>> =
-                    std::collections::HashMap::new();
+    /// Check the written output (and its --split-strand reverse file)
+    /// against `passing`: every record must carry the coordinates of a
+    /// passing input record, no input record may appear twice and none may
+    /// be missing. Records clipped by `trims` are matched on their new query
+    /// span only.
+    fn validate_written_output(
+        &self,
+        output_path: &Path,
+        passing: &HashMap<usize, RecordMeta>,
+        names: &SequenceIndex,
+        trims: &HashMap<usize, (u64, u64)>,
+    ) -> Result<()> {
+        // Passing ranks by (query, query start, query end, strand, target)
+        type RecordKey<'a> = (&'a str, u64, u64, char, &'a str);
+        let mut expected: HashMap<RecordKey, Vec<usize>> = HashMap::new();
+        for (&rank, meta) in passing {
+            let (query_start, query_end) = trims
+                .get(&rank)
+                .copied()
+                .unwrap_or((meta.query_start, meta.query_end));
+            let key = (
+                names.name(meta.query_id),
+                query_start,
+                query_end,
+                meta.strand,
+                names.name(meta.target_id),
+            );
+            expected.entry(key).or_default().push(rank);
+        }
+        for ranks in expected.values_mut() {
+            ranks.sort_unstable();
+        }
 
-                for (i, (_, _q, t)) in plane_sweep_mappings.iter().enumerate() {
-                    by_target.entry(t.clone()).or_default().push(i);
+        let mut seen: HashSet<usize> = HashSet::new();
+        for path in std::iter::once(output_path).chain(self.reverse_output.as_deref()) {
+            for (index, line) in open_paf_input(path)?.lines().enumerate() {
+                let line = line?;
+                if line.is_empty() || crate::paf::is_header_line(&line) {
+                    continue;
                 }
+                let location = format!("{}:{}", path.display(), index + 1);
+                // Undo the output coordinate conventions
+                let mut internal = line;
+                if self.output_coord_base != 0 {
+                    internal = shift_start_coords(&internal, -i64::from(self.output_coord_base))?;
+                }
+                if self.minus_strand_coords {
+                    internal = reflect_reverse_target_coords(&internal)?;
+                }
+                let fields: Vec<&str> = internal.split('\t').collect();
+                if let Some(reason) = malformed_reason(&fields) {
+                    anyhow::bail!("--validate-output: record at {location}: {reason}");
+                }
+                let coord = |i: usize| fields[i].parse::<u64>().unwrap_or_default();
+                let strand = fields[4].chars().next().unwrap_or('+');
+                let key = (fields[0], coord(2), coord(3), strand, fields[5]);
+                let target_span = (coord(7), coord(8));
+                let record = format!(
+                    "{}:{}-{} {strand} {}:{}-{}",
+                    key.0, key.1, key.2, key.4, target_span.0, target_span.1
+                );
 
-                for (_t_chr, indices) in by_target {
-                    let mut target_mappings: Vec<_> =
-                        indices.iter().map(|&i| plane_sweep_mappings[i].0).collect();
-
-                    let kept = plane_sweep_target(
-                        &mut target_mappings,
-                        target_limit,
-                        overlap_threshold,
-                        self.config.scoring_function,
+                let Some(ranks) = expected.get(&key) else {
+                    anyhow::bail!(
+                        "--validate-output: record at {location} ({record}) matches no passing \
+                         input record"
                     );
-                    for k in kept {
-                        target_kept_set.insert(indices[k]);
+                };
+                let matching: Vec<usize> = ranks
+                    .iter()
+                    .copied()
+                    .filter(|rank| {
+                        let meta = &passing[rank];
+                        trims.contains_key(rank)
+                            || (meta.target_start, meta.target_end) == target_span
+                    })
+                    .collect();
+                match matching.iter().find(|rank| !seen.contains(rank)) {
+                    Some(&rank) => {
+                        seen.insert(rank);
                     }
+                    None if !matching.is_empty() => anyhow::bail!(
+                        "--validate-output: record at {location} ({record}) repeats input \
+                         line {}",
+                        matching[0] + 1
+                    ),
+                    None => anyhow::bail!(
+                        "--validate-output: record at {location} ({record}) has other target \
+                         coordinates than input line {}",
+                        ranks[0] + 1
+                    ),
                 }
-
-                // Intersection: keep only indices that passed both sweeps
-                query_kept_set
-                    .intersection(&target_kept_set)
-                    .copied()
-                    .collect()
             }
-        };
-
-        // Convert back to MergedChain
-        let result: Vec<MergedChain> = kept_indices
-            .iter()
-            .map(|&idx| chains[idx].clone())
-            .collect();
+        }
 
-        Ok(result)
+        if let Some(missing) = passing.keys().filter(|rank| !seen.contains(rank)).min() {
+            anyhow::bail!(
+                "--validate-output: wrote {} records but {} passed the filters; input line {} \
+                 is missing",
+                seen.len(),
+                passing.len(),
+                missing + 1
+            );
+        }
+        Ok(())
     }
+}
 
-    /// DEPRECATED - Now using unified apply_scaffold_plane_sweep
-    #[allow(dead_code)]
-    fn scaffold_one_to_one_filter(&self, chains: Vec<MergedChain>) -> Result<Vec<MergedChain>> {
-        use crate::plane_sweep_exact::{plane_sweep_both, PlaneSweepMapping};
+/// Why the tab-split PAF line `fields` is not a readable record, if it isn't:
+/// fewer than 11 columns, or a length, coordinate, match or block length
+/// column that is not a non-negative integer
+fn malformed_reason(fields: &[&str]) -> Option<String> {
+    if fields.len() < 11 {
+        return Some(format!(
+            "expected at least 11 tab-separated columns, found {}",
+            fields.len()
+        ));
+    }
+    [1, 2, 3, 6, 7, 8, 9, 10]
+        .into_iter()
+        .find(|&i| fields[i].parse::<u64>().is_err())
+        .map(|i| format!("column {} is not a number: '{}'", i + 1, fields[i]))
+}
 
-        // Group by (query_chr, target_chr) pairs
-        let mut by_chr_pair: HashMap<(String, String), Vec<MergedChain>> = HashMap::new();
+/// Why the 0-based `start`-`end` range on a `side` sequence of `len` bases
+/// is impossible, if it is: inverted, or running past the sequence end
+fn inconsistent_range(side: &str, start: u64, end: u64, len: u64) -> Option<String> {
+    if start > end {
+        Some(format!("{side} start {start} is after its end {end}"))
+    } else if end > len {
+        Some(format!("{side} end {end} is past its length {len}"))
+    } else {
+        None
+    }
+}
 
-        for chain in chains {
-            let key = (chain.query_name.clone(), chain.target_name.clone());
-            by_chr_pair.entry(key).or_default().push(chain);
-        }
+/// Line count and content hash of a PAF input, checked between the two
+/// passes of [`PafFilter::filter_paf`]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+struct InputFingerprint {
+    lines: usize,
+    hash: u64,
+}
 
-        let mut filtered_chains = Vec::new();
+impl InputFingerprint {
+    fn add(&mut self, line: &str) {
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        self.hash.hash(&mut hasher);
+        line.hash(&mut hasher);
+        self.hash = hasher.finish();
+        self.lines += 1;
+    }
+}
 
-        // Apply plane sweep within each chromosome pair
-        for (_pair, pair_chains) in by_chr_pair {
-            if pair_chains.is_empty() {
-                continue;
-            }
+/// Query and target interval of a chaining candidate
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChainSpan {
+    pub query_start: u64,
+    pub query_end: u64,
+    pub target_start: u64,
+    pub target_end: u64,
+}
 
-            // Convert chains to mappings for plane sweep
-            let mut mappings: Vec<PlaneSweepMapping> = pair_chains
-                .iter()
-                .enumerate()
-                .map(|(idx, chain)| PlaneSweepMapping {
-                    idx,
-                    query_start: chain.query_start,
-                    query_end: chain.query_end,
-                    target_start: chain.target_start,
-                    target_end: chain.target_end,
-                    identity: chain.weighted_identity, // Use weighted identity for scoring
-                    flags: 0,
-                })
-                .collect();
+impl ChainSpan {
+    fn of(meta: &RecordMeta) -> Self {
+        ChainSpan {
+            query_start: meta.query_start,
+            query_end: meta.query_end,
+            target_start: meta.target_start,
+            target_end: meta.target_end,
+        }
+    }
+}
 
-            // Apply plane sweep with 1:1 filtering on both query and target axes
-            // This keeps all non-overlapping scaffolds plus best overlapping ones
-            let kept_indices = plane_sweep_both(
-                &mut mappings,
-                1, // max per query position
-                1, // max per target position
-                self.config.scaffold_overlap_threshold,
-                self.config.scoring_function,
-            );
+/// Squared distance from chain member `a` to a later member `b`, or `None`
+/// if they are more than `max_gap` apart on either axis. Overlaps of up to
+/// `max_gap / 5` count as distance; larger ones reject the pair.
+fn chaining_distance(a: &ChainSpan, b: &ChainSpan, strand: char, max_gap: u64) -> Option<u64> {
+    let gap = |end: u64, start: u64| {
+        if start >= end {
+            start - end
+        } else if end - start <= max_gap / 5 {
+            end - start
+        } else {
+            max_gap + 1
+        }
+    };
+    let q_gap = gap(a.query_end, b.query_start);
+    let r_gap = if strand == '+' {
+        gap(a.target_end, b.target_start)
+    } else {
+        gap(b.target_end, a.target_start)
+    };
+    (q_gap <= max_gap && r_gap <= max_gap).then(|| q_gap * q_gap + r_gap * r_gap)
+}
 
-            // Collect the kept chains
-            for idx in kept_indices {
-                filtered_chains.push(pair_chains[idx].clone());
+/// Best-buddy chaining of spans sorted by query start: each span links to
+/// its closest successor, unless that successor already has a closer
+/// predecessor. Returns each span's predecessor.
+///
+/// Successors are only looked for among spans starting within
+/// `[query_end - max_gap / 5, query_end + max_gap]` (binary searched), as
+/// all others fail `chaining_distance`; dense groups stay near-linear.
+pub fn best_buddy_predecessors(
+    spans: &[ChainSpan],
+    strand: char,
+    max_gap: u64,
+) -> Vec<Option<usize>> {
+    let mut best_pred_score: Vec<u64> = vec![u64::MAX; spans.len()];
+    let mut best_pred_idx: Vec<Option<usize>> = vec![None; spans.len()];
+
+    for i in 0..spans.len() {
+        let query_end = spans[i].query_end;
+        let min_start = query_end.saturating_sub(max_gap / 5);
+        let later = &spans[i + 1..];
+        let first = later.partition_point(|s| s.query_start < min_start);
+        let last = later.partition_point(|s| s.query_start <= query_end + max_gap);
+
+        let mut best_j = None;
+        let mut best_score = u64::MAX;
+        for j in (i + 1 + first)..(i + 1 + last) {
+            let Some(dist_sq) = chaining_distance(&spans[i], &spans[j], strand, max_gap) else {
+                continue;
+            };
+            // Best-buddy: only link if i is the best predecessor for j
+            if dist_sq < best_score && dist_sq < best_pred_score[j] {
+                best_score = dist_sq;
+                best_j = Some(j);
             }
         }
 
-        Ok(filtered_chains)
+        // Record best-buddy relationship for j
+        if let Some(j) = best_j {
+            best_pred_score[j] = best_score;
+            best_pred_idx[j] = Some(i);
+        }
     }
 
-    /// DEPRECATED - Now using unified apply_scaffold_plane_sweep
-    #[allow(dead_code)]
-    fn scaffold_one_to_many_filter(&self, chains: Vec<MergedChain>) -> Result<Vec<MergedChain>> {
-        use crate::plane_sweep_exact::{plane_sweep_query, PlaneSweepMapping};
-
-        // Group by (query_chr, target_chr) pairs
-        let mut by_chr_pair: HashMap<(String, String), Vec<MergedChain>> = HashMap::new();
-
-        for chain in chains {
-            let key = (chain.query_name.clone(), chain.target_name.clone());
-            by_chr_pair.entry(key).or_default().push(chain);
-        }
+    best_pred_idx
+}
 
-        let mut filtered_chains = Vec::new();
+/// Write a record to the main output, or to the reverse-strand output
+/// (`--split-strand`) if there is one and the record is on `-`
+fn write_stranded<W: Write>(
+    writer: &mut W,
+    reverse_writer: &mut Option<W>,
+    strand: char,
+    line: &str,
+) -> std::io::Result<()> {
+    match reverse_writer {
+        Some(reverse_writer) if strand == '-' => writeln!(reverse_writer, "{line}"),
+        _ => writeln!(writer, "{line}"),
+    }
+}
 
-        // Apply plane sweep within each chromosome pair
-        for (_pair, pair_chains) in by_chr_pair {
-            if pair_chains.is_empty() {
-                continue;
-            }
+/// The `percentile`th percentile (0-100) of the ascending `sorted` values,
+/// interpolated linearly between the two nearest ranks, so 50 is the median
+/// for even counts too; 0 without values. The one percentile convention for
+/// aniN identity thresholds and the reported identity percentiles.
+pub fn sorted_percentile(sorted: &[f64], percentile: f64) -> f64 {
+    if sorted.is_empty() {
+        return 0.0;
+    }
+    let rank = (sorted.len() - 1) as f64 * percentile / 100.0;
+    let (lower, upper) = (rank.floor() as usize, rank.ceil() as usize);
+    sorted[lower] + (sorted[upper] - sorted[lower]) * (rank - lower as f64)
+}
 
-            // Convert chains to mappings for plane sweep
-            let mut mappings: Vec<PlaneSweepMapping> = pair_chains
-                .iter()
-                .enumerate()
-                .map(|(idx, chain)| PlaneSweepMapping {
-                    idx,
-                    query_start: chain.query_start,
-                    query_end: chain.query_end,
-                    target_start: chain.target_start,
-                    target_end: chain.target_end,
-                    identity: chain.weighted_identity, // Use weighted identity for scoring
-                    flags: 0,
-                })
-                .collect();
+/// Percentiles (0-100, see [`sorted_percentile`]) of mapping identities, or
+/// `None` if there are no mappings
+pub fn identity_percentiles(identities: &[f64], percentiles: &[f64]) -> Option<Vec<f64>> {
+    if identities.is_empty() {
+        return None;
+    }
+    let mut sorted = identities.to_vec();
+    sorted.sort_by(|a, b| a.total_cmp(b));
+    Some(
+        percentiles
+            .iter()
+            .map(|&p| sorted_percentile(&sorted, p))
+            .collect(),
+    )
+}
 
-            // Apply plane sweep with 1:∞ filtering (limit on query axis only)
-            let max_per_query = self.config.scaffold_max_per_query.unwrap_or(1);
-            let kept_indices = plane_sweep_query(
-                &mut mappings,
-                max_per_query,
-                self.config.scaffold_overlap_threshold,
-                self.config.scoring_function,
-            );
+/// Whether `span` bases cover at least `fraction` of sequence `id`. Sequences
+/// of unknown length (0, e.g. from .1aln input) always pass.
+fn covers(names: &SequenceIndex, id: u32, span: u64, fraction: f64) -> bool {
+    match names.length(id) {
+        Some(len) if len > 0 && fraction > 0.0 => span as f64 / len as f64 >= fraction,
+        _ => true,
+    }
+}
 
-            // Collect the kept chains
-            for idx in kept_indices {
-                filtered_chains.push(pair_chains[idx].clone());
+/// Total length covered by the union of half-open intervals
+fn merged_length(mut intervals: Vec<(u64, u64)>) -> u64 {
+    intervals.sort_unstable();
+    let mut total = 0;
+    let mut current: Option<(u64, u64)> = None;
+    for (start, end) in intervals {
+        match current {
+            Some((cur_start, cur_end)) if start <= cur_end => {
+                current = Some((cur_start, cur_end.max(end)));
+            }
+            _ => {
+                if let Some((cur_start, cur_end)) = current {
+                    total += cur_end - cur_start;
+                }
+                current = Some((start, end));
             }
         }
-
-        Ok(filtered_chains)
     }
+    total + current.map_or(0, |(start, end)| end - start)
+}
 
-    /// Keep all non-overlapping chains (for scaffold filtering with -n inf behavior)
-    fn plane_sweep_keep_all_non_overlapping(
-        &self,
-        chains: Vec<MergedChain>,
-    ) -> Result<Vec<MergedChain>> {
-        // Group chains by query
-        let mut by_query: HashMap<String, Vec<MergedChain>> = HashMap::new();
-        for chain in chains {
-            by_query
-                .entry(chain.query_name.clone())
-                .or_default()
-                .push(chain);
-        }
+/// Shift the query and target start columns of a PAF line by `delta`
+/// (coordinate base conversion; ends are the same in both bases)
+fn shift_start_coords(line: &str, delta: i64) -> Result<String> {
+    let mut fields: Vec<&str> = line.split('\t').collect();
+    anyhow::ensure!(fields.len() >= 11, "PAF record has fewer than 11 columns");
+
+    let shift = |field: &str| -> Result<String> {
+        let start: u64 = field
+            .parse()
+            .with_context(|| format!("Invalid PAF start coordinate: {field}"))?;
+        start
+            .checked_add_signed(delta)
+            .map(|s| s.to_string())
+            .with_context(|| format!("Start coordinate {start} cannot be shifted by {delta}"))
+    };
+    let query_start = shift(fields[2])?;
+    let target_start = shift(fields[7])?;
+    fields[2] = &query_start;
+    fields[7] = &target_start;
 
-        let mut filtered = Vec::new();
+    Ok(fields.join("\t"))
+}
 
-        for (_query, mut query_chains) in by_query {
-            // Sort by chain score (descending) to prioritize better chains
-            let scoring = self.config.scoring_function;
-            query_chains.sort_by(|a, b| {
-                let score_a = a.score(scoring);
-                let score_b = b.score(scoring);
-                score_b
-                    .partial_cmp(&score_a)
-                    .unwrap_or(std::cmp::Ordering::Equal)
-            });
+/// Express a reverse-strand PAF line's target interval on the minus strand:
+/// `[target_len - end, target_len - start)`. Forward records are returned
+/// unchanged. Applying it twice restores the original coordinates.
+pub fn reflect_reverse_target_coords(line: &str) -> Result<String> {
+    let mut fields: Vec<&str> = line.split('\t').collect();
+    anyhow::ensure!(fields.len() >= 11, "PAF record has fewer than 11 columns");
+    if fields[4] != "-" {
+        return Ok(line.to_string());
+    }
 
-            let mut kept: Vec<MergedChain> = Vec::new();
+    let parse = |field: &str, name: &str| -> Result<u64> {
+        field
+            .parse()
+            .with_context(|| format!("Invalid PAF {name}: {field}"))
+    };
+    let target_len = parse(fields[6], "target length")?;
+    let target_start = parse(fields[7], "target start")?;
+    let target_end = parse(fields[8], "target end")?;
+    anyhow::ensure!(
+        target_start <= target_end && target_end <= target_len,
+        "Target interval {target_start}-{target_end} does not fit target length {target_len}"
+    );
+
+    let minus_start = (target_len - target_end).to_string();
+    let minus_end = (target_len - target_start).to_string();
+    fields[7] = &minus_start;
+    fields[8] = &minus_end;
+
+    Ok(fields.join("\t"))
+}
 
-            for chain in query_chains {
-                let mut has_significant_overlap = false;
+/// A configuration that filters nothing (N:N, no length, identity or scaffold cutoffs)
+fn unfiltered_config() -> FilterConfig {
+    FilterConfig {
+        chain_gap: 0,
+        min_block_length: 0,
+        mapping_filter_mode: FilterMode::ManyToMany,
+        mapping_max_per_query: None,
+        mapping_max_per_target: None,
+        plane_sweep_secondaries: 0,
+        scaffold_filter_mode: FilterMode::ManyToMany,
+        scaffold_max_per_query: None,
+        scaffold_max_per_target: None,
+        overlap_threshold: 0.95,
+        sparsity: 1.0,
+        no_merge: true,
+        scaffold_gap: 0,
+        min_scaffold_length: 0,
+        scaffold_overlap_threshold: 0.95,
+        scaffold_max_deviation: 0,
+        prefix_delimiter: '#',
+        skip_prefix: false,
+        scoring_function: ScoringFunction::LogLengthIdentity,
+        min_identity: 0.0,
+        min_scaffold_identity: 0.0,
+        ..FilterConfig::default()
+    }
+}
 
-                // Check overlap with already kept chains
-                for existing in &kept {
-                    let overlap_start = chain.query_start.max(existing.query_start);
-                    let overlap_end = chain.query_end.min(existing.query_end);
+/// Public function to extract PAF metadata without filtering (for testing/debugging),
+/// with the index of the records' sequence IDs
+#[allow(dead_code)]
+pub fn extract_metadata<P: AsRef<Path>>(path: P) -> Result<(Vec<RecordMeta>, SequenceIndex)> {
+    let filter = PafFilter::new(unfiltered_config());
+    let mut names = SequenceIndex::new();
+    let (metadata, _, _) = filter.extract_metadata(path, &mut names)?;
+    Ok((metadata, names))
+}
 
-                    if overlap_start < overlap_end {
-                        let overlap_len = overlap_end - overlap_start;
-                        let chain_len = chain.query_end - chain.query_start;
-                        let existing_len = existing.query_end - existing.query_start;
-                        let min_len = chain_len.min(existing_len);
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-                        // Use scaffold_overlap_threshold to determine significant overlap
-                        let overlap_frac = overlap_len as f64 / min_len as f64;
-                        if overlap_frac > self.config.scaffold_overlap_threshold {
-                            has_significant_overlap = true;
-                            break;
+    /// The quadratic scan that `best_buddy_predecessors` replaces, with its
+    /// original inline gap computation
+    fn best_buddy_predecessors_naive(
+        spans: &[ChainSpan],
+        strand: char,
+        max_gap: u64,
+    ) -> Vec<Option<usize>> {
+        let mut best_pred_score = vec![u64::MAX; spans.len()];
+        let mut best_pred_idx = vec![None; spans.len()];
+        for i in 0..spans.len() {
+            let search_bound = spans[i].query_end + max_gap;
+            let mut best_j = None;
+            let mut best_score = u64::MAX;
+            for j in (i + 1)..spans.len() {
+                if spans[j].query_start > search_bound {
+                    break;
+                }
+
+                let q_gap = if spans[j].query_start >= spans[i].query_end {
+                    spans[j].query_start - spans[i].query_end
+                } else {
+                    let overlap = spans[i].query_end - spans[j].query_start;
+                    if overlap <= max_gap / 5 {
+                        overlap
+                    } else {
+                        max_gap + 1
+                    }
+                };
+                let r_gap = if strand == '+' {
+                    if spans[j].target_start >= spans[i].target_end {
+                        spans[j].target_start - spans[i].target_end
+                    } else {
+                        let overlap = spans[i].target_end - spans[j].target_start;
+                        if overlap <= max_gap / 5 {
+                            overlap
+                        } else {
+                            max_gap + 1
                         }
                     }
-                }
+                } else if spans[i].target_start >= spans[j].target_end {
+                    spans[i].target_start - spans[j].target_end
+                } else {
+                    let overlap = spans[j].target_end - spans[i].target_start;
+                    if overlap <= max_gap / 5 {
+                        overlap
+                    } else {
+                        max_gap + 1
+                    }
+                };
 
-                // Keep chain if it doesn't significantly overlap with any existing
-                if !has_significant_overlap {
-                    kept.push(chain);
+                if q_gap <= max_gap && r_gap <= max_gap {
+                    let dist_sq = q_gap * q_gap + r_gap * r_gap;
+                    if dist_sq < best_score && dist_sq < best_pred_score[j] {
+                        best_score = dist_sq;
+                        best_j = Some(j);
+                    }
                 }
             }
-
-            filtered.extend(kept);
+            if let Some(j) = best_j {
+                best_pred_score[j] = best_score;
+                best_pred_idx[j] = Some(i);
+            }
         }
+        best_pred_idx
+    }
 
-        Ok(filtered)
+    /// A dense single group: short collinear hits, repeats near the diagonal
+    /// and long mappings spanning thousands of others
+    fn dense_group(n: usize) -> Vec<ChainSpan> {
+        let mut state = 0x2545_f491_4f6c_dd1du64;
+        let mut next = |bound: u64| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state % bound
+        };
+        let mut spans: Vec<ChainSpan> = (0..n as u64)
+            .map(|i| {
+                let len = if i % 500 == 0 {
+                    200_000
+                } else {
+                    500 + next(3_000)
+                };
+                let query_start = i * 300 + next(600);
+                let target_start = (query_start + next(4_000)).saturating_sub(2_000);
+                ChainSpan {
+                    query_start,
+                    query_end: query_start + len,
+                    target_start,
+                    target_end: target_start + len,
+                }
+            })
+            .collect();
+        spans.sort_by_key(|s| s.query_start);
+        spans
     }
 
-    fn plane_sweep_filter_chains(&self, chains: Vec<MergedChain>) -> Result<Vec<MergedChain>> {
-        // Group chains by query
-        let mut by_query: HashMap<String, Vec<MergedChain>> = HashMap::new();
-        for chain in chains {
-            by_query
-                .entry(chain.query_name.clone())
-                .or_default()
-                .push(chain);
+    /// PAF of every ordered pair of `genomes` three-chromosome genomes: each
+    /// chromosome maps in a chain of blocks with one inverted block, plus a
+    /// weaker paralogous hit on the next chromosome
+    fn pangenome_paf(genomes: usize) -> String {
+        let mut state = 0x9e37_79b9_7f4a_7c15u64;
+        let mut next = |bound: u64| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state % bound
+        };
+        let mut paf = String::new();
+        let mut record = |q: String, qs: u64, s: char, t: String, ts: u64, identity: f64| {
+            let matches = (10_000.0 * identity) as u64;
+            paf.push_str(&format!(
+                "{q}\t200000\t{qs}\t{}\t{s}\t{t}\t200000\t{ts}\t{}\t{matches}\t10000\t60\n",
+                qs + 10_000,
+                ts + 10_000
+            ));
+        };
+        for query in (0..genomes).rev() {
+            for target in (0..genomes).filter(|&t| t != query) {
+                for chr in 0..3 {
+                    let q = format!("g{query}#1#chr{chr}");
+                    let t = format!("g{target}#1#chr{chr}");
+                    let paralog = format!("g{target}#1#chr{}", (chr + 1) % 3);
+                    let offset = next(20_000);
+                    for block in 0..6 {
+                        let qs = block * 12_000 + next(500);
+                        let strand = if block == 3 { '-' } else { '+' };
+                        record(q.clone(), qs, strand, t.clone(), qs + offset, 0.98);
+                    }
+                    let qs = 24_000 + next(2_000);
+                    record(q.clone(), qs, '+', paralog, 100_000 + qs, 0.90);
+                }
+            }
         }
+        paf
+    }
 
-        let mut filtered = Vec::new();
-
-        for (_query, mut query_chains) in by_query {
-            // Sort by query start
-            query_chains.sort_by_key(|c| c.query_start);
-
-            // Plane sweep to remove overlaps
-            let mut kept: Vec<MergedChain> = Vec::new();
-            let scoring_func = self.config.scoring_function;
+    #[test]
+    fn test_sequence_ids_do_not_change_output() {
+        // IDs are labels only: assigning them in reverse name order instead of
+        // input order must not change anything that is written
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("in.paf");
+        let paf = pangenome_paf(16);
+        std::fs::write(&input, &paf).unwrap();
+
+        let mut config = unfiltered_config();
+        config.mapping_filter_mode = FilterMode::OneToOne;
+        config.scaffold_filter_mode = FilterMode::OneToOne;
+        config.scaffold_gap = 20_000;
+        config.min_scaffold_length = 15_000;
+        config.scaffold_max_deviation = 50_000;
+        let filter = |tag: &str| {
+            PafFilter::new(config.clone())
+                .with_component_tag(true)
+                .with_breakpoints_report(Some(dir.path().join(format!("{tag}.breakpoints"))))
+        };
+        let run = |tag: &str, mut names: SequenceIndex| {
+            let filter = filter(tag);
+            let output = dir.path().join(format!("{tag}.paf"));
+            let (metadata, fingerprint, _) = filter.extract_metadata(&input, &mut names).unwrap();
+            let (passing, _) = filter.apply_filters(metadata, &names).unwrap();
+            filter
+                .write_filtered_output(
+                    &input,
+                    &output,
+                    passing,
+                    &names,
+                    &HashMap::new(),
+                    fingerprint,
+                )
+                .unwrap();
+            let read = |ext: &str| {
+                std::fs::read_to_string(dir.path().join(format!("{tag}.{ext}"))).unwrap()
+            };
+            (read("paf"), read("breakpoints"))
+        };
 
-            for chain in query_chains {
-                // Check overlap with already kept chains
-                let mut has_significant_overlap = false;
+        let (in_order, in_order_breakpoints) = run("in_order", SequenceIndex::new());
+        let mut sequences: Vec<&str> = paf
+            .lines()
+            .flat_map(|line| {
+                let fields: Vec<&str> = line.split('\t').collect();
+                [fields[0], fields[5]]
+            })
+            .collect();
+        sequences.sort_unstable();
+        sequences.dedup();
+        let mut reversed = SequenceIndex::new();
+        for name in sequences.iter().rev() {
+            reversed.get_or_insert(name);
+        }
+        let (reordered, reordered_breakpoints) = run("reordered", reversed);
+
+        let kept = in_order.lines().count();
+        assert!(
+            kept > 0 && kept < paf.lines().count(),
+            "kept {kept} records"
+        );
+        assert_eq!(in_order, reordered);
+        assert_eq!(in_order_breakpoints, reordered_breakpoints);
+
+        let output = dir.path().join("filter_paf.paf");
+        filter("filter_paf").filter_paf(&input, &output).unwrap();
+        assert_eq!(std::fs::read_to_string(&output).unwrap(), in_order);
+    }
 
-                kept.retain(|existing| {
-                    let overlap_start = chain.query_start.max(existing.query_start);
-                    let overlap_end = chain.query_end.min(existing.query_end);
+    #[test]
+    fn test_identity_percentiles() {
+        // 0, 0.01, ..., 1.00 in shuffled order
+        let identities: Vec<f64> = (0..101).map(|i| ((i * 37) % 101) as f64 / 100.0).collect();
+        let percentiles =
+            identity_percentiles(&identities, &[0.0, 10.0, 50.0, 90.0, 100.0]).unwrap();
+        for (got, expected) in percentiles.into_iter().zip([0.0, 0.1, 0.5, 0.9, 1.0]) {
+            assert!((got - expected).abs() < 1e-12, "{got} != {expected}");
+        }
+        assert_eq!(
+            identity_percentiles(&[0.95], &[10.0, 90.0]),
+            Some(vec![0.95, 0.95])
+        );
+        assert_eq!(identity_percentiles(&[], &[50.0]), None);
+
+        // Between ranks the percentile is interpolated: the median of an
+        // even count is the mean of the middle two
+        assert_eq!(sorted_percentile(&[0.9, 0.94, 0.96, 0.99], 50.0), 0.95);
+        assert_eq!(sorted_percentile(&[0.9, 0.95, 0.99], 25.0), 0.925);
+        assert_eq!(sorted_percentile(&[], 50.0), 0.0);
+    }
 
-                    if overlap_start < overlap_end {
-                        let overlap_len = overlap_end - overlap_start;
-                        let chain_len = chain.query_end - chain.query_start;
-                        let existing_len = existing.query_end - existing.query_start;
-                        let min_len = chain_len.min(existing_len);
+    fn mapping(rank: usize, target_start: u64, identity: f64) -> RecordMeta {
+        RecordMeta {
+            rank,
+            query_id: 0,
+            target_id: 1,
+            query_start: 0,
+            query_end: 20_000,
+            target_start,
+            target_end: target_start + 20_000,
+            block_length: 20_000,
+            identity,
+            gap_compressed_identity: identity,
+            mapq: 60,
+            matches: (identity * 20_000.0) as u64,
+            alignment_length: 20_000,
+            strand: '+',
+            chain_id: None,
+            chain_status: ChainStatus::Unassigned,
+            discard: false,
+            overlapped: false,
+            sweep_rank: None,
+            secondary: false,
+            inversions: None,
+        }
+    }
 
-                        // If >50% overlap (wfmash uses scaffold_overlap_threshold)
-                        if overlap_len as f64 / min_len as f64
-                            > self.config.scaffold_overlap_threshold
-                        {
-                            // Keep the better scoring chain
-                            if existing.score(scoring_func) > chain.score(scoring_func) {
-                                has_significant_overlap = true;
-                                true // Keep existing
-                            } else {
-                                false // Remove existing, will add current
-                            }
-                        } else {
-                            true // No significant overlap, keep both
-                        }
-                    } else {
-                        true // No overlap, keep
-                    }
-                });
+    #[test]
+    fn test_no_plane_sweep_keeps_raw_mappings_for_scaffolding() {
+        let mut config = unfiltered_config();
+        config.mapping_filter_mode = FilterMode::OneToOne;
+        config.scaffold_gap = 50_000;
+        // A query region hitting the target twice (a paralog at 100 kb)
+        let metadata = vec![mapping(0, 0, 0.99), mapping(1, 100_000, 0.95)];
+        let mut names = SequenceIndex::new();
+        names.get_or_insert("a#1#chr1");
+        names.get_or_insert("b#1#chr1");
+
+        let swept = PafFilter::new(config.clone())
+            .scaffolding_input(metadata.clone(), &names)
+            .unwrap();
+        let raw = PafFilter::new(config)
+            .with_no_plane_sweep(true)
+            .scaffolding_input(metadata, &names)
+            .unwrap();
+        assert_eq!(swept.iter().map(|m| m.rank).collect::<Vec<_>>(), vec![0]);
+        assert_eq!(raw.iter().map(|m| m.rank).collect::<Vec<_>>(), vec![0, 1]);
+    }
 
-                if !has_significant_overlap {
-                    kept.push(chain);
-                }
+    #[test]
+    fn test_apply_filters_reports_stage_counts() {
+        let mut config = unfiltered_config();
+        config.mapping_filter_mode = FilterMode::OneToOne;
+        config.scaffold_gap = 50_000;
+        config.scaffold_max_deviation = 150_000;
+        // The paralog at 100 kb loses the plane sweep but is rescued near
+        // the scaffold built from the first mapping
+        let metadata = vec![mapping(0, 0, 0.99), mapping(1, 100_000, 0.95)];
+        let mut names = SequenceIndex::new();
+        names.get_or_insert("a#1#chr1");
+        names.get_or_insert("b#1#chr1");
+
+        let (passing, stats) = PafFilter::new(config)
+            .apply_filters(metadata, &names)
+            .unwrap();
+        assert_eq!(passing.len(), 2);
+        assert_eq!(
+            stats,
+            FilterStats {
+                plane_sweep_before: 2,
+                plane_sweep_after: 1,
+                chains_merged: 1,
+                chains_after_length_filter: 1,
+                scaffolds: 1,
+                anchors: 1,
+                rescued: 1,
+                output_mappings: 2,
+                output_mb: 0.04,
+                avg_identity: stats.avg_identity,
+                identity_percentiles: stats.identity_percentiles,
+                malformed_lines: 0,
+                scaffold_bundles: vec![],
             }
-
-            filtered.extend(kept);
+        );
+        assert!((stats.avg_identity - 0.97).abs() < 1e-9);
+        let percentiles = stats.identity_percentiles.unwrap();
+        for (got, expected) in percentiles.into_iter().zip([0.954, 0.97, 0.986]) {
+            assert!((got - expected).abs() < 1e-9, "{got} != {expected}");
         }
+    }
 
-        Ok(filtered)
+    #[test]
+    fn test_no_rescue_keeps_anchors_only() {
+        let mut config = unfiltered_config();
+        config.mapping_filter_mode = FilterMode::OneToOne;
+        config.scaffold_gap = 50_000;
+        config.scaffold_max_deviation = 150_000;
+        // The paralog at 100 kb would be rescued (see above) but for --no-rescue
+        let metadata = vec![mapping(0, 0, 0.99), mapping(1, 100_000, 0.95)];
+        let mut names = SequenceIndex::new();
+        names.get_or_insert("a#1#chr1");
+        names.get_or_insert("b#1#chr1");
+
+        let (passing, stats) = PafFilter::new(config)
+            .with_no_rescue(true)
+            .apply_filters(metadata, &names)
+            .unwrap();
+        assert_eq!(passing.keys().copied().collect::<Vec<_>>(), vec![0]);
+        assert_eq!(passing[&0].chain_status, ChainStatus::Scaffold);
+        assert_eq!((stats.anchors, stats.rescued), (1, 0));
     }
 
-    /// Apply final filtering mode (1:1, 1:N, N:N)
-    fn apply_filtering_mode(&self, metadata: Vec<RecordMeta>) -> Result<Vec<RecordMeta>> {
-        match self.config.mapping_filter_mode {
-            FilterMode::OneToOne => self.one_to_one_filter(metadata),
-            FilterMode::OneToMany => self.one_to_many_filter(metadata),
-            FilterMode::ManyToMany => self.many_to_many_filter(metadata),
-        }
+    #[test]
+    fn test_no_rescue_matches_zero_scaffold_dist() {
+        let mut config = unfiltered_config();
+        config.mapping_filter_mode = FilterMode::OneToOne;
+        config.scaffold_gap = 50_000;
+        config.scaffold_max_deviation = 150_000;
+        let metadata = vec![
+            mapping(0, 0, 0.99),
+            mapping(1, 100_000, 0.95),
+            mapping(2, 400_000, 0.97),
+        ];
+        let mut names = SequenceIndex::new();
+        names.get_or_insert("a#1#chr1");
+        names.get_or_insert("b#1#chr1");
+
+        let kept = |filter: PafFilter| {
+            let (passing, stats) = filter.apply_filters(metadata.clone(), &names).unwrap();
+            let mut ranks: Vec<usize> = passing.keys().copied().collect();
+            ranks.sort_unstable();
+            (ranks, stats.anchors, stats.rescued)
+        };
+        let no_rescue = kept(PafFilter::new(config.clone()).with_no_rescue(true));
+        config.scaffold_max_deviation = 0;
+        assert_eq!(no_rescue, kept(PafFilter::new(config)));
     }
 
-    /// One-to-one filtering
-    fn one_to_one_filter(&self, metadata: Vec<RecordMeta>) -> Result<Vec<RecordMeta>> {
-        let mut best_per_query: HashMap<String, RecordMeta> = HashMap::new();
+    #[test]
+    fn test_min_scaffold_count_drops_single_mapping_chains() {
+        let mut config = unfiltered_config();
+        config.scaffold_gap = 50_000;
+        // Two collinear mappings to b, one lone mapping to c
+        let mut second = mapping(1, 25_000, 0.99);
+        (second.query_start, second.query_end) = (25_000, 45_000);
+        let mut lone = mapping(2, 0, 0.99);
+        lone.target_id = 2;
+        let metadata = vec![mapping(0, 0, 0.99), second, lone];
+        let mut names = SequenceIndex::new();
+        names.get_or_insert("a#1#chr1");
+        names.get_or_insert("b#1#chr1");
+        names.get_or_insert("c#1#chr1");
+
+        let (_, stats) = PafFilter::new(config.clone())
+            .apply_filters(metadata.clone(), &names)
+            .unwrap();
+        assert_eq!(stats.chains_after_length_filter, 2);
+
+        config.min_scaffold_count = 2;
+        let (passing, stats) = PafFilter::new(config)
+            .apply_filters(metadata, &names)
+            .unwrap();
+        assert_eq!(stats.chains_after_length_filter, 1);
+        let mut ranks: Vec<usize> = passing.into_keys().collect();
+        ranks.sort_unstable();
+        assert_eq!(ranks, vec![0, 1]);
+    }
 
-        // Find best mapping per query
-        for meta in metadata {
-            best_per_query
-                .entry(meta.query_name.clone())
-                .and_modify(|e| {
-                    if meta.block_length > e.block_length {
-                        *e = meta.clone();
-                    }
-                })
-                .or_insert(meta);
-        }
+    #[test]
+    fn test_strand_filter_before_scaffolding() {
+        let mut config = unfiltered_config();
+        config.mapping_filter_mode = FilterMode::OneToOne;
+        config.scaffold_gap = 50_000;
+        config.scaffold_max_deviation = 150_000;
+        let mut inverted = mapping(1, 100_000, 0.95);
+        inverted.strand = '-';
+        let metadata = vec![mapping(0, 0, 0.99), inverted];
+        let mut names = SequenceIndex::new();
+        names.get_or_insert("a#1#chr1");
+        names.get_or_insert("b#1#chr1");
+
+        let kept = |strand: StrandFilter, metadata: Vec<RecordMeta>| {
+            let (passing, _) = PafFilter::new(config.clone())
+                .with_strand(strand)
+                .apply_filters(metadata, &names)
+                .unwrap();
+            let mut ranks: Vec<usize> = passing.into_keys().collect();
+            ranks.sort_unstable();
+            ranks
+        };
+        assert_eq!(kept(StrandFilter::Forward, metadata.clone()), vec![0]);
+        assert_eq!(kept(StrandFilter::Reverse, metadata.clone()), vec![1]);
+        // Nothing left on the strand: no chains to build, nothing passes
+        assert!(kept(StrandFilter::Reverse, vec![mapping(0, 0, 0.99)]).is_empty());
+    }
 
-        // From those, keep best per target
-        let mut best_per_target: HashMap<String, RecordMeta> = HashMap::new();
-        for meta in best_per_query.into_values() {
-            best_per_target
-                .entry(meta.target_name.clone())
-                .and_modify(|e| {
-                    if meta.block_length > e.block_length {
-                        *e = meta.clone();
-                    }
-                })
-                .or_insert(meta);
+    #[test]
+    fn test_rescue_metrics() {
+        // A short paralog inside the anchor's query span loses the 1:1 sweep;
+        // its center is 3 kb from the anchor's on the query, 4 kb on the target
+        let anchor = mapping(0, 0, 0.99);
+        let mut candidate = mapping(1, 9_000, 0.95);
+        candidate.query_start = 2_000;
+        candidate.query_end = 12_000;
+        candidate.target_end = 19_000;
+        candidate.block_length = 10_000;
+        let mut names = SequenceIndex::new();
+        names.get_or_insert("a#1#chr1");
+        names.get_or_insert("b#1#chr1");
+
+        // Distances: Euclidean 5 kb, Chebyshev 4 kb, query-only 3 kb
+        for (metric, max_deviation, rescued) in [
+            (RescueMetric::Euclidean, 5_000, true),
+            (RescueMetric::Euclidean, 4_500, false),
+            (RescueMetric::Chebyshev, 4_500, true),
+            (RescueMetric::Chebyshev, 3_500, false),
+            (RescueMetric::QueryOnly, 3_500, true),
+            (RescueMetric::QueryOnly, 2_500, false),
+        ] {
+            let mut config = unfiltered_config();
+            config.mapping_filter_mode = FilterMode::OneToOne;
+            config.scaffold_gap = 50_000;
+            config.scaffold_max_deviation = max_deviation;
+            let (passing, stats) = PafFilter::new(config)
+                .with_rescue_metric(metric)
+                .apply_filters(vec![anchor.clone(), candidate.clone()], &names)
+                .unwrap();
+            assert_eq!(stats.anchors, 1, "{metric:?} at {max_deviation}");
+            assert_eq!(
+                passing.contains_key(&1),
+                rescued,
+                "{metric:?} at {max_deviation}"
+            );
         }
+    }
 
-        Ok(best_per_target.into_values().collect())
+    #[test]
+    fn test_skip_prefix_sweeps_each_contig_pair() {
+        // Non-PanSN contigs whose names happen to share a `ctg#` prefix
+        let mut names = SequenceIndex::new();
+        names.get_or_insert("query");
+        names.get_or_insert("ctg#1");
+        names.get_or_insert("ctg#2");
+        let mut second = mapping(1, 0, 0.95);
+        second.target_id = 2;
+        let mappings = vec![mapping(0, 0, 0.99), second];
+
+        let kept = |skip_prefix: bool| {
+            let mut config = unfiltered_config();
+            config.mapping_filter_mode = FilterMode::OneToOne;
+            config.skip_prefix = skip_prefix;
+            let filter = PafFilter::new(config);
+            let kept = filter
+                .apply_plane_sweep_to_mappings(&mappings, &names)
+                .unwrap();
+            let mut targets: Vec<u32> = kept.iter().map(|m| m.target_id).collect();
+            targets.sort_unstable();
+            targets
+        };
+
+        // Grouped by prefix, both contigs compete for the query region
+        assert_eq!(kept(false), vec![1]);
+        // One group per contig pair: each keeps its best mapping
+        assert_eq!(kept(true), vec![1, 2]);
     }
 
-    /// One-to-many filtering
-    fn one_to_many_filter(&self, metadata: Vec<RecordMeta>) -> Result<Vec<RecordMeta>> {
-        let mut result = Vec::new();
+    #[test]
+    fn test_max_memory_streams_or_fails_fast() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("in.paf");
+        let output = dir.path().join("out.paf");
+        std::fs::write(
+            &input,
+            "q1\t10000\t0\t10000\t+\tt\t20000\t0\t10000\t9900\t10000\t60\n\
+             q2\t10000\t0\t10000\t+\tt\t20000\t0\t10000\t9900\t10000\t60\n",
+        )
+        .unwrap();
+
+        // Within budget: filtered in memory as usual
+        let filter = PafFilter::new(unfiltered_config()).with_max_memory(Some(1 << 30));
+        assert_eq!(filter.filter_paf(&input, &output).unwrap(), 2);
+
+        // Over budget, and the plane sweep needs all of a genome pair at once
+        let filter = PafFilter::new(unfiltered_config()).with_max_memory(Some(1));
+        let err = filter.filter_paf(&input, &output).unwrap_err().to_string();
+        assert!(err.contains("over --max-memory"), "{err}");
+
+        // Over budget, but each query can be filtered on its own
+        let filter = filter.with_reference_mode(true);
+        assert_eq!(filter.filter_paf(&input, &output).unwrap(), 2);
+    }
 
-        // Group by query
-        let mut by_query: HashMap<String, Vec<RecordMeta>> = HashMap::new();
-        for meta in metadata {
-            by_query
-                .entry(meta.query_name.clone())
-                .or_default()
-                .push(meta);
-        }
+    #[test]
+    fn test_validate_output_accepts_converted_coordinates() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("in.paf");
+        let output = dir.path().join("out.paf");
+        std::fs::write(
+            &input,
+            "q1\t10000\t0\t10000\t+\tt\t20000\t0\t10000\t9900\t10000\t60\n\
+             q2\t10000\t0\t10000\t-\tt\t20000\t2000\t12000\t9900\t10000\t60\n",
+        )
+        .unwrap();
+
+        let filter = PafFilter::new(unfiltered_config())
+            .with_coord_base(0, 1)
+            .with_minus_strand_coords(true)
+            .with_sort_output(Some(OutputSort::Target))
+            .with_validate_output(true);
+        assert_eq!(filter.filter_paf(&input, &output).unwrap(), 2);
+    }
 
-        // Keep best per query, optionally limit per target
-        for (_query, mut metas) in by_query {
-            metas.sort_by_key(|m| std::cmp::Reverse(m.block_length));
+    #[test]
+    fn test_validate_output_reports_mismatches() {
+        let dir = tempfile::tempdir().unwrap();
+        let output = dir.path().join("out.paf");
+        let mut names = SequenceIndex::new();
+        names.get_or_insert("q");
+        names.get_or_insert("t");
+        let passing = HashMap::from([(0, mapping(0, 0, 0.99)), (1, mapping(1, 30_000, 0.99))]);
+        let record = |target_start: u64| {
+            format!(
+                "q\t20000\t0\t20000\t+\tt\t60000\t{target_start}\t{}\t19800\t20000\t60\n",
+                target_start + 20_000
+            )
+        };
+        let filter = PafFilter::new(unfiltered_config());
+        let validate = |text: String| {
+            std::fs::write(&output, text).unwrap();
+            filter.validate_written_output(&output, &passing, &names, &HashMap::new())
+        };
 
-            if let Some(limit) = self.config.mapping_max_per_target {
-                metas.truncate(limit);
-            }
+        assert!(validate(record(30_000) + &record(0)).is_ok());
+        let err = validate(record(0) + &record(0)).unwrap_err().to_string();
+        assert!(err.contains("repeats input line 1"), "{err}");
+        let err = validate(record(0) + &record(10_000))
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("other target coordinates"), "{err}");
+        let err = validate(record(0)).unwrap_err().to_string();
+        assert!(err.contains("input line 2 is missing"), "{err}");
+    }
 
-            result.extend(metas);
-        }
+    #[test]
+    fn test_mark_secondary_keeps_swept_out_mappings() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("in.paf");
+        let output = dir.path().join("out.paf");
+        // The same query region hits t1 and, less well, t2; plus a self-mapping
+        std::fs::write(
+            &input,
+            "q\t10000\t0\t10000\t+\tt1\t20000\t0\t10000\t9900\t10000\t60\ttp:A:P\n\
+             q\t10000\t0\t10000\t+\tt2\t20000\t0\t10000\t9000\t10000\t60\ttp:A:P\n\
+             q\t10000\t0\t10000\t+\tq\t10000\t0\t10000\t10000\t10000\t60\ttp:A:P\n",
+        )
+        .unwrap();
+
+        let mut config = unfiltered_config();
+        config.mapping_filter_mode = FilterMode::OneToOne;
+        let written = PafFilter::new(config)
+            .with_mark_secondary(true)
+            .filter_paf(&input, &output)
+            .unwrap();
+        assert_eq!(written, 2);
+
+        let text = std::fs::read_to_string(&output).unwrap();
+        let types: Vec<(&str, Vec<&str>)> = text
+            .lines()
+            .map(|line| {
+                let fields: Vec<&str> = line.split('\t').collect();
+                let tags = fields.iter().filter(|f| f.starts_with("tp:A:")).copied();
+                (fields[5], tags.collect())
+            })
+            .collect();
+        assert_eq!(types, vec![("t1", vec!["tp:A:P"]), ("t2", vec!["tp:A:S"])]);
+    }
 
-        Ok(result)
+    #[test]
+    fn test_mark_secondary_skips_records_dropped_outside_the_sweeps() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("in.paf");
+        let output = dir.path().join("out.paf");
+        std::fs::write(
+            &input,
+            "q\t10000\t0\t10000\t+\tt1\t20000\t0\t10000\t9900\t10000\t60\n\
+             q\t10000\t0\t10000\t+\tt2\t20000\t0\t10000\t9000\t10000\t60\n",
+        )
+        .unwrap();
+
+        // t2 scores below 95% of t1, so the relative score filter drops it
+        // before the plane sweep sees it
+        let written = PafFilter::new(unfiltered_config())
+            .with_mark_secondary(true)
+            .with_min_relative_score(0.95)
+            .filter_paf(&input, &output)
+            .unwrap();
+        assert_eq!(written, 1);
+        let text = std::fs::read_to_string(&output).unwrap();
+        assert!(!text.contains("tp:A:S"), "{text}");
+
+        let err = PafFilter::new(unfiltered_config())
+            .with_mark_secondary(true)
+            .with_max_output_bp(Some(10_000))
+            .filter_paf(&input, &output)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("--max-output-bp"), "{err}");
     }
 
-    /// Many-to-many filtering
-    fn many_to_many_filter(&self, metadata: Vec<RecordMeta>) -> Result<Vec<RecordMeta>> {
-        // For scaffold filtering, we typically don't need additional limiting
-        // but we can apply per-query/target limits if specified
+    #[test]
+    fn test_overlap_axis_selects_sweeps() {
+        let mut names = SequenceIndex::new();
+        names.get_or_insert("a#1#q1");
+        names.get_or_insert("b#1#t");
+        names.get_or_insert("a#1#q2");
+        // Rank 1 hits a paralog of rank 0's target region; rank 2, from
+        // another query, hits the same target region as rank 0
+        let mut other_query = mapping(2, 0, 0.90);
+        other_query.query_id = 2;
+        let mappings = vec![mapping(0, 0, 0.99), mapping(1, 100_000, 0.95), other_query];
+
+        let kept = |axis: OverlapAxis| {
+            let mut config = unfiltered_config();
+            config.mapping_filter_mode = FilterMode::OneToOne;
+            PafFilter::new(config)
+                .with_overlap_axis(axis)
+                .apply_plane_sweep_to_mappings(&mappings, &names)
+                .unwrap()
+                .iter()
+                .map(|m| m.rank)
+                .collect::<Vec<_>>()
+        };
 
-        if self.config.mapping_max_per_query.is_none()
-            && self.config.mapping_max_per_target.is_none()
-        {
-            return Ok(metadata);
-        }
+        assert_eq!(kept(OverlapAxis::Query), vec![0, 2]);
+        assert_eq!(kept(OverlapAxis::Target), vec![0, 1]);
+        assert_eq!(kept(OverlapAxis::Both), vec![0]);
+    }
 
-        let mut result = metadata;
+    #[test]
+    fn test_self_genome_excludes_same_sample_haplotypes() {
+        let mut names = SequenceIndex::new();
+        names.get_or_insert("HG002#1#chr1");
+        names.get_or_insert("HG002#2#chr1");
+        names.get_or_insert("HG003#1#chr1");
+        let mut haplotypes = mapping(0, 0, 0.99);
+        (haplotypes.query_id, haplotypes.target_id) = (0, 1);
+        let mut samples = mapping(1, 0, 0.99);
+        (samples.query_id, samples.target_id) = (0, 2);
+
+        let filter = PafFilter::new(unfiltered_config());
+        assert!(filter.passes_record_filters(&haplotypes, &names));
+
+        let filter = filter.with_self_genome(true);
+        assert!(!filter.passes_record_filters(&haplotypes, &names));
+        assert!(filter.passes_record_filters(&samples, &names));
+        let filter = filter.with_keep_self(true);
+        assert!(filter.passes_record_filters(&haplotypes, &names));
+    }
 
-        // Apply per-query limit
-        if let Some(limit) = self.config.mapping_max_per_query {
-            let mut by_query: HashMap<String, Vec<RecordMeta>> = HashMap::new();
-            for meta in result {
-                by_query
-                    .entry(meta.query_name.clone())
-                    .or_default()
-                    .push(meta);
-            }
+    #[test]
+    fn test_canonical_pairs_keeps_one_direction() {
+        let mut names = SequenceIndex::new();
+        names.get_or_insert("A#1#chr1");
+        names.get_or_insert("B#1#chr1");
+        names.get_or_insert("A#1#chr2");
+        let forward = mapping(0, 0, 0.99);
+        let mut reverse = mapping(1, 0, 0.99);
+        (reverse.query_id, reverse.target_id) = (1, 0);
+        let mut same_genome = mapping(2, 0, 0.99);
+        (same_genome.query_id, same_genome.target_id) = (2, 0);
+
+        let filter = PafFilter::new(unfiltered_config());
+        assert!(filter.passes_record_filters(&reverse, &names));
+
+        let filter = filter.with_canonical_pairs(true);
+        assert!(filter.passes_record_filters(&forward, &names));
+        assert!(!filter.passes_record_filters(&reverse, &names));
+        assert!(filter.passes_record_filters(&same_genome, &names));
+    }
 
-            result = Vec::new();
-            for (_query, mut metas) in by_query {
-                metas.sort_by_key(|m| std::cmp::Reverse(m.block_length));
-                metas.truncate(limit);
-                result.extend(metas);
-            }
-        }
+    #[test]
+    fn test_allow_inversions_chains_across_inverted_segment() {
+        let segment = |rank: usize, start: u64, end: u64, strand: char| {
+            let mut meta = mapping(rank, start, 0.99);
+            meta.query_start = start;
+            meta.query_end = end;
+            meta.target_end = end;
+            meta.block_length = end - start;
+            meta.strand = strand;
+            meta
+        };
+        // + 0-20 kb, - 25-35 kb, + 40-60 kb: the flanks are 20 kb apart,
+        // beyond the 10 kb gap, but each borders the inversion
+        let metadata = vec![
+            segment(0, 0, 20_000, '+'),
+            segment(1, 25_000, 35_000, '-'),
+            segment(2, 40_000, 60_000, '+'),
+        ];
+
+        let plain = PafFilter::new(unfiltered_config())
+            .merge_mappings_into_chains(&metadata, 10_000)
+            .unwrap();
+        assert_eq!(plain.len(), 3);
+
+        let joined = PafFilter::new(unfiltered_config())
+            .with_allow_inversions(true)
+            .merge_mappings_into_chains(&metadata, 10_000)
+            .unwrap();
+        assert_eq!(joined.len(), 1);
+        assert_eq!(joined[0].strand, '+');
+        assert_eq!(joined[0].member_indices.len(), 3);
+        assert_eq!(joined[0].inversions, vec![(25_000, 35_000)]);
+        assert_eq!(joined[0].inversions_tag().as_deref(), Some("25000-35000"));
+
+        // An inverted segment overlapping a flank on the query joins only
+        // within --member-overlap-tolerance
+        let mut metadata = metadata;
+        metadata[1] = segment(1, 19_500, 35_000, '-');
+        let joined_within = |tolerance: u64| {
+            PafFilter::new(unfiltered_config())
+                .with_allow_inversions(true)
+                .with_member_overlap_tolerance(tolerance)
+                .merge_mappings_into_chains(&metadata, 10_000)
+                .unwrap()
+                .len()
+                == 1
+        };
+        assert!(!joined_within(100));
+        assert!(joined_within(500));
+    }
 
-        // Apply per-target limit
-        if let Some(limit) = self.config.mapping_max_per_target {
-            let mut by_target: HashMap<String, Vec<RecordMeta>> = HashMap::new();
-            for meta in result {
-                by_target
-                    .entry(meta.target_name.clone())
-                    .or_default()
-                    .push(meta);
+    #[test]
+    fn test_collinear_matches_matches_pairwise_scan() {
+        // Heaviest collinear subset by comparing every pair of members
+        let pairwise = |members: &[RecordMeta], strand: char| {
+            let mut members: Vec<&RecordMeta> = members.iter().collect();
+            members.sort_by_key(|m| (m.query_start, m.query_end));
+            let mut best: Vec<u64> = Vec::new();
+            for (j, next) in members.iter().enumerate() {
+                let pred = (0..j)
+                    .filter(|&i| {
+                        let prev = members[i];
+                        if strand == '+' {
+                            next.target_start >= prev.target_start
+                        } else {
+                            next.target_end <= prev.target_end
+                        }
+                    })
+                    .map(|i| best[i])
+                    .max()
+                    .unwrap_or(0);
+                best.push(pred + next.matches);
             }
+            best.into_iter().max().unwrap_or(0)
+        };
 
-            result = Vec::new();
-            for (_target, mut metas) in by_target {
-                metas.sort_by_key(|m| std::cmp::Reverse(m.block_length));
-                metas.truncate(limit);
-                result.extend(metas);
-            }
+        let mut state = 42u64;
+        let mut next = |bound: u64| {
+            state = state
+                .wrapping_mul(6_364_136_223_846_793_005)
+                .wrapping_add(1_442_695_040_888_963_407);
+            (state >> 33) % bound
+        };
+        for strand in ['+', '-'] {
+            let metadata: Vec<RecordMeta> = (0..200)
+                .map(|rank| {
+                    let mut meta = mapping(rank, next(50) * 1_000, 0.9);
+                    meta.query_start = next(100) * 1_000;
+                    meta.query_end = meta.query_start + 1_000;
+                    meta.target_end = meta.target_start + 1_000;
+                    meta.matches = 1 + next(1_000);
+                    meta.strand = strand;
+                    meta
+                })
+                .collect();
+            let chain: Vec<(usize, usize)> = (0..metadata.len()).map(|i| (i, i)).collect();
+            assert_eq!(
+                PafFilter::collinear_matches(&chain, strand, &metadata),
+                pairwise(&metadata, strand),
+                "strand {strand}"
+            );
         }
+    }
 
-        Ok(result)
+    #[test]
+    fn test_malformed_lines_are_counted_or_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("in.paf");
+        std::fs::write(
+            &input,
+            "q\t100\t0\t100\t+\tt\t100\t0\t100\t100\t100\t60\n\
+             q\t100\t0\t100\t+\tt\t100\n\
+             q\t100\t0\tten\t+\tt\t100\t0\t100\t100\t100\t60\n",
+        )
+        .unwrap();
+
+        let filter = PafFilter::new(unfiltered_config());
+        let (metadata, _, malformed) = filter
+            .extract_metadata(&input, &mut SequenceIndex::new())
+            .unwrap();
+        assert_eq!((metadata.len(), malformed), (1, 2));
+
+        let err = filter
+            .with_strict(true)
+            .extract_metadata(&input, &mut SequenceIndex::new())
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("line 2: expected at least 11"), "{err}");
     }
 
-    /// Write filtered output with annotations
-    fn write_filtered_output<P: AsRef<Path>>(
-        &self,
-        input_path: P,
-        output_path: P,
-        passing: HashMap<usize, RecordMeta>,
-    ) -> Result<()> {
-        let output_file = File::create(output_path)?;
-        let mut writer = BufWriter::new(output_file);
+    #[test]
+    fn test_default_mapq_for_missing_column() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("no_mapq.paf");
+        std::fs::write(
+            &input,
+            "q1\t10000\t0\t1000\t+\tt1\t10000\t0\t1000\t950\t1000\n\
+             q1\t10000\t2000\t3000\t+\tt1\t10000\t2000\t3000\t950\t1000\t42\n",
+        )
+        .unwrap();
+
+        let mapqs = |config: FilterConfig| -> Vec<u8> {
+            let (metadata, _, _) = PafFilter::new(config)
+                .extract_metadata(&input, &mut SequenceIndex::new())
+                .unwrap();
+            metadata.iter().map(|meta| meta.mapq).collect()
+        };
+        assert_eq!(
+            mapqs(unfiltered_config()),
+            [crate::paf::MAPQ_UNAVAILABLE, 42]
+        );
+        let config = FilterConfig {
+            default_mapq: 7,
+            ..unfiltered_config()
+        };
+        assert_eq!(mapqs(config), [7, 42], "explicit MAPQ must be kept");
+    }
 
-        // Scaffolds_only mode is now handled the same as normal mode
-        // since we're returning actual mappings, not synthetic records
+    #[test]
+    fn test_inconsistent_coordinates_are_malformed() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("in.paf");
+        std::fs::write(
+            &input,
+            "q\t100\t60\t40\t+\tt\t100\t0\t20\t20\t20\t60\n\
+             q\t100\t0\t20\t+\tt\t100\t90\t110\t20\t20\t60\n",
+        )
+        .unwrap();
+
+        let filter = PafFilter::new(unfiltered_config());
+        let (metadata, _, malformed) = filter
+            .extract_metadata(&input, &mut SequenceIndex::new())
+            .unwrap();
+        assert!(metadata.is_empty());
+        assert_eq!(malformed, 2);
+
+        let err = filter
+            .with_strict(true)
+            .extract_metadata(&input, &mut SequenceIndex::new())
+            .unwrap_err()
+            .to_string();
+        assert!(
+            err.contains("line 1: query start 60 is after its end 40"),
+            "{err}"
+        );
+        assert_eq!(
+            inconsistent_range("target", 90, 110, 100).as_deref(),
+            Some("target end 110 is past its length 100")
+        );
+    }
 
-        // Normal mode - read input and filter
-        let reader = open_paf_input(input_path)?;
+    #[test]
+    fn test_scaffold_bed_lists_kept_chains() {
+        let dir = tempfile::tempdir().unwrap();
+        let bed = dir.path().join("scaffolds.bed");
+        let mut config = unfiltered_config();
+        config.scaffold_gap = 50_000;
+        // Two mappings 5 kb apart on the same diagonal merge into one chain
+        let mut second = mapping(1, 25_000, 0.97);
+        second.query_start = 25_000;
+        second.query_end = 45_000;
+        let mut names = SequenceIndex::new();
+        names.get_or_insert("a#1#chr1");
+        names.get_or_insert("b#1#chr1");
+
+        let (passing, _) = PafFilter::new(config)
+            .with_scaffold_bed(Some(bed.clone()))
+            .apply_filters(vec![mapping(0, 0, 0.99), second], &names)
+            .unwrap();
+        assert_eq!(passing[&0].chain_id.as_deref(), Some("chain_1"));
+        assert_eq!(
+            std::fs::read_to_string(&bed).unwrap(),
+            "a#1#chr1\t0\t45000\tchain_1\t45000\t+\n"
+        );
+    }
 
-        for (rank, line) in reader.lines().enumerate() {
-            if let Some(meta) = passing.get(&rank) {
-                let mut line = line?;
+    #[test]
+    fn test_chain_report_counts_anchors_and_rescued() {
+        let dir = tempfile::tempdir().unwrap();
+        let report = dir.path().join("chains.tsv");
+        let mut config = unfiltered_config();
+        config.mapping_filter_mode = FilterMode::OneToOne;
+        config.scaffold_gap = 50_000;
+        config.scaffold_max_deviation = 150_000;
+        // The paralog at 100 kb loses the plane sweep and is rescued into
+        // the chain of the first mapping
+        let metadata = vec![mapping(0, 0, 0.99), mapping(1, 100_000, 0.95)];
+        let mut names = SequenceIndex::new();
+        names.get_or_insert("a#1#chr1");
+        names.get_or_insert("b#1#chr1");
+
+        PafFilter::new(config)
+            .with_chain_report(Some(report.clone()))
+            .apply_filters(metadata, &names)
+            .unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&report).unwrap(),
+            "#chain\tquery\tquery_start\tquery_end\ttarget\ttarget_start\ttarget_end\tstrand\t\
+             anchors\trescued\tidentity\tmatches\n\
+             chain_1\ta#1#chr1\t0\t20000\tb#1#chr1\t0\t20000\t+\t1\t1\t0.9900\t19800\n"
+        );
+    }
 
-                // Add our annotations as tags
-                if let Some(ref chain_id) = meta.chain_id {
-                    line.push_str(&format!("\tch:Z:{chain_id}"));
-                }
-                // Output status tag like wfmash does
-                let status_str = match meta.chain_status {
-                    ChainStatus::Scaffold => "scaffold",
-                    ChainStatus::Rescued => "rescued",
-                    ChainStatus::Unassigned => "unassigned",
-                };
-                line.push_str(&format!("\tst:Z:{status_str}"));
+    #[test]
+    fn test_input_changed_between_passes_is_an_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("in.paf");
+        let output = dir.path().join("out.paf");
+        let record = "q\t20000\t0\t20000\t+\tt\t20000\t0\t20000\t19000\t20000\t60\n";
+        std::fs::write(&input, record).unwrap();
+
+        let filter = PafFilter::new(unfiltered_config());
+        let mut names = SequenceIndex::new();
+        let (metadata, fingerprint, _) = filter.extract_metadata(&input, &mut names).unwrap();
+        let passing: HashMap<usize, RecordMeta> =
+            metadata.into_iter().map(|m| (m.rank, m)).collect();
+        let written = filter
+            .write_filtered_output(
+                &input,
+                &output,
+                passing.clone(),
+                &names,
+                &HashMap::new(),
+                fingerprint,
+            )
+            .unwrap();
+        assert_eq!(written, 1);
+
+        // Another record lands in front of the first between the passes
+        std::fs::write(
+            &input,
+            format!("{}{record}", record.replace("19000", "15000")),
+        )
+        .unwrap();
+        let err = filter
+            .write_filtered_output(
+                &input,
+                &output,
+                passing,
+                &names,
+                &HashMap::new(),
+                fingerprint,
+            )
+            .unwrap_err();
+        assert!(err.to_string().contains("changed between"), "{err}");
+        assert!(
+            !output.exists(),
+            "output from the changed input was left behind"
+        );
+    }
 
-                writeln!(writer, "{line}")?;
+    #[test]
+    fn test_best_buddy_window_matches_naive_scan() {
+        let spans = dense_group(20_000);
+        for strand in ['+', '-'] {
+            for max_gap in [0, 1_000, 10_000] {
+                let windowed = best_buddy_predecessors(&spans, strand, max_gap);
+                let naive = best_buddy_predecessors_naive(&spans, strand, max_gap);
+                assert_eq!(windowed, naive, "strand {strand}, max_gap {max_gap}");
             }
         }
+    }
 
-        writer.flush()?;
-        Ok(())
+    #[test]
+    fn test_config_builder_defaults_and_setters() {
+        let config = FilterConfig::builder().build().unwrap();
+        assert_eq!(config.mapping_filter_mode, FilterMode::ManyToMany);
+        assert_eq!((config.chain_gap, config.scaffold_gap), (50_000, 50_000));
+        assert_eq!(config.min_scaffold_length, 10_000);
+        assert_eq!(config.scaffold_overlap_threshold, 0.5);
+
+        let config = FilterConfig::builder()
+            .num_mappings(1, 1)
+            .scaffold_filter(1, 0)
+            .scaffold_jump(10_000)
+            .min_identity(0.95)
+            .build()
+            .unwrap();
+        assert_eq!(config.mapping_filter_mode, FilterMode::OneToOne);
+        assert_eq!(config.mapping_max_per_target, Some(1));
+        assert_eq!(config.scaffold_filter_mode, FilterMode::OneToMany);
+        assert_eq!(config.scaffold_max_per_target, None);
+        assert_eq!((config.chain_gap, config.scaffold_gap), (10_000, 10_000));
+        // The scaffold identity cutoff follows the mapping one unless set
+        assert_eq!(config.min_scaffold_identity, 0.95);
     }
-}
 
-/// Public function to extract PAF metadata without filtering (for testing/debugging)
-#[allow(dead_code)]
-pub fn extract_metadata<P: AsRef<Path>>(path: P) -> Result<(Vec<RecordMeta>, ())> {
-    let config = FilterConfig {
-        chain_gap: 0,
-        min_block_length: 0,
-        mapping_filter_mode: FilterMode::ManyToMany,
-        mapping_max_per_query: None,
-        mapping_max_per_target: None,
-        plane_sweep_secondaries: 0,
-        scaffold_filter_mode: FilterMode::ManyToMany,
-        scaffold_max_per_query: None,
-        scaffold_max_per_target: None,
-        overlap_threshold: 0.95,
-        sparsity: 1.0,
-        no_merge: true,
-        scaffold_gap: 0,
-        min_scaffold_length: 0,
-        scaffold_overlap_threshold: 0.95,
-        scaffold_max_deviation: 0,
-        prefix_delimiter: '#',
-        skip_prefix: false,
-        scoring_function: ScoringFunction::LogLengthIdentity,
-        min_identity: 0.0,
-        min_scaffold_identity: 0.0,
-    };
+    #[test]
+    fn test_config_builder_rejects_invalid_settings() {
+        let err = |builder: FilterConfigBuilder| builder.build().unwrap_err().to_string();
+        assert!(err(FilterConfig::builder().min_identity(95.0)).contains("min_identity"));
+        assert!(err(FilterConfig::builder().sparsity(0.0)).contains("sparsity"));
+        // Rescue and scaffold limits do nothing without scaffolds
+        let unscaffolded = || FilterConfig::builder().scaffold_jump(0);
+        assert!(err(unscaffolded().scaffold_dist(10_000)).contains("scaffold_dist"));
+        assert!(err(unscaffolded().scaffold_filter(1, 1)).contains("scaffold_filter"));
+        assert!(unscaffolded().num_mappings(1, 1).build().is_ok());
+    }
+
+    #[test]
+    fn test_prefix_depth_comes_from_config() {
+        let filter = PafFilter::new(FilterConfig::default());
+        assert_eq!(filter.genome_of("HG002#1#chr1"), "HG002#1#");
+        let config = FilterConfig::builder().prefix_depth(1).build().unwrap();
+        assert_eq!(PafFilter::new(config).genome_of("HG002#1#chr1"), "HG002#");
+    }
 
-    let filter = PafFilter::new(config);
-    let metadata = filter.extract_metadata(path)?;
-    Ok((metadata, ()))
+    #[test]
+    fn test_gap_compressed_identity_scoring() {
+        // t1: 500 scattered mismatches; t2: ten 100 bp insertions. Block
+        // identity prefers t1 (0.95 vs 0.90), gap-compressed identity t2
+        let t1_cigar = "19=1X".repeat(500);
+        let t2_cigar = "900=100I".repeat(10);
+        assert_eq!(
+            crate::paf::gap_compressed_identity(&t1_cigar, 9500).unwrap(),
+            0.95
+        );
+        assert_eq!(
+            crate::paf::gap_compressed_identity(&t2_cigar, 9000).unwrap(),
+            9000.0 / 9010.0
+        );
+        // 'M' bases beyond the PAF matches count as mismatches
+        assert_eq!(
+            crate::paf::gap_compressed_identity("50M2D50M", 90).unwrap(),
+            90.0 / 101.0
+        );
+
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("in.paf");
+        std::fs::write(
+            &input,
+            format!(
+                "q\t10000\t0\t10000\t+\tt1\t20000\t0\t10000\t9500\t10000\t60\tcg:Z:{t1_cigar}\n\
+                 q\t10000\t0\t10000\t+\tt2\t20000\t0\t9000\t9000\t10000\t60\tcg:Z:{t2_cigar}\n"
+            ),
+        )
+        .unwrap();
+
+        let kept_target = |scoring: ScoringFunction| {
+            let mut config = unfiltered_config();
+            config.mapping_filter_mode = FilterMode::OneToOne;
+            config.mapping_max_per_query = Some(1);
+            config.scoring_function = scoring;
+            let output = dir.path().join("out.paf");
+            PafFilter::new(config)
+                .with_reference_mode(true)
+                .filter_paf(&input, &output)
+                .unwrap();
+            let text = std::fs::read_to_string(&output).unwrap();
+            let targets: Vec<String> = text
+                .lines()
+                .map(|line| line.split('\t').nth(5).unwrap().to_string())
+                .collect();
+            assert_eq!(targets.len(), 1, "{text}");
+            targets[0].clone()
+        };
+        assert_eq!(kept_target(ScoringFunction::Identity), "t1");
+        assert_eq!(kept_target(ScoringFunction::GapCompressedIdentity), "t2");
+    }
 }
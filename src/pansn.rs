@@ -21,7 +21,7 @@ use std::path::Path;
 /// Returns a boxed `BufRead` over the decompressed stream. Uses
 /// `MultiGzDecoder` so concatenated gzip members are all consumed (bgzipped
 /// FASTAs are stored this way).
-fn open_fasta(path: &Path) -> Result<Box<dyn BufRead>> {
+pub fn open_fasta(path: &Path) -> Result<Box<dyn BufRead>> {
     let file = File::open(path)
         .with_context(|| format!("Failed to open FASTA '{}'", path.display()))?;
 
@@ -43,19 +43,20 @@ pub enum PanSnLevel {
     /// counting where PanSN grouping is not desired.
     #[value(skip)]
     Sequence,
-    /// First `#`-separated segment (`SAMPLE`). Falls back to the whole
-    /// name for non-PanSN inputs.
+    /// First delimiter-separated segment (`SAMPLE`). Falls back to the
+    /// whole name for non-PanSN inputs.
     Sample,
-    /// First two `#`-separated segments (`SAMPLE#HAPLOTYPE`). Falls back
+    /// First two delimiter-separated segments (`SAMPLE#HAPLOTYPE`). Falls back
     /// to just `SAMPLE` if the second segment is missing/empty, which in
     /// turn falls back to the whole name for non-PanSN inputs.
     Haplotype,
 }
 
-/// Extract a grouping key from a sequence name at the requested level.
+/// Extract a grouping key from a sequence name at the requested level, with
+/// `delimiter` separating the PanSN fields.
 ///
 /// Returns `None` when the normalized name is empty.
-pub fn extract_pansn_key(name: &str, level: PanSnLevel) -> Option<String> {
+pub fn extract_pansn_key(name: &str, level: PanSnLevel, delimiter: char) -> Option<String> {
     let name = name.trim_start_matches('>').trim();
     let name = name.split_whitespace().next().unwrap_or("");
     let base = name.split(':').next().unwrap_or(name);
@@ -65,7 +66,7 @@ pub fn extract_pansn_key(name: &str, level: PanSnLevel) -> Option<String> {
     match level {
         PanSnLevel::Sequence => Some(base.to_string()),
         PanSnLevel::Sample => {
-            let sample = base.split('#').next().unwrap_or(base);
+            let sample = base.split(delimiter).next().unwrap_or(base);
             if sample.is_empty() {
                 None
             } else {
@@ -73,33 +74,93 @@ pub fn extract_pansn_key(name: &str, level: PanSnLevel) -> Option<String> {
             }
         }
         PanSnLevel::Haplotype => {
-            let mut parts = base.split('#');
+            let mut parts = base.split(delimiter);
             let sample = parts.next().unwrap_or("");
             if sample.is_empty() {
                 return None;
             }
             match parts.next() {
-                Some(h) if !h.is_empty() => Some(format!("{sample}#{h}")),
+                Some(h) if !h.is_empty() => Some(format!("{sample}{delimiter}{h}")),
                 _ => Some(sample.to_string()),
             }
         }
     }
 }
 
+/// Genome group of a sequence name (`--prefix-depth`): the first `depth`
+/// delimiter-separated fields of `name`, including the trailing delimiter,
+/// or everything up to the last delimiter when `depth` is 0. The last field
+/// (the contig) is never included, so `sample#contig` groups as `sample#` at
+/// any depth. Names without the delimiter are their own group.
+pub fn genome_prefix(name: &str, delimiter: char, depth: usize) -> &str {
+    let Some(last) = name.rfind(delimiter) else {
+        return name;
+    };
+    let end = match depth {
+        0 => last,
+        _ => name
+            .match_indices(delimiter)
+            .nth(depth - 1)
+            .map_or(last, |(pos, _)| pos),
+    };
+    &name[..end + delimiter.len_utf8()]
+}
+
+/// Genome prefixes chosen with `--keep-pairs` / `--exclude-pairs`. Prefixes
+/// are compared without their trailing delimiter, so `HG002#1` and `HG002#1#`
+/// name the same genome.
+#[derive(Debug, Clone, Default)]
+pub struct PairSelection {
+    keep: Vec<String>,
+    exclude: Vec<String>,
+}
+
+impl PairSelection {
+    pub fn new(keep: &[String], exclude: &[String], delimiter: char) -> Self {
+        let trimmed = |prefixes: &[String]| -> Vec<String> {
+            prefixes
+                .iter()
+                .map(|p| p.trim_end_matches(delimiter).to_string())
+                .collect()
+        };
+        PairSelection {
+            keep: trimmed(keep),
+            exclude: trimmed(exclude),
+        }
+    }
+
+    /// Whether every record is selected
+    pub fn is_all(&self) -> bool {
+        self.keep.is_empty() && self.exclude.is_empty()
+    }
+
+    /// Whether a record between genomes `query` and `target` (as returned by
+    /// [`genome_prefix`]) is selected: with `--keep-pairs` one of them must be
+    /// kept, and neither may be excluded
+    pub fn accepts(&self, query: &str, target: &str, delimiter: char) -> bool {
+        let query = query.trim_end_matches(delimiter);
+        let target = target.trim_end_matches(delimiter);
+        let listed = |prefixes: &[String], genome: &str| prefixes.iter().any(|p| p == genome);
+        (self.keep.is_empty() || listed(&self.keep, query) || listed(&self.keep, target))
+            && !listed(&self.exclude, query)
+            && !listed(&self.exclude, target)
+    }
+}
+
 /// Count unique PanSN keys at the requested level across an iterator of names.
-pub fn count_pansn_keys<'a, I>(names: I, level: PanSnLevel) -> usize
+pub fn count_pansn_keys<'a, I>(names: I, level: PanSnLevel, delimiter: char) -> usize
 where
     I: IntoIterator<Item = &'a str>,
 {
     let set: HashSet<String> = names
         .into_iter()
-        .filter_map(|n| extract_pansn_key(n, level))
+        .filter_map(|n| extract_pansn_key(n, level, delimiter))
         .collect();
     set.len().max(1)
 }
 
 /// Count unique PanSN haplotypes across one or more FASTA files.
-pub fn count_haplotypes<P: AsRef<Path>>(fasta_paths: &[P]) -> Result<usize> {
+pub fn count_haplotypes<P: AsRef<Path>>(fasta_paths: &[P], delimiter: char) -> Result<usize> {
     let mut haplotypes: HashSet<String> = HashSet::new();
 
     for path in fasta_paths {
@@ -111,7 +172,7 @@ pub fn count_haplotypes<P: AsRef<Path>>(fasta_paths: &[P]) -> Result<usize> {
                 format!("Failed to read a line from '{}'", path.display())
             })?;
             if line.starts_with('>') {
-                if let Some(key) = extract_pansn_key(&line, PanSnLevel::Haplotype) {
+                if let Some(key) = extract_pansn_key(&line, PanSnLevel::Haplotype, delimiter) {
                     haplotypes.insert(key);
                 }
             }
@@ -129,7 +190,8 @@ pub fn count_haplotypes<P: AsRef<Path>>(fasta_paths: &[P]) -> Result<usize> {
 ///   historical behavior of using the raw haplotype count for small
 ///   pangenomes — FastGA's internal floor of 10 is not reimposed here
 ///   because users explicitly requesting `multiplier * num_haplotypes`
-///   may want a tight threshold on small inputs.
+///   may want a tight threshold on small inputs. Haplotypes are split on
+///   `delimiter` (`--prefix-delimiter`).
 ///
 /// Callers that already have a haplotype count should pass `explicit`
 /// rather than re-counting.
@@ -137,11 +199,12 @@ pub fn resolve_fastga_frequency<P: AsRef<Path>>(
     explicit: Option<usize>,
     multiplier: usize,
     fasta_paths: &[P],
+    delimiter: char,
 ) -> Result<usize> {
     if let Some(n) = explicit {
         return Ok(n);
     }
-    let num_haplotypes = count_haplotypes(fasta_paths)?;
+    let num_haplotypes = count_haplotypes(fasta_paths, delimiter)?;
     Ok(num_haplotypes.saturating_mul(multiplier.max(1)))
 }
 
@@ -207,11 +270,15 @@ mod tests {
     #[test]
     fn extract_pansn_key_haplotype() {
         assert_eq!(
-            extract_pansn_key(">HG01106#1#CM087962.1", PanSnLevel::Haplotype),
+            extract_pansn_key(">HG01106#1#CM087962.1", PanSnLevel::Haplotype, '#'),
             Some("HG01106#1".to_string())
         );
         assert_eq!(
-            extract_pansn_key(">HG01106#1#chr1 extra annotation", PanSnLevel::Haplotype),
+            extract_pansn_key(
+                ">HG01106#1#chr1 extra annotation",
+                PanSnLevel::Haplotype,
+                '#'
+            ),
             Some("HG01106#1".to_string())
         );
     }
@@ -219,7 +286,7 @@ mod tests {
     #[test]
     fn extract_pansn_key_sample() {
         assert_eq!(
-            extract_pansn_key("HG01106#1#CM087962.1", PanSnLevel::Sample),
+            extract_pansn_key("HG01106#1#CM087962.1", PanSnLevel::Sample, '#'),
             Some("HG01106".to_string())
         );
     }
@@ -228,11 +295,19 @@ mod tests {
     fn extract_pansn_key_sequence_strips_interval() {
         // impg query-output names carry `:start-end` — interval suffix is stripped.
         assert_eq!(
-            extract_pansn_key("HG01106#1#chr1:29000000-29003000", PanSnLevel::Sequence),
+            extract_pansn_key(
+                "HG01106#1#chr1:29000000-29003000",
+                PanSnLevel::Sequence,
+                '#'
+            ),
             Some("HG01106#1#chr1".to_string())
         );
         assert_eq!(
-            extract_pansn_key("HG01106#1#chr1:29000000-29003000", PanSnLevel::Haplotype),
+            extract_pansn_key(
+                "HG01106#1#chr1:29000000-29003000",
+                PanSnLevel::Haplotype,
+                '#'
+            ),
             Some("HG01106#1".to_string())
         );
     }
@@ -241,15 +316,28 @@ mod tests {
     fn extract_pansn_key_non_pansn_returns_whole_name() {
         // Fewer than 2 `#` segments: fall back to whole name for haplotype mode.
         assert_eq!(
-            extract_pansn_key("chr1", PanSnLevel::Haplotype),
+            extract_pansn_key("chr1", PanSnLevel::Haplotype, '#'),
             Some("chr1".to_string())
         );
         assert_eq!(
-            extract_pansn_key(">chr1 extra", PanSnLevel::Haplotype),
+            extract_pansn_key(">chr1 extra", PanSnLevel::Haplotype, '#'),
             Some("chr1".to_string())
         );
     }
 
+    #[test]
+    fn extract_pansn_key_custom_delimiter() {
+        assert_eq!(
+            extract_pansn_key("HG01106|1|chr1", PanSnLevel::Haplotype, '|'),
+            Some("HG01106|1".to_string())
+        );
+        // '#' is an ordinary character once another delimiter is chosen
+        assert_eq!(
+            extract_pansn_key("HG01106#1#chr1", PanSnLevel::Sample, '|'),
+            Some("HG01106#1#chr1".to_string())
+        );
+    }
+
     #[test]
     fn count_pansn_keys_basic() {
         let names = [
@@ -259,22 +347,74 @@ mod tests {
             "HG02#1#chr1",
             "chm13#0#chr6",
         ];
-        assert_eq!(count_pansn_keys(names.iter().copied(), PanSnLevel::Sample), 3);
-        assert_eq!(count_pansn_keys(names.iter().copied(), PanSnLevel::Haplotype), 4);
-        assert_eq!(count_pansn_keys(names.iter().copied(), PanSnLevel::Sequence), 5);
+        assert_eq!(
+            count_pansn_keys(names.iter().copied(), PanSnLevel::Sample, '#'),
+            3
+        );
+        assert_eq!(
+            count_pansn_keys(names.iter().copied(), PanSnLevel::Haplotype, '#'),
+            4
+        );
+        assert_eq!(
+            count_pansn_keys(names.iter().copied(), PanSnLevel::Sequence, '#'),
+            5
+        );
+    }
+
+    #[test]
+    fn genome_prefix_depth_groups_differently() {
+        let names = [
+            "HG01#1#chr1",
+            "HG01#2#chr1",
+            "HG02#1#chr1",
+            "chm13#chr1",
+            "chm13#chr2",
+            "plain",
+        ];
+        let groups = |depth| {
+            names
+                .iter()
+                .map(|n| genome_prefix(n, '#', depth))
+                .collect::<HashSet<_>>()
+        };
+
+        // Depth 1: one group per sample
+        assert_eq!(
+            groups(1),
+            HashSet::from(["HG01#", "HG02#", "chm13#", "plain"])
+        );
+        // Depth 2: haplotypes split, 2-part names still group by sample
+        assert_eq!(
+            groups(2),
+            HashSet::from(["HG01#1#", "HG01#2#", "HG02#1#", "chm13#", "plain"])
+        );
+        // Default: everything up to the last delimiter
+        assert_eq!(genome_prefix("a#1#chr1#x", '#', 0), "a#1#chr1#");
+        assert_eq!(genome_prefix("a#1#chr1#x", '#', 2), "a#1#");
+    }
+
+    #[test]
+    fn genome_prefix_with_custom_delimiter() {
+        assert_eq!(genome_prefix("GenomeA.1.chr1", '.', 0), "GenomeA.1.");
+        assert_eq!(genome_prefix("GenomeA.1.chr1", '.', 1), "GenomeA.");
+        // '#' is an ordinary character once another delimiter is chosen
+        assert_eq!(genome_prefix("a#1#chr1", '.', 0), "a#1#chr1");
     }
 
     #[test]
     fn count_pansn_keys_empty_returns_one() {
         let names: [&str; 0] = [];
         // `.max(1)` invariant: never return 0 for `num * multiplier` math.
-        assert_eq!(count_pansn_keys(names.iter().copied(), PanSnLevel::Haplotype), 1);
+        assert_eq!(
+            count_pansn_keys(names.iter().copied(), PanSnLevel::Haplotype, '#'),
+            1
+        );
     }
 
     #[test]
     fn resolve_frequency_uses_explicit_override() {
         let empty: [&Path; 0] = [];
-        let f = resolve_fastga_frequency(Some(42), 99, &empty).unwrap();
+        let f = resolve_fastga_frequency(Some(42), 99, &empty, '#').unwrap();
         assert_eq!(f, 42);
     }
 
@@ -308,6 +448,22 @@ mod tests {
         assert_eq!(m, 600);
     }
 
+    #[test]
+    fn pair_selection_keeps_and_excludes_genomes() {
+        let all = PairSelection::default();
+        assert!(all.is_all() && all.accepts("A#1#", "B#1#", '#'));
+
+        let keep = PairSelection::new(&["ref#1".to_string()], &[], '#');
+        assert!(keep.accepts("A#1#", "ref#1#", '#'));
+        assert!(keep.accepts("ref#1#", "B#1#", '#'));
+        assert!(!keep.accepts("A#1#", "B#1#", '#'));
+
+        let exclude = PairSelection::new(&[], &["A#1#".to_string(), "C#1".to_string()], '#');
+        assert!(!exclude.accepts("A#1#", "B#1#", '#'));
+        assert!(!exclude.accepts("B#1#", "C#1#", '#'));
+        assert!(exclude.accepts("B#1#", "D#1#", '#'));
+    }
+
     #[test]
     fn clamp_scaffolds_preserves_small_user_values() {
         // If the user already passed tighter values, clamping never loosens them.
@@ -404,14 +404,7 @@ fn extract_prefix(seq_name: &str, delimiter: char, skip_prefix: bool) -> String
         return seq_name.to_string();
     }
 
-    // Find the last occurrence of the delimiter
-    if let Some(last_pos) = seq_name.rfind(delimiter) {
-        // Include the delimiter in the prefix
-        seq_name[..=last_pos].to_string()
-    } else {
-        // No delimiter found, use the whole name as prefix
-        seq_name.to_string()
-    }
+    crate::pansn::genome_prefix(seq_name, delimiter, 0).to_string()
 }
 
 /// Apply plane sweep filtering based on filter specification
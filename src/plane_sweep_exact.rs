@@ -5,6 +5,16 @@ use crate::paf_filter::ScoringFunction;
 use std::cmp::Ordering;
 use std::collections::BTreeSet;
 
+/// Scale MAPQ to a 0-1 weight: min(MAPQ, 60) / 60, with 255 (unavailable)
+/// treated as fully confident so inputs without MAPQ score like identity
+pub fn mapq_weight(mapq: u8) -> f64 {
+    if mapq == crate::paf::MAPQ_UNAVAILABLE {
+        1.0
+    } else {
+        f64::from(mapq.min(60)) / 60.0
+    }
+}
+
 /// Compact mapping for plane sweep (minimal fields needed)
 #[derive(Debug, Clone, Copy)]
 pub struct PlaneSweepMapping {
@@ -14,6 +24,7 @@ pub struct PlaneSweepMapping {
     pub target_start: u64,
     pub target_end: u64,
     pub identity: f64, // 0.0 - 1.0
+    pub mapq: u8,      // PAF column 12; 255 = unavailable
     pub flags: u8,     // bit 0: discard, bit 1: overlapped
 }
 
@@ -33,6 +44,40 @@ impl PlaneSweepMapping {
             ScoringFunction::LengthIdentity => self.score_length_identity(),
             ScoringFunction::LogLengthIdentity => self.score_log_length_identity(),
             ScoringFunction::Matches => self.score_matches(),
+            ScoringFunction::MapqIdentity => self.score_mapq_identity(),
+            // `identity` holds the gap-compressed identity for this function
+            ScoringFunction::GapCompressedIdentity => self.score_identity(),
+            ScoringFunction::Weighted { identity, length } => {
+                self.score_weighted(identity, length, 1.0, 1.0)
+            }
+            ScoringFunction::Custom {
+                len_weight,
+                id_weight,
+                use_log,
+            } => self.score_custom(len_weight, id_weight, use_log),
+        }
+    }
+
+    /// `len_weight * length + id_weight * identity`, with `ln(length)` when
+    /// `use_log`
+    pub fn score_custom(&self, len_weight: f64, id_weight: f64, use_log: bool) -> f64 {
+        let length = (self.query_end - self.query_start) as f64;
+        if length <= 0.0 || self.identity <= 0.0 {
+            f64::NEG_INFINITY
+        } else {
+            let length = if use_log { length.ln() } else { length };
+            len_weight * length + id_weight * self.identity
+        }
+    }
+
+    /// `a * identity / max_identity + b * ln(length) / max_log_length`; the
+    /// plane sweeps pass their group's maxima, standalone scoring uses 1.0
+    pub fn score_weighted(&self, a: f64, b: f64, max_identity: f64, max_log_length: f64) -> f64 {
+        let length = (self.query_end - self.query_start) as f64;
+        if length <= 0.0 || self.identity <= 0.0 {
+            f64::NEG_INFINITY
+        } else {
+            a * self.identity / max_identity + b * length.ln() / max_log_length
         }
     }
 
@@ -85,6 +130,15 @@ impl PlaneSweepMapping {
         }
     }
 
+    pub fn score_mapq_identity(&self) -> f64 {
+        // Identity * MAPQ weight (MAPQ capped at 60; 255/unavailable counts as 60)
+        if self.identity <= 0.0 {
+            f64::NEG_INFINITY
+        } else {
+            self.identity * mapq_weight(self.mapq)
+        }
+    }
+
     pub fn is_discard(&self) -> bool {
         (self.flags & Self::FLAG_DISCARD) != 0
     }
@@ -193,10 +247,12 @@ impl Ord for MappingOrder {
     }
 }
 
-/// Mark good mappings (exact implementation of wfmash's markGood)
+/// Mark good mappings (exact implementation of wfmash's markGood).
+/// `sweep_ranks` tracks the lowest position each kept mapping has held.
 fn mark_good(
     bst: &BTreeSet<MappingOrder>,
     mappings: &mut [PlaneSweepMapping],
+    sweep_ranks: &mut [usize],
     mappings_to_keep: usize,
     overlap_threshold: f64,
     axis: Axis,
@@ -224,6 +280,7 @@ fn mark_good(
 
         // Mark as good and increment kept
         mappings[mapping_order.idx].set_discard(false);
+        sweep_ranks[mapping_order.idx] = sweep_ranks[mapping_order.idx].max(kept);
         kept_indices.push(mapping_order.idx);
     }
 
@@ -264,6 +321,31 @@ pub enum Axis {
     Target,
 }
 
+/// Score every mapping of a competing group. Weighted scoring normalizes
+/// identity and log length against the group's maxima; other functions score
+/// each mapping on its own.
+fn group_scores(mappings: &[PlaneSweepMapping], scoring: ScoringFunction) -> Vec<f64> {
+    let ScoringFunction::Weighted { identity, length } = scoring else {
+        return mappings
+            .iter()
+            .map(|m| m.score_with_function(scoring))
+            .collect();
+    };
+
+    let positive_or_one = |max: f64| if max > 0.0 { max } else { 1.0 };
+    let max_identity = positive_or_one(mappings.iter().map(|m| m.identity).fold(0.0, f64::max));
+    let max_log_length = positive_or_one(
+        mappings
+            .iter()
+            .map(|m| ((m.query_end - m.query_start) as f64).ln())
+            .fold(0.0, f64::max),
+    );
+    mappings
+        .iter()
+        .map(|m| m.score_weighted(identity, length, max_identity, max_log_length))
+        .collect()
+}
+
 /// Apply plane sweep on query axis (exact wfmash algorithm)
 pub fn plane_sweep_query(
     mappings: &mut [PlaneSweepMapping],
@@ -271,8 +353,20 @@ pub fn plane_sweep_query(
     overlap_threshold: f64,
     scoring: ScoringFunction,
 ) -> Vec<usize> {
+    plane_sweep_query_ranked(mappings, mappings_to_keep, overlap_threshold, scoring).0
+}
+
+/// [`plane_sweep_query`], also returning each mapping's sweep rank: the
+/// lowest position it was kept at among its competitors at any locus, so 0
+/// means primary wherever it was kept. Only meaningful for kept mappings.
+pub fn plane_sweep_query_ranked(
+    mappings: &mut [PlaneSweepMapping],
+    mappings_to_keep: usize,
+    overlap_threshold: f64,
+    scoring: ScoringFunction,
+) -> (Vec<usize>, Vec<usize>) {
     if mappings.is_empty() || mappings.len() == 1 {
-        return (0..mappings.len()).collect();
+        return ((0..mappings.len()).collect(), vec![0; mappings.len()]);
     }
 
     // Initially mark all as discarded
@@ -280,6 +374,8 @@ pub fn plane_sweep_query(
         mapping.set_discard(true);
         mapping.set_overlapped(false);
     }
+    let scores = group_scores(mappings, scoring);
+    let mut sweep_ranks = vec![0; mappings.len()];
 
     // Create event schedule
     let mut events = Vec::with_capacity(mappings.len() * 2);
@@ -316,7 +412,7 @@ pub fn plane_sweep_query(
         for event in &events[i..j] {
             let mapping_order = MappingOrder {
                 idx: event.mapping_idx,
-                score: mappings[event.mapping_idx].score_with_function(scoring),
+                score: scores[event.mapping_idx],
                 start_pos: mappings[event.mapping_idx].query_start,
             };
 
@@ -334,6 +430,7 @@ pub fn plane_sweep_query(
         mark_good(
             &bst,
             mappings,
+            &mut sweep_ranks,
             mappings_to_keep,
             overlap_threshold,
             Axis::Query,
@@ -343,12 +440,13 @@ pub fn plane_sweep_query(
     }
 
     // Return indices of kept mappings
-    mappings
+    let kept = mappings
         .iter()
         .enumerate()
         .filter(|(_, m)| !m.is_discard() && !m.is_overlapped())
         .map(|(idx, _)| idx)
-        .collect()
+        .collect();
+    (kept, sweep_ranks)
 }
 
 /// Apply plane sweep on target axis
@@ -358,8 +456,19 @@ pub fn plane_sweep_target(
     overlap_threshold: f64,
     scoring: ScoringFunction,
 ) -> Vec<usize> {
+    plane_sweep_target_ranked(mappings, mappings_to_keep, overlap_threshold, scoring).0
+}
+
+/// [`plane_sweep_target`], also returning each mapping's sweep rank (see
+/// [`plane_sweep_query_ranked`])
+pub fn plane_sweep_target_ranked(
+    mappings: &mut [PlaneSweepMapping],
+    mappings_to_keep: usize,
+    overlap_threshold: f64,
+    scoring: ScoringFunction,
+) -> (Vec<usize>, Vec<usize>) {
     if mappings.is_empty() || mappings.len() == 1 {
-        return (0..mappings.len()).collect();
+        return ((0..mappings.len()).collect(), vec![0; mappings.len()]);
     }
 
     // Initially mark all as discarded
@@ -367,6 +476,8 @@ pub fn plane_sweep_target(
         mapping.set_discard(true);
         mapping.set_overlapped(false);
     }
+    let scores = group_scores(mappings, scoring);
+    let mut sweep_ranks = vec![0; mappings.len()];
 
     // Create event schedule
     let mut events = Vec::with_capacity(mappings.len() * 2);
@@ -399,7 +510,7 @@ pub fn plane_sweep_target(
         for event in &events[i..j] {
             let mapping_order = MappingOrder {
                 idx: event.mapping_idx,
-                score: mappings[event.mapping_idx].score_with_function(scoring),
+                score: scores[event.mapping_idx],
                 start_pos: mappings[event.mapping_idx].target_start,
             };
 
@@ -416,6 +527,7 @@ pub fn plane_sweep_target(
         mark_good(
             &bst,
             mappings,
+            &mut sweep_ranks,
             mappings_to_keep,
             overlap_threshold,
             Axis::Target,
@@ -424,12 +536,13 @@ pub fn plane_sweep_target(
         i = j;
     }
 
-    mappings
+    let kept = mappings
         .iter()
         .enumerate()
         .filter(|(_, m)| !m.is_discard() && !m.is_overlapped())
         .map(|(idx, _)| idx)
-        .collect()
+        .collect();
+    (kept, sweep_ranks)
 }
 
 /// Apply both query and target filtering
@@ -638,6 +751,7 @@ mod tests {
             target_start: 300,
             target_end: 400,
             identity: 0.95,
+            mapq: 255,
             flags: 0,
         }];
         let kept = plane_sweep_query(&mut mappings, 1, 0.95, ScoringFunction::LogLengthIdentity);
@@ -654,6 +768,7 @@ mod tests {
                 target_start: 300,
                 target_end: 400,
                 identity: 0.95,
+                mapq: 255,
                 flags: 0,
             },
             PlaneSweepMapping {
@@ -663,6 +778,7 @@ mod tests {
                 target_start: 500,
                 target_end: 600,
                 identity: 0.90,
+                mapq: 255,
                 flags: 0,
             },
         ];
@@ -682,6 +798,7 @@ mod tests {
                 target_start: 300,
                 target_end: 400,
                 identity: 0.95,
+                mapq: 255,
                 flags: 0,
             },
             PlaneSweepMapping {
@@ -691,6 +808,7 @@ mod tests {
                 target_start: 350,
                 target_end: 450,
                 identity: 0.90,
+                mapq: 255,
                 flags: 0,
             },
         ];
@@ -711,6 +829,7 @@ mod tests {
                 target_start: 300,
                 target_end: 400,
                 identity: 0.95,
+                mapq: 255,
                 flags: 0,
             },
             PlaneSweepMapping {
@@ -720,6 +839,7 @@ mod tests {
                 target_start: 500,
                 target_end: 600,
                 identity: 0.90,
+                mapq: 255,
                 flags: 0,
             },
             PlaneSweepMapping {
@@ -729,6 +849,7 @@ mod tests {
                 target_start: 700,
                 target_end: 800,
                 identity: 0.85,
+                mapq: 255,
                 flags: 0,
             },
         ];
@@ -755,6 +876,7 @@ mod tests {
                 target_start: 300,
                 target_end: 400,
                 identity: 0.95,
+                mapq: 255,
                 flags: 0,
             },
             PlaneSweepMapping {
@@ -764,6 +886,7 @@ mod tests {
                 target_start: 500, // Different target
                 target_end: 600,
                 identity: 0.90, // Lower score - will be secondary
+                mapq: 255,
                 flags: 0,
             },
             PlaneSweepMapping {
@@ -773,6 +896,7 @@ mod tests {
                 target_start: 700,
                 target_end: 800,
                 identity: 0.85, // Even lower score
+                mapq: 255,
                 flags: 0,
             },
         ];
@@ -808,6 +932,7 @@ mod tests {
                 target_start: 0,
                 target_end: 100,
                 identity: 0.95,
+                mapq: 255,
                 flags: 0,
             },
             PlaneSweepMapping {
@@ -817,6 +942,7 @@ mod tests {
                 target_start: 1000,
                 target_end: 1100,
                 identity: 0.90,
+                mapq: 255,
                 flags: 0,
             },
         ];
@@ -8,17 +8,10 @@ use anyhow::Result;
 use indexmap::IndexMap;
 
 /// Extract PanSN genome prefix from sequence name
-/// Default: first two parts (genome#haplotype#) for standard PanSN format
+/// Default: everything before the contig; see `pansn::genome_prefix`
 /// e.g., "SGDref#1#chrI" -> "SGDref#1#"
-fn extract_genome_prefix(name: &str) -> String {
-    let parts: Vec<&str> = name.split('#').collect();
-    if parts.len() >= 2 {
-        // Take first two parts: genome#haplotype#
-        format!("{}#{}#", parts[0], parts[1])
-    } else {
-        // No standard PanSN format, use whole name
-        name.to_string()
-    }
+fn extract_genome_prefix(name: &str, delimiter: char, prefix_depth: usize) -> String {
+    crate::pansn::genome_prefix(name, delimiter, prefix_depth).to_string()
 }
 
 /// Trait for any structure that can be converted to plane sweep coordinates
@@ -30,6 +23,14 @@ pub trait ScaffoldLike {
     fn target_start(&self) -> u64;
     fn target_end(&self) -> u64;
     fn identity(&self) -> f64;
+    /// Mapping quality for MAPQ-aware scoring (255 = unavailable)
+    fn mapq(&self) -> u8 {
+        255
+    }
+    /// Gap-compressed identity for `--scoring gap-compressed-identity`
+    fn gap_compressed_identity(&self) -> f64 {
+        self.identity()
+    }
 }
 
 /// Apply plane sweep filter to a collection of scaffold chains
@@ -39,18 +40,26 @@ pub trait ScaffoldLike {
 /// * `filter_mode` - Filtering mode (1:1, 1:N, N:N)
 /// * `max_per_query` - Maximum scaffolds per query (None = unlimited)
 /// * `max_per_target` - Maximum scaffolds per target (None = unlimited)
+/// * `keep_fraction` - Keep `ceil(fraction × n)` of the n scaffolds on each
+///   chromosome pair per position, in place of the mode and limits above
 /// * `overlap_threshold` - Overlap threshold for plane sweep (0.0-1.0)
 /// * `scoring_function` - How to score/rank scaffolds
+/// * `delimiter`, `prefix_depth` - Name fields forming a genome group
+///   (`--prefix-delimiter`, `--prefix-depth`)
 ///
 /// # Returns
 /// Vector of indices into the input `chains` vector representing which chains to keep
+#[allow(clippy::too_many_arguments)]
 pub fn plane_sweep_scaffolds<T: ScaffoldLike>(
     chains: &[T],
     filter_mode: FilterMode,
     max_per_query: Option<usize>,
     max_per_target: Option<usize>,
+    keep_fraction: Option<f64>,
     overlap_threshold: f64,
     scoring_function: ScoringFunction,
+    delimiter: char,
+    prefix_depth: usize,
 ) -> Result<Vec<usize>> {
     if chains.is_empty() || chains.len() <= 1 {
         return Ok((0..chains.len()).collect());
@@ -67,7 +76,9 @@ pub fn plane_sweep_scaffolds<T: ScaffoldLike>(
                 query_end: chain.query_end(),
                 target_start: chain.target_start(),
                 target_end: chain.target_end(),
-                identity: chain.identity(),
+                identity: scoring_function
+                    .scored_identity(chain.identity(), chain.gap_compressed_identity()),
+                mapq: chain.mapq(),
                 flags: 0,
             };
             let query_group = chain.query_name().to_string();
@@ -77,16 +88,23 @@ pub fn plane_sweep_scaffolds<T: ScaffoldLike>(
         .collect();
 
     // Apply plane sweep based on filter mode
-    let kept_indices: Vec<usize> = match filter_mode {
-        FilterMode::OneToOne => {
-            apply_one_to_one_sweep(&plane_sweep_mappings, overlap_threshold, scoring_function)?
-        }
-        FilterMode::OneToMany | FilterMode::ManyToMany => apply_many_sweep(
+    let kept_indices: Vec<usize> = match (filter_mode, keep_fraction) {
+        (FilterMode::OneToOne, None) => apply_one_to_one_sweep(
+            &plane_sweep_mappings,
+            overlap_threshold,
+            scoring_function,
+            delimiter,
+            prefix_depth,
+        )?,
+        _ => apply_many_sweep(
             &plane_sweep_mappings,
             max_per_query,
             max_per_target,
+            keep_fraction,
             overlap_threshold,
             scoring_function,
+            delimiter,
+            prefix_depth,
         )?,
     };
 
@@ -109,6 +127,8 @@ fn apply_one_to_one_sweep(
     plane_sweep_mappings: &[(PlaneSweepMapping, String, String)],
     overlap_threshold: f64,
     scoring_function: ScoringFunction,
+    delimiter: char,
+    prefix_depth: usize,
 ) -> Result<Vec<usize>> {
     // First, organize by genome pair (for logging/organization).
     // IndexMap preserves PAF-input insertion order so plane-sweep tie-breaks
@@ -117,8 +137,8 @@ fn apply_one_to_one_sweep(
         IndexMap::new();
 
     for (i, (_, q, t)) in plane_sweep_mappings.iter().enumerate() {
-        let q_prefix = extract_genome_prefix(q);
-        let t_prefix = extract_genome_prefix(t);
+        let q_prefix = extract_genome_prefix(q, delimiter, prefix_depth);
+        let t_prefix = extract_genome_prefix(t, delimiter, prefix_depth);
         let chr_pair = (q.clone(), t.clone());
 
         genome_pairs
@@ -144,8 +164,8 @@ fn apply_one_to_one_sweep(
                 .unwrap_or(0);
             log::info!(
                 "[sweepga::scaffold]   {} -> {} ({} chromosome pairs)",
-                q_prefix.trim_end_matches('#'),
-                t_prefix.trim_end_matches('#'),
+                q_prefix.trim_end_matches(delimiter),
+                t_prefix.trim_end_matches(delimiter),
                 chr_count
             );
         }
@@ -188,13 +208,18 @@ fn apply_one_to_one_sweep(
 /// Apply M:N plane sweep - group by GENOME PAIR, filter by CHROMOSOME PAIR
 ///
 /// Same grouping hierarchy as 1:1, but with configurable M:N limits per position.
-#[allow(clippy::type_complexity)]
+/// With `keep_fraction`, each chromosome pair of n scaffolds instead gets a
+/// limit of `ceil(fraction × n)` on both axes.
+#[allow(clippy::type_complexity, clippy::too_many_arguments)]
 fn apply_many_sweep(
     plane_sweep_mappings: &[(PlaneSweepMapping, String, String)],
     max_per_query: Option<usize>,
     max_per_target: Option<usize>,
+    keep_fraction: Option<f64>,
     overlap_threshold: f64,
     scoring_function: ScoringFunction,
+    delimiter: char,
+    prefix_depth: usize,
 ) -> Result<Vec<usize>> {
     let query_limit = max_per_query.unwrap_or(usize::MAX);
     let target_limit = max_per_target.unwrap_or(usize::MAX);
@@ -205,8 +230,8 @@ fn apply_many_sweep(
         IndexMap::new();
 
     for (i, (_, q, t)) in plane_sweep_mappings.iter().enumerate() {
-        let q_prefix = extract_genome_prefix(q);
-        let t_prefix = extract_genome_prefix(t);
+        let q_prefix = extract_genome_prefix(q, delimiter, prefix_depth);
+        let t_prefix = extract_genome_prefix(t, delimiter, prefix_depth);
         let chr_pair = (q.clone(), t.clone());
 
         genome_pairs
@@ -231,6 +256,14 @@ fn apply_many_sweep(
             let mut pair_mappings: Vec<PlaneSweepMapping> =
                 indices.iter().map(|&i| plane_sweep_mappings[i].0).collect();
 
+            let (query_limit, target_limit) = match keep_fraction {
+                Some(fraction) => {
+                    let limit = ((fraction * indices.len() as f64).ceil() as usize).max(1);
+                    (limit, limit)
+                }
+                None => (query_limit, target_limit),
+            };
+
             // Apply M:N plane sweep within this chromosome pair
             let kept_in_pair = plane_sweep_both(
                 &mut pair_mappings,
@@ -317,8 +350,11 @@ mod tests {
             FilterMode::OneToOne,
             Some(1),
             Some(1),
+            None,
             0.5,
             ScoringFunction::LogLengthIdentity,
+            '#',
+            0,
         )
         .unwrap();
 
@@ -354,8 +390,11 @@ mod tests {
             FilterMode::OneToOne,
             Some(1),
             Some(1),
+            None,
             0.95, // Use default wfmash overlap threshold
             ScoringFunction::LogLengthIdentity,
+            '#',
+            0,
         )
         .unwrap();
 
@@ -369,4 +408,42 @@ mod tests {
             assert_eq!(kept[0], 1);
         }
     }
+
+    #[test]
+    fn test_keep_fraction_limits_each_chromosome_pair() {
+        // Four stacked chr1 scaffolds and one chr2 scaffold: 0.5 keeps the
+        // best ceil(0.5 × 4) = 2 on chr1 and ceil(0.5 × 1) = 1 on chr2
+        let chain = |name: &str, identity: f64| TestChain {
+            query_name: name.to_string(),
+            target_name: name.to_string(),
+            query_start: 0,
+            query_end: 1000,
+            target_start: 0,
+            target_end: 1000,
+            identity,
+        };
+        let chains = vec![
+            chain("chr1", 0.90),
+            chain("chr1", 0.96),
+            chain("chr1", 0.92),
+            chain("chr1", 0.94),
+            chain("chr2", 0.90),
+        ];
+
+        let mut kept = plane_sweep_scaffolds(
+            &chains,
+            FilterMode::OneToOne,
+            Some(1),
+            Some(1),
+            Some(0.5),
+            1.0,
+            ScoringFunction::LogLengthIdentity,
+            '#',
+            0,
+        )
+        .unwrap();
+        kept.sort_unstable();
+
+        assert_eq!(kept, vec![1, 3, 4]);
+    }
 }
@@ -0,0 +1,190 @@
+//! SAM export (`--sam`)
+//!
+//! Filtered PAF records become SAM alignments of the query against the
+//! target. The `cg:Z:` CIGAR (rebuilt from .1aln trace points for FASTA and
+//! .1aln input) is kept as is, with the unaligned query ends hard-clipped,
+//! since SEQ and QUAL are not stored (`*`). Records without a CIGAR get a
+//! single `M` operation over the aligned query span so the stream stays valid.
+//!
+//! `@SQ` lines list the targets in order of first appearance, with their
+//! lengths, as registered in a [`SequenceIndex`].
+
+use anyhow::{Context, Result};
+use std::fs::File;
+use std::io::{BufRead, BufWriter, Write};
+use std::path::Path;
+
+use crate::sequence_index::SequenceIndex;
+use crate::trim_overlaps::parse_cigar_ops;
+
+/// SAM FLAG bit for a reverse-complemented query
+const FLAG_REVERSE: u16 = 0x10;
+
+/// Convert one 0-based PAF line into a SAM record (without newline)
+pub fn paf_line_to_sam(line: &str) -> Result<String> {
+    let fields: Vec<&str> = line.split('\t').collect();
+    anyhow::ensure!(fields.len() >= 12, "PAF record has fewer than 12 columns");
+    let num = |i: usize| -> Result<u64> {
+        fields[i]
+            .parse()
+            .with_context(|| format!("Invalid PAF column {}: {}", i + 1, fields[i]))
+    };
+    let (query_len, query_start, query_end) = (num(1)?, num(2)?, num(3)?);
+    let (target_start, matches, block_len) = (num(7)?, num(9)?, num(10)?);
+    let reverse = fields[4] == "-";
+    anyhow::ensure!(
+        query_start <= query_end && query_end <= query_len,
+        "PAF query range {query_start}-{query_end} does not fit the query length {query_len}"
+    );
+
+    let (ops, edit_distance) = match fields.iter().find_map(|f| f.strip_prefix("cg:Z:")) {
+        Some(cigar) => {
+            let ops = parse_cigar_ops(cigar)?;
+            let count = |wanted: &[char]| -> u64 {
+                ops.iter()
+                    .filter(|(_, op)| wanted.contains(op))
+                    .map(|&(len, _)| len)
+                    .sum()
+            };
+            // 'M' hides mismatches: those are the M bases not counted as matches
+            let unmatched_m = count(&['M']).saturating_sub(matches.saturating_sub(count(&['='])));
+            let edit_distance = count(&['X', 'I', 'D']) + unmatched_m;
+            (ops, edit_distance)
+        }
+        None => (
+            vec![(query_end - query_start, 'M')],
+            block_len.saturating_sub(matches),
+        ),
+    };
+
+    // The CIGAR runs along the forward target, so on '-' the query is
+    // reversed and its clips swap ends
+    let (lead_clip, trail_clip) = if reverse {
+        (query_len - query_end, query_start)
+    } else {
+        (query_start, query_len - query_end)
+    };
+    let mut cigar = String::new();
+    if lead_clip > 0 {
+        cigar.push_str(&format!("{lead_clip}H"));
+    }
+    for (len, op) in &ops {
+        cigar.push_str(&format!("{len}{op}"));
+    }
+    if trail_clip > 0 {
+        cigar.push_str(&format!("{trail_clip}H"));
+    }
+
+    Ok(format!(
+        "{}\t{}\t{}\t{}\t{}\t{cigar}\t*\t0\t0\t*\t*\tNM:i:{edit_distance}",
+        fields[0],
+        if reverse { FLAG_REVERSE } else { 0 },
+        fields[5],
+        target_start + 1,
+        fields[11],
+    ))
+}
+
+/// Convert the PAF file `paf_path` to SAM at `sam_path`, with `@HD`, one
+/// `@SQ` per target and `@PG` header lines. Returns the number of records.
+pub fn write_sam(paf_path: &Path, sam_path: &Path) -> Result<usize> {
+    let is_record = |line: &str| !line.is_empty() && !crate::paf::is_header_line(line);
+
+    let mut targets = SequenceIndex::new();
+    for line in crate::paf::open_paf_input(paf_path)?.lines() {
+        let line = line?;
+        if !is_record(&line) {
+            continue;
+        }
+        let fields: Vec<&str> = line.splitn(8, '\t').collect();
+        anyhow::ensure!(fields.len() >= 7, "PAF record has fewer than 7 columns");
+        let length = fields[6]
+            .parse()
+            .with_context(|| format!("Invalid PAF target length: {}", fields[6]))?;
+        targets.get_or_insert_with_length(fields[5], length);
+    }
+
+    let file = File::create(sam_path)
+        .with_context(|| format!("Failed to create SAM file {}", sam_path.display()))?;
+    let mut writer = BufWriter::new(file);
+    writeln!(writer, "@HD\tVN:1.6\tSO:unsorted")?;
+    for (id, name) in targets.names().iter().enumerate() {
+        let length = targets.length(id as u32).unwrap_or(0);
+        writeln!(writer, "@SQ\tSN:{name}\tLN:{length}")?;
+    }
+    writeln!(
+        writer,
+        "@PG\tID:sweepga\tPN:sweepga\tVN:{}",
+        env!("CARGO_PKG_VERSION")
+    )?;
+
+    let mut written = 0;
+    for line in crate::paf::open_paf_input(paf_path)?.lines() {
+        let line = line?;
+        if !is_record(&line) {
+            continue;
+        }
+        writeln!(writer, "{}", paf_line_to_sam(&line)?)?;
+        written += 1;
+    }
+    writer.flush()?;
+    Ok(written)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_forward_record_with_cigar() {
+        let line = "q\t1000\t100\t211\t+\tt\t5000\t2000\t2111\t105\t112\t60\tcg:Z:50=1X4=1I50=1D5=";
+        assert_eq!(
+            paf_line_to_sam(line).unwrap(),
+            "q\t0\tt\t2001\t60\t100H50=1X4=1I50=1D5=789H\t*\t0\t0\t*\t*\tNM:i:3"
+        );
+    }
+
+    #[test]
+    fn test_reverse_record_swaps_clips() {
+        let line = "q\t1000\t100\t200\t-\tt\t5000\t0\t100\t100\t100\t255\tcg:Z:100=";
+        assert_eq!(
+            paf_line_to_sam(line).unwrap(),
+            "q\t16\tt\t1\t255\t800H100=100H\t*\t0\t0\t*\t*\tNM:i:0"
+        );
+    }
+
+    #[test]
+    fn test_record_without_cigar_falls_back_to_query_span() {
+        let line = "q\t100\t10\t90\t+\tt\t100\t0\t82\t75\t82\t60";
+        assert_eq!(
+            paf_line_to_sam(line).unwrap(),
+            "q\t0\tt\t1\t60\t10H80M10H\t*\t0\t0\t*\t*\tNM:i:7"
+        );
+    }
+
+    #[test]
+    fn test_query_end_past_query_length_is_an_error() {
+        let line = "q\t100\t0\t120\t+\tt\t500\t0\t120\t120\t120\t60\tcg:Z:120=";
+        assert!(paf_line_to_sam(line).is_err());
+    }
+
+    #[test]
+    fn test_header_lists_targets_once() {
+        let dir = tempfile::tempdir().unwrap();
+        let paf = dir.path().join("in.paf");
+        std::fs::write(
+            &paf,
+            "q1\t100\t0\t100\t+\tt1\t500\t0\t100\t100\t100\t60\tcg:Z:100=\n\
+             q2\t100\t0\t100\t+\tt2\t700\t0\t100\t100\t100\t60\tcg:Z:100=\n\
+             q2\t100\t0\t100\t-\tt1\t500\t200\t300\t100\t100\t60\tcg:Z:100=\n",
+        )
+        .unwrap();
+        let sam = dir.path().join("out.sam");
+        assert_eq!(write_sam(&paf, &sam).unwrap(), 3);
+
+        let text = std::fs::read_to_string(&sam).unwrap();
+        let headers: Vec<&str> = text.lines().filter(|l| l.starts_with("@SQ")).collect();
+        assert_eq!(headers, ["@SQ\tSN:t1\tLN:500", "@SQ\tSN:t2\tLN:700"]);
+        assert_eq!(text.lines().filter(|l| !l.starts_with('@')).count(), 3);
+    }
+}
@@ -0,0 +1,103 @@
+//! Per-scaffold bundles (`--scaffold-bundle`)
+//!
+//! Every output scaffold chain (the `ch:Z:` tag) is written to its own
+//! `<DIR>/<chain id>.paf`: one `#` header line summarizing the chain, then
+//! its member records exactly as output (CIGARs and tags included), so each
+//! syntenic block can be processed on its own, e.g. by a variant caller.
+//!
+//! Header spans are 0-based half-open and cover all members.
+
+use anyhow::{Context, Result};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use crate::chain_format::ChainMember;
+
+/// The records of one scaffold chain, in output order
+#[derive(Debug, Clone, Default)]
+pub struct Bundle {
+    pub members: Vec<ChainMember>,
+    /// Output lines of the members
+    pub lines: Vec<String>,
+}
+
+impl Bundle {
+    pub fn push(&mut self, member: ChainMember, line: String) {
+        self.members.push(member);
+        self.lines.push(line);
+    }
+
+    /// Header line summarizing the chain, without the trailing newline
+    pub fn header(&self, chain_id: &str) -> String {
+        let members = &self.members;
+        let Some(first) = members.first() else {
+            return format!("#chain_id={chain_id}\tmembers=0");
+        };
+        let query_start = members.iter().map(|m| m.query_start).min().unwrap_or(0);
+        let query_end = members.iter().map(|m| m.query_end).max().unwrap_or(0);
+        let target_start = members.iter().map(|m| m.target_start).min().unwrap_or(0);
+        let target_end = members.iter().map(|m| m.target_end).max().unwrap_or(0);
+        let matches: u64 = members.iter().map(|m| m.matches).sum();
+        format!(
+            "#chain_id={chain_id}\tmembers={}\tmatches={matches}\t\
+             query={}:{query_start}-{query_end}\tstrand={}\t\
+             target={}:{target_start}-{target_end}",
+            members.len(),
+            first.query_name,
+            first.strand,
+            first.target_name,
+        )
+    }
+
+    /// Write the header, then the member lines
+    pub fn write<W: Write>(&self, writer: &mut W, chain_id: &str) -> Result<()> {
+        writeln!(writer, "{}", self.header(chain_id))?;
+        for line in &self.lines {
+            writeln!(writer, "{line}")?;
+        }
+        Ok(())
+    }
+}
+
+/// File of chain `chain_id`'s bundle in `dir`
+pub fn bundle_path(dir: &Path, chain_id: &str) -> PathBuf {
+    dir.join(format!("{chain_id}.paf"))
+}
+
+/// Write each `(chain ID, bundle)` to `dir/<chain ID>.paf`, creating `dir`
+/// if needed. Returns the number of files written.
+pub fn write_bundle_dir(dir: &Path, bundles: &[(String, Bundle)]) -> Result<usize> {
+    std::fs::create_dir_all(dir)
+        .with_context(|| format!("Failed to create bundle directory {}", dir.display()))?;
+    for (chain_id, bundle) in bundles {
+        let path = bundle_path(dir, chain_id);
+        let file = File::create(&path)
+            .with_context(|| format!("Failed to create bundle {}", path.display()))?;
+        let mut writer = BufWriter::new(file);
+        bundle.write(&mut writer, chain_id)?;
+        writer.flush()?;
+    }
+    Ok(bundles.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bundle_header_spans_all_members() {
+        let mut bundle = Bundle::default();
+        for line in [
+            "q\t50000\t12000\t20000\t+\tt\t60000\t13000\t21000\t7600\t8000\t60",
+            "q\t50000\t0\t10000\t+\tt\t60000\t1000\t11000\t9500\t10000\t60",
+        ] {
+            bundle.push(ChainMember::from_paf_line(line).unwrap(), line.to_string());
+        }
+        assert_eq!(
+            bundle.header("chain_3"),
+            "#chain_id=chain_3\tmembers=2\tmatches=17100\tquery=q:0-20000\tstrand=+\t\
+             target=t:1000-21000"
+        );
+    }
+}
@@ -1,6 +1,7 @@
 #![allow(dead_code)]
 /// Efficient sequence name indexing for compact storage
 use std::collections::HashMap;
+use std::io::Write;
 
 /// Maps sequence names to compact integer IDs
 #[derive(Debug, Clone, Default)]
@@ -9,6 +10,8 @@ pub struct SequenceIndex {
     names: Vec<String>,
     /// Map from name to ID for fast lookup
     name_to_id: HashMap<String, u32>,
+    /// Sequence lengths by ID (0 = not recorded)
+    lengths: Vec<u64>,
 }
 
 impl SequenceIndex {
@@ -26,10 +29,27 @@ impl SequenceIndex {
             let id = self.names.len() as u32;
             self.names.push(name.to_string());
             self.name_to_id.insert(name.to_string(), id);
+            self.lengths.push(0);
             id
         }
     }
 
+    /// Get or create an ID for a sequence, recording its length if none
+    /// was recorded yet
+    pub fn get_or_insert_with_length(&mut self, name: &str, length: u64) -> u32 {
+        let id = self.get_or_insert(name);
+        let recorded = &mut self.lengths[id as usize];
+        if *recorded == 0 {
+            *recorded = length;
+        }
+        id
+    }
+
+    /// Get the recorded length for an ID (0 if none was recorded)
+    pub fn length(&self, id: u32) -> Option<u64> {
+        self.lengths.get(id as usize).copied()
+    }
+
     /// Get the ID for a name (returns None if not found)
     pub fn get_id(&self, name: &str) -> Option<u32> {
         self.name_to_id.get(name).copied()
@@ -64,12 +84,24 @@ impl SequenceIndex {
     pub fn clear(&mut self) {
         self.names.clear();
         self.name_to_id.clear();
+        self.lengths.clear();
     }
 
     /// Reserve capacity for efficiency
     pub fn reserve(&mut self, additional: usize) {
         self.names.reserve(additional);
         self.name_to_id.reserve(additional);
+        self.lengths.reserve(additional);
+    }
+
+    /// Write the index as TSV: a `#id name length` header, then one line per
+    /// sequence in ID order
+    pub fn write_tsv<W: Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        writeln!(writer, "#id\tname\tlength")?;
+        for (id, (name, length)) in self.names.iter().zip(&self.lengths).enumerate() {
+            writeln!(writer, "{id}\t{name}\t{length}")?;
+        }
+        Ok(())
     }
 }
 
@@ -105,4 +137,20 @@ mod tests {
 
         assert_eq!(index.len(), 2);
     }
+
+    #[test]
+    fn test_write_tsv() {
+        let mut index = SequenceIndex::new();
+        index.get_or_insert_with_length("chr1", 1000);
+        index.get_or_insert("chr2");
+        index.get_or_insert_with_length("chr2", 500);
+        index.get_or_insert_with_length("chr1", 7);
+
+        let mut out = Vec::new();
+        index.write_tsv(&mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "#id\tname\tlength\n0\tchr1\t1000\n1\tchr2\t500\n"
+        );
+    }
 }
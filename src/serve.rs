@@ -0,0 +1,256 @@
+//! Long-lived filter server (`--serve`)
+//!
+//! Workflow engines that filter many small PAFs pay process startup (embedded
+//! binary extraction, rayon pool creation) once per job. In serve mode sweepga
+//! reads filter requests from stdin, one per line, and answers each on stdout.
+//!
+//! Request (tab-separated, so paths may contain spaces):
+//!
+//! ```text
+//! <input.paf>\t<output.paf>[\t<key>=<value>]...
+//! ```
+//!
+//! Supported overrides: `overlap`, `scoring`, `min-aln-length`,
+//! `min-aln-identity`, `scaffold-jump`, `scaffold-mass`, `min-scaffold-count`,
+//! `scaffold-overlap`, `scaffold-dist`. Anything not overridden uses the command-line settings.
+//!
+//! Response: `ok\t<output>\t<records written>` or `error\t<message>`.
+//! Blank lines and lines starting with `#` are ignored; EOF or `quit` stops.
+
+use anyhow::{Context, Result};
+use std::io::{BufRead, Write};
+use std::path::PathBuf;
+
+use crate::cli::{parse_identity_value, parse_metric_number};
+use crate::paf_filter::{FilterConfig, PafFilter};
+
+/// A single parsed serve-mode request
+#[derive(Debug)]
+pub struct ServeRequest {
+    pub input: PathBuf,
+    pub output: PathBuf,
+    pub overrides: Vec<(String, String)>,
+}
+
+/// Parse one protocol line. Returns `Ok(None)` for blank and comment lines.
+pub fn parse_request(line: &str) -> Result<Option<ServeRequest>> {
+    let line = line.trim_end_matches(['\r', '\n']);
+    if line.trim().is_empty() || line.starts_with('#') {
+        return Ok(None);
+    }
+
+    let mut fields = line.split('\t');
+    let input = fields.next().unwrap_or_default();
+    let output = fields
+        .next()
+        .context("Expected '<input>\\t<output>[\\t<key>=<value>...]'")?;
+
+    let overrides = fields
+        .filter(|f| !f.is_empty())
+        .map(|f| {
+            f.split_once('=')
+                .map(|(k, v)| (k.trim().to_string(), v.trim().to_string()))
+                .with_context(|| format!("Override '{f}' is not of the form key=value"))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(Some(ServeRequest {
+        input: PathBuf::from(input),
+        output: PathBuf::from(output),
+        overrides,
+    }))
+}
+
+/// Apply one `key=value` override on top of the base filter config
+pub fn apply_override(config: &mut FilterConfig, key: &str, value: &str) -> Result<()> {
+    let metric = |v: &str| parse_metric_number(v).map_err(|e| anyhow::anyhow!("{key}: {e}"));
+    let float = |v: &str| {
+        v.parse::<f64>()
+            .with_context(|| format!("{key}: invalid number"))
+    };
+
+    match key {
+        "overlap" => config.overlap_threshold = float(value)?,
+        "scoring" => config.scoring_function = value.parse().map_err(anyhow::Error::msg)?,
+        "min-aln-length" => config.min_block_length = metric(value)?,
+        "min-aln-identity" => config.min_identity = parse_identity_value(value, None)?,
+        "scaffold-jump" => {
+            config.scaffold_gap = metric(value)?;
+            config.chain_gap = config.scaffold_gap;
+        }
+        "scaffold-mass" => config.min_scaffold_length = metric(value)?,
+        "min-scaffold-count" => {
+            config.min_scaffold_count = value
+                .parse()
+                .with_context(|| format!("{key}: invalid count"))?
+        }
+        "scaffold-overlap" => config.scaffold_overlap_threshold = float(value)?,
+        "scaffold-dist" => config.scaffold_max_deviation = metric(value)?,
+        _ => anyhow::bail!("Unknown override '{key}'"),
+    }
+    Ok(())
+}
+
+/// Run one request and return the number of records written
+fn handle_request<F>(request: &ServeRequest, base: &FilterConfig, make_filter: &F) -> Result<usize>
+where
+    F: Fn(FilterConfig) -> PafFilter,
+{
+    let mut config = base.clone();
+    for (key, value) in &request.overrides {
+        apply_override(&mut config, key, value)?;
+    }
+
+    make_filter(config)
+        .filter_paf(&request.input, &request.output)
+        .with_context(|| format!("Failed to filter {}", request.input.display()))
+}
+
+/// What one [`serve`] session did
+#[derive(Debug, Default)]
+pub struct ServeSummary {
+    /// Requests answered, failed ones included
+    pub handled: usize,
+    /// `(input, output)` files of the requests that succeeded, in order
+    pub filtered: Vec<(PathBuf, PathBuf)>,
+}
+
+/// Serve filter requests from `reader` until EOF or `quit`, writing one
+/// response line per request to `writer`. Failed requests are reported
+/// and do not stop the server.
+pub fn serve<R, W, F>(
+    reader: R,
+    mut writer: W,
+    base: &FilterConfig,
+    make_filter: F,
+) -> Result<ServeSummary>
+where
+    R: BufRead,
+    W: Write,
+    F: Fn(FilterConfig) -> PafFilter,
+{
+    let mut summary = ServeSummary::default();
+
+    for line in reader.lines() {
+        let line = line?;
+        if line.trim() == "quit" {
+            break;
+        }
+
+        let response = match parse_request(&line) {
+            Ok(None) => continue,
+            Ok(Some(request)) => match handle_request(&request, base, &make_filter) {
+                Ok(records) => {
+                    let response = format!("ok\t{}\t{records}", request.output.display());
+                    summary.filtered.push((request.input, request.output));
+                    response
+                }
+                Err(e) => format!("error\t{e:#}"),
+            },
+            Err(e) => format!("error\t{e:#}"),
+        };
+        summary.handled += 1;
+
+        writeln!(writer, "{response}")?;
+        writer.flush()?;
+    }
+
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn base_config() -> FilterConfig {
+        FilterConfig {
+            chain_gap: 0,
+            scaffold_gap: 0,
+            min_scaffold_length: 0,
+            scaffold_overlap_threshold: 0.95,
+            ..FilterConfig::default()
+        }
+    }
+
+    #[test]
+    fn test_parse_request() {
+        assert!(parse_request("").unwrap().is_none());
+        assert!(parse_request("# comment").unwrap().is_none());
+        assert!(parse_request("only_input.paf").is_err());
+        assert!(parse_request("a.paf\tb.paf\tnot-a-pair").is_err());
+
+        let req = parse_request("my in.paf\tout.paf\tmin-aln-length=1k\n")
+            .unwrap()
+            .unwrap();
+        assert_eq!(req.input, PathBuf::from("my in.paf"));
+        assert_eq!(req.output, PathBuf::from("out.paf"));
+        assert_eq!(
+            req.overrides,
+            vec![("min-aln-length".to_string(), "1k".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_serve_two_sequential_requests() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("in.paf");
+        std::fs::write(
+            &input,
+            "q1\t10000\t0\t1000\t+\tt1\t10000\t0\t1000\t950\t1000\t60\n\
+             q1\t10000\t2000\t2500\t+\tt2\t10000\t0\t500\t475\t500\t60\n",
+        )
+        .unwrap();
+        let out1 = dir.path().join("out1.paf");
+        let out2 = dir.path().join("out2.paf");
+
+        // Second request overrides the minimum length to drop the 500bp mapping
+        let requests = format!(
+            "{}\t{}\n\n{}\t{}\tmin-aln-length=800\nquit\n",
+            input.display(),
+            out1.display(),
+            input.display(),
+            out2.display()
+        );
+
+        let mut responses = Vec::new();
+        let summary = serve(
+            Cursor::new(requests),
+            &mut responses,
+            &base_config(),
+            PafFilter::new,
+        )
+        .unwrap();
+        assert_eq!(summary.handled, 2);
+        assert_eq!(
+            summary.filtered,
+            vec![(input.clone(), out1.clone()), (input.clone(), out2.clone())]
+        );
+
+        let responses = String::from_utf8(responses).unwrap();
+        let lines: Vec<&str> = responses.lines().collect();
+        assert_eq!(lines[0], format!("ok\t{}\t2", out1.display()));
+        assert_eq!(lines[1], format!("ok\t{}\t1", out2.display()));
+
+        assert_eq!(std::fs::read_to_string(&out1).unwrap().lines().count(), 2);
+        assert_eq!(std::fs::read_to_string(&out2).unwrap().lines().count(), 1);
+    }
+
+    #[test]
+    fn test_serve_reports_errors_and_continues() {
+        let requests = "missing.paf\tout.paf\tbogus=1\nmissing.paf\n";
+        let mut responses = Vec::new();
+        let summary = serve(
+            Cursor::new(requests),
+            &mut responses,
+            &base_config(),
+            PafFilter::new,
+        )
+        .unwrap();
+
+        assert_eq!(summary.handled, 2);
+        assert!(summary.filtered.is_empty());
+        let responses = String::from_utf8(responses).unwrap();
+        assert!(responses.lines().all(|l| l.starts_with("error\t")));
+    }
+}
@@ -12,15 +12,9 @@ use std::collections::{HashMap, HashSet};
 
 /// Extract PanSN genome prefix from sequence name
 /// Example: "HG002#1#chr1" -> "HG002#1#"
-fn extract_genome_prefix(seq_name: &str) -> String {
-    // Look for PanSN format: genome#haplotype#chromosome
-    let parts: Vec<&str> = seq_name.split('#').collect();
-    if parts.len() >= 2 {
-        format!("{}#{}#", parts[0], parts[1])
-    } else {
-        // Fallback: use whole name as genome
-        seq_name.to_string()
-    }
+fn extract_genome_prefix(seq_name: &str, delimiter: char, prefix_depth: usize) -> String {
+    // Non-PanSN names fall back to the whole name as genome
+    crate::pansn::genome_prefix(seq_name, delimiter, prefix_depth).to_string()
 }
 
 /// PAF alignment record (minimal fields needed for filtering)
@@ -36,12 +30,16 @@ pub(crate) struct PafAlignment {
 
 /// Build genome-pair identity matrix from PAF alignments
 /// Returns map of (genome1, genome2) -> weighted average identity
-pub(crate) fn build_identity_matrix(alignments: &[PafAlignment]) -> HashMap<(String, String), f64> {
+pub(crate) fn build_identity_matrix(
+    alignments: &[PafAlignment],
+    delimiter: char,
+    prefix_depth: usize,
+) -> HashMap<(String, String), f64> {
     let mut genome_pairs: HashMap<(String, String), (f64, f64)> = HashMap::new();
 
     for aln in alignments {
-        let query_genome = extract_genome_prefix(&aln.query_name);
-        let target_genome = extract_genome_prefix(&aln.target_name);
+        let query_genome = extract_genome_prefix(&aln.query_name, delimiter, prefix_depth);
+        let target_genome = extract_genome_prefix(&aln.target_name, delimiter, prefix_depth);
 
         // Skip self-comparisons
         if query_genome == target_genome {
@@ -166,9 +164,11 @@ pub(crate) fn filter_tree_based(
     k_nearest: usize,
     k_farthest: usize,
     random_fraction: f64,
+    delimiter: char,
+    prefix_depth: usize,
 ) -> Vec<usize> {
     // Build identity matrix from alignments
-    let identity_matrix = build_identity_matrix(alignments);
+    let identity_matrix = build_identity_matrix(alignments, delimiter, prefix_depth);
 
     // Select genome pairs to keep
     let selected_pairs =
@@ -177,8 +177,8 @@ pub(crate) fn filter_tree_based(
     // Filter alignments based on selected pairs
     let mut keep_indices = Vec::new();
     for (i, aln) in alignments.iter().enumerate() {
-        let query_genome = extract_genome_prefix(&aln.query_name);
-        let target_genome = extract_genome_prefix(&aln.target_name);
+        let query_genome = extract_genome_prefix(&aln.query_name, delimiter, prefix_depth);
+        let target_genome = extract_genome_prefix(&aln.target_name, delimiter, prefix_depth);
 
         // Skip self-comparisons
         if query_genome == target_genome {
@@ -208,6 +208,8 @@ pub fn apply_tree_filter_to_paf(
     k_nearest: usize,
     k_farthest: usize,
     random_fraction: f64,
+    delimiter: char,
+    prefix_depth: usize,
 ) -> Result<()> {
     use std::fs::File;
     use std::io::{BufRead, BufReader, Write};
@@ -253,7 +255,14 @@ pub fn apply_tree_filter_to_paf(
     // );
 
     // Apply tree filtering
-    let keep_indices = filter_tree_based(&alignments, k_nearest, k_farthest, random_fraction);
+    let keep_indices = filter_tree_based(
+        &alignments,
+        k_nearest,
+        k_farthest,
+        random_fraction,
+        delimiter,
+        prefix_depth,
+    );
 
     // eprintln!(
     //     "[sweepga] Tree filtering: keeping {} alignments (tree:{}{}{})",
@@ -283,12 +292,15 @@ pub fn apply_tree_filter_to_paf(
 
 /// Apply tree-based filtering directly to .1aln file (native format, no conversion)
 /// Reads .1aln alignments, builds identity matrix, selects tree pairs, writes filtered .1aln
+#[allow(clippy::too_many_arguments)]
 pub fn apply_tree_filter_to_1aln(
     input_path: &str,
     output_path: &str,
     k_nearest: usize,
     k_farthest: usize,
     random_fraction: f64,
+    delimiter: char,
+    prefix_depth: usize,
     quiet: bool,
 ) -> Result<()> {
     use crate::aln_filter::AlnFilterReader;
@@ -299,8 +311,8 @@ pub fn apply_tree_filter_to_1aln(
     let mut genome_pairs: HashMap<(String, String), (f64, f64)> = HashMap::new();
 
     while let Some(aln) = reader.read_alignment()? {
-        let query_genome = extract_genome_prefix(&aln.query_name);
-        let target_genome = extract_genome_prefix(&aln.target_name);
+        let query_genome = extract_genome_prefix(&aln.query_name, delimiter, prefix_depth);
+        let target_genome = extract_genome_prefix(&aln.target_name, delimiter, prefix_depth);
 
         // Skip self-comparisons
         if query_genome != target_genome {
@@ -349,8 +361,8 @@ pub fn apply_tree_filter_to_1aln(
         .iter()
         .enumerate()
         .filter_map(|(idx, aln)| {
-            let query_genome = extract_genome_prefix(&aln.query_name);
-            let target_genome = extract_genome_prefix(&aln.target_name);
+            let query_genome = extract_genome_prefix(&aln.query_name, delimiter, prefix_depth);
+            let target_genome = extract_genome_prefix(&aln.target_name, delimiter, prefix_depth);
 
             // Skip self-comparisons
             if query_genome == target_genome {
@@ -445,9 +457,12 @@ mod tests {
 
     #[test]
     fn test_extract_genome_prefix() {
-        assert_eq!(extract_genome_prefix("HG002#1#chr1"), "HG002#1#");
-        assert_eq!(extract_genome_prefix("HG002#2#chr2"), "HG002#2#");
-        assert_eq!(extract_genome_prefix("NA12878#1#chrX"), "NA12878#1#");
-        assert_eq!(extract_genome_prefix("simple"), "simple");
+        assert_eq!(extract_genome_prefix("HG002#1#chr1", '#', 0), "HG002#1#");
+        assert_eq!(extract_genome_prefix("HG002#2#chr2", '#', 0), "HG002#2#");
+        assert_eq!(
+            extract_genome_prefix("NA12878#1#chrX", '#', 0),
+            "NA12878#1#"
+        );
+        assert_eq!(extract_genome_prefix("simple", '#', 0), "simple");
     }
 }
@@ -0,0 +1,299 @@
+//! Query-axis overlap trimming (`--trim-overlaps`)
+//!
+//! Unlike every other filter in sweepga, which only keeps or drops whole
+//! records, trimming MODIFIES coordinates: after filtering, kept mappings are
+//! clipped so that no two overlap on the same query sequence, producing a
+//! clean partition of each query. Higher-scoring mappings claim their span
+//! first; lower-scoring ones are cut back to their longest unclaimed piece or
+//! dropped when fully covered.
+//!
+//! Target coordinates, matches, block length and `cg:Z:` CIGARs are adjusted
+//! to match. Without a CIGAR the target side is clipped proportionally (an
+//! approximation). `cs:Z:` strings are removed from trimmed records since
+//! they can no longer be trusted.
+
+use anyhow::{Context, Result};
+use std::collections::HashMap;
+use std::fmt::Write;
+
+/// A kept mapping as seen by the trimming sweep
+#[derive(Debug, Clone)]
+pub struct TrimCandidate<'a> {
+    pub rank: usize,
+    pub query_name: &'a str,
+    pub query_start: u64,
+    pub query_end: u64,
+    pub score: f64,
+}
+
+/// Assign each mapping a non-overlapping query span.
+///
+/// Returns `rank -> Some((start, end))` with the (possibly unchanged) span, or
+/// `None` for mappings entirely covered by better-scoring ones.
+pub fn compute_query_trims(candidates: &[TrimCandidate]) -> HashMap<usize, Option<(u64, u64)>> {
+    let mut by_query: HashMap<&str, Vec<&TrimCandidate>> = HashMap::new();
+    for c in candidates {
+        by_query.entry(c.query_name).or_default().push(c);
+    }
+
+    let mut trims = HashMap::new();
+    for (_query, mut group) in by_query {
+        // Best first; rank breaks ties so the output is deterministic
+        group.sort_by(|a, b| b.score.total_cmp(&a.score).then(a.rank.cmp(&b.rank)));
+
+        // Claimed spans, kept sorted by start and non-overlapping
+        let mut claimed: Vec<(u64, u64)> = Vec::new();
+        for c in group {
+            let span = longest_free_span(&claimed, c.query_start, c.query_end);
+            if let Some((s, e)) = span {
+                let pos = claimed.partition_point(|&(cs, _)| cs < s);
+                claimed.insert(pos, (s, e));
+            }
+            trims.insert(c.rank, span);
+        }
+    }
+    trims
+}
+
+/// Longest piece of `[start, end)` not covered by any claimed span
+fn longest_free_span(claimed: &[(u64, u64)], start: u64, end: u64) -> Option<(u64, u64)> {
+    let mut best: Option<(u64, u64)> = None;
+    let mut cursor = start;
+    let mut consider = |s: u64, e: u64| {
+        if e > s && best.is_none_or(|(bs, be)| e - s > be - bs) {
+            best = Some((s, e));
+        }
+    };
+
+    for &(cs, ce) in claimed {
+        if ce <= cursor {
+            continue;
+        }
+        if cs >= end {
+            break;
+        }
+        consider(cursor, cs.min(end));
+        cursor = cursor.max(ce);
+        if cursor >= end {
+            break;
+        }
+    }
+    if cursor < end {
+        consider(cursor, end);
+    }
+    best
+}
+
+/// Parse a CIGAR into (length, op) pairs
+pub(crate) fn parse_cigar_ops(cigar: &str) -> Result<Vec<(u64, char)>> {
+    let mut ops = Vec::new();
+    let mut num = 0u64;
+    let mut has_digits = false;
+    for ch in cigar.chars() {
+        if let Some(d) = ch.to_digit(10) {
+            num = num * 10 + u64::from(d);
+            has_digits = true;
+        } else {
+            anyhow::ensure!(has_digits, "Invalid CIGAR: {cigar}");
+            ops.push((num, ch));
+            num = 0;
+            has_digits = false;
+        }
+    }
+    Ok(ops)
+}
+
+fn consumes_query(op: char) -> bool {
+    matches!(op, 'M' | '=' | 'X' | 'I')
+}
+
+fn consumes_target(op: char) -> bool {
+    matches!(op, 'M' | '=' | 'X' | 'D')
+}
+
+/// Remove `query_bases` query-consuming bases from the front of `ops`
+/// (plus any deletions left dangling at the new start). Returns the number
+/// of target bases removed.
+fn clip_cigar_front(ops: &mut Vec<(u64, char)>, query_bases: u64) -> u64 {
+    let mut remaining = query_bases;
+    let mut target_clipped = 0u64;
+    let mut i = 0;
+
+    while i < ops.len() && (remaining > 0 || ops[i].1 == 'D') {
+        let (len, op) = ops[i];
+        let take = if consumes_query(op) {
+            len.min(remaining)
+        } else {
+            len
+        };
+        if consumes_query(op) {
+            remaining -= take;
+        }
+        if consumes_target(op) {
+            target_clipped += take;
+        }
+        if take == len {
+            i += 1;
+        } else {
+            ops[i].0 -= take;
+            break;
+        }
+    }
+    ops.drain(..i);
+    target_clipped
+}
+
+/// Same as `clip_cigar_front`, from the end of the alignment
+fn clip_cigar_back(ops: &mut Vec<(u64, char)>, query_bases: u64) -> u64 {
+    ops.reverse();
+    let target_clipped = clip_cigar_front(ops, query_bases);
+    ops.reverse();
+    target_clipped
+}
+
+/// Rewrite one PAF line so its query span becomes `[new_start, new_end)`.
+pub fn trim_paf_line(line: &str, new_start: u64, new_end: u64) -> Result<String> {
+    let mut fields: Vec<String> = line.split('\t').map(String::from).collect();
+    anyhow::ensure!(fields.len() >= 12, "PAF record has fewer than 12 columns");
+
+    let num = |i: usize| -> Result<u64> {
+        fields[i]
+            .parse::<u64>()
+            .with_context(|| format!("Invalid PAF column {}: {}", i + 1, fields[i]))
+    };
+    let (q_start, q_end) = (num(2)?, num(3)?);
+    let (t_start, t_end) = (num(7)?, num(8)?);
+    let (matches, block_len) = (num(9)?, num(10)?);
+    let reverse = fields[4] == "-";
+
+    anyhow::ensure!(
+        q_start <= new_start && new_start < new_end && new_end <= q_end,
+        "Trim span {new_start}-{new_end} outside record span {q_start}-{q_end}"
+    );
+    if (new_start, new_end) == (q_start, q_end) {
+        return Ok(line.to_string());
+    }
+
+    let left = new_start - q_start;
+    let right = q_end - new_end;
+
+    // CIGARs run along the forward target; on '-' they traverse the query backwards
+    let (cigar_front, cigar_back) = if reverse {
+        (right, left)
+    } else {
+        (left, right)
+    };
+
+    let cigar_idx = fields.iter().position(|f| f.starts_with("cg:Z:"));
+    let (new_t_start, new_t_end, new_matches, new_block_len) = if let Some(idx) = cigar_idx {
+        let mut ops = parse_cigar_ops(&fields[idx][5..])?;
+        let original_m: u64 = ops.iter().filter(|o| o.1 == 'M').map(|o| o.0).sum();
+        let t_front = clip_cigar_front(&mut ops, cigar_front);
+        let t_back = clip_cigar_back(&mut ops, cigar_back);
+
+        let exact: u64 = ops.iter().filter(|o| o.1 == '=').map(|o| o.0).sum();
+        let kept_m: u64 = ops.iter().filter(|o| o.1 == 'M').map(|o| o.0).sum();
+        // 'M' hides matches vs mismatches: scale the original count instead
+        let approx = if original_m > 0 {
+            (matches as f64 * kept_m as f64 / original_m as f64).round() as u64
+        } else {
+            0
+        };
+        let new_block_len: u64 = ops.iter().map(|o| o.0).sum();
+
+        fields[idx] = ops.iter().fold(String::from("cg:Z:"), |mut cigar, (l, o)| {
+            let _ = write!(cigar, "{l}{o}");
+            cigar
+        });
+        (
+            t_start + t_front,
+            t_end - t_back,
+            exact + approx,
+            new_block_len,
+        )
+    } else {
+        // No CIGAR: clip the target side in proportion to the query
+        let q_len = (q_end - q_start) as f64;
+        let t_len = (t_end - t_start) as f64;
+        let (t_front_frac, t_back_frac) = if reverse {
+            (right as f64 / q_len, left as f64 / q_len)
+        } else {
+            (left as f64 / q_len, right as f64 / q_len)
+        };
+        let kept_frac = (new_end - new_start) as f64 / q_len;
+        (
+            t_start + (t_len * t_front_frac).round() as u64,
+            t_end - (t_len * t_back_frac).round() as u64,
+            (matches as f64 * kept_frac).round() as u64,
+            ((block_len as f64 * kept_frac).round() as u64).max(1),
+        )
+    };
+
+    fields[2] = new_start.to_string();
+    fields[3] = new_end.to_string();
+    fields[7] = new_t_start.to_string();
+    fields[8] = new_t_end.to_string();
+    fields[9] = new_matches.to_string();
+    fields[10] = new_block_len.to_string();
+    fields.retain(|f| !f.starts_with("cs:Z:"));
+
+    Ok(fields.join("\t"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn candidate(rank: usize, start: u64, end: u64, score: f64) -> TrimCandidate<'static> {
+        TrimCandidate {
+            rank,
+            query_name: "q1",
+            query_start: start,
+            query_end: end,
+            score,
+        }
+    }
+
+    #[test]
+    fn test_lower_score_is_trimmed() {
+        let trims = compute_query_trims(&[
+            candidate(0, 0, 1000, 10.0),
+            candidate(1, 800, 1600, 5.0),
+            candidate(2, 100, 900, 1.0), // fully covered by the other two
+        ]);
+        assert_eq!(trims[&0], Some((0, 1000)));
+        assert_eq!(trims[&1], Some((1000, 1600)));
+        assert_eq!(trims[&2], None);
+    }
+
+    #[test]
+    fn test_contained_better_mapping_keeps_longest_piece() {
+        let trims =
+            compute_query_trims(&[candidate(0, 400, 600, 10.0), candidate(1, 0, 1000, 5.0)]);
+        assert_eq!(trims[&0], Some((400, 600)));
+        assert_eq!(trims[&1], Some((600, 1000)));
+    }
+
+    #[test]
+    fn test_trim_paf_line_with_cigar() {
+        let line = "q1\t5000\t800\t1600\t+\tt1\t9000\t5000\t5810\t800\t810\t60\tcg:Z:100=10D700=";
+        let trimmed = trim_paf_line(line, 1000, 1600).unwrap();
+        let f: Vec<&str> = trimmed.split('\t').collect();
+        assert_eq!((f[2], f[3]), ("1000", "1600"));
+        // 200 query bases = 100= + 10D + 100= on the target
+        assert_eq!((f[7], f[8]), ("5210", "5810"));
+        assert_eq!((f[9], f[10]), ("600", "600"));
+        assert_eq!(f[12], "cg:Z:600=");
+    }
+
+    #[test]
+    fn test_trim_paf_line_reverse_strand_without_cigar() {
+        let line = "q1\t5000\t0\t1000\t-\tt1\t9000\t2000\t3000\t900\t1000\t60\tcs:Z::1000";
+        let trimmed = trim_paf_line(line, 0, 600).unwrap();
+        let f: Vec<&str> = trimmed.split('\t').collect();
+        // Clipping the query end removes the target start on '-'
+        assert_eq!((f[7], f[8]), ("2400", "3000"));
+        assert_eq!((f[9], f[10]), ("540", "600"));
+        assert_eq!(f.len(), 12, "cs:Z: must be dropped");
+    }
+}
@@ -12,22 +12,30 @@
 
 use anyhow::{Context, Result};
 use std::collections::HashMap;
+use std::io::Write;
 use std::path::Path;
 
 use crate::mapping::ChainStatus;
-use crate::paf_filter::{FilterConfig, PafFilter, RecordMeta};
+use crate::paf_filter::{FilterConfig, FilterStats, PafFilter, RecordMeta};
+use crate::sequence_index::SequenceIndex;
 
-/// Extract RecordMeta from .1aln file (analogous to PAF extract_metadata)
+/// Extract RecordMeta from .1aln file (analogous to PAF extract_metadata), with
+/// the index of the records' sequence IDs. .1aln has no MAPQ, so every record
+/// gets `default_mapq` (`--default-mapq`)
 pub fn extract_1aln_metadata<P: AsRef<Path>>(
     path: P,
-) -> Result<(Vec<RecordMeta>, HashMap<String, i64>)> {
+    default_mapq: u8,
+) -> Result<(Vec<RecordMeta>, SequenceIndex)> {
     let path_str = path.as_ref().to_str().context("Invalid path")?;
 
     // Open .1aln reader
     let mut reader = fastga_rs::AlnReader::open(path_str).context("Failed to open .1aln file")?;
 
-    // Get all sequence names upfront (efficient bulk lookup)
+    // Get all query sequence names upfront (efficient bulk lookup). Targets
+    // are looked up in the second GDB, which differs from the first when the
+    // .1aln aligns two FASTA files, and cached by ID.
     let id_to_name = reader.get_all_seq_names();
+    let mut target_id_to_name: HashMap<i64, String> = HashMap::new();
 
     // WORKAROUND: onecode-rs get_all_contig_offsets() consumes the first 'A' record
     // and fails to properly restore file position. We need to explicitly seek to
@@ -41,12 +49,6 @@ pub fn extract_1aln_metadata<P: AsRef<Path>>(
         );
     }
 
-    // Build reverse mapping for writing
-    let mut name_to_id = HashMap::new();
-    for (id, name) in &id_to_name {
-        name_to_id.insert(name.clone(), *id);
-    }
-
     // log::info!(
     //     "[unified_filter] Loaded {} sequence names from .1aln",
     //     id_to_name.len()
@@ -54,6 +56,7 @@ pub fn extract_1aln_metadata<P: AsRef<Path>>(
 
     // Read all alignments and create RecordMeta
     let mut metadata = Vec::new();
+    let mut names = SequenceIndex::new();
     let mut rank = 0;
 
     // Note: fastga-rs AlnReader correctly reads X records and calculates identity
@@ -73,23 +76,28 @@ pub fn extract_1aln_metadata<P: AsRef<Path>>(
             .get(&query_id_num)
             .cloned()
             .unwrap_or_else(|| aln.query_name.clone());
-        let target_name_full = id_to_name
-            .get(&target_id_num)
-            .cloned()
-            .unwrap_or_else(|| aln.target_name.clone());
+        let target_name_full = match target_id_to_name.get(&target_id_num) {
+            Some(name) => name.clone(),
+            None if target_id_num < 0 => aln.target_name.clone(),
+            None => {
+                let name = reader
+                    .get_seq_name(target_id_num, 1)
+                    .unwrap_or_else(|_| aln.target_name.clone());
+                target_id_to_name.insert(target_id_num, name.clone());
+                name
+            }
+        };
 
         // Truncate to first word (before first space) to match PAF format
         // FASTA headers can contain descriptions after the ID, but PAF only uses the ID
         let query_name = query_name_full
             .split_whitespace()
             .next()
-            .unwrap_or(&query_name_full)
-            .to_string();
+            .unwrap_or(&query_name_full);
         let target_name = target_name_full
             .split_whitespace()
             .next()
-            .unwrap_or(&target_name_full)
-            .to_string();
+            .unwrap_or(&target_name_full);
 
         // Calculate identity and matches from .1aln data
         //
@@ -125,14 +133,16 @@ pub fn extract_1aln_metadata<P: AsRef<Path>>(
         // Create RecordMeta (same structure as PAF filter!)
         metadata.push(RecordMeta {
             rank,
-            query_name,
-            target_name,
+            query_id: names.get_or_insert(query_name),
+            target_id: names.get_or_insert(target_name),
             query_start: aln.query_start as u64,
             query_end: aln.query_end as u64,
             target_start: aln.target_start as u64,
             target_end: aln.target_end as u64,
             block_length,
             identity,
+            gap_compressed_identity: identity, // No CIGAR to compress gaps from
+            mapq: default_mapq,                // .1aln carries no mapping quality
             matches,
             alignment_length: block_length, // Total alignment length including gaps
             strand: aln.strand,
@@ -140,6 +150,9 @@ pub fn extract_1aln_metadata<P: AsRef<Path>>(
             chain_status: ChainStatus::Unassigned,
             discard: false,
             overlapped: false,
+            sweep_rank: None,
+            secondary: false,
+            inversions: None,
         });
 
         rank += 1;
@@ -150,7 +163,7 @@ pub fn extract_1aln_metadata<P: AsRef<Path>>(
     //     metadata.len()
     // );
 
-    Ok((metadata, name_to_id))
+    Ok((metadata, names))
 }
 
 /// Write filtered .1aln using passing ranks
@@ -159,7 +172,6 @@ pub fn write_1aln_filtered<P1: AsRef<Path>, P2: AsRef<Path>>(
     input_path: P1,
     output_path: P2,
     passing_ranks: &HashMap<usize, RecordMeta>,
-    _name_to_id: &HashMap<String, i64>,
 ) -> Result<()> {
     let path_str = input_path.as_ref().to_str().context("Invalid path")?;
 
@@ -276,14 +288,85 @@ pub fn write_1aln_filtered<P1: AsRef<Path>, P2: AsRef<Path>>(
     Ok(())
 }
 
-/// Main unified filtering function - works for both .1aln and PAF
+/// Write the records of the .1aln `input_path` at `passing_ranks` to
+/// `output_path` keeping their trace points, which `write_1aln_filtered`
+/// drops: the input goes through `ALNtoPAF -x` (CIGARs rebuilt from the trace
+/// points), the passing lines are kept, and `PAFtoALN` turns their CIGARs
+/// back into trace points. Needs the input's `.1gdb` next to it; returns
+/// false without writing anything if it is missing or the PAF does not have
+/// one line per input record (`records`).
+pub fn write_1aln_filtered_with_traces(
+    input_path: &Path,
+    output_path: &Path,
+    passing_ranks: &HashMap<usize, RecordMeta>,
+    records: usize,
+) -> Result<bool> {
+    let gdb_path = input_path.with_extension("1gdb");
+    if !gdb_path.exists() {
+        log::warn!(
+            "[sweepga] No {} next to the .1aln input: filtered .1aln written without \
+             trace points",
+            gdb_path.display()
+        );
+        return Ok(false);
+    }
+
+    // PAFtoALN writes <stem>.1aln next to its input
+    let temp_dir = tempfile::tempdir()?;
+    let filtered_paf = temp_dir.path().join("filtered.paf");
+    let mut writer = std::io::BufWriter::new(std::fs::File::create(&filtered_paf)?);
+
+    // Stream ALNtoPAF's output, keeping the passing lines by rank
+    let threads = rayon::current_num_threads();
+    let mut lines = 0;
+    crate::fastga_integration::for_each_aln_paf_line(input_path, threads, |line| {
+        if passing_ranks.contains_key(&lines) {
+            writeln!(writer, "{line}")?;
+        }
+        lines += 1;
+        Ok(())
+    })?;
+    writer.flush()?;
+    drop(writer);
+
+    if lines != records {
+        log::warn!(
+            "[sweepga] ALNtoPAF wrote {lines} lines for {records} .1aln records: filtered \
+             .1aln written without trace points"
+        );
+        return Ok(false);
+    }
+
+    let aln_path =
+        crate::fastga_integration::paf_to_aln(&filtered_paf, &[gdb_path], threads, true)?;
+    std::fs::copy(&aln_path, output_path)
+        .with_context(|| format!("Failed to write {}", output_path.display()))?;
+    Ok(true)
+}
+
+/// Main unified filtering function - works for both .1aln and PAF.
+/// Returns the number of records written.
 pub fn filter_file<P1: AsRef<Path>, P2: AsRef<Path>>(
     input_path: P1,
     output_path: P2,
     config: &FilterConfig,
     force_paf_output: bool,
     keep_self: bool,
-) -> Result<()> {
+) -> Result<usize> {
+    let filter = PafFilter::new(config.clone()).with_keep_self(keep_self);
+    filter_file_with(input_path, output_path, &filter, force_paf_output).map(|(written, _)| written)
+}
+
+/// [`filter_file`] with a fully configured `filter`, also returning the
+/// counts of each filtering stage. Only its record selection
+/// (`PafFilter::apply_filters`) applies to .1aln input; options that rewrite
+/// or annotate records are left to the caller to reject.
+pub fn filter_file_with<P1: AsRef<Path>, P2: AsRef<Path>>(
+    input_path: P1,
+    output_path: P2,
+    filter: &PafFilter,
+    force_paf_output: bool,
+) -> Result<(usize, FilterStats)> {
     let input_str = input_path.as_ref().to_str().context("Invalid input path")?;
 
     // Determine input format by checking file content
@@ -305,15 +388,16 @@ pub fn filter_file<P1: AsRef<Path>, P2: AsRef<Path>>(
         }
     };
 
-    if is_1aln {
+    let result = if is_1aln {
         // .1aln input workflow
         // log::info!("[unified_filter] Reading .1aln metadata...");
-        let (metadata, name_to_id) = extract_1aln_metadata(&input_path)?;
+        let (metadata, names) = extract_1aln_metadata(&input_path, filter.config().default_mapq)?;
+        let records = metadata.len();
 
         // Use SAME filtering logic as PAF!
         // log::info!("[unified_filter] Applying filters...");
-        let filter = PafFilter::new(config.clone()).with_keep_self(keep_self);
-        let passing_ranks = filter.apply_filters(metadata)?;
+        let (passing_ranks, stats) = filter.apply_filters(metadata, &names)?;
+        stats.log_summary();
 
         // log::info!(
         //     "[unified_filter] {} records passed filtering",
@@ -328,8 +412,18 @@ pub fn filter_file<P1: AsRef<Path>, P2: AsRef<Path>>(
         let output_1aln = !force_paf_output && !output_str.ends_with(".paf");
 
         if output_1aln {
-            // Write .1aln output
-            write_1aln_filtered(&input_path, &output_path, &passing_ranks, &name_to_id)?;
+            // Write .1aln output (header included even if nothing passed, so it stays valid)
+            if passing_ranks.is_empty()
+                || !write_1aln_filtered_with_traces(
+                    input_path.as_ref(),
+                    output_path.as_ref(),
+                    &passing_ranks,
+                    records,
+                )?
+            {
+                write_1aln_filtered(&input_path, &output_path, &passing_ranks)?;
+            }
+            (passing_ranks.len(), stats)
         } else {
             // Write PAF output - need to convert .1aln → PAF first
             // For now, use existing ALNtoPAF tool or implement direct conversion
@@ -337,13 +431,12 @@ pub fn filter_file<P1: AsRef<Path>, P2: AsRef<Path>>(
         }
     } else {
         // PAF input workflow - use existing PAF filter directly
-        let filter = PafFilter::new(config.clone()).with_keep_self(keep_self);
         let input_str = input_path.as_ref();
         let output_str = output_path.as_ref();
-        filter.filter_paf(input_str, output_str)?;
-    }
+        filter.filter_paf_with_stats(input_str, output_str)?
+    };
 
-    Ok(())
+    Ok(result)
 }
 
 #[cfg(test)]
@@ -381,6 +474,7 @@ mod tests {
             scoring_function: ScoringFunction::LogLengthIdentity,
             min_identity: 0.0,
             min_scaffold_identity: 0.0,
+            ..FilterConfig::default()
         };
 
         filter_file(
@@ -37,6 +37,8 @@ impl WfmashIntegration {
     /// `avg_seq_len`: used to compute adaptive segment length as `avg_seq_len / 2` (capped at 5000).
     /// `sparsify`: fraction of mappings to keep (wfmash `-x`). `None` or `Some(1.0)` = keep all.
     /// `num_mappings`: number of mappings per segment pair (`-n`). `None` = wfmash default (1).
+    /// `prefix_delimiter`: PanSN field separator wfmash groups sequences by (`--prefix-delimiter`).
+    #[allow(clippy::too_many_arguments)]
     pub fn adaptive(
         num_threads: usize,
         min_alignment_length: Option<u64>,
@@ -47,10 +49,11 @@ impl WfmashIntegration {
         sparsify: Option<f64>,
         num_mappings: Option<usize>,
         pairs_file: Option<PathBuf>,
+        prefix_delimiter: char,
     ) -> Result<Self> {
         let mut builder = wfmash_rs::Config::builder()
             .num_threads(num_threads)
-            .prefix_delimiter('#');
+            .prefix_delimiter(prefix_delimiter);
 
         if let Some(n) = num_mappings {
             builder = builder.num_mappings(n);
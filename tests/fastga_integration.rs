@@ -470,6 +470,99 @@ fn test_multisequence_fasta() {
     }
 }
 
+#[test]
+fn test_multiple_fastas_to_1aln() {
+    use self::synthetic_genomes::generate_test_pair;
+
+    let temp_dir = TempDir::new().unwrap();
+    let fasta_a = temp_dir.path().join("a.fa");
+    let fasta_b = temp_dir.path().join("b.fa");
+    let output = temp_dir.path().join("out.1aln");
+
+    let (seq_a, seq_b) = generate_test_pair(20000, 0.01);
+    fs::write(&fasta_a, format!(">A#1#chr1\n{seq_a}\n")).unwrap();
+    fs::write(&fasta_b, format!(">B#1#chr1\n{seq_b}\n")).unwrap();
+
+    let result = run_sweepga(&[
+        fasta_a.to_str().unwrap(),
+        fasta_b.to_str().unwrap(),
+        "-t",
+        "1",
+        "--output-file",
+        output.to_str().unwrap(),
+    ]);
+    assert!(
+        result.is_ok(),
+        "Multi-FASTA .1aln workflow failed: {result:?}"
+    );
+
+    // Two single-genome files are aligned query (b) against target (a), so
+    // every record names one sequence of each file
+    let paf = run_sweepga(&[output.to_str().unwrap(), "--paf"]).unwrap();
+    assert!(paf.lines().next().is_some(), "Empty .1aln output");
+    assert!(
+        paf.lines().all(|line| {
+            let fields: Vec<&str> = line.split('\t').collect();
+            fields[0] != fields[5]
+                && ["A#1#chr1", "B#1#chr1"].contains(&fields[0])
+                && ["A#1#chr1", "B#1#chr1"].contains(&fields[5])
+        }),
+        "Not every record aligns A to B in the .1aln output:\n{paf}"
+    );
+
+    // More files are combined into one, where a repeated file repeats every
+    // sequence name
+    let result = run_sweepga(&[
+        fasta_a.to_str().unwrap(),
+        fasta_b.to_str().unwrap(),
+        fasta_a.to_str().unwrap(),
+        "--1aln",
+    ]);
+    assert!(
+        matches!(&result, Err(e) if e.contains("appears more than once")),
+        "Duplicate sequence names accepted: {result:?}"
+    );
+}
+
+#[test]
+fn test_default_mapq_in_converted_paf() {
+    use self::synthetic_genomes::generate_test_pair;
+
+    let temp_dir = TempDir::new().unwrap();
+    let fasta_a = temp_dir.path().join("a.fa");
+    let fasta_b = temp_dir.path().join("b.fa");
+    let output = temp_dir.path().join("out.1aln");
+
+    let (seq_a, seq_b) = generate_test_pair(20000, 0.01);
+    fs::write(&fasta_a, format!(">A#1#chr1\n{seq_a}\n")).unwrap();
+    fs::write(&fasta_b, format!(">B#1#chr1\n{seq_b}\n")).unwrap();
+    run_sweepga(&[
+        fasta_a.to_str().unwrap(),
+        fasta_b.to_str().unwrap(),
+        "-t",
+        "1",
+        "--output-file",
+        output.to_str().unwrap(),
+    ])
+    .expect("Multi-FASTA .1aln workflow failed");
+
+    // The native .1aln to PAF conversion writes MAPQ 60 unless told otherwise
+    let mapqs = |extra: &[&str]| -> Vec<String> {
+        let mut args = vec![output.to_str().unwrap(), "--paf"];
+        args.extend_from_slice(extra);
+        let paf = run_sweepga(&args).expect("conversion failed");
+        paf.lines()
+            .map(|line| line.split('\t').nth(11).unwrap().to_string())
+            .collect()
+    };
+    let default = mapqs(&[]);
+    assert!(!default.is_empty(), "Empty .1aln output");
+    assert!(default.iter().all(|mapq| mapq == "60"), "{default:?}");
+    let configured = mapqs(&["--default-mapq", "7"]);
+    assert!(!configured.is_empty(), "Empty .1aln output");
+    assert!(configured.iter().all(|mapq| mapq == "7"), "{configured:?}");
+}
+
 #[test]
 #[cfg_attr(target_os = "macos", ignore)] // FastGA has macOS-specific issues
 fn test_performance_regression() {
@@ -14,7 +14,7 @@
 #[cfg(feature = "enable_1aln_tests")]
 use anyhow::Result;
 #[cfg(feature = "enable_1aln_tests")]
-use sweepga::paf_filter::{FilterConfig, FilterMode, ScoringFunction};
+use sweepga::paf_filter::{FilterConfig, FilterMode};
 #[cfg(feature = "enable_1aln_tests")]
 use sweepga::unified_filter;
 #[cfg(feature = "enable_1aln_tests")]
@@ -59,7 +59,7 @@ fn test_1aln_roundtrip_preserves_data() -> Result<()> {
     // This prevents "failed to remove temporary file" errors from the ONE library
     {
         // Read original metadata
-        let (original_meta, _) = unified_filter::extract_1aln_metadata(input_path)?;
+        let (original_meta, original_names) = unified_filter::extract_1aln_metadata(input_path)?;
         let original_count = original_meta.len();
 
         assert!(original_count > 0, "Should have alignments");
@@ -67,33 +67,17 @@ fn test_1aln_roundtrip_preserves_data() -> Result<()> {
         // Filter with permissive config (should keep most records)
         let config = FilterConfig {
             chain_gap: 0,
-            min_block_length: 0,
-            mapping_filter_mode: FilterMode::ManyToMany,
-            mapping_max_per_query: None,
-            mapping_max_per_target: None,
-            plane_sweep_secondaries: 0,
-            scaffold_filter_mode: FilterMode::ManyToMany,
-            scaffold_max_per_query: None,
-            scaffold_max_per_target: None,
-            overlap_threshold: 0.95,
-            sparsity: 1.0,
-            no_merge: true,
             scaffold_gap: 0,
             min_scaffold_length: 0,
             scaffold_overlap_threshold: 0.95,
-            scaffold_max_deviation: 0,
-            prefix_delimiter: '#',
-            skip_prefix: false,
-            scoring_function: ScoringFunction::LogLengthIdentity,
-            min_identity: 0.0,
-            min_scaffold_identity: 0.0,
+            ..FilterConfig::default()
         };
 
         let output_path = temp_dir.join("filtered.1aln");
         unified_filter::filter_file(input_path, &output_path, &config, false, true)?; // keep_self=true
 
         // Read filtered metadata
-        let (filtered_meta, _) = unified_filter::extract_1aln_metadata(&output_path)?;
+        let (filtered_meta, filtered_names) = unified_filter::extract_1aln_metadata(&output_path)?;
         let filtered_count = filtered_meta.len();
 
         assert_eq!(
@@ -104,11 +88,13 @@ fn test_1aln_roundtrip_preserves_data() -> Result<()> {
         // Verify each record matches (including sequence names preserved via GDB)
         for (i, (orig, filt)) in original_meta.iter().zip(filtered_meta.iter()).enumerate() {
             assert_eq!(
-                orig.query_name, filt.query_name,
+                original_names.name(orig.query_id),
+                filtered_names.name(filt.query_id),
                 "Record {i} query name mismatch"
             );
             assert_eq!(
-                orig.target_name, filt.target_name,
+                original_names.name(orig.target_id),
+                filtered_names.name(filt.target_id),
                 "Record {i} target name mismatch"
             );
             assert_eq!(
@@ -184,37 +170,24 @@ fn test_1aln_roundtrip_with_filtering() -> Result<()> {
     // Scope block to ensure all AlnReaders are dropped before TempDir cleanup
     // This prevents "failed to remove temporary file" errors from the ONE library
     {
-        let (original_meta, _) = unified_filter::extract_1aln_metadata(input_path)?;
+        let (original_meta, original_names) = unified_filter::extract_1aln_metadata(input_path)?;
 
         // Apply 1:1 filtering (should reduce records)
         let config = FilterConfig {
             chain_gap: 0,
-            min_block_length: 0,
             mapping_filter_mode: FilterMode::OneToOne,
             mapping_max_per_query: Some(1),
             mapping_max_per_target: Some(1),
-            plane_sweep_secondaries: 0,
-            scaffold_filter_mode: FilterMode::ManyToMany,
-            scaffold_max_per_query: None,
-            scaffold_max_per_target: None,
-            overlap_threshold: 0.95,
-            sparsity: 1.0,
-            no_merge: true,
             scaffold_gap: 0,
             min_scaffold_length: 0,
             scaffold_overlap_threshold: 0.95,
-            scaffold_max_deviation: 0,
-            prefix_delimiter: '#',
-            skip_prefix: false,
-            scoring_function: ScoringFunction::LogLengthIdentity,
-            min_identity: 0.0,
-            min_scaffold_identity: 0.0,
+            ..FilterConfig::default()
         };
 
         let output_path = temp_dir.join("filtered.1aln");
         unified_filter::filter_file(input_path, &output_path, &config, false, true)?; // keep_self=true
 
-        let (filtered_meta, _) = unified_filter::extract_1aln_metadata(&output_path)?;
+        let (filtered_meta, filtered_names) = unified_filter::extract_1aln_metadata(&output_path)?;
 
         // Should have filtered some out
         assert!(
@@ -227,8 +200,8 @@ fn test_1aln_roundtrip_with_filtering() -> Result<()> {
         // Every filtered record should have a corresponding original with matching coordinates
         for filt in &filtered_meta {
             let matching_orig = original_meta.iter().find(|orig| {
-                orig.query_name == filt.query_name
-                    && orig.target_name == filt.target_name
+                original_names.name(orig.query_id) == filtered_names.name(filt.query_id)
+                    && original_names.name(orig.target_id) == filtered_names.name(filt.target_id)
                     && orig.query_start == filt.query_start
                     && orig.query_end == filt.query_end
                     && orig.target_start == filt.target_start
@@ -238,8 +211,8 @@ fn test_1aln_roundtrip_with_filtering() -> Result<()> {
             assert!(
                 matching_orig.is_some(),
                 "Filtered record not found in original: {} → {} [{}-{}, {}-{}]",
-                filt.query_name,
-                filt.target_name,
+                filtered_names.name(filt.query_id),
+                filtered_names.name(filt.target_id),
                 filt.query_start,
                 filt.query_end,
                 filt.target_start,
@@ -250,3 +223,135 @@ fn test_1aln_roundtrip_with_filtering() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+#[cfg(feature = "enable_1aln_tests")]
+fn test_cigar_survives_paf_1aln_roundtrip() -> Result<()> {
+    use std::path::PathBuf;
+    use sweepga::fastga_integration::{aln_to_paf_with_cigar, paf_to_aln};
+    use synthetic_genomes::{generate_base_sequence, mutate_sequence};
+
+    // Use a persistent temp directory to avoid race condition with ONE library cleanup
+    let temp_dir = TempDir::new()?;
+    let temp_dir = temp_dir.keep();
+    let input_fasta = temp_dir.join("test.fa");
+
+    let base = generate_base_sequence(3000, 999);
+    let seq1 = base.clone();
+    let seq2 = mutate_sequence(&base, 30, 1000); // 1% divergence → X ops in the CIGAR
+
+    std::fs::write(&input_fasta, format!(">seq1\n{seq1}\n>seq2\n{seq2}\n"))?;
+
+    let fastga = sweepga::fastga_integration::FastGAIntegration::new(10, 1, 100, None);
+    let aln_result = fastga.align_to_temp_1aln(&input_fasta, &input_fasta);
+
+    if aln_result.is_err() {
+        eprintln!(
+            "Skipping test - FastGA alignment failed (expected when running from temp directories)"
+        );
+        return Ok(());
+    }
+    let temp_1aln = aln_result.unwrap();
+
+    let cigars_of = |paf: &[u8]| -> Vec<String> {
+        String::from_utf8_lossy(paf)
+            .lines()
+            .filter_map(|l| {
+                l.split('\t')
+                    .find_map(|f| f.strip_prefix("cg:Z:"))
+                    .map(str::to_string)
+            })
+            .collect()
+    };
+
+    // .1aln → PAF with CIGARs
+    let first_paf = aln_to_paf_with_cigar(temp_1aln.path(), 1)?;
+    let first_cigars = cigars_of(&first_paf);
+    assert!(
+        !first_cigars.is_empty(),
+        "ALNtoPAF -x should emit cg:Z: tags"
+    );
+    assert!(
+        first_cigars.iter().any(|c| c.contains('X')),
+        "Mutated sequences should produce mismatches in the CIGAR"
+    );
+
+    // PAF → .1aln → PAF
+    let paf_path = temp_dir.join("roundtrip.paf");
+    std::fs::write(&paf_path, &first_paf)?;
+    let aln_path = paf_to_aln(&paf_path, &[PathBuf::from(&input_fasta)], 1, true)?;
+    let second_paf = aln_to_paf_with_cigar(&aln_path, 1)?;
+
+    assert_eq!(
+        first_cigars,
+        cigars_of(&second_paf),
+        "CIGARs should survive the PAF → .1aln → PAF round-trip"
+    );
+
+    Ok(())
+}
+
+#[test]
+#[cfg(feature = "enable_1aln_tests")]
+fn test_filtered_1aln_keeps_trace_points() -> Result<()> {
+    use sweepga::fastga_integration::aln_to_paf_with_cigar;
+    use synthetic_genomes::{generate_base_sequence, mutate_sequence};
+
+    // Use a persistent temp directory to avoid race condition with ONE library cleanup
+    let temp_dir = TempDir::new()?;
+    let temp_dir = temp_dir.keep();
+    let input_fasta = temp_dir.join("test.fa");
+
+    let base = generate_base_sequence(3000, 777);
+    let seq1 = base.clone();
+    let seq2 = mutate_sequence(&base, 30, 778); // 1% divergence → X ops in the CIGAR
+
+    std::fs::write(&input_fasta, format!(">seq1\n{seq1}\n>seq2\n{seq2}\n"))?;
+
+    let fastga = sweepga::fastga_integration::FastGAIntegration::new(10, 1, 100, None);
+    let aln_result = fastga.align_to_temp_1aln(&input_fasta, &input_fasta);
+
+    if aln_result.is_err() {
+        eprintln!(
+            "Skipping test - FastGA alignment failed (expected when running from temp directories)"
+        );
+        return Ok(());
+    }
+    let temp_1aln = aln_result.unwrap();
+
+    let config = FilterConfig {
+        chain_gap: 0,
+        mapping_filter_mode: FilterMode::OneToOne,
+        mapping_max_per_query: Some(1),
+        mapping_max_per_target: Some(1),
+        scaffold_gap: 0,
+        min_scaffold_length: 0,
+        scaffold_overlap_threshold: 0.95,
+        ..FilterConfig::default()
+    };
+    let output_path = temp_dir.join("filtered.1aln");
+    let written =
+        unified_filter::filter_file(temp_1aln.path(), &output_path, &config, false, false)?;
+    assert!(
+        written > 0,
+        "1:1 filtering should keep the seq1/seq2 alignments"
+    );
+
+    // The filtered records still carry the mismatches of the alignment
+    let paf = aln_to_paf_with_cigar(&output_path, 1)?;
+    let cigars: Vec<String> = String::from_utf8_lossy(&paf)
+        .lines()
+        .filter_map(|l| {
+            l.split('\t')
+                .find_map(|f| f.strip_prefix("cg:Z:"))
+                .map(str::to_string)
+        })
+        .collect();
+    assert_eq!(cigars.len(), written);
+    assert!(
+        cigars.iter().any(|c| c.contains('X')),
+        "Trace points should survive .1aln filtering: {cigars:?}"
+    );
+
+    Ok(())
+}
@@ -149,7 +149,7 @@ fn test_nearest_neighbor_chaining() {
     // Test that mappings chain to their nearest neighbors, not distant ones
     use std::fs;
     use std::io::Write;
-    use sweepga::paf_filter::{FilterConfig, FilterMode, PafFilter, ScoringFunction};
+    use sweepga::paf_filter::{FilterConfig, PafFilter};
     use tempfile::NamedTempFile;
 
     // Create synthetic test data with three collinear mappings:
@@ -176,30 +176,18 @@ fn test_nearest_neighbor_chaining() {
 
     let config = FilterConfig {
         chain_gap: 0,
-        min_block_length: 0,
-        mapping_filter_mode: FilterMode::ManyToMany,
-        mapping_max_per_query: None,
-        mapping_max_per_target: None,
-        plane_sweep_secondaries: 0,
-        scaffold_filter_mode: FilterMode::ManyToMany,
-        scaffold_max_per_query: None,
-        scaffold_max_per_target: None,
         overlap_threshold: 0.0,
-        sparsity: 1.0,
         no_merge: false,      // Enable chaining
         scaffold_gap: 10_000, // 10kb gap allows all three to chain
         min_scaffold_length: 0,
         scaffold_overlap_threshold: 0.0,
         scaffold_max_deviation: 20_000,
-        prefix_delimiter: '#',
-        skip_prefix: false,
-        scoring_function: ScoringFunction::LogLengthIdentity,
-        min_identity: 0.0,
-        min_scaffold_identity: 0.0,
+        ..FilterConfig::default()
     };
 
     let filter = PafFilter::new(config);
-    let temp_out = std::env::temp_dir().join("test_nearest_neighbor_out.paf");
+    let dir = tempfile::tempdir().expect("Failed to create temp dir");
+    let temp_out = dir.path().join("test_nearest_neighbor_out.paf");
 
     filter
         .filter_paf(
@@ -243,7 +231,6 @@ fn test_nearest_neighbor_chaining() {
     );
 
     // Cleanup
-    let _ = fs::remove_file(temp_out);
 }
 
 #[test]
@@ -251,7 +238,7 @@ fn test_overlap_penalty() {
     // Test that overlapping mappings are penalized correctly
     use std::fs;
     use std::io::Write;
-    use sweepga::paf_filter::{FilterConfig, FilterMode, PafFilter, ScoringFunction};
+    use sweepga::paf_filter::{FilterConfig, PafFilter};
     use tempfile::NamedTempFile;
 
     // Create synthetic test data with three mappings:
@@ -278,30 +265,18 @@ fn test_overlap_penalty() {
 
     let config = FilterConfig {
         chain_gap: 0,
-        min_block_length: 0,
-        mapping_filter_mode: FilterMode::ManyToMany,
-        mapping_max_per_query: None,
-        mapping_max_per_target: None,
-        plane_sweep_secondaries: 0,
-        scaffold_filter_mode: FilterMode::ManyToMany,
-        scaffold_max_per_query: None,
-        scaffold_max_per_target: None,
         overlap_threshold: 0.0,
-        sparsity: 1.0,
         no_merge: false,      // Enable chaining
         scaffold_gap: 10_000, // Large enough to allow chaining
         min_scaffold_length: 0,
         scaffold_overlap_threshold: 0.0,
         scaffold_max_deviation: 20_000,
-        prefix_delimiter: '#',
-        skip_prefix: false,
-        scoring_function: ScoringFunction::LogLengthIdentity,
-        min_identity: 0.0,
-        min_scaffold_identity: 0.0,
+        ..FilterConfig::default()
     };
 
     let filter = PafFilter::new(config);
-    let temp_out = std::env::temp_dir().join("test_overlap_penalty_out.paf");
+    let dir = tempfile::tempdir().expect("Failed to create temp dir");
+    let temp_out = dir.path().join("test_overlap_penalty_out.paf");
 
     filter
         .filter_paf(
@@ -346,5 +321,579 @@ fn test_overlap_penalty() {
     }
 
     // Cleanup
-    let _ = fs::remove_file(temp_out);
+}
+
+#[test]
+fn test_split_overlapping_members() {
+    // Test that chains whose members overlap on the query (collapsed tandem
+    // duplication) are split with --split-overlapping-members
+    use std::fs;
+    use std::io::Write;
+    use sweepga::paf_filter::{FilterConfig, PafFilter};
+    use tempfile::NamedTempFile;
+
+    // Mapping A: query 0-1000, target 0-1000
+    // Mapping B: query 800-1800, target 1100-2100 (200bp query OVERLAP with A)
+    let mut test_input = NamedTempFile::new().expect("Failed to create temp file");
+    writeln!(
+        test_input,
+        "querySeq\t10000\t0\t1000\t+\ttargetSeq\t10000\t0\t1000\t950\t1000\t60"
+    )
+    .unwrap();
+    writeln!(
+        test_input,
+        "querySeq\t10000\t800\t1800\t+\ttargetSeq\t10000\t1100\t2100\t950\t1000\t60"
+    )
+    .unwrap();
+    test_input.flush().unwrap();
+
+    let config = FilterConfig {
+        chain_gap: 0,
+        no_merge: false,      // Enable chaining
+        scaffold_gap: 10_000, // Overlap of 200bp is within the chaining tolerance
+        min_scaffold_length: 0,
+        scaffold_overlap_threshold: 0.95,
+        ..FilterConfig::default()
+    };
+
+    let dir = tempfile::tempdir().expect("Failed to create temp dir");
+    let run = |split: bool, tolerance: u64, name: &str| {
+        let filter = PafFilter::new(config.clone())
+            .with_split_overlapping_members(split)
+            .with_member_overlap_tolerance(tolerance);
+        let temp_out = dir.path().join(name);
+        filter
+            .filter_paf(
+                test_input.path().to_str().unwrap(),
+                temp_out.to_str().unwrap(),
+            )
+            .expect("Failed to filter PAF");
+        let output = fs::read_to_string(&temp_out).expect("Failed to read output");
+        parse_chains(&output)
+    };
+
+    let chains = run(false, 100, "kept.paf");
+    assert_eq!(chains.len(), 1, "Without splitting, A and B form one chain");
+
+    let chains = run(true, 100, "split.paf");
+    assert_eq!(
+        chains.len(),
+        2,
+        "With --split-overlapping-members, A and B should be in separate chains"
+    );
+    assert!(chains.values().all(|members| members.len() == 1));
+
+    // A 200bp overlap is within a 300bp tolerance, so the chain is not split
+    let chains = run(true, 300, "tolerated.paf");
+    assert_eq!(
+        chains.len(),
+        1,
+        "An overlap within --member-overlap-tolerance should not split the chain"
+    );
+}
+
+#[test]
+fn test_max_diagonal_deviation() {
+    // A member linked into a chain far off the chain's diagonal is dropped
+    // from it with --max-diagonal-deviation
+    use std::fs;
+    use std::io::Write;
+    use sweepga::paf_filter::{FilterConfig, PafFilter};
+    use tempfile::NamedTempFile;
+
+    // A, B, C on diagonal 0; D jumps 20kb along the target (diagonal 20000)
+    let mut test_input = NamedTempFile::new().expect("Failed to create temp file");
+    for (qs, ts) in [(0, 0), (12_000, 12_000), (24_000, 24_000), (36_000, 56_000)] {
+        writeln!(
+            test_input,
+            "querySeq\t100000\t{qs}\t{}\t+\ttargetSeq\t100000\t{ts}\t{}\t9500\t10000\t60",
+            qs + 10_000,
+            ts + 10_000
+        )
+        .unwrap();
+    }
+    test_input.flush().unwrap();
+
+    let config = FilterConfig {
+        chain_gap: 0,
+        no_merge: false,      // Enable chaining
+        scaffold_gap: 50_000, // D's 22kb target gap is within the chaining distance
+        min_scaffold_length: 0,
+        scaffold_overlap_threshold: 0.95,
+        ..FilterConfig::default()
+    };
+
+    let dir = tempfile::tempdir().expect("Failed to create temp dir");
+    let run = |max_deviation: u64, name: &str| {
+        let filter = PafFilter::new(config.clone()).with_max_diagonal_deviation(max_deviation);
+        let temp_out = dir.path().join(name);
+        filter
+            .filter_paf(
+                test_input.path().to_str().unwrap(),
+                temp_out.to_str().unwrap(),
+            )
+            .expect("Failed to filter PAF");
+        let output = fs::read_to_string(&temp_out).expect("Failed to read output");
+        parse_chains(&output)
+    };
+
+    let chains = run(0, "test_diagonal_deviation_off.paf");
+    assert_eq!(
+        chains.len(),
+        1,
+        "Without the check, all four form one chain"
+    );
+    assert_eq!(chains.values().next().unwrap().len(), 4);
+
+    let chains = run(5_000, "test_diagonal_deviation_on.paf");
+    let main_chain = chains
+        .values()
+        .find(|members| members.len() == 3)
+        .expect("A, B and C should remain chained");
+    assert!(
+        !main_chain.contains(&"querySeq:36000-46000".to_string()),
+        "Off-diagonal member D must be excluded from the chain"
+    );
+}
+
+#[test]
+fn test_inversions_report() {
+    // A reverse-strand block between two forward blocks is reported as an
+    // inversion, with the forward chains as flanking context
+    use std::fs;
+    use std::io::Write;
+    use sweepga::paf_filter::{FilterConfig, PafFilter};
+    use tempfile::NamedTempFile;
+
+    let mut test_input = NamedTempFile::new().expect("Failed to create temp file");
+    for (qs, qe, strand, ts, te) in [
+        (0, 20000, '+', 0, 20000),
+        (20000, 30000, '-', 20000, 30000),
+        (30000, 50000, '+', 30000, 50000),
+    ] {
+        writeln!(
+            test_input,
+            "a#1#chr1\t60000\t{qs}\t{qe}\t{strand}\tb#1#chr1\t60000\t{ts}\t{te}\t{}\t{}\t60",
+            (qe - qs) * 95 / 100,
+            qe - qs
+        )
+        .unwrap();
+    }
+    test_input.flush().unwrap();
+
+    let config = FilterConfig {
+        chain_gap: 0,
+        no_merge: false,
+        scaffold_gap: 1_000, // Keep the three blocks as separate chains
+        min_scaffold_length: 0,
+        scaffold_overlap_threshold: 0.95,
+        ..FilterConfig::default()
+    };
+
+    let report = NamedTempFile::new().expect("Failed to create temp file");
+    let dir = tempfile::tempdir().expect("Failed to create temp dir");
+    let temp_out = dir.path().join("test_inversions_report_out.paf");
+    PafFilter::new(config)
+        .with_inversions_report(Some(report.path().to_path_buf()))
+        .filter_paf(
+            test_input.path().to_str().unwrap(),
+            temp_out.to_str().unwrap(),
+        )
+        .expect("Failed to filter PAF");
+
+    let report = fs::read_to_string(report.path()).expect("Failed to read report");
+    let rows: Vec<Vec<&str>> = report
+        .lines()
+        .filter(|l| !l.starts_with('#'))
+        .map(|l| l.split('\t').collect())
+        .collect();
+
+    assert_eq!(rows.len(), 1, "Expected exactly one inversion: {report}");
+    let row = &rows[0];
+    assert_eq!((row[0], row[1]), ("a#1#", "b#1#"));
+    assert_eq!((row[3], row[4]), ("20000", "30000"));
+    assert_eq!((row[6], row[7]), ("20000", "30000"));
+    assert_eq!(row[10], "0-20000:0-20000");
+    assert_eq!(row[11], "30000-50000:30000-50000");
+}
+
+#[test]
+fn test_breakpoints_report() {
+    // Two chains on diagonals 0 and 10000 meet at query 20000: one
+    // breakpoint, from target 20000 to target 30000
+    use std::fs;
+    use std::io::Write;
+    use sweepga::paf_filter::{FilterConfig, PafFilter};
+    use tempfile::NamedTempFile;
+
+    let mut test_input = NamedTempFile::new().expect("Failed to create temp file");
+    for (qs, qe, ts, te) in [(0, 20000, 0, 20000), (20000, 40000, 30000, 50000)] {
+        writeln!(
+            test_input,
+            "a#1#chr1\t60000\t{qs}\t{qe}\t+\tb#1#chr1\t60000\t{ts}\t{te}\t{}\t{}\t60",
+            (qe - qs) * 95 / 100,
+            qe - qs
+        )
+        .unwrap();
+    }
+    test_input.flush().unwrap();
+
+    let config = FilterConfig {
+        chain_gap: 0,
+        no_merge: false,
+        scaffold_gap: 1_000, // The 10kb target gap keeps the blocks apart
+        min_scaffold_length: 0,
+        scaffold_overlap_threshold: 0.95,
+        ..FilterConfig::default()
+    };
+
+    let report = NamedTempFile::new().expect("Failed to create temp file");
+    let dir = tempfile::tempdir().expect("Failed to create temp dir");
+    let temp_out = dir.path().join("test_breakpoints_report_out.paf");
+    PafFilter::new(config)
+        .with_breakpoints_report(Some(report.path().to_path_buf()))
+        .filter_paf(
+            test_input.path().to_str().unwrap(),
+            temp_out.to_str().unwrap(),
+        )
+        .expect("Failed to filter PAF");
+
+    let report = fs::read_to_string(report.path()).expect("Failed to read report");
+    let rows: Vec<Vec<&str>> = report
+        .lines()
+        .filter(|l| !l.starts_with('#'))
+        .map(|l| l.split('\t').collect())
+        .collect();
+
+    assert_eq!(rows.len(), 1, "Expected exactly one breakpoint: {report}");
+    let row = &rows[0];
+    assert_eq!((row[0], row[1], row[2]), ("a#1#", "b#1#", "a#1#chr1"));
+    assert_eq!((row[3], row[4]), ("20000", "20000"));
+    assert_eq!((row[6], row[7]), ("20000", "+"));
+    assert_eq!((row[9], row[10]), ("30000", "+"));
+    assert_eq!(row[11], "10000");
+}
+
+#[test]
+fn test_report_bridges() {
+    // A short mapping in the gap between two chains on the same diagonal is
+    // rescued and reported as bridging them
+    use std::fs;
+    use std::io::Write;
+    use sweepga::paf_filter::{FilterConfig, PafFilter};
+    use tempfile::NamedTempFile;
+
+    let mut test_input = NamedTempFile::new().expect("Failed to create temp file");
+    for (qs, qe) in [(0, 10000), (15000, 25000), (12000, 13000)] {
+        writeln!(
+            test_input,
+            "querySeq\t50000\t{qs}\t{qe}\t+\ttargetSeq\t50000\t{qs}\t{qe}\t{}\t{}\t60",
+            (qe - qs) * 95 / 100,
+            qe - qs
+        )
+        .unwrap();
+    }
+    test_input.flush().unwrap();
+
+    let config = FilterConfig {
+        chain_gap: 0,
+        no_merge: false,
+        scaffold_gap: 1_000,        // 2kb gaps keep all three blocks apart
+        min_scaffold_length: 5_000, // The 1kb block is no scaffold on its own
+        scaffold_overlap_threshold: 0.95,
+        scaffold_max_deviation: 15_000, // Both chains' anchors are within reach
+        ..FilterConfig::default()
+    };
+
+    let report = NamedTempFile::new().expect("Failed to create temp file");
+    let dir = tempfile::tempdir().expect("Failed to create temp dir");
+    let temp_out = dir.path().join("test_report_bridges_out.paf");
+    PafFilter::new(config)
+        .with_bridges_report(Some(report.path().to_path_buf()))
+        .filter_paf(
+            test_input.path().to_str().unwrap(),
+            temp_out.to_str().unwrap(),
+        )
+        .expect("Failed to filter PAF");
+
+    let output = fs::read_to_string(&temp_out).expect("Failed to read output");
+    assert!(
+        output
+            .lines()
+            .any(|l| l.contains("\t12000\t13000\t") && l.contains("st:Z:rescued")),
+        "The bridging mapping should be rescued: {output}"
+    );
+
+    let report = fs::read_to_string(report.path()).expect("Failed to read report");
+    let rows: Vec<Vec<&str>> = report
+        .lines()
+        .filter(|l| !l.starts_with('#'))
+        .map(|l| l.split('\t').collect())
+        .collect();
+
+    assert_eq!(rows.len(), 1, "Expected exactly one bridge: {report}");
+    let row = &rows[0];
+    assert_eq!((row[1], row[2]), ("12000", "13000"));
+    let mut chains: Vec<&str> = row[7].split(',').collect();
+    chains.sort_unstable();
+    assert_eq!(chains, ["chain_1", "chain_2"]);
+}
+
+#[test]
+fn test_max_rescued_per_scaffold() {
+    // Fifty short mappings near one anchor chain are all rescued by default;
+    // --max-rescued-per-scaffold keeps only the closest N
+    use std::fs;
+    use std::io::Write;
+    use sweepga::paf_filter::{FilterConfig, PafFilter};
+    use tempfile::NamedTempFile;
+
+    let mut test_input = NamedTempFile::new().expect("Failed to create temp file");
+    writeln!(
+        test_input,
+        "querySeq\t200000\t0\t10000\t+\ttargetSeq\t200000\t0\t10000\t9500\t10000\t60"
+    )
+    .unwrap();
+    for i in 0..50u64 {
+        let start = 12_000 + i * 2_000;
+        writeln!(
+            test_input,
+            "querySeq\t200000\t{start}\t{}\t+\ttargetSeq\t200000\t{start}\t{}\t190\t200\t60",
+            start + 200,
+            start + 200
+        )
+        .unwrap();
+    }
+    test_input.flush().unwrap();
+
+    let config = FilterConfig {
+        chain_gap: 0,
+        no_merge: false,
+        scaffold_gap: 1_000, // 1.8kb gaps keep the short mappings unchained
+        min_scaffold_length: 5_000, // Only the 10kb block is a scaffold
+        scaffold_overlap_threshold: 0.95,
+        scaffold_max_deviation: 200_000, // Every short mapping is within reach
+        ..FilterConfig::default()
+    };
+
+    let dir = tempfile::tempdir().expect("Failed to create temp dir");
+    let rescued_starts = |max_rescued: Option<usize>, name: &str| -> Vec<u64> {
+        let temp_out = dir.path().join(name);
+        PafFilter::new(config.clone())
+            .with_max_rescued_per_scaffold(max_rescued)
+            .filter_paf(
+                test_input.path().to_str().unwrap(),
+                temp_out.to_str().unwrap(),
+            )
+            .expect("Failed to filter PAF");
+        let output = fs::read_to_string(&temp_out).expect("Failed to read output");
+        let mut starts: Vec<u64> = output
+            .lines()
+            .filter(|l| l.contains("st:Z:rescued"))
+            .map(|l| l.split('\t').nth(2).unwrap().parse().unwrap())
+            .collect();
+        starts.sort_unstable();
+        starts
+    };
+
+    assert_eq!(
+        rescued_starts(None, "test_max_rescued_off.paf").len(),
+        50,
+        "Without a cap every nearby mapping is rescued"
+    );
+
+    let capped = rescued_starts(Some(10), "test_max_rescued_on.paf");
+    let closest: Vec<u64> = (0..10).map(|i| 12_000 + i * 2_000).collect();
+    assert_eq!(
+        capped, closest,
+        "Only the 10 closest mappings should be rescued"
+    );
+}
+
+#[test]
+fn test_chain_identity_lis() {
+    // A chain whose third member jumps back on the target keeps its naive
+    // identity (~95%) but drops to ~63% with --chain-identity-lis, falling
+    // below --min-scaffold-identity
+    use std::fs;
+    use std::io::Write;
+    use sweepga::paf_filter::{FilterConfig, PafFilter};
+    use tempfile::NamedTempFile;
+
+    let mut test_input = NamedTempFile::new().expect("Failed to create temp file");
+    for (qs, ts) in [(0, 0), (1_100, 1_100), (2_200, 500)] {
+        writeln!(
+            test_input,
+            "querySeq\t10000\t{qs}\t{}\t+\ttargetSeq\t10000\t{ts}\t{}\t950\t1000\t60",
+            qs + 1_000,
+            ts + 1_000
+        )
+        .unwrap();
+    }
+    test_input.flush().unwrap();
+
+    let config = FilterConfig {
+        chain_gap: 0,
+        no_merge: false,
+        scaffold_gap: 10_000, // The 1.6kb target overlap is tolerated when chaining
+        min_scaffold_length: 0,
+        scaffold_overlap_threshold: 0.95,
+        min_scaffold_identity: 0.8,
+        ..FilterConfig::default()
+    };
+
+    let dir = tempfile::tempdir().expect("Failed to create temp dir");
+    let run = |lis: bool, name: &str| {
+        let temp_out = dir.path().join(name);
+        PafFilter::new(config.clone())
+            .with_chain_identity_lis(lis)
+            .filter_paf(
+                test_input.path().to_str().unwrap(),
+                temp_out.to_str().unwrap(),
+            )
+            .expect("Failed to filter PAF");
+        let output = fs::read_to_string(&temp_out).expect("Failed to read output");
+        parse_chains(&output)
+    };
+
+    let chains = run(false, "test_chain_identity_naive.paf");
+    assert_eq!(chains.len(), 1, "All three members should form one chain");
+    assert_eq!(chains.values().next().unwrap().len(), 3);
+
+    let chains = run(true, "test_chain_identity_lis.paf");
+    assert!(
+        chains.is_empty(),
+        "The collinear identity should fall below the threshold: {chains:?}"
+    );
+}
+
+#[test]
+fn test_group_by_chain() {
+    // Members of two chains are interleaved in the input; --group-by-chain
+    // writes each chain's members together, the higher-scoring chain first
+    use std::fs;
+    use std::io::Write;
+    use sweepga::paf_filter::{FilterConfig, PafFilter};
+    use tempfile::NamedTempFile;
+
+    let mut test_input = NamedTempFile::new().expect("Failed to create temp file");
+    for (query, start, len) in [
+        ("qA", 0, 5_000),
+        ("qB", 0, 10_000),
+        ("qA", 6_000, 5_000),
+        ("qB", 11_000, 10_000),
+        ("qB", 22_000, 10_000),
+    ] {
+        let target = query.replace('q', "t");
+        writeln!(
+            test_input,
+            "{query}\t100000\t{start}\t{end}\t+\t{target}\t100000\t{start}\t{end}\t{}\t{len}\t60",
+            len * 95 / 100,
+            end = start + len
+        )
+        .unwrap();
+    }
+    test_input.flush().unwrap();
+
+    let config = FilterConfig {
+        chain_gap: 0,
+        no_merge: false,
+        scaffold_gap: 2_000,
+        min_scaffold_length: 5_000,
+        scaffold_overlap_threshold: 0.95,
+        scaffold_max_deviation: 10_000,
+        ..FilterConfig::default()
+    };
+
+    let dir = tempfile::tempdir().expect("Failed to create temp dir");
+    let output_order = |group_by_chain: bool, name: &str| -> Vec<(String, String)> {
+        let temp_out = dir.path().join(name);
+        PafFilter::new(config.clone())
+            .with_group_by_chain(group_by_chain)
+            .filter_paf(
+                test_input.path().to_str().unwrap(),
+                temp_out.to_str().unwrap(),
+            )
+            .expect("Failed to filter PAF");
+        let output = fs::read_to_string(&temp_out).expect("Failed to read output");
+        output
+            .lines()
+            .map(|l| {
+                let chain = l
+                    .split('\t')
+                    .find_map(|f| f.strip_prefix("ch:Z:"))
+                    .expect("every record should be chained");
+                (l.split('\t').next().unwrap().to_string(), chain.to_string())
+            })
+            .collect()
+    };
+
+    let queries = |order: &[(String, String)]| -> Vec<String> {
+        order.iter().map(|(query, _)| query.clone()).collect()
+    };
+
+    let input_order = output_order(false, "test_group_by_chain_off.paf");
+    assert_eq!(queries(&input_order), ["qA", "qB", "qA", "qB", "qB"]);
+
+    let grouped = output_order(true, "test_group_by_chain_on.paf");
+    assert_eq!(queries(&grouped), ["qB", "qB", "qB", "qA", "qA"]);
+    assert!(grouped[..3].iter().all(|(_, chain)| *chain == grouped[0].1));
+    assert!(grouped[3..].iter().all(|(_, chain)| *chain == grouped[3].1));
+    assert_ne!(grouped[0].1, grouped[3].1);
+}
+
+#[test]
+fn test_scaffold_bundle() {
+    // A two-member chain becomes one bundle: a summary header, then both
+    // members with their CIGARs
+    use std::fs;
+    use std::io::Write;
+    use sweepga::paf_filter::{FilterConfig, PafFilter};
+    use tempfile::NamedTempFile;
+
+    let mut test_input = NamedTempFile::new().expect("Failed to create temp file");
+    for (start, matches) in [(0, 9_500), (11_000, 9_000)] {
+        writeln!(
+            test_input,
+            "q\t100000\t{start}\t{end}\t+\tt\t100000\t{start}\t{end}\t{matches}\t10000\t60\t\
+             cg:Z:10000M",
+            end = start + 10_000
+        )
+        .unwrap();
+    }
+    test_input.flush().unwrap();
+
+    let config = FilterConfig {
+        chain_gap: 0,
+        no_merge: false,
+        scaffold_gap: 2_000,
+        min_scaffold_length: 5_000,
+        scaffold_overlap_threshold: 0.95,
+        ..FilterConfig::default()
+    };
+
+    let dir = tempfile::tempdir().unwrap();
+    let bundle_dir = dir.path().join("bundles");
+    let temp_out = dir.path().join("out.paf");
+    PafFilter::new(config)
+        .with_scaffold_bundle(Some(bundle_dir.clone()))
+        .filter_paf(test_input.path(), temp_out.as_path())
+        .expect("Failed to filter PAF");
+
+    let bundles: Vec<_> = fs::read_dir(&bundle_dir).unwrap().collect();
+    assert_eq!(bundles.len(), 1, "one chain, one bundle");
+    let bundle = fs::read_to_string(bundle_dir.join("chain_1.paf")).unwrap();
+    let lines: Vec<&str> = bundle.lines().collect();
+    assert_eq!(lines.len(), 3, "{bundle}");
+    assert_eq!(
+        lines[0],
+        "#chain_id=chain_1\tmembers=2\tmatches=18500\tquery=q:0-21000\tstrand=+\t\
+         target=t:0-21000"
+    );
+    for (line, start) in lines[1..].iter().zip(["0", "11000"]) {
+        let fields: Vec<&str> = line.split('\t').collect();
+        assert_eq!(fields[2], start);
+        assert!(fields.contains(&"cg:Z:10000M"), "{line}");
+        assert!(fields.contains(&"ch:Z:chain_1"), "{line}");
+    }
 }
@@ -105,3 +105,64 @@ fn test_ci_fastga_b3106() {
     assert!(n > 0, "FastGA produced no alignments");
     eprintln!("FastGA on B-3106.fa: {n} alignments");
 }
+
+/// Test --add-to: growing a 3-genome alignment set by a fourth genome keeps
+/// the existing records and adds only the new genome's pairs
+#[test]
+fn test_ci_add_to_existing_alignments() {
+    let temp_dir = TempDir::new().unwrap();
+    let source = fs::read_to_string("data/B-3106.fa").expect("data/B-3106.fa not found");
+    // The first four sequences as single-contig genomes g1..g4
+    let genomes: Vec<String> = source
+        .split('>')
+        .filter(|record| !record.is_empty())
+        .take(4)
+        .enumerate()
+        .map(|(i, record)| {
+            let (_, seq) = record.split_once('\n').unwrap();
+            format!(">g{}#1#chr1\n{seq}", i + 1)
+        })
+        .collect();
+    let three = temp_dir.path().join("three.fa");
+    fs::write(&three, genomes[..3].concat()).unwrap();
+    let four = temp_dir.path().join("four.fa");
+    fs::write(&four, genomes.concat()).unwrap();
+
+    let run = |input: &Path, output: &Path, extra: &[&str]| {
+        let result = Command::new(sweepga_bin())
+            .arg(input)
+            .args(["--aligner", "fastga", "--paf", "-t", "1"])
+            .args(["--num-mappings", "N:N", "--scaffold-jump", "0"])
+            .args(extra)
+            .stdout(fs::File::create(output).unwrap())
+            .output()
+            .expect("Failed to run sweepga");
+        assert!(
+            result.status.success(),
+            "sweepga failed: {}",
+            String::from_utf8_lossy(&result.stderr)
+        );
+        fs::read_to_string(output).unwrap()
+    };
+
+    let existing_path = temp_dir.path().join("existing.paf");
+    let existing = run(&three, &existing_path, &["--all-pairs"]);
+    assert!(!existing.is_empty(), "FastGA produced no alignments");
+
+    let grown = run(
+        &four,
+        &temp_dir.path().join("grown.paf"),
+        &["--add-to", existing_path.to_str().unwrap()],
+    );
+    let added = grown
+        .strip_prefix(existing.as_str())
+        .expect("Existing records should come first, unchanged");
+    assert!(!added.is_empty(), "g4 should align to the existing genomes");
+    for line in added.lines() {
+        let fields: Vec<&str> = line.split('\t').collect();
+        assert!(
+            fields[0].starts_with("g4#") || fields[5].starts_with("g4#"),
+            "Only pairs with the new genome should be added: {line}"
+        );
+    }
+}
@@ -435,3 +435,781 @@ fn test_wfmash_end_to_end() -> Result<()> {
     eprintln!("✓ wfmash end-to-end pipeline produces good coverage");
     Ok(())
 }
+
+/// Test that --manifest records checksums of the input and the output
+#[test]
+fn test_manifest_records_checksums() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let input = temp_dir.path().join("input.paf");
+    let output = temp_dir.path().join("output.paf");
+    let manifest_path = temp_dir.path().join("manifest.json");
+
+    fs::write(
+        &input,
+        "a#1#chr1\t10000\t0\t5000\t+\tb#1#chr1\t10000\t0\t5000\t4900\t5000\t60\n",
+    )?;
+
+    let status = Command::new("cargo")
+        .args([
+            "run",
+            "--release",
+            "--quiet",
+            "--bin",
+            "sweepga",
+            "--",
+            input.to_str().unwrap(),
+            "--output-file",
+            output.to_str().unwrap(),
+            "--manifest",
+            manifest_path.to_str().unwrap(),
+        ])
+        .status()?;
+    assert!(status.success(), "sweepga with --manifest failed");
+
+    let manifest: serde_json::Value = serde_json::from_str(&fs::read_to_string(&manifest_path)?)?;
+    let input_sha = sweepga::manifest::sha256_file(&input)?;
+    let output_sha = sweepga::manifest::sha256_file(&output)?;
+
+    assert_eq!(manifest["inputs"][0]["sha256"], input_sha.as_str());
+    assert_eq!(manifest["outputs"][0]["sha256"], output_sha.as_str());
+    assert_eq!(manifest["outputs"][0]["size"], fs::metadata(&output)?.len());
+    assert!(manifest["config"]["scoring_function"].is_string());
+    assert_eq!(manifest["config"]["keep_self"], false);
+    assert_eq!(manifest["stats"]["output_mappings"], 1);
+    assert_eq!(manifest["stats"]["identity_p50"], 0.98);
+
+    Ok(())
+}
+
+/// Test that --manifest is also written when --no-filter skips filtering
+#[test]
+fn test_manifest_written_without_filtering() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let input = temp_dir.path().join("input.paf");
+    let manifest_path = temp_dir.path().join("manifest.json");
+
+    fs::write(
+        &input,
+        "a#1#chr1\t10000\t0\t5000\t+\tb#1#chr1\t10000\t0\t5000\t4900\t5000\t60\n",
+    )?;
+
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--release",
+            "--quiet",
+            "--bin",
+            "sweepga",
+            "--",
+            input.to_str().unwrap(),
+            "--no-filter",
+            "--manifest",
+            manifest_path.to_str().unwrap(),
+        ])
+        .output()?;
+    assert!(
+        output.status.success(),
+        "sweepga --no-filter with --manifest failed"
+    );
+
+    let manifest: serde_json::Value = serde_json::from_str(&fs::read_to_string(&manifest_path)?)?;
+    assert_eq!(
+        manifest["inputs"][0]["sha256"],
+        sweepga::manifest::sha256_file(&input)?.as_str()
+    );
+    assert_eq!(manifest["outputs"][0]["path"], "-");
+    assert!(manifest["config"].is_null());
+
+    Ok(())
+}
+
+/// Test that --profile writes folded stacks (`path;to;scope <micros>`)
+#[test]
+fn test_profile_writes_folded_stacks() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let input = temp_dir.path().join("input.paf");
+    let output = temp_dir.path().join("output.paf");
+    let profile_path = temp_dir.path().join("profile.folded");
+
+    fs::write(
+        &input,
+        "a#1#chr1\t10000\t0\t5000\t+\tb#1#chr1\t10000\t0\t5000\t4900\t5000\t60\n",
+    )?;
+
+    let status = Command::new("cargo")
+        .args([
+            "run",
+            "--release",
+            "--quiet",
+            "--bin",
+            "sweepga",
+            "--",
+            input.to_str().unwrap(),
+            "--output-file",
+            output.to_str().unwrap(),
+            "--profile",
+            profile_path.to_str().unwrap(),
+        ])
+        .status()?;
+    assert!(status.success(), "sweepga with --profile failed");
+
+    let profile = fs::read_to_string(&profile_path)?;
+    assert!(!profile.is_empty(), "profile should not be empty");
+    for line in profile.lines() {
+        let (stack, micros) = line.rsplit_once(' ').expect("folded line: stack count");
+        assert!(stack.starts_with("filter_paf"), "unexpected stack: {line}");
+        assert!(micros.parse::<u64>()? > 0);
+    }
+    assert!(profile.contains("filter_paf;parse "), "{profile}");
+
+    Ok(())
+}
+
+/// Test that --repair-input drops a truncated final line with a warning
+#[test]
+fn test_repair_input_drops_truncated_final_line() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let input = temp_dir.path().join("input.paf");
+    let output = temp_dir.path().join("output.paf");
+
+    // The second record was cut off mid-write, inside its tags
+    fs::write(
+        &input,
+        "a#1#chr1\t20000\t0\t12000\t+\tb#1#chr1\t20000\t0\t12000\t11800\t12000\t60\n\
+         a#1#chr2\t20000\t0\t12000\t+\tb#1#chr2\t20000\t0\t12000\t11800\t12000\t60\tcg:Z:25",
+    )?;
+
+    let result = Command::new("cargo")
+        .args([
+            "run",
+            "--release",
+            "--quiet",
+            "--bin",
+            "sweepga",
+            "--",
+            input.to_str().unwrap(),
+            "--output-file",
+            output.to_str().unwrap(),
+            "--repair-input",
+        ])
+        .output()?;
+    let stderr = String::from_utf8_lossy(&result.stderr);
+    assert!(
+        result.status.success(),
+        "sweepga --repair-input failed: {stderr}"
+    );
+    assert!(
+        stderr.contains("dropping truncated final line 2"),
+        "missing warning: {stderr}"
+    );
+
+    let filtered = fs::read_to_string(&output)?;
+    assert_eq!(filtered.lines().count(), 1);
+    assert!(filtered.starts_with("a#1#chr1\t"));
+
+    Ok(())
+}
+
+/// Test that aligner-supplied tags such as cg:Z: survive a --paf filter run
+#[test]
+fn test_paf_filter_keeps_aligner_tags() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let input = temp_dir.path().join("input.paf");
+    let output = temp_dir.path().join("output.paf");
+
+    fs::write(
+        &input,
+        "a#1#chr1\t20000\t0\t12000\t+\tb#1#chr1\t20000\t0\t12000\t11998\t12000\t60\t\
+         tp:A:P\tcg:Z:6000=2X5998=\tAS:i:23900\n",
+    )?;
+
+    let status = Command::new("cargo")
+        .args([
+            "run",
+            "--release",
+            "--quiet",
+            "--bin",
+            "sweepga",
+            "--",
+            input.to_str().unwrap(),
+            "--paf",
+            "--scaffold-jump",
+            "0",
+            "--output-file",
+            output.to_str().unwrap(),
+        ])
+        .status()?;
+    assert!(status.success(), "sweepga --paf failed");
+
+    let filtered = fs::read_to_string(&output)?;
+    let tags: Vec<&str> = filtered.trim_end().split('\t').skip(12).collect();
+    for tag in ["tp:A:P", "cg:Z:6000=2X5998=", "AS:i:23900"] {
+        assert!(tags.contains(&tag), "{tag} lost: {filtered}");
+    }
+
+    Ok(())
+}
+
+/// Test that gzipped PAF input, including bgzip-style multi-member files,
+/// filters to the same output as the uncompressed file
+#[test]
+fn test_gzipped_paf_input_matches_plain() -> Result<()> {
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+    use std::io::Write;
+
+    let temp_dir = TempDir::new()?;
+    let records = [
+        "a#1#chr1\t20000\t0\t12000\t+\tb#1#chr1\t20000\t0\t12000\t11800\t12000\t60\n",
+        "a#1#chr1\t20000\t500\t11000\t+\tb#1#chr2\t20000\t0\t10500\t9000\t10500\t60\n",
+        "a#1#chr2\t20000\t0\t15000\t-\tb#1#chr2\t20000\t0\t15000\t14900\t15000\t60\n",
+    ];
+    let plain = temp_dir.path().join("input.paf");
+    fs::write(&plain, records.concat())?;
+
+    // One gzip member per record, like the blocks of a bgzip file
+    let gzipped = temp_dir.path().join("input.paf.gz");
+    let mut members = Vec::new();
+    for record in records {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(record.as_bytes())?;
+        members.extend(encoder.finish()?);
+    }
+    fs::write(&gzipped, members)?;
+
+    let run = |input: &Path, output: &Path| -> Result<()> {
+        let result = Command::new("cargo")
+            .args([
+                "run",
+                "--release",
+                "--quiet",
+                "--bin",
+                "sweepga",
+                "--",
+                input.to_str().unwrap(),
+                "--paf",
+                "--output-file",
+                output.to_str().unwrap(),
+            ])
+            .output()?;
+        assert!(
+            result.status.success(),
+            "sweepga failed on {}: {}",
+            input.display(),
+            String::from_utf8_lossy(&result.stderr)
+        );
+        Ok(())
+    };
+
+    let from_plain = temp_dir.path().join("from_plain.paf");
+    let from_gzipped = temp_dir.path().join("from_gzipped.paf");
+    run(&plain, &from_plain)?;
+    run(&gzipped, &from_gzipped)?;
+    let expected = fs::read_to_string(&from_plain)?;
+    assert!(!expected.is_empty(), "nothing passed the filter");
+    assert_eq!(fs::read_to_string(&from_gzipped)?, expected);
+
+    Ok(())
+}
+
+/// Test that --bgzip (and a .gz --output-file) writes BGZF that decompresses
+/// to the plain PAF output, both to a file and to stdout
+#[test]
+fn test_bgzip_output_decompresses_to_plain() -> Result<()> {
+    use flate2::read::MultiGzDecoder;
+    use std::io::Read;
+
+    let temp_dir = TempDir::new()?;
+    let input = temp_dir.path().join("input.paf");
+    fs::write(
+        &input,
+        "a#1#chr1\t20000\t0\t12000\t+\tb#1#chr1\t20000\t0\t12000\t11800\t12000\t60\n\
+         a#1#chr2\t20000\t0\t15000\t-\tb#1#chr2\t20000\t0\t15000\t14900\t15000\t60\n",
+    )?;
+
+    let run = |extra: &[&str]| -> Result<Vec<u8>> {
+        let mut args = vec![
+            "run",
+            "--release",
+            "--quiet",
+            "--bin",
+            "sweepga",
+            "--",
+            input.to_str().unwrap(),
+            "--paf",
+        ];
+        args.extend(extra);
+        let result = Command::new("cargo").args(&args).output()?;
+        assert!(
+            result.status.success(),
+            "sweepga {extra:?} failed: {}",
+            String::from_utf8_lossy(&result.stderr)
+        );
+        Ok(result.stdout)
+    };
+    let gunzip = |bytes: &[u8]| -> Result<String> {
+        // BGZF blocks are gzip members carrying a "BC" extra subfield
+        assert_eq!(&bytes[..2], [0x1f, 0x8b], "not gzip");
+        assert_eq!(&bytes[12..14], b"BC", "not BGZF");
+        let mut text = String::new();
+        MultiGzDecoder::new(bytes).read_to_string(&mut text)?;
+        Ok(text)
+    };
+
+    let expected = String::from_utf8(run(&[])?)?;
+    assert!(!expected.is_empty(), "nothing passed the filter");
+
+    let output = temp_dir.path().join("output.paf.gz");
+    run(&["--output-file", output.to_str().unwrap()])?;
+    assert_eq!(gunzip(&fs::read(&output)?)?, expected);
+
+    assert_eq!(gunzip(&run(&["--bgzip"])?)?, expected);
+
+    Ok(())
+}
+
+/// Test that --low-memory streams query-grouped input to the same output as
+/// the in-memory filter, and rejects input whose queries are not grouped
+#[test]
+fn test_low_memory_matches_in_memory() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let input = temp_dir.path().join("input.paf");
+    let unsorted = temp_dir.path().join("unsorted.paf");
+
+    let records = [
+        "a#1#chr1\t20000\t0\t12000\t+\tb#1#chr1\t20000\t0\t12000\t11800\t12000\t60",
+        "a#1#chr1\t20000\t500\t11000\t+\tb#1#chr2\t20000\t0\t10500\t9000\t10500\t60",
+        "a#1#chr2\t20000\t0\t15000\t-\tb#1#chr2\t20000\t0\t15000\t14900\t15000\t60",
+        "a#1#chr3\t20000\t0\t12000\t+\tb#1#chr3\t20000\t0\t12000\t11800\t12000\t60",
+    ];
+    fs::write(&input, records.join("\n") + "\n")?;
+    let shuffled = [records[0], records[2], records[1]];
+    fs::write(&unsorted, shuffled.join("\n") + "\n")?;
+
+    let run = |input: &Path, output: &Path, low_memory: bool| -> Result<bool> {
+        let mut args = vec![
+            "run",
+            "--release",
+            "--quiet",
+            "--bin",
+            "sweepga",
+            "--",
+            input.to_str().unwrap(),
+            "--paf",
+            "--scaffold-jump",
+            "0",
+            "--reference-mode",
+            "--output-file",
+            output.to_str().unwrap(),
+        ];
+        if low_memory {
+            args.push("--low-memory");
+        }
+        Ok(Command::new("cargo").args(&args).status()?.success())
+    };
+
+    let in_memory = temp_dir.path().join("in_memory.paf");
+    let streamed = temp_dir.path().join("streamed.paf");
+    assert!(
+        run(&input, &in_memory, false)?,
+        "in-memory filtering failed"
+    );
+    assert!(
+        run(&input, &streamed, true)?,
+        "--low-memory filtering failed"
+    );
+    assert_eq!(
+        fs::read_to_string(&streamed)?,
+        fs::read_to_string(&in_memory)?
+    );
+
+    let rejected = temp_dir.path().join("rejected.paf");
+    assert!(
+        !run(&unsorted, &rejected, true)?,
+        "--low-memory accepted input not grouped by query"
+    );
+
+    Ok(())
+}
+
+/// Test that --low-memory matches the in-memory filter on real alignments:
+/// the unfiltered yeast PAF, grouped by query
+#[test]
+fn test_low_memory_matches_in_memory_on_yeast() -> Result<()> {
+    let input = Path::new("data/scerevisiae8.fa.gz");
+    assert!(
+        input.exists(),
+        "Test data not found: data/scerevisiae8.fa.gz - required for CI"
+    );
+
+    let temp_dir = TempDir::new()?;
+    let temp_input = temp_dir.path().join("test_input.fa.gz");
+    fs::copy(input, &temp_input)?;
+
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--release",
+            "--quiet",
+            "--bin",
+            "sweepga",
+            "--",
+            temp_input.to_str().unwrap(),
+            "--paf",
+            "--no-filter",
+        ])
+        .output()?;
+    assert!(output.status.success(), "unfiltered alignment failed");
+    let raw = String::from_utf8(output.stdout)?;
+    let mut lines: Vec<&str> = raw.lines().collect();
+    lines.sort_by_key(|line| line.split('\t').next().unwrap_or(""));
+    assert!(!lines.is_empty(), "no alignments to filter");
+    let grouped = temp_dir.path().join("grouped.paf");
+    fs::write(&grouped, lines.join("\n") + "\n")?;
+
+    let run = |output: &Path, low_memory: bool| -> Result<bool> {
+        let mut args = vec![
+            "run",
+            "--release",
+            "--quiet",
+            "--bin",
+            "sweepga",
+            "--",
+            grouped.to_str().unwrap(),
+            "--paf",
+            "--scaffold-jump",
+            "0",
+            "--reference-mode",
+            "--output-file",
+            output.to_str().unwrap(),
+        ];
+        if low_memory {
+            args.push("--low-memory");
+        }
+        Ok(Command::new("cargo").args(&args).status()?.success())
+    };
+
+    let in_memory = temp_dir.path().join("in_memory.paf");
+    let streamed = temp_dir.path().join("streamed.paf");
+    assert!(run(&in_memory, false)?, "in-memory filtering failed");
+    assert!(run(&streamed, true)?, "--low-memory filtering failed");
+    let filtered = fs::read_to_string(&in_memory)?;
+    assert!(!filtered.is_empty(), "filtering kept no alignments");
+    assert_eq!(fs::read_to_string(&streamed)?, filtered);
+
+    Ok(())
+}
+
+/// Test that --split-strand writes forward and reverse mappings to separate files
+#[test]
+fn test_split_strand_outputs() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let input = temp_dir.path().join("input.paf");
+    let output = temp_dir.path().join("out.paf");
+
+    fs::write(
+        &input,
+        "a#1#chr1\t20000\t0\t12000\t+\tb#1#chr1\t20000\t0\t12000\t11800\t12000\t60\n\
+         a#1#chr2\t20000\t0\t12000\t-\tb#1#chr2\t20000\t0\t12000\t11800\t12000\t60\n\
+         a#1#chr3\t20000\t0\t12000\t+\tb#1#chr3\t20000\t0\t12000\t11800\t12000\t60\n",
+    )?;
+
+    let status = Command::new("cargo")
+        .args([
+            "run",
+            "--release",
+            "--quiet",
+            "--bin",
+            "sweepga",
+            "--",
+            input.to_str().unwrap(),
+            "--output-file",
+            output.to_str().unwrap(),
+            "--split-strand",
+        ])
+        .status()?;
+    assert!(status.success(), "sweepga with --split-strand failed");
+    assert!(
+        !output.exists(),
+        "split output replaces the single output file"
+    );
+
+    let queries = |name: &str| -> Result<Vec<String>> {
+        let text = fs::read_to_string(temp_dir.path().join(name))?;
+        Ok(text
+            .lines()
+            .map(|line| {
+                assert!(line.contains("\tch:Z:"), "chain tag lost: {line}");
+                assert!(line.contains("\tst:Z:scaffold"), "status tag lost: {line}");
+                line.split('\t').next().unwrap().to_string()
+            })
+            .collect())
+    };
+    assert_eq!(queries("out.fwd.paf")?, vec!["a#1#chr1", "a#1#chr3"]);
+    assert_eq!(queries("out.rev.paf")?, vec!["a#1#chr2"]);
+
+    Ok(())
+}
+
+/// Test that --sort-output reorders the kept records by query, target or score
+#[test]
+fn test_sort_output_keys() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let input = temp_dir.path().join("input.paf");
+
+    // Scores (log length * identity): chr3 8.73, chr1 8.29, chr2 8.27
+    fs::write(
+        &input,
+        "a#1#chr2\t20000\t0\t10000\t+\tb#1#chr1\t20000\t5000\t15000\t9000\t10000\t60\n\
+         a#1#chr1\t20000\t5000\t12000\t+\tb#1#chr3\t20000\t0\t7000\t6900\t7000\t60\n\
+         a#1#chr1\t20000\t0\t4000\t+\tb#1#chr2\t20000\t0\t4000\t3990\t4000\t60\n",
+    )?;
+
+    let targets = |key: &str| -> Result<Vec<String>> {
+        let output = Command::new("cargo")
+            .args(["run", "--release", "--quiet", "--bin", "sweepga", "--"])
+            .arg(&input)
+            .args(["--scaffold-jump", "0", "--sort-output", key])
+            .output()?;
+        assert!(
+            output.status.success(),
+            "sweepga --sort-output {key} failed"
+        );
+        Ok(String::from_utf8(output.stdout)?
+            .lines()
+            .map(|line| line.split('\t').nth(5).unwrap().to_string())
+            .collect())
+    };
+
+    assert_eq!(targets("query")?, ["b#1#chr2", "b#1#chr3", "b#1#chr1"]);
+    assert_eq!(targets("target")?, ["b#1#chr1", "b#1#chr2", "b#1#chr3"]);
+    assert_eq!(targets("score")?, ["b#1#chr3", "b#1#chr1", "b#1#chr2"]);
+
+    Ok(())
+}
+
+/// Test that --ani-score-weight changes which alignments the nX-score ANI uses
+#[test]
+fn test_ani_score_weight_changes_selection() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let input = temp_dir.path().join("input.paf");
+
+    // A long 90% alignment and a short 99% one; 1% of the 30 kb of genome is
+    // covered by whichever sorts first
+    fs::write(
+        &input,
+        "a#1#chr1\t10000\t0\t9000\t+\tb#1#chr1\t10000\t0\t9000\t8100\t9000\t60\n\
+         a#1#chr1\t10000\t9000\t10000\t+\tc#1#chr1\t10000\t0\t1000\t990\t1000\t60\n",
+    )?;
+
+    let kept_targets = |weight: &str| -> Result<Vec<String>> {
+        let output = temp_dir.path().join(format!("weight_{weight}.paf"));
+        let status = Command::new("cargo")
+            .args([
+                "run",
+                "--release",
+                "--quiet",
+                "--bin",
+                "sweepga",
+                "--",
+                input.to_str().unwrap(),
+                "--output-file",
+                output.to_str().unwrap(),
+                "--scaffold-jump",
+                "0",
+                "--ani-method",
+                "n1-score",
+                "--ani-score-weight",
+                weight,
+                "--min-aln-identity",
+                "ani-1",
+            ])
+            .status()?;
+        assert!(
+            status.success(),
+            "sweepga --ani-score-weight {weight} failed"
+        );
+        Ok(fs::read_to_string(&output)?
+            .lines()
+            .map(|line| line.split('\t').nth(5).unwrap().to_string())
+            .collect())
+    };
+
+    // identity * ln(length) favors the long alignment: ANI 90%, both kept
+    assert_eq!(kept_targets("1")?, vec!["b#1#chr1", "c#1#chr1"]);
+    // identity^50 favors the short one: ANI 99% drops the 90% alignment
+    assert_eq!(kept_targets("50")?, vec!["c#1#chr1"]);
+
+    Ok(())
+}
+
+/// Test that aniN thresholds use the Nth percentile of the genome-pair ANIs
+#[test]
+fn test_ani_percentile_thresholds() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let input = temp_dir.path().join("input.paf");
+
+    // Genome pairs at 90%, 95% and 99% ANI: ani25 interpolates to 92.5%,
+    // ani75 to 97%
+    fs::write(
+        &input,
+        "a#1#chr1\t30000\t0\t10000\t+\tb#1#chr1\t10000\t0\t10000\t9000\t10000\t60\n\
+         a#1#chr1\t30000\t10000\t20000\t+\tc#1#chr1\t10000\t0\t10000\t9500\t10000\t60\n\
+         a#1#chr1\t30000\t20000\t30000\t+\td#1#chr1\t10000\t0\t10000\t9900\t10000\t60\n",
+    )?;
+
+    let kept_targets = |threshold: &str| -> Result<Vec<String>> {
+        let output = temp_dir.path().join(format!("{threshold}.paf"));
+        let status = Command::new("cargo")
+            .args([
+                "run",
+                "--release",
+                "--quiet",
+                "--bin",
+                "sweepga",
+                "--",
+                input.to_str().unwrap(),
+                "--output-file",
+                output.to_str().unwrap(),
+                "--scaffold-jump",
+                "0",
+                "--ani-method",
+                "all",
+                "--min-aln-identity",
+                threshold,
+            ])
+            .status()?;
+        assert!(
+            status.success(),
+            "sweepga --min-aln-identity {threshold} failed"
+        );
+        Ok(fs::read_to_string(&output)?
+            .lines()
+            .map(|line| line.split('\t').nth(5).unwrap().to_string())
+            .collect())
+    };
+
+    assert_eq!(kept_targets("ani25")?, vec!["c#1#chr1", "d#1#chr1"]);
+    assert_eq!(kept_targets("ani")?, vec!["c#1#chr1", "d#1#chr1"]);
+    assert_eq!(kept_targets("ani75")?, vec!["d#1#chr1"]);
+
+    Ok(())
+}
+
+/// Test that --output-dir splits the filtered output into one PAF per genome
+/// pair, holding the same records as the single-stream output, and that the
+/// manifest lists only the files the run wrote
+#[test]
+fn test_output_dir_writes_one_paf_per_pair() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let input = temp_dir.path().join("input.paf");
+    let output_dir = temp_dir.path().join("pairs");
+    fs::write(
+        &input,
+        "a#1#chr1\t20000\t0\t12000\t+\tb#1#chr1\t20000\t0\t12000\t11800\t12000\t60\n\
+         a#1#chr1\t20000\t0\t15000\t+\tc#1#chr1\t20000\t0\t15000\t14900\t15000\t60\n\
+         b#1#chr1\t20000\t0\t12000\t-\tc#1#chr1\t20000\t0\t12000\t11800\t12000\t60\n",
+    )?;
+
+    let run = |extra: &[&str]| -> Result<String> {
+        let mut args = vec![
+            "run",
+            "--release",
+            "--quiet",
+            "--bin",
+            "sweepga",
+            "--",
+            input.to_str().unwrap(),
+            "--paf",
+        ];
+        args.extend(extra);
+        let result = Command::new("cargo").args(&args).output()?;
+        assert!(
+            result.status.success(),
+            "sweepga {extra:?} failed: {}",
+            String::from_utf8_lossy(&result.stderr)
+        );
+        Ok(String::from_utf8(result.stdout)?)
+    };
+
+    // A pair file left over from an earlier run
+    fs::create_dir(&output_dir)?;
+    fs::write(output_dir.join("x_1__y_1.paf"), "")?;
+    let manifest_path = temp_dir.path().join("manifest.json");
+
+    let merged = run(&[])?;
+    assert!(run(&[
+        "--output-dir",
+        output_dir.to_str().unwrap(),
+        "--manifest",
+        manifest_path.to_str().unwrap(),
+    ])?
+    .is_empty());
+
+    let mut files: Vec<String> = fs::read_dir(&output_dir)?
+        .map(|entry| Ok(entry?.file_name().to_string_lossy().into_owned()))
+        .filter(|name| !matches!(name, Ok(name) if name == "x_1__y_1.paf"))
+        .collect::<Result<_>>()?;
+    files.sort();
+    assert_eq!(files, ["a_1__b_1.paf", "a_1__c_1.paf", "b_1__c_1.paf"]);
+
+    let manifest: serde_json::Value = serde_json::from_str(&fs::read_to_string(&manifest_path)?)?;
+    let listed: Vec<&str> = manifest["outputs"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|output| output["path"].as_str().unwrap())
+        .collect();
+    let expected: Vec<String> = files
+        .iter()
+        .map(|file| output_dir.join(file).to_string_lossy().into_owned())
+        .collect();
+    assert_eq!(listed, expected);
+
+    let mut split: Vec<String> = Vec::new();
+    for file in &files {
+        let text = fs::read_to_string(output_dir.join(file))?;
+        let (query, target) = file.trim_end_matches(".paf").split_once("__").unwrap();
+        for line in text.lines() {
+            assert!(line.starts_with(&query.replace('_', "#")), "{file}: {line}");
+            assert!(line.contains(&format!("\t{}", target.replace('_', "#"))));
+            split.push(line.to_string());
+        }
+    }
+    let mut merged: Vec<String> = merged.lines().map(str::to_string).collect();
+    merged.sort();
+    split.sort();
+    assert_eq!(split, merged);
+
+    Ok(())
+}
+
+/// Test that --checkpoint-dir refuses pairs aligned with other settings
+/// instead of silently reusing them
+#[test]
+fn test_checkpoint_dir_refuses_other_settings() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let fasta = temp_dir.path().join("genomes.fa");
+    fs::write(&fasta, ">a#1#chr1\nACGTACGTAC\n>b#1#chr1\nACGTACGTAC\n")?;
+    let checkpoints = temp_dir.path().join("checkpoints");
+    fs::create_dir(&checkpoints)?;
+    fs::write(
+        checkpoints.join("settings.txt"),
+        "aligner=fastga\nfrequency=999\nmin_alignment_length=0\nmap_pct_identity=\n",
+    )?;
+    fs::write(checkpoints.join("a_1_vs_b_1.paf"), "")?;
+
+    let result = Command::new("cargo")
+        .args(["run", "--release", "--quiet", "--bin", "sweepga", "--"])
+        .arg(&fasta)
+        .args(["--paf", "--checkpoint-dir"])
+        .arg(&checkpoints)
+        .output()?;
+    assert!(!result.status.success());
+    let stderr = String::from_utf8_lossy(&result.stderr);
+    assert!(
+        stderr.contains("aligned with other settings") && stderr.contains("frequency="),
+        "unexpected error: {stderr}"
+    );
+    Ok(())
+}
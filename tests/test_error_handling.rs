@@ -143,14 +143,13 @@ fn test_missing_file_error() -> Result<()> {
 }
 
 /// Test handling of invalid coordinate ranges
-/// Note: Currently the program passes through invalid coordinates (start > end)
-/// This documents the behavior but doesn't enforce validation
+/// Records with start > end are skipped with a warning, or rejected with --strict
 #[test]
 fn test_invalid_coordinate_ranges() -> Result<()> {
     let temp_dir = TempDir::new()?;
     let bad_coords = temp_dir.path().join("bad_coords.paf");
 
-    // Write PAF with start > end (invalid, but currently accepted)
+    // Write PAF with start > end
     fs::write(
         &bad_coords,
         "seq1\t1000\t500\t200\t+\tseq2\t2000\t100\t300\t150\t200\t60\n",
@@ -168,12 +167,34 @@ fn test_invalid_coordinate_ranges() -> Result<()> {
         ])
         .output()?;
 
-    // Currently passes through without validation
-    // This test documents that behavior - may want stricter validation in future
+    let stderr = String::from_utf8_lossy(&output.stderr);
     assert!(
         output.status.success(),
-        "Program should not crash on invalid coordinates (currently passes them through)"
+        "Program should skip invalid coordinates: {stderr}"
+    );
+    assert!(
+        output.stdout.is_empty(),
+        "Invalid record should not be written"
     );
+    assert!(stderr.contains("query start 500 is after its end 200"));
+
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--release",
+            "--quiet",
+            "--bin",
+            "sweepga",
+            "--",
+            bad_coords.to_str().unwrap(),
+            "--strict",
+        ])
+        .output()?;
+    assert!(
+        !output.status.success(),
+        "--strict should reject the record"
+    );
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Malformed PAF line 1"));
 
     Ok(())
 }
@@ -410,3 +431,96 @@ fn test_permission_denied() -> Result<()> {
 
     Ok(())
 }
+
+/// Test that --on-empty controls the output when filtering keeps nothing
+#[test]
+fn test_on_empty_modes() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+
+    // Only a self-mapping, which is excluded by default
+    let input = temp_dir.path().join("self_only.paf");
+    fs::write(
+        &input,
+        "seq1\t10000\t0\t5000\t+\tseq1\t10000\t0\t5000\t4900\t5000\t60\n",
+    )?;
+
+    let run = |mode: &str| -> Result<(std::process::Output, std::path::PathBuf)> {
+        let output_file = temp_dir.path().join(format!("out_{mode}.paf"));
+        let output = Command::new("cargo")
+            .args([
+                "run",
+                "--release",
+                "--quiet",
+                "--bin",
+                "sweepga",
+                "--",
+                input.to_str().unwrap(),
+                "--output-file",
+                output_file.to_str().unwrap(),
+                "--on-empty",
+                mode,
+            ])
+            .output()?;
+        Ok((output, output_file))
+    };
+
+    let (output, output_file) = run("empty-file")?;
+    assert!(output.status.success(), "empty-file should succeed");
+    assert_eq!(fs::read(&output_file)?.len(), 0, "Expected an empty file");
+
+    let (output, output_file) = run("no-file")?;
+    assert!(output.status.success(), "no-file should succeed");
+    assert!(!output_file.exists(), "no-file must not create the output");
+
+    let (output, output_file) = run("error")?;
+    assert!(!output.status.success(), "error mode should fail");
+    assert!(!output_file.exists(), "error mode must not leave an output");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("kept no records"),
+        "Should explain the failure, got: {stderr}"
+    );
+
+    Ok(())
+}
+
+/// Test that the .1aln workflow rejects options it cannot apply
+#[test]
+fn test_1aln_workflow_rejects_paf_only_options() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let fasta = temp_dir.path().join("genome.fa");
+    fs::write(&fasta, ">seq1\nACGTACGTACGTACGTACGT\n")?;
+    let output_file = temp_dir.path().join("out.1aln");
+
+    for flags in [
+        &["--min-pair-coverage", "0.5"][..],
+        &["--block-length-from", "query-span"],
+        &["--trim-overlaps"],
+        &["--component-tag"],
+        &["--group-by-chain"],
+    ] {
+        let output = Command::new("cargo")
+            .args([
+                "run",
+                "--release",
+                "--quiet",
+                "--bin",
+                "sweepga",
+                "--",
+                fasta.to_str().unwrap(),
+                "--output-file",
+                output_file.to_str().unwrap(),
+            ])
+            .args(flags)
+            .output()?;
+
+        assert!(!output.status.success(), "{flags:?} should be rejected");
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(
+            stderr.contains("only applies to PAF filtering"),
+            "{flags:?} should explain the rejection, got: {stderr}"
+        );
+    }
+
+    Ok(())
+}
@@ -358,3 +358,39 @@ fn test_paf_vs_1aln_with_small_data() {
 
     eprintln!("✅ PAF and .1aln produce identical unfiltered output");
 }
+
+#[test]
+#[ignore] // Requires FastGA binaries
+fn test_1aln_workflow_writes_chain_reports() {
+    let fasta_path = Path::new("data/B-3106.fa");
+
+    if !fasta_path.exists() {
+        eprintln!("Skipping test - data/B-3106.fa not found");
+        return;
+    }
+
+    let temp_dir = TempDir::new().unwrap();
+    let aln_output = temp_dir.path().join("filtered.1aln");
+    let scaffold_bed = temp_dir.path().join("scaffolds.bed");
+    let chain_report = temp_dir.path().join("chains.tsv");
+
+    let result = std::process::Command::new("./target/release/sweepga")
+        .arg(fasta_path)
+        .arg("--output-file")
+        .arg(&aln_output)
+        .arg("--scaffold-bed")
+        .arg(&scaffold_bed)
+        .arg("--chain-report")
+        .arg(&chain_report)
+        .output()
+        .expect("Failed to run sweepga for .1aln workflow");
+    assert!(
+        result.status.success(),
+        ".1aln workflow failed: {}",
+        String::from_utf8_lossy(&result.stderr)
+    );
+
+    assert!(aln_output.exists(), ".1aln output file not created");
+    assert!(scaffold_bed.exists(), "--scaffold-bed not written");
+    assert!(chain_report.exists(), "--chain-report not written");
+}
@@ -228,12 +228,38 @@ fn test_paf_vs_1aln_internal_representation() {
     }
 }
 
+/// A RecordMeta with its sequence names resolved, so records read through
+/// different sequence indexes compare by name
+struct NamedRecord {
+    query_name: String,
+    target_name: String,
+    meta: sweepga::paf_filter::RecordMeta,
+}
+
+impl std::ops::Deref for NamedRecord {
+    type Target = sweepga::paf_filter::RecordMeta;
+
+    fn deref(&self) -> &Self::Target {
+        &self.meta
+    }
+}
+
+fn named_records(
+    metadata: Vec<sweepga::paf_filter::RecordMeta>,
+    names: &sweepga::sequence_index::SequenceIndex,
+) -> Vec<NamedRecord> {
+    metadata
+        .into_iter()
+        .map(|meta| NamedRecord {
+            query_name: names.name(meta.query_id).to_string(),
+            target_name: names.name(meta.target_id).to_string(),
+            meta,
+        })
+        .collect()
+}
+
 /// Compare two RecordMeta structures
-fn compare_records(
-    paf: &sweepga::paf_filter::RecordMeta,
-    aln: &sweepga::paf_filter::RecordMeta,
-    _idx: usize,
-) -> bool {
+fn compare_records(paf: &NamedRecord, aln: &NamedRecord, _idx: usize) -> bool {
     paf.query_name == aln.query_name
         && paf.target_name == aln.target_name
         && paf.query_start == aln.query_start
@@ -247,19 +273,19 @@ fn compare_records(
 }
 
 /// Read PAF file into RecordMeta structures (using existing PAF filter logic)
-fn read_paf_to_records(paf_path: &Path) -> Vec<sweepga::paf_filter::RecordMeta> {
+fn read_paf_to_records(paf_path: &Path) -> Vec<NamedRecord> {
     // Use the public extract_metadata function
-    let (metadata, _) =
+    let (metadata, names) =
         sweepga::paf_filter::extract_metadata(paf_path).expect("Failed to read PAF");
-    metadata
+    named_records(metadata, &names)
 }
 
 /// Read .1aln file into RecordMeta structures (using unified filter logic)
-fn read_1aln_to_records(aln_path: &Path) -> Vec<sweepga::paf_filter::RecordMeta> {
-    let (metadata, _) =
+fn read_1aln_to_records(aln_path: &Path) -> Vec<NamedRecord> {
+    let (metadata, names) =
         sweepga::unified_filter::extract_1aln_metadata(aln_path).expect("Failed to read .1aln");
 
-    metadata
+    named_records(metadata, &names)
 }
 
 /// Find a binary in target/debug or target/release build directories
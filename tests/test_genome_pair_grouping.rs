@@ -112,6 +112,238 @@ A#1#chr2\t1000\t0\t500\t+\tB#1#chr1\t1000\t0\t500\t350\t500\t60\tcg:Z:500M
     );
 }
 
+#[test]
+fn test_skip_prefix_groups_by_sequence() {
+    use std::fs;
+    use tempfile::TempDir;
+
+    let temp_dir = TempDir::new().unwrap();
+    let input_paf = temp_dir.path().join("input.paf");
+
+    // Two contigs of one genome compete for the same query region
+    let paf_content = "\
+A#1#chr1\t1000\t0\t500\t+\tB#1#ctg1\t1000\t0\t500\t450\t500\t60\tcg:Z:500M
+A#1#chr1\t1000\t0\t500\t+\tB#1#ctg2\t1000\t0\t500\t400\t500\t60\tcg:Z:500M
+";
+    fs::write(&input_paf, paf_content).unwrap();
+
+    let targets = |extra: &[&str]| -> Vec<String> {
+        let result = std::process::Command::new("cargo")
+            .args(["run", "--release", "--bin", "sweepga", "--quiet", "--"])
+            .arg("--scaffold-jump")
+            .arg("0")
+            .args(extra)
+            .arg(&input_paf)
+            .output()
+            .expect("Failed to run sweepga");
+        assert!(result.status.success(), "sweepga failed: {:?}", result);
+        String::from_utf8_lossy(&result.stdout)
+            .lines()
+            .map(|line| line.split('\t').nth(5).unwrap().to_string())
+            .collect()
+    };
+
+    // Grouped by genome pair, 1:1 keeps only the best contig
+    assert_eq!(targets(&[]), vec!["B#1#ctg1"]);
+    // Per sequence pair, each contig is swept on its own
+    assert_eq!(targets(&["--skip-prefix"]), vec!["B#1#ctg1", "B#1#ctg2"]);
+}
+
+#[test]
+fn test_keep_and_exclude_pairs() {
+    use std::fs;
+    use tempfile::TempDir;
+
+    let temp_dir = TempDir::new().unwrap();
+    let input_paf = temp_dir.path().join("input.paf");
+    let paf_content = "\
+A#1#chr1\t1000\t0\t500\t+\tB#1#chr1\t1000\t0\t500\t450\t500\t60
+A#1#chr1\t1000\t0\t500\t+\tC#1#chr1\t1000\t0\t500\t400\t500\t60
+C#1#chr1\t1000\t0\t500\t+\tD#1#chr1\t1000\t0\t500\t350\t500\t60
+";
+    fs::write(&input_paf, paf_content).unwrap();
+
+    let pairs = |flag: &str, prefixes: &str| -> Vec<String> {
+        let result = std::process::Command::new("cargo")
+            .args(["run", "--release", "--bin", "sweepga", "--quiet", "--"])
+            .arg("--scaffold-jump")
+            .arg("0")
+            .arg(flag)
+            .arg(prefixes)
+            .arg(&input_paf)
+            .output()
+            .expect("Failed to run sweepga");
+        assert!(result.status.success(), "sweepga failed: {:?}", result);
+        String::from_utf8_lossy(&result.stdout)
+            .lines()
+            .map(|line| {
+                let fields: Vec<&str> = line.split('\t').collect();
+                format!("{}>{}", fields[0], fields[5])
+            })
+            .collect()
+    };
+
+    // Either side of a record may match a kept genome
+    assert_eq!(
+        pairs("--keep-pairs", "B#1,D#1#"),
+        vec!["A#1#chr1>B#1#chr1", "C#1#chr1>D#1#chr1"]
+    );
+    assert_eq!(
+        pairs("--exclude-pairs", "B#1"),
+        vec!["A#1#chr1>C#1#chr1", "C#1#chr1>D#1#chr1"]
+    );
+}
+
+#[test]
+fn test_ani_matrix_per_genome_pair() {
+    use std::fs;
+    use tempfile::TempDir;
+
+    let temp_dir = TempDir::new().unwrap();
+    let input_paf = temp_dir.path().join("input.paf");
+    let matrix = temp_dir.path().join("ani.tsv");
+    let counts = temp_dir.path().join("counts.tsv");
+
+    // A-B from two alignments (850/1000 matches), A-C from one (400/500);
+    // D only aligns to itself, so all its pairs are missing
+    let paf_content = "\
+A#1#chr1\t1000\t0\t500\t+\tB#1#chr1\t1000\t0\t500\t450\t500\t60
+B#1#chr2\t1000\t0\t500\t+\tA#1#chr2\t1000\t0\t500\t400\t500\t60
+A#1#chr1\t1000\t0\t500\t+\tC#1#chr1\t1000\t0\t500\t400\t500\t60
+D#1#chr1\t1000\t0\t500\t+\tD#1#chr2\t1000\t0\t500\t500\t500\t60
+";
+    fs::write(&input_paf, paf_content).unwrap();
+
+    let result = std::process::Command::new("cargo")
+        .args(["run", "--release", "--bin", "sweepga", "--quiet", "--"])
+        .arg("--scaffold-jump")
+        .arg("0")
+        .arg("--ani-matrix")
+        .arg(&matrix)
+        .arg("--ani-matrix-counts")
+        .arg(&counts)
+        .arg(&input_paf)
+        .output()
+        .expect("Failed to run sweepga");
+
+    assert!(result.status.success(), "sweepga failed: {:?}", result);
+
+    assert_eq!(
+        fs::read_to_string(&matrix).unwrap(),
+        "genome\tA#1\tB#1\tC#1\tD#1
+A#1\t1\t0.850000\t0.800000\tNA
+B#1\t0.850000\t1\tNA\tNA
+C#1\t0.800000\tNA\t1\tNA
+D#1\tNA\tNA\tNA\t1
+"
+    );
+    assert_eq!(
+        fs::read_to_string(&counts).unwrap(),
+        "genome\tA#1\tB#1\tC#1\tD#1
+A#1\t0\t2\t1\t0
+B#1\t2\t0\t0\t0
+C#1\t1\t0\t0\t0
+D#1\t0\t0\t0\t0
+"
+    );
+}
+
+#[test]
+fn test_prefix_delimiter_groups_genomes() {
+    use std::fs;
+    use tempfile::TempDir;
+
+    let temp_dir = TempDir::new().unwrap();
+    let input_paf = temp_dir.path().join("input.paf");
+    let matrix = temp_dir.path().join("ani.tsv");
+
+    // Dot-separated PanSN names: both A chromosomes group under A.1
+    let paf_content = "\
+A.1.chr1\t1000\t0\t500\t+\tB.1.chr1\t1000\t0\t500\t450\t500\t60
+A.1.chr2\t1000\t0\t500\t+\tB.1.chr2\t1000\t0\t500\t400\t500\t60
+A.1.chr1\t1000\t0\t500\t+\tC.1.chr1\t1000\t0\t500\t400\t500\t60
+";
+    fs::write(&input_paf, paf_content).unwrap();
+
+    let run = |delimiter: &str| {
+        std::process::Command::new("cargo")
+            .args(["run", "--release", "--bin", "sweepga", "--quiet", "--"])
+            .arg("--scaffold-jump")
+            .arg("0")
+            .arg("--prefix-delimiter")
+            .arg(delimiter)
+            .arg("--ani-matrix")
+            .arg(&matrix)
+            .arg(&input_paf)
+            .output()
+            .expect("Failed to run sweepga")
+    };
+
+    let result = run(".");
+    assert!(result.status.success(), "sweepga failed: {:?}", result);
+    assert_eq!(
+        fs::read_to_string(&matrix).unwrap(),
+        "genome\tA.1\tB.1\tC.1
+A.1\t1\t0.850000\t0.800000
+B.1\t0.850000\t1\tNA
+C.1\t0.800000\tNA\t1
+"
+    );
+
+    let result = run("ab");
+    assert!(
+        !result.status.success(),
+        "multi-character delimiter accepted"
+    );
+    assert!(
+        String::from_utf8_lossy(&result.stderr).contains("single character"),
+        "unexpected error: {:?}",
+        result
+    );
+}
+
+#[test]
+fn test_ani_per_chrom_pairs() {
+    use std::fs;
+    use tempfile::TempDir;
+
+    let temp_dir = TempDir::new().unwrap();
+    let input_paf = temp_dir.path().join("input.paf");
+    let per_chrom = temp_dir.path().join("ani_chrom.tsv");
+
+    // chr1 of A and B from two alignments (850/1000 matches); the A chr1-chr2
+    // alignment stays within one genome and is left out
+    let paf_content = "\
+A#1#chr1\t1000\t0\t500\t+\tB#1#chr1\t1000\t0\t500\t450\t500\t60
+B#1#chr1\t1000\t500\t1000\t+\tA#1#chr1\t1000\t500\t1000\t400\t500\t60
+B#1#chr2\t1000\t0\t500\t+\tA#1#chr2\t1000\t0\t500\t400\t500\t60
+A#1#chr1\t1000\t0\t500\t+\tC#1#chr1\t1000\t0\t500\t400\t500\t60
+A#1#chr1\t1000\t0\t500\t+\tA#1#chr2\t1000\t0\t500\t500\t500\t60
+";
+    fs::write(&input_paf, paf_content).unwrap();
+
+    let result = std::process::Command::new("cargo")
+        .args(["run", "--release", "--bin", "sweepga", "--quiet", "--"])
+        .arg("--scaffold-jump")
+        .arg("0")
+        .arg("--ani-per-chrom")
+        .arg(&per_chrom)
+        .arg(&input_paf)
+        .output()
+        .expect("Failed to run sweepga");
+
+    assert!(result.status.success(), "sweepga failed: {:?}", result);
+
+    assert_eq!(
+        fs::read_to_string(&per_chrom).unwrap(),
+        "query_chr\ttarget_chr\tmatches\tblock_len\tani
+A#1#chr1\tB#1#chr1\t850\t1000\t0.850000
+A#1#chr1\tC#1#chr1\t400\t500\t0.800000
+A#1#chr2\tB#1#chr2\t400\t500\t0.800000
+"
+    );
+}
+
 #[test]
 #[ignore] // Requires z.paf file which doesn't exist in CI
 fn test_yeast_genome_pairs_preserved() {
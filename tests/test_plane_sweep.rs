@@ -19,6 +19,7 @@ fn make_mapping(
         target_start: t_start,
         target_end: t_end,
         identity: 1.0, // Using 1.0 for length-based scoring
+        mapq: 255,
         flags: 0,
     }
 }
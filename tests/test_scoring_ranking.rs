@@ -18,6 +18,7 @@ fn make_mapping_with_identity(
         target_start,
         target_end,
         identity,
+        mapq: 255,
         flags: 0,
     }
 }
@@ -239,3 +240,101 @@ fn test_extreme_values() {
         "Log-length-identity should prefer balanced mapping"
     );
 }
+
+#[test]
+fn test_mapq_identity_scoring_prefers_high_mapq() {
+    // Two placements of the same query region with equal identity:
+    // the confidently placed one (MAPQ 60) must beat the ambiguous one (MAPQ 5)
+    let mut low = make_mapping_with_identity(0, 100, 1100, 1000, 2000, 0.95);
+    low.mapq = 5;
+    let mut high = make_mapping_with_identity(1, 100, 1100, 5000, 6000, 0.95);
+    high.mapq = 60;
+    let mut mappings = vec![low, high];
+
+    let kept = plane_sweep_query(&mut mappings, 1, 0.95, ScoringFunction::MapqIdentity);
+    assert_eq!(kept.len(), 1, "Should keep exactly 1 mapping");
+    assert_eq!(kept[0], 1, "Should keep the high-MAPQ mapping");
+
+    // Without MAPQ weighting the two are tied on identity
+    let a = make_mapping_with_identity(0, 100, 1100, 1000, 2000, 0.95);
+    assert_eq!(
+        a.score_with_function(ScoringFunction::MapqIdentity),
+        a.score_with_function(ScoringFunction::Identity),
+        "MAPQ 255 (unavailable) should not penalize identity"
+    );
+}
+
+#[test]
+fn test_weighted_scoring_blends_identity_and_length() {
+    // A: shorter but more identical; B: 10% longer, less identical
+    let a = make_mapping_with_identity(0, 0, 1000, 0, 1000, 0.99);
+    let b = make_mapping_with_identity(1, 0, 1100, 5000, 6100, 0.90);
+
+    let winner = |spec: &str| -> usize {
+        let scoring: ScoringFunction = spec.parse().unwrap();
+        let kept = plane_sweep_query(&mut [a, b], 1, 0.95, scoring);
+        assert_eq!(kept.len(), 1, "{spec} should keep exactly 1 mapping");
+        kept[0]
+    };
+
+    assert_eq!(winner("weighted:1:0"), 0, "identity-only weights pick A");
+    assert_eq!(winner("weighted:0:1"), 1, "length-only weights pick B");
+    // Normalized: A = 1 + 0.986, B = 0.909 + 1
+    assert_eq!(winner("weighted:1:1"), 0);
+    // A = 1 + 9.86, B = 0.909 + 10
+    assert_eq!(winner("weighted:1:10"), 1);
+
+    for bad in [
+        "weighted:1",
+        "weighted:a:1",
+        "weighted:-1:1",
+        "weighted:0:0",
+    ] {
+        assert!(
+            bad.parse::<ScoringFunction>().is_err(),
+            "{bad} should not parse"
+        );
+    }
+}
+
+#[test]
+fn test_custom_scoring_formula() {
+    // A: shorter but more identical; B: 10% longer, less identical
+    let a = make_mapping_with_identity(0, 0, 1000, 0, 1000, 0.99);
+    let b = make_mapping_with_identity(1, 0, 1100, 5000, 6100, 0.90);
+
+    let winner = |spec: &str| -> usize {
+        let scoring: ScoringFunction = spec.parse().unwrap();
+        let kept = plane_sweep_query(&mut [a, b], 1, 0.95, scoring);
+        assert_eq!(kept.len(), 1, "{spec} should keep exactly 1 mapping");
+        kept[0]
+    };
+
+    assert_eq!(
+        "custom:0.7*loglen+0.3*identity".parse::<ScoringFunction>(),
+        Ok(ScoringFunction::Custom {
+            len_weight: 0.7,
+            id_weight: 0.3,
+            use_log: true
+        })
+    );
+    assert_eq!(winner("custom:identity"), 0);
+    assert_eq!(winner("custom:len"), 1);
+    // A = 0.1 * 6.908 + 0.99 = 1.681, B = 0.1 * 7.003 + 0.90 = 1.600
+    assert_eq!(winner("custom:0.1*loglen+identity"), 0);
+    // A = 0.01 * 1000 + 0.99 = 10.99, B = 0.01 * 1100 + 0.90 = 11.90
+    assert_eq!(winner("custom:0.01*len+identity"), 1);
+
+    for bad in [
+        "custom:",
+        "custom:2*matches",
+        "custom:-1*len+identity",
+        "custom:0*len+0*identity",
+        "custom:len+loglen",
+    ] {
+        assert!(
+            bad.parse::<ScoringFunction>().is_err(),
+            "{bad} should not parse"
+        );
+    }
+}
@@ -43,10 +43,39 @@ fn test_fastga_alignment_with_b3106() {
     );
 }
 
+/// `--fastga-args` must reach FastGA on the .1aln and PAF paths too, not
+/// only in direct PAF mode: an option FastGA rejects has to fail both.
+#[test]
+fn test_fastga_extra_args_forwarded_on_all_paths() {
+    let fasta_path = Path::new("data/B-3106.fa");
+
+    if !fasta_path.exists() {
+        eprintln!("Skipping test - data/B-3106.fa not found");
+        return;
+    }
+
+    let plain = sweepga::fastga_integration::FastGAIntegration::new(10, 1, 100, None);
+    if plain.align_to_temp_paf(fasta_path, fasta_path).is_err() {
+        eprintln!("Skipping test - FastGA alignment failed (binaries may not be available in CI)");
+        return;
+    }
+
+    let bogus = sweepga::fastga_integration::FastGAIntegration::new(10, 1, 100, None)
+        .with_extra_args(vec!["--not-a-fastga-option".to_string()]);
+    assert!(
+        bogus.align_to_temp_paf(fasta_path, fasta_path).is_err(),
+        "PAF path ignored the extra FastGA arguments"
+    );
+    assert!(
+        bogus.align_to_temp_1aln(fasta_path, fasta_path).is_err(),
+        ".1aln path ignored the extra FastGA arguments"
+    );
+}
+
 /// Test filtering API without crashing
 #[test]
 fn test_filtering_api() {
-    use sweepga::paf_filter::{FilterConfig, FilterMode, PafFilter, ScoringFunction};
+    use sweepga::paf_filter::{FilterConfig, PafFilter};
 
     let temp_dir = TempDir::new().unwrap();
     let input_paf = temp_dir.path().join("input.paf");
@@ -57,26 +86,10 @@ fn test_filtering_api() {
 
     let config = FilterConfig {
         chain_gap: 0,
-        min_block_length: 0,
-        mapping_filter_mode: FilterMode::ManyToMany,
-        mapping_max_per_query: None,
-        mapping_max_per_target: None,
-        plane_sweep_secondaries: 0,
-        scaffold_filter_mode: FilterMode::ManyToMany,
-        scaffold_max_per_query: None,
-        scaffold_max_per_target: None,
-        overlap_threshold: 0.95,
-        sparsity: 1.0,
-        no_merge: true,
         scaffold_gap: 0,
         min_scaffold_length: 0,
         scaffold_overlap_threshold: 0.95,
-        scaffold_max_deviation: 0,
-        prefix_delimiter: '#',
-        skip_prefix: false,
-        scoring_function: ScoringFunction::LogLengthIdentity,
-        min_identity: 0.0,
-        min_scaffold_identity: 0.0,
+        ..FilterConfig::default()
     };
 
     let output = temp_dir.path().join("out.paf");
@@ -91,6 +104,94 @@ fn test_filtering_api() {
     eprintln!("✓ Filtering API works without crashing");
 }
 
+/// Test that --min-relative-score drops mappings well below the query's best
+#[test]
+fn test_min_relative_score_filter() {
+    use sweepga::paf_filter::{FilterConfig, PafFilter, ScoringFunction};
+
+    let temp_dir = TempDir::new().unwrap();
+    let input_paf = temp_dir.path().join("input.paf");
+
+    // Same query, non-overlapping query intervals, length scoring:
+    // best = 1000bp, 0.6x = 600bp, 0.4x = 400bp
+    let paf_content = "q1\t10000\t0\t1000\t+\tt1\t10000\t0\t1000\t1000\t1000\t60\n\
+                       q1\t10000\t2000\t2600\t+\tt2\t10000\t0\t600\t600\t600\t60\n\
+                       q1\t10000\t4000\t4400\t+\tt3\t10000\t0\t400\t400\t400\t60\n";
+    fs::write(&input_paf, paf_content).unwrap();
+
+    let config = FilterConfig {
+        chain_gap: 0,
+        scaffold_gap: 0,
+        min_scaffold_length: 0,
+        scaffold_overlap_threshold: 0.95,
+        scoring_function: ScoringFunction::Length,
+        ..FilterConfig::default()
+    };
+
+    let output = temp_dir.path().join("out.paf");
+    let filter = PafFilter::new(config).with_min_relative_score(0.5);
+    filter.filter_paf(&input_paf, &output).unwrap();
+
+    let content = fs::read_to_string(&output).unwrap();
+    let targets: Vec<&str> = content
+        .lines()
+        .map(|l| l.split('\t').nth(5).unwrap())
+        .collect();
+
+    assert!(targets.contains(&"t1"), "best mapping must be kept");
+    assert!(
+        targets.contains(&"t2"),
+        "mapping at 0.6x best should survive"
+    );
+    assert!(
+        !targets.contains(&"t3"),
+        "mapping at 0.4x best should be removed"
+    );
+}
+
+/// Test that --trim-overlaps clips the lower-scoring of two overlapping mappings
+#[test]
+fn test_trim_overlaps_clips_query_spans() {
+    use sweepga::paf_filter::{FilterConfig, PafFilter, ScoringFunction};
+
+    let temp_dir = TempDir::new().unwrap();
+    let input_paf = temp_dir.path().join("input.paf");
+
+    // Both survive filtering (20% query overlap); the 800bp one must be clipped
+    let paf_content = "q1\t10000\t0\t1000\t+\tt1\t10000\t0\t1000\t1000\t1000\t60\tcg:Z:1000=\n\
+                       q1\t10000\t800\t1600\t+\tt2\t10000\t5000\t5800\t800\t800\t60\tcg:Z:800=\n";
+    fs::write(&input_paf, paf_content).unwrap();
+
+    let config = FilterConfig {
+        chain_gap: 0,
+        scaffold_gap: 0,
+        min_scaffold_length: 0,
+        scaffold_overlap_threshold: 0.95,
+        scoring_function: ScoringFunction::Length,
+        ..FilterConfig::default()
+    };
+
+    let output = temp_dir.path().join("out.paf");
+    let filter = PafFilter::new(config).with_trim_overlaps(true);
+    filter.filter_paf(&input_paf, &output).unwrap();
+
+    let content = fs::read_to_string(&output).unwrap();
+    let records: Vec<Vec<&str>> = content.lines().map(|l| l.split('\t').collect()).collect();
+    assert_eq!(records.len(), 2);
+
+    let best = records.iter().find(|r| r[5] == "t1").unwrap();
+    assert_eq!(
+        (best[2], best[3], best[7], best[8]),
+        ("0", "1000", "0", "1000")
+    );
+
+    let trimmed = records.iter().find(|r| r[5] == "t2").unwrap();
+    assert_eq!((trimmed[2], trimmed[3]), ("1000", "1600"));
+    assert_eq!((trimmed[7], trimmed[8]), ("5200", "5800"));
+    assert_eq!((trimmed[9], trimmed[10]), ("600", "600"));
+    assert_eq!(trimmed[12], "cg:Z:600=");
+}
+
 /// Test multisequence FASTA handling using synthetic data
 #[test]
 fn test_multisequence_synthetic() {
@@ -130,7 +231,7 @@ fn test_multisequence_synthetic() {
 /// Test 1aln filtering directly
 #[test]
 fn test_1aln_filtering_api() {
-    use sweepga::paf_filter::{FilterConfig, FilterMode, ScoringFunction};
+    use sweepga::paf_filter::FilterConfig;
     use synthetic_genomes::generate_base_sequence;
 
     let temp_dir = TempDir::new().unwrap();
@@ -157,26 +258,10 @@ fn test_1aln_filtering_api() {
     // Apply filtering
     let config = FilterConfig {
         chain_gap: 0,
-        min_block_length: 0,
-        mapping_filter_mode: FilterMode::ManyToMany,
-        mapping_max_per_query: None,
-        mapping_max_per_target: None,
-        plane_sweep_secondaries: 0,
-        scaffold_filter_mode: FilterMode::ManyToMany,
-        scaffold_max_per_query: None,
-        scaffold_max_per_target: None,
-        overlap_threshold: 0.95,
-        sparsity: 1.0,
-        no_merge: true,
         scaffold_gap: 0,
         min_scaffold_length: 0,
         scaffold_overlap_threshold: 0.95,
-        scaffold_max_deviation: 0,
-        prefix_delimiter: '#',
-        skip_prefix: false,
-        scoring_function: ScoringFunction::LogLengthIdentity,
-        min_identity: 0.0,
-        min_scaffold_identity: 0.0,
+        ..FilterConfig::default()
     };
 
     let filter_result =
@@ -245,3 +330,601 @@ fn test_pairwise_b3106() {
     assert!(aln_size > 0, "Should produce alignment");
     eprintln!("✓ Pairwise B-3106 alignment: {} bytes", aln_size);
 }
+
+/// Test that 1-based input is converted to 0-based and round-trips with --output-coord-base 1
+#[test]
+fn test_coord_base_conversion() {
+    use sweepga::paf_filter::{FilterConfig, PafFilter};
+
+    let temp_dir = TempDir::new().unwrap();
+    let input_paf = temp_dir.path().join("one_based.paf");
+
+    // 1-based inclusive: query [1, 1000] is 0-based [0, 1000)
+    let paf_content = "q1\t10000\t1\t1000\t+\tt1\t10000\t501\t1500\t950\t1000\t60\n";
+    fs::write(&input_paf, paf_content).unwrap();
+
+    let config = FilterConfig {
+        chain_gap: 0,
+        scaffold_gap: 0,
+        min_scaffold_length: 0,
+        scaffold_overlap_threshold: 0.95,
+        ..FilterConfig::default()
+    };
+
+    let run = |output_base: u8| -> Vec<String> {
+        let output = temp_dir.path().join(format!("out{output_base}.paf"));
+        PafFilter::new(config.clone())
+            .with_coord_base(1, output_base)
+            .filter_paf(&input_paf, &output)
+            .unwrap();
+        let content = fs::read_to_string(&output).unwrap();
+        let line = content.lines().next().expect("record should be kept");
+        line.split('\t').take(9).map(str::to_string).collect()
+    };
+
+    let zero_based = run(0);
+    assert_eq!((&*zero_based[2], &*zero_based[3]), ("0", "1000"));
+    assert_eq!((&*zero_based[7], &*zero_based[8]), ("500", "1500"));
+
+    let round_trip = run(1);
+    assert_eq!((&*round_trip[2], &*round_trip[3]), ("1", "1000"));
+    assert_eq!((&*round_trip[7], &*round_trip[8]), ("501", "1500"));
+
+    // A zero start cannot be 1-based
+    let bad_input = temp_dir.path().join("bad.paf");
+    fs::write(
+        &bad_input,
+        "q1\t10000\t0\t1000\t+\tt1\t10000\t1\t1000\t950\t1000\t60\n",
+    )
+    .unwrap();
+    let result = PafFilter::new(config)
+        .with_coord_base(1, 0)
+        .filter_paf(&bad_input, &temp_dir.path().join("bad_out.paf"));
+    assert!(result.is_err());
+}
+
+/// .1aln records carry no MAPQ and pick up the configured placeholder
+#[test]
+fn test_default_mapq_for_1aln_records() {
+    use sweepga::unified_filter::extract_1aln_metadata;
+    use synthetic_genomes::generate_base_sequence;
+
+    let temp_dir = TempDir::new().unwrap();
+    let test_fa = temp_dir.path().join("test.fa");
+    let seq = generate_base_sequence(3000, 456);
+    fs::write(&test_fa, format!(">test\n{seq}\n")).unwrap();
+
+    let integration = sweepga::fastga_integration::FastGAIntegration::new(10, 1, 100, None);
+    let Ok(aln_file) = integration.align_to_temp_1aln(&test_fa, &test_fa) else {
+        eprintln!("Skipping test - FastGA alignment failed");
+        return;
+    };
+
+    let (metadata, _) = extract_1aln_metadata(aln_file.path(), 7).unwrap();
+    assert!(!metadata.is_empty(), "no .1aln records");
+    assert!(metadata.iter().all(|meta| meta.mapq == 7));
+}
+
+/// --reference-mode sweeps each query against all reference chromosomes at once
+#[test]
+fn test_reference_mode_keeps_best_hit_across_chromosomes() {
+    use sweepga::paf_filter::{FilterConfig, PafFilter};
+
+    let temp_dir = TempDir::new().unwrap();
+    let input = temp_dir.path().join("input.paf");
+    // genome1's region hits both reference chromosomes; chr1 is the better hit.
+    // genome2 maps to the same chr1 region and must not be swept against genome1.
+    fs::write(
+        &input,
+        "genome1#1#chrA\t50000\t10000\t20000\t+\tref#1#chr1\t90000\t30000\t40000\t9500\t10000\t60\n\
+         genome1#1#chrA\t50000\t12000\t18000\t+\tref#1#chr2\t90000\t5000\t11000\t5400\t6000\t60\n\
+         genome2#1#chrA\t50000\t10000\t20000\t+\tref#1#chr1\t90000\t30000\t40000\t9000\t10000\t60\n",
+    )
+    .unwrap();
+
+    let config = FilterConfig {
+        chain_gap: 0,
+        overlap_threshold: 0.5,
+        scaffold_gap: 0,
+        min_scaffold_length: 0,
+        scaffold_overlap_threshold: 0.95,
+        ..FilterConfig::default()
+    };
+
+    let hits = |reference_mode: bool| -> Vec<(String, String)> {
+        let output = temp_dir.path().join(format!("out_{reference_mode}.paf"));
+        PafFilter::new(config.clone())
+            .with_reference_mode(reference_mode)
+            .filter_paf(&input, &output)
+            .unwrap();
+        fs::read_to_string(&output)
+            .unwrap()
+            .lines()
+            .map(|l| {
+                let f: Vec<&str> = l.split('\t').collect();
+                (f[0].to_string(), f[5].to_string())
+            })
+            .collect()
+    };
+
+    let default_hits = hits(false);
+    assert_eq!(default_hits.len(), 3, "N:N keeps every mapping");
+
+    let reference_hits = hits(true);
+    assert_eq!(
+        reference_hits,
+        vec![
+            ("genome1#1#chrA".to_string(), "ref#1#chr1".to_string()),
+            ("genome2#1#chrA".to_string(), "ref#1#chr1".to_string()),
+        ]
+    );
+}
+
+/// --min-pair-coverage drops every mapping of a weakly covered genome pair
+#[test]
+fn test_min_pair_coverage_drops_weak_pairs() {
+    use sweepga::paf_filter::{FilterConfig, PafFilter};
+
+    let temp_dir = TempDir::new().unwrap();
+    let input = temp_dir.path().join("input.paf");
+    // A-B covers 50% of B (two overlapping mappings, 50 kb merged); A-C covers 1% of C
+    fs::write(
+        &input,
+        "A#1#chr1\t100000\t0\t30000\t+\tB#1#chr1\t100000\t0\t30000\t29000\t30000\t60\n\
+         A#1#chr1\t100000\t20000\t50000\t+\tB#1#chr1\t100000\t20000\t50000\t29000\t30000\t60\n\
+         A#1#chr1\t100000\t60000\t61000\t+\tC#1#chr1\t100000\t5000\t6000\t990\t1000\t60\n",
+    )
+    .unwrap();
+
+    let config = FilterConfig {
+        chain_gap: 0,
+        scaffold_gap: 0,
+        min_scaffold_length: 0,
+        scaffold_overlap_threshold: 0.95,
+        ..FilterConfig::default()
+    };
+
+    let targets = |min_coverage: Option<f64>| -> Vec<String> {
+        let output = temp_dir.path().join(format!("out_{min_coverage:?}.paf"));
+        PafFilter::new(config.clone())
+            .with_min_pair_coverage(min_coverage)
+            .filter_paf(&input, &output)
+            .unwrap();
+        fs::read_to_string(&output)
+            .unwrap()
+            .lines()
+            .map(|l| l.split('\t').nth(5).unwrap().to_string())
+            .collect()
+    };
+
+    assert_eq!(targets(None).len(), 3);
+    assert_eq!(targets(Some(0.1)), vec!["B#1#chr1", "B#1#chr1"]);
+    assert!(targets(Some(0.6)).is_empty(), "50% coverage is below 0.6");
+}
+
+/// --min-query-coverage / --min-target-coverage drop mappings spanning too
+/// little of their query or target sequence
+#[test]
+fn test_min_query_and_target_coverage() {
+    use sweepga::paf_filter::{FilterConfig, PafFilter};
+
+    let temp_dir = TempDir::new().unwrap();
+    let input = temp_dir.path().join("input.paf");
+    // q1 is 80% covered (t1 8%), q2 8% (t2 80%), q3 8% (t3 8%)
+    fs::write(
+        &input,
+        "q1\t10000\t0\t8000\t+\tt1\t100000\t0\t8000\t8000\t8000\t60\n\
+         q2\t100000\t0\t8000\t+\tt2\t10000\t0\t8000\t8000\t8000\t60\n\
+         q3\t100000\t0\t8000\t+\tt3\t100000\t0\t8000\t8000\t8000\t60\n",
+    )
+    .unwrap();
+
+    let config = FilterConfig {
+        chain_gap: 0,
+        scaffold_gap: 0,
+        min_scaffold_length: 0,
+        scaffold_overlap_threshold: 0.95,
+        ..FilterConfig::default()
+    };
+
+    let queries = |query_coverage: f64, target_coverage: f64| -> Vec<String> {
+        let output = temp_dir
+            .path()
+            .join(format!("out_{query_coverage}_{target_coverage}.paf"));
+        PafFilter::new(config.clone())
+            .with_min_query_coverage(query_coverage)
+            .with_min_target_coverage(target_coverage)
+            .filter_paf(&input, &output)
+            .unwrap();
+        let mut queries: Vec<String> = fs::read_to_string(&output)
+            .unwrap()
+            .lines()
+            .map(|l| l.split('\t').next().unwrap().to_string())
+            .collect();
+        queries.sort();
+        queries
+    };
+
+    assert_eq!(queries(0.0, 0.0), vec!["q1", "q2", "q3"]);
+    assert_eq!(queries(0.5, 0.0), vec!["q1"]);
+    assert_eq!(queries(0.0, 0.5), vec!["q2"]);
+    assert!(queries(0.5, 0.5).is_empty());
+}
+
+/// --max-output-bp keeps the best-scoring mappings within the bp budget
+#[test]
+fn test_max_output_bp_caps_kept_bases() {
+    use sweepga::paf_filter::{FilterConfig, PafFilter};
+
+    let temp_dir = TempDir::new().unwrap();
+    let input = temp_dir.path().join("input.paf");
+    // Same length, so the higher identity scores higher: q2 > q3 > q1
+    fs::write(
+        &input,
+        "q1\t100000\t0\t30000\t+\tt1\t100000\t0\t30000\t27000\t30000\t60\n\
+         q2\t100000\t0\t30000\t+\tt1\t100000\t30000\t60000\t29700\t30000\t60\n\
+         q3\t100000\t0\t30000\t+\tt1\t100000\t60000\t90000\t28500\t30000\t60\n",
+    )
+    .unwrap();
+
+    let config = FilterConfig {
+        chain_gap: 0,
+        scaffold_gap: 0,
+        min_scaffold_length: 0,
+        scaffold_overlap_threshold: 0.95,
+        ..FilterConfig::default()
+    };
+
+    let kept = |max_bp: Option<u64>| -> Vec<(String, u64)> {
+        let output = temp_dir.path().join(format!("out_{max_bp:?}.paf"));
+        PafFilter::new(config.clone())
+            .with_max_output_bp(max_bp)
+            .filter_paf(&input, &output)
+            .unwrap();
+        fs::read_to_string(&output)
+            .unwrap()
+            .lines()
+            .map(|l| {
+                let fields: Vec<&str> = l.split('\t').collect();
+                (fields[0].to_string(), fields[10].parse().unwrap())
+            })
+            .collect()
+    };
+
+    assert_eq!(kept(None).len(), 3);
+    for (max_bp, expected) in [
+        (70_000, vec!["q2", "q3"]),
+        (60_000, vec!["q2", "q3"]),
+        (59_999, vec!["q2"]),
+        (10_000, vec![]),
+    ] {
+        let records = kept(Some(max_bp));
+        let total: u64 = records.iter().map(|(_, length)| length).sum();
+        assert!(total <= max_bp, "{total} bp kept with a cap of {max_bp}");
+        let queries: Vec<&str> = records.iter().map(|(q, _)| q.as_str()).collect();
+        assert_eq!(queries, expected, "cap {max_bp}");
+    }
+}
+
+/// --tag-sweep-rank marks the primary sr:i:0 and a kept secondary sr:i:1
+#[test]
+fn test_tag_sweep_rank() {
+    use sweepga::paf_filter::{FilterConfig, FilterMode, PafFilter};
+
+    let temp_dir = TempDir::new().unwrap();
+    let input = temp_dir.path().join("input.paf");
+    // One query region hitting two target loci; keeping 2 per query keeps both
+    fs::write(
+        &input,
+        "q\t100000\t0\t20000\t+\tt\t100000\t50000\t70000\t19000\t20000\t60\n\
+         q\t100000\t0\t20000\t+\tt\t100000\t0\t20000\t19800\t20000\t60\n",
+    )
+    .unwrap();
+
+    let config = FilterConfig {
+        chain_gap: 0,
+        mapping_filter_mode: FilterMode::OneToMany,
+        mapping_max_per_query: Some(2),
+        scaffold_gap: 0,
+        min_scaffold_length: 0,
+        scaffold_overlap_threshold: 0.95,
+        ..FilterConfig::default()
+    };
+
+    let output = temp_dir.path().join("out.paf");
+    PafFilter::new(config)
+        .with_tag_sweep_rank(true)
+        .filter_paf(&input, &output)
+        .unwrap();
+    let ranks: Vec<(String, String)> = fs::read_to_string(&output)
+        .unwrap()
+        .lines()
+        .map(|l| {
+            let fields: Vec<&str> = l.split('\t').collect();
+            let rank = fields.iter().find_map(|f| f.strip_prefix("sr:i:"));
+            (fields[7].to_string(), rank.expect("sr:i: tag").to_string())
+        })
+        .collect();
+    assert_eq!(
+        ranks,
+        vec![
+            ("50000".to_string(), "1".to_string()),
+            ("0".to_string(), "0".to_string())
+        ]
+    );
+}
+
+/// --minus-strand-coords reflects reverse-strand target intervals only
+#[test]
+fn test_minus_strand_coords() {
+    use sweepga::paf_filter::{reflect_reverse_target_coords, FilterConfig, PafFilter};
+
+    let temp_dir = TempDir::new().unwrap();
+    let input = temp_dir.path().join("input.paf");
+    fs::write(
+        &input,
+        "q1\t10000\t0\t1000\t+\tt1\t5000\t100\t1100\t950\t1000\t60\n\
+         q1\t10000\t2000\t3000\t-\tt1\t5000\t1000\t2000\t950\t1000\t60\n",
+    )
+    .unwrap();
+
+    let config = FilterConfig {
+        chain_gap: 0,
+        scaffold_gap: 0,
+        min_scaffold_length: 0,
+        scaffold_overlap_threshold: 0.95,
+        ..FilterConfig::default()
+    };
+
+    let output = temp_dir.path().join("out.paf");
+    PafFilter::new(config)
+        .with_minus_strand_coords(true)
+        .filter_paf(&input, &output)
+        .unwrap();
+
+    let content = fs::read_to_string(&output).unwrap();
+    let lines: Vec<&str> = content.lines().collect();
+    assert_eq!(lines.len(), 2);
+    let coords = |line: &str| -> (String, String) {
+        let f: Vec<&str> = line.split('\t').collect();
+        (f[7].to_string(), f[8].to_string())
+    };
+    assert_eq!(
+        coords(lines[0]),
+        ("100".into(), "1100".into()),
+        "forward unchanged"
+    );
+    assert_eq!(
+        coords(lines[1]),
+        ("3000".into(), "4000".into()),
+        "reverse reflected"
+    );
+
+    // Reflecting again restores the input coordinates
+    let restored = reflect_reverse_target_coords(lines[1]).unwrap();
+    assert_eq!(coords(&restored), ("1000".into(), "2000".into()));
+}
+
+/// --component-tag labels records by connected component of the genome graph
+#[test]
+fn test_component_tag_separates_clusters() {
+    use sweepga::paf_filter::{FilterConfig, PafFilter};
+
+    let temp_dir = TempDir::new().unwrap();
+    let input = temp_dir.path().join("input.paf");
+    // Two clusters: {A, B, E} (E joins through B) and {C, D}
+    fs::write(
+        &input,
+        "A#1#chr1\t10000\t0\t1000\t+\tB#1#chr1\t10000\t0\t1000\t950\t1000\t60\n\
+         C#1#chr1\t10000\t0\t1000\t+\tD#1#chr1\t10000\t0\t1000\t950\t1000\t60\n\
+         E#1#chr1\t10000\t0\t1000\t+\tB#1#chr1\t10000\t0\t1000\t950\t1000\t60\n\
+         D#1#chr1\t10000\t0\t1000\t+\tC#1#chr1\t10000\t0\t1000\t950\t1000\t60\n",
+    )
+    .unwrap();
+
+    let config = FilterConfig {
+        chain_gap: 0,
+        scaffold_gap: 0,
+        min_scaffold_length: 0,
+        scaffold_overlap_threshold: 0.95,
+        ..FilterConfig::default()
+    };
+
+    let output = temp_dir.path().join("out.paf");
+    PafFilter::new(config)
+        .with_component_tag(true)
+        .filter_paf(&input, &output)
+        .unwrap();
+
+    let content = fs::read_to_string(&output).unwrap();
+    let components: Vec<&str> = content
+        .lines()
+        .map(|l| {
+            l.split('\t')
+                .find_map(|f| f.strip_prefix("cc:i:"))
+                .expect("every record should carry a cc:i: tag")
+        })
+        .collect();
+
+    assert_eq!(components, ["1", "2", "1", "2"]);
+}
+
+/// --block-length-from picks the length used by -l for a gapped record
+#[test]
+fn test_block_length_from() {
+    use sweepga::paf_filter::{BlockLengthSource, FilterConfig, PafFilter};
+
+    let temp_dir = TempDir::new().unwrap();
+    let input = temp_dir.path().join("gapped.paf");
+    // Query span 1000, target span 1100, gap-inclusive column 11 of 1150
+    fs::write(
+        &input,
+        "q1\t10000\t0\t1000\t+\tt1\t10000\t0\t1100\t950\t1150\t60\n",
+    )
+    .unwrap();
+
+    let config = |min_block_length: u64| FilterConfig {
+        chain_gap: 0,
+        min_block_length,
+        scaffold_gap: 0,
+        min_scaffold_length: 0,
+        scaffold_overlap_threshold: 0.95,
+        ..FilterConfig::default()
+    };
+    let output = temp_dir.path().join("out.paf");
+    let kept = |source: BlockLengthSource, min_block_length: u64| -> usize {
+        PafFilter::new(config(min_block_length))
+            .with_block_length_from(source)
+            .filter_paf(&input, &output)
+            .unwrap()
+    };
+
+    // A record passes -l L exactly when its block length is at least L
+    for (source, expected) in [
+        (BlockLengthSource::Column, 1150),
+        (BlockLengthSource::QuerySpan, 1000),
+        (BlockLengthSource::TargetSpan, 1100),
+        (BlockLengthSource::MaxSpan, 1100),
+    ] {
+        assert_eq!(kept(source, expected), 1, "{source:?} should be {expected}");
+        assert_eq!(
+            kept(source, expected + 1),
+            0,
+            "{source:?} should be {expected}"
+        );
+    }
+}
+
+/// --preserve-headers copies leading comment/header lines to the output
+#[test]
+fn test_preserve_headers() {
+    use sweepga::paf_filter::{FilterConfig, PafFilter};
+
+    let temp_dir = TempDir::new().unwrap();
+    let input = temp_dir.path().join("input.paf");
+    fs::write(
+        &input,
+        "@HD\tVN:1.0\n\
+         # aligner: test\n\
+         q1\t10000\t0\t1000\t+\tt1\t10000\t0\t1000\t950\t1000\t60\n",
+    )
+    .unwrap();
+
+    let config = FilterConfig {
+        chain_gap: 0,
+        scaffold_gap: 0,
+        min_scaffold_length: 0,
+        scaffold_overlap_threshold: 0.95,
+        ..FilterConfig::default()
+    };
+
+    let output = temp_dir.path().join("out.paf");
+    let records = PafFilter::new(config.clone())
+        .with_preserve_headers(true)
+        .filter_paf(&input, &output)
+        .unwrap();
+    assert_eq!(records, 1, "header lines are not counted as records");
+
+    let content = fs::read_to_string(&output).unwrap();
+    let lines: Vec<&str> = content.lines().collect();
+    assert_eq!(lines.len(), 3);
+    assert_eq!(lines[0], "@HD\tVN:1.0");
+    assert_eq!(lines[1], "# aligner: test");
+    assert!(lines[2].starts_with("q1\t"));
+
+    // Without the flag headers are dropped
+    PafFilter::new(config).filter_paf(&input, &output).unwrap();
+    let content = fs::read_to_string(&output).unwrap();
+    assert_eq!(content.lines().count(), 1);
+}
+
+/// Skipped-pair markers reach the output without --preserve-headers, in
+/// both the in-memory and the streaming filter
+#[test]
+fn test_skipped_pair_markers_pass_through() {
+    use sweepga::paf_filter::{FilterConfig, PafFilter};
+
+    let temp_dir = TempDir::new().unwrap();
+    let input = temp_dir.path().join("input.paf");
+    let marker = "#sweepga:skipped\tA#1\tC#1\tmash_distance:f:0.3100";
+    fs::write(
+        &input,
+        format!(
+            "{marker}\n\
+             # aligner: test\n\
+             A#1#chr1\t10000\t0\t1000\t+\tB#1#chr1\t10000\t0\t1000\t950\t1000\t60\n"
+        ),
+    )
+    .unwrap();
+    assert_eq!(sweepga::paf::skipped_pair(marker), Some(("A#1", "C#1")));
+
+    let config = FilterConfig {
+        scaffold_gap: 0,
+        ..FilterConfig::default()
+    };
+    let output = temp_dir.path().join("out.paf");
+    for filter in [
+        PafFilter::new(config.clone()),
+        PafFilter::new(config.clone())
+            .with_low_memory(true)
+            .with_no_plane_sweep(true),
+    ] {
+        assert_eq!(filter.filter_paf(&input, &output).unwrap(), 1);
+        let content = fs::read_to_string(&output).unwrap();
+        let lines: Vec<&str> = content.lines().collect();
+        assert_eq!(lines.len(), 2, "other comment lines are dropped");
+        assert_eq!(lines[0], marker);
+        assert!(lines[1].starts_with("A#1#chr1\t"));
+    }
+}
+
+/// --dump-registry lists every input sequence once, with stable IDs and lengths
+#[test]
+fn test_dump_registry() {
+    use sweepga::paf_filter::{FilterConfig, FilterMode, PafFilter};
+
+    let temp_dir = TempDir::new().unwrap();
+    let input = temp_dir.path().join("input.paf");
+    fs::write(
+        &input,
+        "A#1#chr1\t50000\t0\t10000\t+\tB#1#chr1\t60000\t0\t10000\t9900\t10000\t60\n\
+         A#1#chr2\t40000\t0\t10000\t-\tB#1#chr1\t60000\t20000\t30000\t9900\t10000\t60\n\
+         C#1#chr1\t70000\t0\t10000\t+\tA#1#chr1\t50000\t0\t10000\t9900\t10000\t60\n",
+    )
+    .unwrap();
+
+    let config = FilterConfig {
+        chain_gap: 0,
+        mapping_filter_mode: FilterMode::OneToOne,
+        mapping_max_per_query: Some(1),
+        mapping_max_per_target: Some(1),
+        scaffold_gap: 0,
+        min_scaffold_length: 0,
+        scaffold_overlap_threshold: 0.95,
+        ..FilterConfig::default()
+    };
+
+    let dump = |name: &str| -> String {
+        let registry = temp_dir.path().join(name);
+        PafFilter::new(config.clone())
+            .with_registry_output(Some(registry.clone()))
+            .filter_paf(&input, &temp_dir.path().join("out.paf"))
+            .unwrap();
+        fs::read_to_string(registry).unwrap()
+    };
+
+    let registry = dump("registry1.tsv");
+    assert_eq!(
+        registry,
+        "#id\tname\tlength\n\
+         0\tA#1#chr1\t50000\n\
+         1\tB#1#chr1\t60000\n\
+         2\tA#1#chr2\t40000\n\
+         3\tC#1#chr1\t70000\n"
+    );
+    assert_eq!(
+        dump("registry2.tsv"),
+        registry,
+        "IDs should be stable across runs"
+    );
+}
@@ -187,6 +187,95 @@ fn test_cigar_extended_format() {
     }
 }
 
+#[test]
+fn test_aln_identity_matches_cigar_for_gapped_record() {
+    use sweepga::filter_types::AlnIdentity;
+    use sweepga::paf::parse_cigar_counts;
+
+    // What ALNtoPAF -x reports for a gapped record: matches and columns from
+    // its CIGAR. The .1aln record only carries spans and the edit distance.
+    for cigar in ["600=3X10D387=", "500=12I2X486="] {
+        let (matches, mismatches, insertions, deletions) = parse_cigar_counts(cigar).unwrap();
+        let query_span = matches + mismatches + insertions;
+        let target_span = matches + mismatches + deletions;
+        let diffs = mismatches + insertions + deletions;
+        let columns = matches + diffs;
+
+        let (native_matches, native_length) =
+            AlnIdentity::Columns.matches_and_length(query_span, target_span, diffs);
+        assert_eq!(
+            (native_matches, native_length),
+            (matches, columns),
+            "{cigar}"
+        );
+    }
+
+    // The query-span definition misses deleted target bases: 977/990, not 987/1000
+    assert_eq!(
+        AlnIdentity::QuerySpan.matches_and_length(990, 1000, 13),
+        (977, 990)
+    );
+}
+
+#[test]
+fn test_paf_reader_carries_extra_tags() {
+    use sweepga::paf::PafReader;
+
+    let line =
+        "q\t1000\t0\t100\t+\tt\t1000\t0\t100\t98\t100\t60\ttp:A:P\tcg:Z:50=2X48=\tAS:i:180\n";
+    let mut reader = PafReader::new(line.as_bytes());
+    let (_, _, aux) = reader.read_record().unwrap().unwrap();
+    let tags: Vec<(&str, &str)> = aux
+        .extra_tags
+        .iter()
+        .map(|(tag, value)| (tag.as_str(), value.as_str()))
+        .collect();
+    assert_eq!(tags, [("cg:Z", "50=2X48="), ("tp:A", "P"), ("AS:i", "180")]);
+}
+
+#[test]
+fn test_identity_transform_raises_effective_threshold() {
+    use sweepga::filter_types::IdentityTransform;
+    use sweepga::paf_filter::{FilterConfig, PafFilter};
+
+    // 5% observed differences: JC and K2P distances are both ~5.2%
+    assert!((IdentityTransform::JukesCantor.apply(0.95) - 0.948255).abs() < 1e-6);
+    assert!((IdentityTransform::Kimura.apply(0.95) - 0.948019).abs() < 1e-6);
+    assert_eq!(IdentityTransform::None.apply(0.95), 0.95);
+    assert_eq!(IdentityTransform::JukesCantor.apply(1.0), 1.0);
+    // Beyond saturation (p >= 3/4) the distance is infinite
+    assert_eq!(IdentityTransform::JukesCantor.apply(0.2), 0.0);
+
+    // A 95% identity mapping passes --min-aln-identity 0.949 raw, but not
+    // once corrected
+    let input = NamedTempFile::new().unwrap();
+    fs::write(
+        input.path(),
+        "q\t20000\t0\t20000\t+\tt\t20000\t0\t20000\t19000\t20000\t60\n",
+    )
+    .unwrap();
+    let config = FilterConfig {
+        chain_gap: 0,
+        scaffold_gap: 0,
+        min_scaffold_length: 0,
+        scaffold_overlap_threshold: 0.95,
+        min_identity: 0.949,
+        ..FilterConfig::default()
+    };
+    for (transform, expected) in [
+        (IdentityTransform::None, 1),
+        (IdentityTransform::JukesCantor, 0),
+        (IdentityTransform::Kimura, 0),
+    ] {
+        let output = NamedTempFile::new().unwrap();
+        let written = PafFilter::new(config.clone())
+            .with_identity_transform(transform)
+            .filter_paf(input.path(), output.path())
+            .unwrap();
+        assert_eq!(written, expected, "{transform:?}");
+    }
+}
+
 #[test]
 fn test_scaffold_annotations() {
     // Test that scaffold annotations are properly formatted